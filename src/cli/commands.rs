@@ -1,6 +1,13 @@
-use crate::cli::{Commands, ToolAction, AgentAction};
+use crate::cli::{Commands, ToolAction, SplatAction, CameraAction, AgentAction};
+use crate::core::CameraSetFiles;
 use crate::core::HylaeanSplat;
-use crate::errors::Result;
+use crate::errors::{Result, HylaeanError};
+use crate::formats::camera_params::align::{AlignOptions, Ransac};
+use crate::formats::point_cloud::tiles::TilingScheme;
+use crate::formats::splat_ops::edit::{EditOperation, Region};
+use crate::formats::splat_ops::lod::{LodMethod, LodOptions};
+use crate::formats::splat_ops::merge::{MergeInput, MergeOptions};
+use crate::formats::splat_ops::transform::{self, Similarity};
 use log::{info, warn};
 
 impl HylaeanSplat {
@@ -19,9 +26,22 @@ impl HylaeanSplat {
                 input, 
                 output, 
                 input_format, 
-                output_format 
+                output_format,
+                encoding,
             } => {
-                self.convert_file(input, output, input_format, output_format).await?;
+                self.convert_file(input, output, input_format, output_format, encoding).await?;
+            }
+            
+            Commands::Inspect { input, input_format, json } => {
+                self.inspect_file(input, input_format, json).await?;
+            }
+            
+            Commands::Splat { action } => {
+                self.execute_splat_action(action).await?;
+            }
+            
+            Commands::Cameras { action } => {
+                self.execute_camera_action(action).await?;
             }
             
             Commands::Agent { action } => {
@@ -78,6 +98,179 @@ impl HylaeanSplat {
         Ok(())
     }
     
+    async fn execute_splat_action(&mut self, action: SplatAction) -> Result<()> {
+        match action {
+            SplatAction::Crop { input, output, aabb, obb, sphere, invert } => {
+                let region = match (aabb, obb, sphere) {
+                    (Some(values), _, _) => Region::from_box_values(&values)?,
+                    (_, Some(values), _) => Region::from_oriented_box_values(&values)?,
+                    (_, _, Some(values)) => Region::from_sphere_values(&values)?,
+                    _ => unreachable!("clap requires one region"),
+                };
+                self.edit_splat(input, output, vec![EditOperation::Crop { region, invert }]).await?;
+            }
+            
+            SplatAction::Filter { input, output, min_opacity, min_scale, max_scale } => {
+                let mut operations = Vec::new();
+                if let Some(min_alpha) = min_opacity {
+                    operations.push(EditOperation::MinOpacity(min_alpha));
+                }
+                if min_scale.is_some() || max_scale.is_some() {
+                    operations.push(EditOperation::Scale { min: min_scale, max: max_scale });
+                }
+                self.edit_splat(input, output, require_operations(operations)?).await?;
+            }
+            
+            SplatAction::Prune { input, output, outlier_neighbors, outlier_std_ratio, keep } => {
+                let mut operations = Vec::new();
+                if let Some(neighbors) = outlier_neighbors {
+                    operations.push(EditOperation::Outliers { neighbors, std_ratio: outlier_std_ratio });
+                }
+                if let Some(count) = keep {
+                    operations.push(EditOperation::KeepLargest(count));
+                }
+                self.edit_splat(input, output, require_operations(operations)?).await?;
+            }
+            
+            SplatAction::Transform { input, output, matrix, rotate, scale, translate } => {
+                let mut similarity = Similarity::default();
+                if let Some(matrix) = matrix {
+                    similarity = Similarity::from_matrix(&transform::parse_matrix(&matrix)?)?;
+                }
+                if let Some(scale) = scale {
+                    similarity = similarity.then(&Similarity::from_scale(scale)?);
+                }
+                if let Some(rotate) = rotate {
+                    let rotation = match rotate.as_slice() {
+                        [x, y, z] => Similarity::from_euler_degrees([*x, *y, *z]),
+                        [w, x, y, z] => Similarity::from_quaternion([*w, *x, *y, *z])?,
+                        _ => {
+                            return Err(HylaeanError::ConfigError {
+                                message: format!("--rotate takes 3 Euler angles or 4 quaternion values, got {}", rotate.len()),
+                            });
+                        }
+                    };
+                    similarity = similarity.then(&rotation);
+                }
+                if let Some(translate) = translate {
+                    let [x, y, z] = translate.as_slice() else {
+                        return Err(HylaeanError::ConfigError {
+                            message: format!("--translate takes 3 values, got {}", translate.len()),
+                        });
+                    };
+                    similarity = similarity.then(&Similarity::from_translation([*x, *y, *z]));
+                }
+                self.transform_file(input, output, similarity).await?;
+            }
+            
+            SplatAction::Merge { inputs, output, transform, sh_degree, dedupe_radius } => {
+                let mut inputs: Vec<MergeInput> = inputs.into_iter()
+                    .map(|path| MergeInput { path: path.into(), transform: Similarity::default() })
+                    .collect();
+                for placement in transform {
+                    let (index, matrix) = parse_placement(&placement, inputs.len())?;
+                    inputs[index].transform = matrix;
+                }
+                let options = MergeOptions { sh_degree, dedupe_radius };
+                self.merge_splats(inputs, output, options).await?;
+            }
+            
+            SplatAction::ShDegree { input, output, degree } => {
+                self.set_sh_degree(input, output, degree).await?;
+            }
+            
+            SplatAction::Bake { input, output, encoding, alpha } => {
+                self.bake_splat(input, output, encoding, alpha).await?;
+            }
+            
+            SplatAction::Downsample { input, output, voxel_size, target } => {
+                self.downsample_file(input, output, voxel_size, target).await?;
+            }
+            
+            SplatAction::Lod { input, output_dir, levels, ratio, method, format } => {
+                let options = LodOptions { levels, ratio, method: LodMethod::parse(&method)? };
+                self.build_lod(input, output_dir, options, format).await?;
+            }
+            
+            SplatAction::Tile { input, output_dir, grid, octree, max_depth, lod_levels, lod_ratio, lod_method, format } => {
+                let scheme = match (grid, octree) {
+                    (Some(tile_size), _) => TilingScheme::Grid { tile_size },
+                    (None, Some(max_gaussians)) => TilingScheme::Octree { max_gaussians, max_depth },
+                    (None, None) => {
+                        return Err(HylaeanError::ConfigError {
+                            message: "tiling needs --grid or --octree".to_string(),
+                        });
+                    }
+                };
+                let lod = LodOptions { levels: lod_levels, ratio: lod_ratio, method: LodMethod::parse(&lod_method)? };
+                self.tile_splat(input, output_dir, scheme, lod, format).await?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    async fn execute_camera_action(&mut self, action: CameraAction) -> Result<()> {
+        match action {
+            CameraAction::Align {
+                input,
+                reference,
+                output,
+                input_format,
+                reference_format,
+                output_format,
+                no_scale,
+                ransac_threshold,
+                ransac_iterations,
+                point_cloud,
+                point_cloud_output,
+                transform_output,
+                encoding,
+            } => {
+                let files = CameraSetFiles {
+                    input,
+                    input_format,
+                    output,
+                    output_format,
+                    encoding,
+                    point_cloud: point_cloud.zip(point_cloud_output),
+                    transform_output,
+                };
+                let options = AlignOptions {
+                    with_scale: !no_scale,
+                    ransac: ransac_threshold.map(|threshold| Ransac { threshold, iterations: ransac_iterations }),
+                };
+                self.align_cameras(files, reference, reference_format, options).await?;
+            }
+            
+            CameraAction::Normalize {
+                input,
+                output,
+                input_format,
+                output_format,
+                radius,
+                orient_up,
+                point_cloud,
+                point_cloud_output,
+                transform_output,
+                encoding,
+            } => {
+                let files = CameraSetFiles {
+                    input,
+                    input_format,
+                    output,
+                    output_format,
+                    encoding,
+                    point_cloud: point_cloud.zip(point_cloud_output),
+                    transform_output,
+                };
+                self.normalize_cameras(files, orient_up, radius).await?;
+            }
+        }
+        
+        Ok(())
+    }
+    
     async fn execute_agent_action(&mut self, action: AgentAction) -> Result<()> {
         match action {
             AgentAction::Start { daemon } => {
@@ -111,4 +304,26 @@ impl HylaeanSplat {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Parse `INDEX=MATRIX` from `splat merge --transform`
+fn parse_placement(value: &str, inputs: usize) -> Result<(usize, Similarity)> {
+    let invalid = || HylaeanError::ConfigError {
+        message: format!("--transform expects INDEX=MATRIX with INDEX below {}, got '{}'", inputs, value),
+    };
+    let (index, matrix) = value.split_once('=').ok_or_else(invalid)?;
+    let index: usize = index.trim().parse().map_err(|_| invalid())?;
+    if index >= inputs {
+        return Err(invalid());
+    }
+    Ok((index, Similarity::from_matrix(&transform::parse_matrix(matrix)?)?))
+}
+
+fn require_operations(operations: Vec<EditOperation>) -> Result<Vec<EditOperation>> {
+    if operations.is_empty() {
+        return Err(HylaeanError::ConfigError {
+            message: "no edit requested; see --help for the available options".to_string(),
+        });
+    }
+    Ok(operations)
+}
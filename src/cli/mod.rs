@@ -1,4 +1,4 @@
-use clap::Subcommand;
+use clap::{ArgGroup, Subcommand};
 
 pub mod commands;
 
@@ -34,6 +34,38 @@ pub enum Commands {
         /// Output format
         #[arg(long)]
         output_format: String,
+        
+        /// Output encoding: ascii, binary_little_endian or binary_big_endian for PLY;
+        /// ascii, binary or binary_compressed for PCD; text or binary for COLMAP models
+        #[arg(long)]
+        encoding: Option<String>,
+    },
+    
+    /// Show the schema, bounds and statistics of a point cloud or splat file, or the
+    /// track and reprojection statistics of a COLMAP sparse model
+    Inspect {
+        /// Input file path
+        input: String,
+        
+        /// Input format (auto-detect if not specified)
+        #[arg(long)]
+        input_format: Option<String>,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Crop, filter, prune, transform, merge, downsample, tile and bake trained Gaussian splat scenes
+    Splat {
+        #[command(subcommand)]
+        action: SplatAction,
+    },
+    
+    /// Align camera sets from different reconstructions, or normalize a scene's cameras
+    Cameras {
+        #[command(subcommand)]
+        action: CameraAction,
     },
     
     /// Run the agentic component
@@ -119,6 +151,352 @@ pub enum ToolAction {
     },
 }
 
+#[derive(Subcommand)]
+pub enum SplatAction {
+    /// Keep only the Gaussians inside a box, oriented box or sphere
+    #[command(group(ArgGroup::new("region").required(true).args(["aabb", "obb", "sphere"])))]
+    Crop {
+        /// Input splat (PLY, .splat or .spz)
+        input: String,
+        
+        /// Output file; the extension selects the format
+        #[arg(short, long)]
+        output: String,
+        
+        /// Axis-aligned box: min_x,min_y,min_z,max_x,max_y,max_z
+        #[arg(long = "box", value_delimiter = ',', allow_negative_numbers = true)]
+        aabb: Option<Vec<f32>>,
+        
+        /// Oriented box: center, half extents and rotation quaternion as cx,cy,cz,hx,hy,hz,qw,qx,qy,qz
+        #[arg(long, value_delimiter = ',', allow_negative_numbers = true)]
+        obb: Option<Vec<f32>>,
+        
+        /// Sphere: cx,cy,cz,radius
+        #[arg(long, value_delimiter = ',', allow_negative_numbers = true)]
+        sphere: Option<Vec<f32>>,
+        
+        /// Remove the inside of the region instead
+        #[arg(long)]
+        invert: bool,
+    },
+    
+    /// Remove Gaussians by opacity and scale thresholds
+    Filter {
+        /// Input splat (PLY, .splat or .spz)
+        input: String,
+        
+        /// Output file; the extension selects the format
+        #[arg(short, long)]
+        output: String,
+        
+        /// Minimum linear opacity (0-1)
+        #[arg(long)]
+        min_opacity: Option<f32>,
+        
+        /// Minimum largest-axis scale, in scene units
+        #[arg(long)]
+        min_scale: Option<f32>,
+        
+        /// Maximum largest-axis scale, in scene units
+        #[arg(long)]
+        max_scale: Option<f32>,
+    },
+    
+    /// Remove floaters with statistical outlier removal and keep the most important Gaussians
+    Prune {
+        /// Input splat (PLY, .splat or .spz)
+        input: String,
+        
+        /// Output file; the extension selects the format
+        #[arg(short, long)]
+        output: String,
+        
+        /// Neighbors used for statistical outlier removal (disabled if not given)
+        #[arg(long)]
+        outlier_neighbors: Option<usize>,
+        
+        /// Standard deviations above the mean neighbor distance that count as an outlier
+        #[arg(long, default_value_t = 2.0)]
+        outlier_std_ratio: f32,
+        
+        /// Keep only the N Gaussians with the highest opacity times volume
+        #[arg(long)]
+        keep: Option<usize>,
+    },
+    
+    /// Apply a similarity transform to a splat or point cloud; steps apply in the order
+    /// matrix, scale, rotate, translate
+    #[command(group(ArgGroup::new("steps").required(true).multiple(true).args(["matrix", "rotate", "scale", "translate"])))]
+    Transform {
+        /// Input splat (PLY, .splat or .spz) or point cloud (PLY, PCD, XYZ, LAS/LAZ)
+        input: String,
+        
+        /// Output file; the extension selects the format
+        #[arg(short, long)]
+        output: String,
+        
+        /// Row-major 4x4 similarity matrix as 16 comma-separated values, or a file holding them
+        #[arg(long, allow_hyphen_values = true)]
+        matrix: Option<String>,
+        
+        /// Rotation as Euler angles in degrees about fixed X, Y, Z (rx,ry,rz) or a quaternion (qw,qx,qy,qz)
+        #[arg(long, value_delimiter = ',', allow_negative_numbers = true)]
+        rotate: Option<Vec<f64>>,
+        
+        /// Uniform scale factor
+        #[arg(long)]
+        scale: Option<f64>,
+        
+        /// Translation: tx,ty,tz
+        #[arg(long, value_delimiter = ',', allow_negative_numbers = true)]
+        translate: Option<Vec<f64>>,
+    },
+    
+    /// Merge several splat scenes into one, e.g. separately trained tiles of a site
+    Merge {
+        /// Input splats (PLY, .splat or .spz)
+        #[arg(required = true, num_args = 1..)]
+        inputs: Vec<String>,
+        
+        /// Output file; the extension selects the format
+        #[arg(short, long)]
+        output: String,
+        
+        /// Place input INDEX (from 0) with a row-major 4x4 similarity matrix, given as
+        /// INDEX=16 comma-separated values or INDEX=path; repeat for several inputs
+        #[arg(long, allow_hyphen_values = true)]
+        transform: Vec<String>,
+        
+        /// SH degree of the output (default: the highest input degree); higher inputs are truncated, lower ones zero-padded
+        #[arg(long)]
+        sh_degree: Option<u32>,
+        
+        /// Where Gaussians from different inputs lie within this distance, keep only the most important one
+        #[arg(long)]
+        dedupe_radius: Option<f32>,
+    },
+    
+    /// Truncate (or zero-pad) the spherical harmonics to another degree
+    ShDegree {
+        /// Input splat (PLY, .splat or .spz)
+        input: String,
+        
+        /// Output file; the extension selects the format
+        #[arg(short, long)]
+        output: String,
+        
+        /// Target SH degree (0-3)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(0..=3))]
+        degree: u32,
+    },
+    
+    /// Export Gaussian centers with their base color as 8-bit RGB in a plain point cloud
+    Bake {
+        /// Input splat (PLY, .splat or .spz)
+        input: String,
+        
+        /// Output point cloud (PLY, PCD, XYZ or LAS/LAZ)
+        #[arg(short, long)]
+        output: String,
+        
+        /// Output encoding: ascii, binary_little_endian or binary_big_endian for PLY;
+        /// ascii, binary or binary_compressed for PCD
+        #[arg(long)]
+        encoding: Option<String>,
+        
+        /// Also write linear opacity as an 8-bit alpha channel
+        #[arg(long)]
+        alpha: bool,
+    },
+    
+    /// Reduce a splat by merging Gaussians per voxel or keeping the most important ones,
+    /// or a point cloud to one averaged point per voxel
+    #[command(group(ArgGroup::new("method").required(true).args(["voxel_size", "target"])))]
+    Downsample {
+        /// Input splat (PLY, .splat or .spz) or point cloud (PLY, PCD, XYZ, LAS/LAZ)
+        input: String,
+        
+        /// Output file; the extension selects the format
+        #[arg(short, long)]
+        output: String,
+        
+        /// Voxel edge length, in scene units
+        #[arg(long)]
+        voxel_size: Option<f32>,
+        
+        /// Keep this many Gaussians, the most important (opacity times volume) first
+        #[arg(long)]
+        target: Option<usize>,
+    },
+    
+    /// Write progressively coarser levels of detail of a splat and a JSON manifest
+    Lod {
+        /// Input splat (PLY, .splat or .spz)
+        input: String,
+        
+        /// Directory for the level files and the manifest
+        #[arg(short, long)]
+        output_dir: String,
+        
+        /// Number of levels, including the full-resolution level 0
+        #[arg(long, default_value_t = 4)]
+        levels: usize,
+        
+        /// Fraction of Gaussians each level keeps from the one before
+        #[arg(long, default_value_t = 0.5)]
+        ratio: f64,
+        
+        /// Reduction method: voxel (merge Gaussians per voxel) or importance
+        #[arg(long, default_value = "voxel")]
+        method: String,
+        
+        /// Level file format: ply, splat, ksplat or spz (default: the input's)
+        #[arg(long)]
+        format: Option<String>,
+    },
+    
+    /// Split a splat into a grid or octree of tile files with a tileset.json index for streaming
+    #[command(group(ArgGroup::new("scheme").required(true).args(["grid", "octree"])))]
+    Tile {
+        /// Input splat (PLY, .splat or .spz)
+        input: String,
+        
+        /// Directory for the tile files and tileset.json
+        #[arg(short, long)]
+        output_dir: String,
+        
+        /// Fixed grid of cubic tiles with this edge length, in scene units
+        #[arg(long)]
+        grid: Option<f64>,
+        
+        /// Adaptive octree: split cells holding more than this many Gaussians
+        #[arg(long)]
+        octree: Option<usize>,
+        
+        /// Deepest octree level
+        #[arg(long, default_value_t = 10)]
+        max_depth: u32,
+        
+        /// LOD levels per tile, including the full-resolution level 0
+        #[arg(long, default_value_t = 1)]
+        lod_levels: usize,
+        
+        /// Fraction of Gaussians each LOD level keeps from the one before
+        #[arg(long, default_value_t = 0.5)]
+        lod_ratio: f64,
+        
+        /// LOD reduction method: voxel or importance
+        #[arg(long, default_value = "voxel")]
+        lod_method: String,
+        
+        /// Tile file format: ply, splat, ksplat or spz (default: the input's)
+        #[arg(long)]
+        format: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CameraAction {
+    /// Estimate the similarity taking the input cameras into the reference's world from
+    /// the camera centers of images both contain (Umeyama), and apply it
+    Align {
+        /// Cameras to move: a COLMAP model, transforms.json or another camera format
+        input: String,
+        
+        /// Cameras in the target world; images are matched by name
+        #[arg(long)]
+        reference: String,
+        
+        /// Aligned camera set: a COLMAP model directory or transforms.json
+        #[arg(short, long)]
+        output: String,
+        
+        /// Input format (auto-detect if not specified)
+        #[arg(long)]
+        input_format: Option<String>,
+        
+        /// Reference format (auto-detect if not specified)
+        #[arg(long)]
+        reference_format: Option<String>,
+        
+        /// Output format: colmap or nerf (default: the input's, or colmap for other inputs)
+        #[arg(long)]
+        output_format: Option<String>,
+        
+        /// Estimate a rigid transform, keeping the input's scale
+        #[arg(long)]
+        no_scale: bool,
+        
+        /// Fit with RANSAC, counting cameras within this distance of their reference
+        /// (in reference units) as inliers
+        #[arg(long)]
+        ransac_threshold: Option<f64>,
+        
+        /// RANSAC iterations
+        #[arg(long, default_value_t = 1000)]
+        ransac_iterations: usize,
+        
+        /// Point cloud or splat in the input's world to move with the cameras
+        #[arg(long, requires = "point_cloud_output")]
+        point_cloud: Option<String>,
+        
+        /// Where the moved point cloud or splat goes; the extension selects the format
+        #[arg(long, requires = "point_cloud")]
+        point_cloud_output: Option<String>,
+        
+        /// Also write the row-major 4x4 transform to this file, for `splat transform --matrix`
+        #[arg(long)]
+        transform_output: Option<String>,
+        
+        /// COLMAP output encoding: text or binary
+        #[arg(long)]
+        encoding: Option<String>,
+    },
+    
+    /// Center the cameras on their mean position and scale them into a sphere, as
+    /// nerfstudio does before training
+    Normalize {
+        /// Cameras to normalize: a COLMAP model, transforms.json or another camera format
+        input: String,
+        
+        /// Normalized camera set: a COLMAP model directory or transforms.json
+        #[arg(short, long)]
+        output: String,
+        
+        /// Input format (auto-detect if not specified)
+        #[arg(long)]
+        input_format: Option<String>,
+        
+        /// Output format: colmap or nerf (default: the input's, or colmap for other inputs)
+        #[arg(long)]
+        output_format: Option<String>,
+        
+        /// Radius of the sphere the farthest camera lands on
+        #[arg(long, default_value_t = 1.0)]
+        radius: f64,
+        
+        /// First rotate the mean camera up direction onto +z
+        #[arg(long)]
+        orient_up: bool,
+        
+        /// Point cloud or splat in the input's world to move with the cameras
+        #[arg(long, requires = "point_cloud_output")]
+        point_cloud: Option<String>,
+        
+        /// Where the moved point cloud or splat goes; the extension selects the format
+        #[arg(long, requires = "point_cloud")]
+        point_cloud_output: Option<String>,
+        
+        /// Also write the row-major 4x4 transform to this file, for `splat transform --matrix`
+        #[arg(long)]
+        transform_output: Option<String>,
+        
+        /// COLMAP output encoding: text or binary
+        #[arg(long)]
+        encoding: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum AgentAction {
     /// Start monitoring repositories and papers
@@ -1,47 +1,44 @@
 use crate::errors::{Result, HylaeanError};
-use log::{info, warn, error, debug};
+use crate::formats::{self, CameraFormat, DataFormat, DatasetFormat, FormatConverter, PointCloudFormat};
+use crate::formats::camera_params::{CameraParamsConverter, ColmapCamera, ColmapImage, ColmapPoint3D, ModelEncoding};
+use crate::formats::camera_params::align::{self, AlignOptions};
+use crate::formats::camera_params::colmap::{self, Points3DSource};
+use crate::formats::point_cloud::PointCloudConverter;
+use crate::formats::{inspect, pcd, ply};
+use crate::formats::ply::Encoding;
+use crate::formats::splat_ops::{self, EditSummary};
+use crate::formats::gaussian;
+use crate::formats::pcd::PcdData;
+use crate::formats::point_cloud::stream::{self, SinkOptions};
+use crate::formats::point_cloud::tiles::{self, TilingScheme};
+use crate::formats::splat_ops::bake::GaussianSource;
+use crate::formats::splat_ops::{downsample, lod};
+use crate::formats::splat_ops::edit::EditOperation;
+use crate::formats::splat_ops::lod::LodOptions;
+use crate::formats::splat_ops::merge::{self, MergeInput, MergeOptions};
+use crate::formats::splat_ops::transform::{self, Similarity};
+use log::{info, warn};
 use std::path::{Path, PathBuf};
-use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum DataFormat {
-    PointCloud(PointCloudFormat),
-    CameraParameters(CameraFormat),
-    Dataset(DatasetFormat),
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum PointCloudFormat {
-    PLY,
-    PCD,
-    XYZ,
-    LAZ,
-    Custom(String),
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum CameraFormat {
-    COLMAP,
-    NeRF,
-    OpenCV,
-    Blender,
-    Custom(String),
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum DatasetFormat {
-    NeRFSynthetic,
-    LLFF,
-    TanksAndTemples,
-    Custom(String),
-}
 
 pub struct DataManager {
     conversion_cache: PathBuf,
 }
 
+/// The camera set `cameras align` and `cameras normalize` move, and where the results go
+pub struct CameraSetFiles {
+    pub input: String,
+    pub input_format: Option<String>,
+    pub output: String,
+    /// Default: the input's format when it is COLMAP or NeRF, otherwise COLMAP
+    pub output_format: Option<String>,
+    /// COLMAP encoding: text or binary
+    pub encoding: Option<String>,
+    /// A point cloud or splat in the input's world, and where to write it once moved
+    pub point_cloud: Option<(String, String)>,
+    /// File for the row-major 4x4 matrix, as `splat transform --matrix` reads it
+    pub transform_output: Option<String>,
+}
+
 impl DataManager {
     pub fn new() -> Result<Self> {
         let cache_dir = dirs::cache_dir()
@@ -62,26 +59,57 @@ impl DataManager {
         output: String,
         input_format: Option<String>,
         output_format: String,
+        encoding: Option<String>,
     ) -> Result<()> {
         let input_path = PathBuf::from(input);
         let output_path = PathBuf::from(output);
         
         let input_fmt = if let Some(fmt) = input_format {
-            self.parse_format(&fmt)?
+            formats::parse_format(&fmt)?
         } else {
-            self.detect_format(&input_path)?
+            formats::detect_format(&input_path)?
         };
         
-        let output_fmt = self.parse_format(&output_format)?;
+        let output_fmt = formats::parse_format(&output_format)?;
         
         info!("Converting {:?} to {:?}", input_fmt, output_fmt);
         
         match (&input_fmt, &output_fmt) {
-            (DataFormat::PointCloud(input_pc), DataFormat::PointCloud(output_pc)) => {
-                self.convert_point_cloud(&input_path, &output_path, input_pc, output_pc).await?;
+            (DataFormat::PointCloud(_), DataFormat::PointCloud(_)) => {
+                let mut converter = PointCloudConverter::new();
+                if let Some(encoding) = encoding {
+                    converter = match output_fmt {
+                        DataFormat::PointCloud(PointCloudFormat::PCD) => converter.with_pcd_data(pcd::parse_data(&encoding)?),
+                        _ => converter.with_ply_encoding(ply::parse_encoding(&encoding)?),
+                    };
+                }
+                converter.convert(&input_path, &output_path, &input_fmt, &output_fmt)?;
             }
-            (DataFormat::CameraParameters(input_cam), DataFormat::CameraParameters(output_cam)) => {
-                self.convert_camera_params(&input_path, &output_path, input_cam, output_cam).await?;
+            (DataFormat::CameraParameters(CameraFormat::COLMAP), DataFormat::PointCloud(format)) if stream::is_streamable(format) => {
+                let points = colmap::read_points3d(&colmap::model_dir(&input_path))?;
+                let mut options = SinkOptions {
+                    ply_encoding: Encoding::BinaryLittleEndian,
+                    pcd_data: PcdData::Binary,
+                };
+                if let Some(encoding) = encoding {
+                    match format {
+                        PointCloudFormat::PCD => options.pcd_data = pcd::parse_data(&encoding)?,
+                        _ => options.ply_encoding = ply::parse_encoding(&encoding)?,
+                    }
+                }
+                let mut source = Points3DSource::new(points);
+                let sink = stream::create_sink(&output_path, format, &source, options)?;
+                let written = stream::pump(&mut source, sink, stream::DEFAULT_CHUNK_SIZE)?;
+                info!("Exported {} COLMAP 3D points", written);
+            }
+            (DataFormat::CameraParameters(_), DataFormat::CameraParameters(_))
+            | (DataFormat::Dataset(DatasetFormat::LLFF), DataFormat::CameraParameters(_))
+            | (DataFormat::CameraParameters(_), DataFormat::Dataset(DatasetFormat::LLFF)) => {
+                let mut converter = CameraParamsConverter::new();
+                if let Some(encoding) = encoding {
+                    converter = converter.with_colmap_encoding(ModelEncoding::parse(&encoding)?);
+                }
+                converter.convert(&input_path, &output_path, &input_fmt, &output_fmt)?;
             }
             _ => {
                 return Err(HylaeanError::ConversionFailed {
@@ -95,387 +123,359 @@ impl DataManager {
         Ok(())
     }
     
-    fn parse_format(&self, format_str: &str) -> Result<DataFormat> {
-        match format_str.to_lowercase().as_str() {
-            "ply" => Ok(DataFormat::PointCloud(PointCloudFormat::PLY)),
-            "pcd" => Ok(DataFormat::PointCloud(PointCloudFormat::PCD)),
-            "xyz" => Ok(DataFormat::PointCloud(PointCloudFormat::XYZ)),
-            "laz" => Ok(DataFormat::PointCloud(PointCloudFormat::LAZ)),
-            "colmap" => Ok(DataFormat::CameraParameters(CameraFormat::COLMAP)),
-            "nerf" => Ok(DataFormat::CameraParameters(CameraFormat::NeRF)),
-            "opencv" => Ok(DataFormat::CameraParameters(CameraFormat::OpenCV)),
-            "blender" => Ok(DataFormat::CameraParameters(CameraFormat::Blender)),
-            _ => Err(HylaeanError::UnsupportedFormat {
-                format: format_str.to_string(),
-            }),
-        }
-    }
-    
-    fn detect_format(&self, path: &Path) -> Result<DataFormat> {
-        let extension = path.extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("");
-        
-        match extension.to_lowercase().as_str() {
-            "ply" => Ok(DataFormat::PointCloud(PointCloudFormat::PLY)),
-            "pcd" => Ok(DataFormat::PointCloud(PointCloudFormat::PCD)),
-            "xyz" => Ok(DataFormat::PointCloud(PointCloudFormat::XYZ)),
-            "laz" => Ok(DataFormat::PointCloud(PointCloudFormat::LAZ)),
-            "txt" => {
-                // Could be camera parameters or point cloud
-                if self.looks_like_camera_params(path)? {
-                    Ok(DataFormat::CameraParameters(CameraFormat::COLMAP))
+    /// Print a summary of a file from its header alone
+    pub async fn inspect_file(&self, input: String, input_format: Option<String>, json: bool) -> Result<()> {
+        let input_path = PathBuf::from(input);
+        let input_fmt = if let Some(fmt) = input_format {
+            formats::parse_format(&fmt)?
+        } else {
+            formats::detect_format(&input_path)?
+        };
+
+        match input_fmt {
+            DataFormat::PointCloud(format) => {
+                let report = inspect::inspect(&input_path, &format)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
                 } else {
-                    Ok(DataFormat::PointCloud(PointCloudFormat::XYZ))
+                    print!("{}", report);
                 }
+                Ok(())
             }
-            "json" => Ok(DataFormat::CameraParameters(CameraFormat::NeRF)),
-            _ => Err(HylaeanError::UnsupportedFormat {
-                format: extension.to_string(),
-            }),
-        }
-    }
-    
-    fn looks_like_camera_params(&self, path: &Path) -> Result<bool> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        
-        for line in reader.lines().take(5) {
-            let line = line?;
-            if line.contains("# Camera list") || line.contains("# Image list") {
-                return Ok(true);
+            DataFormat::CameraParameters(CameraFormat::COLMAP) => {
+                let dir = colmap::model_dir(&input_path);
+                let encoding = colmap::detect_encoding(&dir)?;
+                let points = if colmap::has_points3d(&dir, encoding) {
+                    colmap::read_points3d(&dir)?
+                } else {
+                    warn!("No points3D in {}; point statistics are empty", dir.display());
+                    Vec::new()
+                };
+                let stats = colmap::model_stats(&colmap::read_cameras(&dir)?, &colmap::read_images(&dir)?, &points);
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&stats)?);
+                } else {
+                    println!("{}", dir.display());
+                    println!("  {:<29} COLMAP ({:?})", "Format:", encoding);
+                    print!("{}", stats);
+                }
+                Ok(())
             }
+            other => Err(HylaeanError::UnsupportedFormat {
+                format: format!("{:?} (inspect)", other),
+            }),
         }
-        
-        Ok(false)
     }
-    
-    async fn convert_point_cloud(
-        &self,
-        input_path: &Path,
-        output_path: &Path,
-        input_format: &PointCloudFormat,
-        output_format: &PointCloudFormat,
-    ) -> Result<()> {
-        match (input_format, output_format) {
-            (PointCloudFormat::PLY, PointCloudFormat::XYZ) => {
-                self.ply_to_xyz(input_path, output_path).await?;
-            }
-            (PointCloudFormat::XYZ, PointCloudFormat::PLY) => {
-                self.xyz_to_ply(input_path, output_path).await?;
-            }
-            (PointCloudFormat::PLY, PointCloudFormat::PCD) => {
-                self.ply_to_pcd(input_path, output_path).await?;
-            }
-            (PointCloudFormat::PCD, PointCloudFormat::PLY) => {
-                self.pcd_to_ply(input_path, output_path).await?;
-            }
-            (PointCloudFormat::XYZ, PointCloudFormat::PCD) => {
-                self.xyz_to_pcd(input_path, output_path).await?;
-            }
-            (PointCloudFormat::PCD, PointCloudFormat::XYZ) => {
-                self.pcd_to_xyz(input_path, output_path).await?;
-            }
-            _ => {
-                return Err(HylaeanError::ConversionFailed {
-                    source_format: format!("{:?}", input_format),
-                    target_format: format!("{:?}", output_format),
-                });
-            }
+
+    /// Apply edit operations to a Gaussian scene in order and print what each removed
+    pub async fn edit_splat(&self, input: String, output: String, operations: Vec<EditOperation>) -> Result<()> {
+        let input_path = PathBuf::from(input);
+        let output_path = PathBuf::from(output);
+
+        let (mut cloud, encoding) = splat_ops::load_cloud(&input_path)?;
+        let mut summary = EditSummary::new(cloud.len());
+        for operation in &operations {
+            let (description, removed) = operation.apply(&mut cloud);
+            cloud.comments.push(format!("hylaeansplat removed {} Gaussians: {}", removed, description));
+            summary.record(description, removed);
         }
-        
+
+        splat_ops::save_cloud(&cloud, &output_path, encoding)?;
+        print!("{}", summary);
+        info!("Edited splat written: {} -> {}", input_path.display(), output_path.display());
         Ok(())
     }
-    
-    async fn ply_to_xyz(&self, input_path: &Path, output_path: &Path) -> Result<()> {
-        info!("Converting PLY to XYZ: {} -> {}", input_path.display(), output_path.display());
-        
-        let file = File::open(input_path)?;
-        let reader = BufReader::new(file);
-        let mut output_file = File::create(output_path)?;
-        
-        let mut in_vertex_data = false;
-        let mut vertex_count = 0;
-        let mut processed_vertices = 0;
-        
-        for line in reader.lines() {
-            let line = line?;
-            
-            if line.starts_with("element vertex") {
-                vertex_count = line.split_whitespace()
-                    .nth(2)
-                    .and_then(|s| s.parse::<usize>().ok())
-                    .unwrap_or(0);
-                continue;
-            }
-            
-            if line == "end_header" {
-                in_vertex_data = true;
-                continue;
-            }
-            
-            if in_vertex_data && processed_vertices < vertex_count {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 3 {
-                    writeln!(output_file, "{} {} {}", parts[0], parts[1], parts[2])?;
-                    processed_vertices += 1;
-                }
-            }
+
+    /// Merge several Gaussian scenes, each placed by its own transform, into one output
+    pub async fn merge_splats(&self, inputs: Vec<MergeInput>, output: String, options: MergeOptions) -> Result<()> {
+        let output_path = PathBuf::from(output);
+
+        let mut loaded = Vec::with_capacity(inputs.len());
+        let mut output_encoding = None;
+        for input in inputs {
+            let (cloud, encoding) = splat_ops::load_cloud(&input.path)?;
+            output_encoding.get_or_insert(encoding);
+            loaded.push((input, cloud));
         }
-        
-        info!("Converted {} vertices", processed_vertices);
+
+        let (cloud, removed) = merge::merge_clouds(loaded, &options)?;
+        splat_ops::save_cloud(&cloud, &output_path, output_encoding.unwrap_or(Encoding::BinaryLittleEndian))?;
+        if removed > 0 {
+            println!("Removed {} overlapping Gaussians", removed);
+        }
+        println!("Merged {} Gaussians (SH degree {}) into {}", cloud.len(), cloud.sh_degree, output_path.display());
         Ok(())
     }
-    
-    async fn xyz_to_ply(&self, input_path: &Path, output_path: &Path) -> Result<()> {
-        info!("Converting XYZ to PLY: {} -> {}", input_path.display(), output_path.display());
-        
-        let file = File::open(input_path)?;
-        let reader = BufReader::new(file);
-        
-        // First pass: count vertices
-        let vertex_count = reader.lines().count();
-        
-        // Second pass: write PLY
-        let file = File::open(input_path)?;
-        let reader = BufReader::new(file);
-        let mut output_file = File::create(output_path)?;
-        
-        // Write PLY header
-        writeln!(output_file, "ply")?;
-        writeln!(output_file, "format ascii 1.0")?;
-        writeln!(output_file, "element vertex {}", vertex_count)?;
-        writeln!(output_file, "property float x")?;
-        writeln!(output_file, "property float y")?;
-        writeln!(output_file, "property float z")?;
-        writeln!(output_file, "end_header")?;
-        
-        // Write vertex data
-        for line in reader.lines() {
-            let line = line?;
-            if !line.trim().is_empty() {
-                writeln!(output_file, "{}", line)?;
-            }
+
+    /// Rewrite a Gaussian scene with its view-dependent color reduced (or padded) to `degree`
+    pub async fn set_sh_degree(&self, input: String, output: String, degree: u32) -> Result<()> {
+        let input_path = PathBuf::from(input);
+        let output_path = PathBuf::from(output);
+
+        let (mut cloud, encoding) = splat_ops::load_cloud(&input_path)?;
+        let original = cloud.sh_degree;
+        if degree > original {
+            warn!("{} has SH degree {}; zero-padding to degree {} adds no detail", input_path.display(), original, degree);
         }
-        
-        info!("Converted {} vertices", vertex_count);
+        cloud.set_sh_degree(degree);
+        cloud.comments.push(format!("hylaeansplat SH degree {} -> {}", original, degree));
+        splat_ops::save_cloud(&cloud, &output_path, encoding)?;
+
+        println!("SH degree {} -> {}: {} coefficients per Gaussian", original, degree, 3 + gaussian::sh_rest_len(degree));
         Ok(())
     }
-    
-    async fn ply_to_pcd(&self, input_path: &Path, output_path: &Path) -> Result<()> {
-        info!("Converting PLY to PCD: {} -> {}", input_path.display(), output_path.display());
-        
-        let file = File::open(input_path)?;
-        let reader = BufReader::new(file);
-        let mut output_file = File::create(output_path)?;
-        
-        let mut in_vertex_data = false;
-        let mut vertex_count = 0;
-        let mut processed_vertices = 0;
-        let mut vertices = Vec::new();
-        
-        // Read PLY and collect vertices
-        for line in reader.lines() {
-            let line = line?;
-            
-            if line.starts_with("element vertex") {
-                vertex_count = line.split_whitespace()
-                    .nth(2)
-                    .and_then(|s| s.parse::<usize>().ok())
-                    .unwrap_or(0);
-                continue;
-            }
-            
-            if line == "end_header" {
-                in_vertex_data = true;
-                continue;
+
+    /// Write Gaussian centers with their DC color as 8-bit RGB in a plain point cloud format
+    pub async fn bake_splat(&self, input: String, output: String, encoding: Option<String>, alpha: bool) -> Result<()> {
+        let input_path = PathBuf::from(input);
+        let output_path = PathBuf::from(output);
+        let format = match formats::detect_format(&output_path)? {
+            DataFormat::PointCloud(format) if stream::is_streamable(&format) => format,
+            other => {
+                return Err(HylaeanError::UnsupportedFormat {
+                    format: format!("{:?} (baked point cloud output)", other),
+                });
             }
-            
-            if in_vertex_data && processed_vertices < vertex_count {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 3 {
-                    vertices.push(line.clone());
-                    processed_vertices += 1;
-                }
+        };
+
+        let (cloud, ply_encoding) = splat_ops::load_cloud(&input_path)?;
+        let mut options = SinkOptions {
+            ply_encoding,
+            pcd_data: PcdData::Binary,
+        };
+        if let Some(encoding) = encoding {
+            match format {
+                PointCloudFormat::PCD => options.pcd_data = pcd::parse_data(&encoding)?,
+                _ => options.ply_encoding = ply::parse_encoding(&encoding)?,
             }
         }
-        
-        // Write PCD header
-        writeln!(output_file, "# .PCD v0.7 - Point Cloud Data file format")?;
-        writeln!(output_file, "VERSION 0.7")?;
-        writeln!(output_file, "FIELDS x y z")?;
-        writeln!(output_file, "SIZE 4 4 4")?;
-        writeln!(output_file, "TYPE F F F")?;
-        writeln!(output_file, "COUNT 1 1 1")?;
-        writeln!(output_file, "WIDTH {}", vertices.len())?;
-        writeln!(output_file, "HEIGHT 1")?;
-        writeln!(output_file, "VIEWPOINT 0 0 0 1 0 0 0")?;
-        writeln!(output_file, "POINTS {}", vertices.len())?;
-        writeln!(output_file, "DATA ascii")?;
-        
-        // Write vertex data
-        for vertex in vertices {
-            writeln!(output_file, "{}", vertex)?;
+
+        let mut source = GaussianSource::new(cloud, alpha);
+        let sink = stream::create_sink(&output_path, &format, &source, options)?;
+        let written = stream::pump(&mut source, sink, stream::DEFAULT_CHUNK_SIZE)?;
+        println!("Baked {} Gaussians to colored points in {}", written, output_path.display());
+        Ok(())
+    }
+
+    /// Transform a Gaussian scene (positions, orientations, scales and SH) or stream a
+    /// plain point cloud through the transform
+    pub async fn transform_file(&self, input: String, output: String, transform: Similarity) -> Result<()> {
+        let input_path = PathBuf::from(input);
+        let output_path = PathBuf::from(output);
+        let matrix = transform.to_matrix();
+        if splat_ops::is_gaussian_file(&input_path)? {
+            let (mut cloud, encoding) = splat_ops::load_cloud(&input_path)?;
+            transform::transform_cloud(&mut cloud, &transform);
+            cloud.comments.push(format!("hylaeansplat transform {:?}", matrix));
+            splat_ops::save_cloud(&cloud, &output_path, encoding)?;
+            info!("Transformed {} Gaussians (SH degree {})", cloud.len(), cloud.sh_degree);
+        } else {
+            let (from, to) = point_cloud_formats(&input_path, &output_path)?;
+            let point_count = transform::transform_points(&input_path, &from, &output_path, &to, &transform)?;
+            info!("Transformed {} points", point_count);
         }
-        
-        info!("Converted {} vertices", processed_vertices);
+
+        println!("Applied {:?}", matrix);
         Ok(())
     }
-    
-    async fn pcd_to_ply(&self, input_path: &Path, output_path: &Path) -> Result<()> {
-        info!("Converting PCD to PLY: {} -> {}", input_path.display(), output_path.display());
-        
-        let file = File::open(input_path)?;
-        let reader = BufReader::new(file);
-        let mut output_file = File::create(output_path)?;
-        
-        let mut in_data = false;
-        let mut vertex_count = 0;
-        let mut vertices = Vec::new();
-        
-        // Read PCD and collect vertices
-        for line in reader.lines() {
-            let line = line?;
-            
-            if line.starts_with("POINTS") {
-                vertex_count = line.split_whitespace()
-                    .nth(1)
-                    .and_then(|s| s.parse::<usize>().ok())
-                    .unwrap_or(0);
-                continue;
-            }
-            
-            if line == "DATA ascii" {
-                in_data = true;
-                continue;
-            }
-            
-            if in_data && !line.trim().is_empty() {
-                vertices.push(line.clone());
-            }
+
+    /// Estimate the similarity taking the input cameras into the reference's world from
+    /// the images both contain, and apply it
+    pub async fn align_cameras(&self, files: CameraSetFiles, reference: String, reference_format: Option<String>, options: AlignOptions) -> Result<()> {
+        let converter = CameraParamsConverter::new();
+        let reference_path = PathBuf::from(reference);
+        let (_, reference_images, _) = converter.read_model(&reference_path, &camera_format(&reference_path, reference_format)?)?;
+        let (input_path, input_format, output_format) = camera_input(&files)?;
+        let (cameras, images, points) = converter.read_model(&input_path, &input_format)?;
+
+        let (source, target) = align::matched_centers(&images, &reference_images);
+        info!("{} of {} images are in the reference's {}", source.len(), images.len(), reference_images.len());
+        let alignment = align::align(&source, &target, &options)?;
+        println!(
+            "Matched {} images, {} inliers, RMS center error {:.6}",
+            alignment.matched, alignment.inliers, alignment.rms_error
+        );
+        self.move_camera_set(&files, &output_format, cameras, images, points, &alignment.transform).await
+    }
+
+    /// Center the cameras and scale them into a sphere of `radius`, optionally turning the
+    /// mean camera up direction to +z first
+    pub async fn normalize_cameras(&self, files: CameraSetFiles, orient_up: bool, radius: f64) -> Result<()> {
+        let (input_path, input_format, output_format) = camera_input(&files)?;
+        let (cameras, images, points) = CameraParamsConverter::new().read_model(&input_path, &input_format)?;
+        let transform = align::normalize(&images, orient_up, radius)?;
+        self.move_camera_set(&files, &output_format, cameras, images, points, &transform).await
+    }
+
+    async fn move_camera_set(
+        &self,
+        files: &CameraSetFiles,
+        output_format: &CameraFormat,
+        cameras: Vec<ColmapCamera>,
+        mut images: Vec<ColmapImage>,
+        mut points: Vec<ColmapPoint3D>,
+        transform: &Similarity,
+    ) -> Result<()> {
+        align::transform_model(&mut images, &mut points, transform);
+        let mut converter = CameraParamsConverter::new();
+        if let Some(encoding) = &files.encoding {
+            converter = converter.with_colmap_encoding(ModelEncoding::parse(encoding)?);
         }
-        
-        // Write PLY header
-        writeln!(output_file, "ply")?;
-        writeln!(output_file, "format ascii 1.0")?;
-        writeln!(output_file, "element vertex {}", vertices.len())?;
-        writeln!(output_file, "property float x")?;
-        writeln!(output_file, "property float y")?;
-        writeln!(output_file, "property float z")?;
-        writeln!(output_file, "end_header")?;
-        
-        // Write vertex data
-        for vertex in vertices {
-            writeln!(output_file, "{}", vertex)?;
+        let point_count = points.len();
+        converter.write_model(Path::new(&files.output), output_format, &cameras, &images, points)?;
+        info!("Wrote {} images and {} points as {:?} to {}", images.len(), point_count, output_format, files.output);
+
+        let matrix = transform.to_matrix();
+        let rows: Vec<String> = matrix.iter()
+            .map(|row| row.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(" "))
+            .collect();
+        if let Some(path) = &files.transform_output {
+            std::fs::write(path, rows.join("\n") + "\n")?;
+            info!("Wrote the transform to {}", path);
         }
-        
-        info!("Converted {} vertices", vertex_count);
+        if let Some((input, output)) = &files.point_cloud {
+            self.transform_file(input.clone(), output.clone(), transform.clone()).await?;
+        }
+        println!("Transform (row-major, scale {}):\n{}", transform.scale, rows.join("\n"));
         Ok(())
     }
-    
-    async fn xyz_to_pcd(&self, input_path: &Path, output_path: &Path) -> Result<()> {
-        info!("Converting XYZ to PCD: {} -> {}", input_path.display(), output_path.display());
-        
-        let file = File::open(input_path)?;
-        let reader = BufReader::new(file);
-        
-        // First pass: count vertices
-        let vertex_count = reader.lines().count();
-        
-        // Second pass: write PCD
-        let file = File::open(input_path)?;
-        let reader = BufReader::new(file);
-        let mut output_file = File::create(output_path)?;
-        
-        // Write PCD header
-        writeln!(output_file, "# .PCD v0.7 - Point Cloud Data file format")?;
-        writeln!(output_file, "VERSION 0.7")?;
-        writeln!(output_file, "FIELDS x y z")?;
-        writeln!(output_file, "SIZE 4 4 4")?;
-        writeln!(output_file, "TYPE F F F")?;
-        writeln!(output_file, "COUNT 1 1 1")?;
-        writeln!(output_file, "WIDTH {}", vertex_count)?;
-        writeln!(output_file, "HEIGHT 1")?;
-        writeln!(output_file, "VIEWPOINT 0 0 0 1 0 0 0")?;
-        writeln!(output_file, "POINTS {}", vertex_count)?;
-        writeln!(output_file, "DATA ascii")?;
-        
-        // Write vertex data
-        for line in reader.lines() {
-            let line = line?;
-            if !line.trim().is_empty() {
-                writeln!(output_file, "{}", line)?;
-            }
+
+    /// Reduce a splat by voxel merging or importance, or a point cloud by voxel averaging
+    pub async fn downsample_file(&self, input: String, output: String, voxel_size: Option<f32>, target: Option<usize>) -> Result<()> {
+        let input_path = PathBuf::from(input);
+        let output_path = PathBuf::from(output);
+
+        if splat_ops::is_gaussian_file(&input_path)? {
+            let (mut cloud, encoding) = splat_ops::load_cloud(&input_path)?;
+            let before = cloud.len();
+            let description = match (voxel_size, target) {
+                (Some(voxel_size), _) => {
+                    downsample::voxel_downsample(&mut cloud, voxel_size)?;
+                    format!("voxel merge at size {}", voxel_size)
+                }
+                (None, Some(target)) => {
+                    downsample::importance_downsample(&mut cloud, target);
+                    format!("the {} most important", target)
+                }
+                (None, None) => return Err(missing_downsample_method()),
+            };
+            cloud.comments.push(format!("hylaeansplat downsampled {} Gaussians to {}: {}", before, cloud.len(), description));
+            splat_ops::save_cloud(&cloud, &output_path, encoding)?;
+            println!("Downsampled {} Gaussians to {} ({})", before, cloud.len(), description);
+        } else {
+            let Some(voxel_size) = voxel_size else {
+                return Err(match target {
+                    Some(_) => HylaeanError::ConfigError {
+                        message: "importance downsampling needs a Gaussian splat input; use --voxel-size for point clouds".to_string(),
+                    },
+                    None => missing_downsample_method(),
+                });
+            };
+            let (from, to) = point_cloud_formats(&input_path, &output_path)?;
+            let written = downsample::voxel_downsample_points(&input_path, &from, &output_path, &to, voxel_size)?;
+            println!("Downsampled to {} points at voxel size {}", written, voxel_size);
         }
-        
-        info!("Converted {} vertices", vertex_count);
         Ok(())
     }
-    
-    async fn pcd_to_xyz(&self, input_path: &Path, output_path: &Path) -> Result<()> {
-        info!("Converting PCD to XYZ: {} -> {}", input_path.display(), output_path.display());
-        
-        let file = File::open(input_path)?;
-        let reader = BufReader::new(file);
-        let mut output_file = File::create(output_path)?;
-        
-        let mut in_data = false;
-        
-        for line in reader.lines() {
-            let line = line?;
-            
-            if line == "DATA ascii" {
-                in_data = true;
-                continue;
-            }
-            
-            if in_data && !line.trim().is_empty() {
-                writeln!(output_file, "{}", line)?;
-            }
+
+    /// Write a chain of progressively coarser versions of a splat and a JSON manifest
+    pub async fn build_lod(&self, input: String, output_dir: String, options: LodOptions, format: Option<String>) -> Result<()> {
+        let input_path = PathBuf::from(input);
+        let output_dir = PathBuf::from(output_dir);
+
+        let (cloud, encoding) = splat_ops::load_cloud(&input_path)?;
+        let extension = output_extension(&input_path, format);
+        let manifest = lod::build_lod_chain(&cloud, &input_path, &output_dir, &options, &extension, encoding)?;
+        for level in &manifest.levels {
+            println!("  LOD {}  {:>10} Gaussians  {:>12} bytes  {}", level.level, level.gaussians, level.bytes, level.file);
         }
-        
-        info!("PCD to XYZ conversion completed");
         Ok(())
     }
-    
-    async fn convert_camera_params(
+
+    /// Split a splat into spatial tiles with per-tile LOD levels and a tileset index
+    pub async fn tile_splat(
         &self,
-        input_path: &Path,
-        output_path: &Path,
-        input_format: &CameraFormat,
-        output_format: &CameraFormat,
+        input: String,
+        output_dir: String,
+        scheme: TilingScheme,
+        lod: LodOptions,
+        format: Option<String>,
     ) -> Result<()> {
-        info!("Converting camera parameters: {:?} -> {:?}", input_format, output_format);
-        
-        match (input_format, output_format) {
-            (CameraFormat::COLMAP, CameraFormat::NeRF) => {
-                self.colmap_to_nerf(input_path, output_path).await?;
-            }
-            (CameraFormat::NeRF, CameraFormat::COLMAP) => {
-                self.nerf_to_colmap(input_path, output_path).await?;
-            }
-            _ => {
-                return Err(HylaeanError::ConversionFailed {
-                    source_format: format!("{:?}", input_format),
-                    target_format: format!("{:?}", output_format),
-                });
-            }
-        }
-        
+        let input_path = PathBuf::from(input);
+        let output_dir = PathBuf::from(output_dir);
+
+        let (cloud, encoding) = splat_ops::load_cloud(&input_path)?;
+        let extension = output_extension(&input_path, format);
+        let tileset = tiles::tile_cloud(&cloud, &input_path, &output_dir, scheme, &lod, &extension, encoding)?;
+
+        let largest = tileset.tiles.iter().map(|tile| tile.gaussians).max().unwrap_or(0);
+        println!(
+            "Split {} Gaussians into {} tiles (largest {}), index: {}",
+            tileset.gaussians,
+            tileset.tiles.len(),
+            largest,
+            output_dir.join(tiles::TILESET_FILE).display()
+        );
         Ok(())
     }
-    
-    async fn colmap_to_nerf(&self, input_path: &Path, output_path: &Path) -> Result<()> {
-        info!("Converting COLMAP to NeRF format");
-        // TODO: Implement COLMAP to NeRF conversion
-        warn!("COLMAP to NeRF conversion not yet implemented");
-        Ok(())
+}
+
+/// Point cloud formats of a streaming input and output
+fn point_cloud_formats(input_path: &Path, output_path: &Path) -> Result<(PointCloudFormat, PointCloudFormat)> {
+    match (formats::detect_format(input_path)?, formats::detect_format(output_path)?) {
+        (DataFormat::PointCloud(from), DataFormat::PointCloud(to)) => Ok((from, to)),
+        (from, to) => Err(HylaeanError::ConversionFailed {
+            source_format: format!("{:?}", from),
+            target_format: format!("{:?}", to),
+        }),
     }
-    
-    async fn nerf_to_colmap(&self, input_path: &Path, output_path: &Path) -> Result<()> {
-        info!("Converting NeRF to COLMAP format");
-        // TODO: Implement NeRF to COLMAP conversion
-        warn!("NeRF to COLMAP conversion not yet implemented");
-        Ok(())
+}
+
+/// The camera format of `path`, named or detected
+fn camera_format(path: &Path, format: Option<String>) -> Result<CameraFormat> {
+    let format = match format {
+        Some(format) => formats::parse_format(&format)?,
+        None => formats::detect_format(path)?,
+    };
+    match format {
+        DataFormat::CameraParameters(format) => Ok(format),
+        other => Err(HylaeanError::UnsupportedFormat {
+            format: format!("{:?} as a camera set ({})", other, path.display()),
+        }),
+    }
+}
+
+/// The input camera set, its format and the format to write it back in. Only COLMAP and
+/// NeRF are written, so other inputs default to COLMAP, and any other output is rejected
+/// before the input is read.
+fn camera_input(files: &CameraSetFiles) -> Result<(PathBuf, CameraFormat, CameraFormat)> {
+    let path = PathBuf::from(&files.input);
+    let format = camera_format(&path, files.input_format.clone())?;
+    let output_format = match &files.output_format {
+        Some(output_format) => camera_format(Path::new(&files.output), Some(output_format.clone()))?,
+        None if matches!(format, CameraFormat::NeRF) => CameraFormat::NeRF,
+        None => CameraFormat::COLMAP,
+    };
+    if !matches!(output_format, CameraFormat::COLMAP | CameraFormat::NeRF) {
+        return Err(HylaeanError::UnsupportedFormat {
+            format: format!("{:?} camera set output (COLMAP and NeRF are written)", output_format),
+        });
     }
-}
\ No newline at end of file
+    Ok((path, format, output_format))
+}
+
+/// Extension for generated splat files: `format` if given, otherwise the input's
+fn output_extension(input_path: &Path, format: Option<String>) -> String {
+    match format {
+        Some(format) => format.trim_start_matches('.').to_lowercase(),
+        None => input_path.extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| "ply".to_string()),
+    }
+}
+
+fn missing_downsample_method() -> HylaeanError {
+    HylaeanError::ConfigError {
+        message: "downsampling needs --voxel-size or --target".to_string(),
+    }
+}
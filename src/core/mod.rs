@@ -1,5 +1,11 @@
 use crate::errors::{Result, HylaeanError};
 use crate::config::Config;
+use crate::formats::camera_params::align::AlignOptions;
+use crate::formats::splat_ops::edit::EditOperation;
+use crate::formats::point_cloud::tiles::TilingScheme;
+use crate::formats::splat_ops::lod::LodOptions;
+use crate::formats::splat_ops::merge::{MergeInput, MergeOptions};
+use crate::formats::splat_ops::transform::Similarity;
 use sled::Db;
 use std::path::PathBuf;
 use std::collections::HashMap;
@@ -12,7 +18,7 @@ pub mod data_manager;
 pub mod agent;
 
 pub use tool_manager::ToolManager;
-pub use data_manager::DataManager;
+pub use data_manager::{CameraSetFiles, DataManager};
 pub use agent::Agent;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -128,8 +134,60 @@ impl HylaeanSplat {
         output: String,
         input_format: Option<String>,
         output_format: String,
+        encoding: Option<String>,
     ) -> Result<()> {
-        self.data_manager.convert_file(input, output, input_format, output_format).await
+        self.data_manager.convert_file(input, output, input_format, output_format, encoding).await
+    }
+    
+    pub async fn edit_splat(&self, input: String, output: String, operations: Vec<EditOperation>) -> Result<()> {
+        self.data_manager.edit_splat(input, output, operations).await
+    }
+    
+    pub async fn merge_splats(&self, inputs: Vec<MergeInput>, output: String, options: MergeOptions) -> Result<()> {
+        self.data_manager.merge_splats(inputs, output, options).await
+    }
+    
+    pub async fn set_sh_degree(&self, input: String, output: String, degree: u32) -> Result<()> {
+        self.data_manager.set_sh_degree(input, output, degree).await
+    }
+    
+    pub async fn bake_splat(&self, input: String, output: String, encoding: Option<String>, alpha: bool) -> Result<()> {
+        self.data_manager.bake_splat(input, output, encoding, alpha).await
+    }
+    
+    pub async fn downsample_file(&self, input: String, output: String, voxel_size: Option<f32>, target: Option<usize>) -> Result<()> {
+        self.data_manager.downsample_file(input, output, voxel_size, target).await
+    }
+    
+    pub async fn build_lod(&self, input: String, output_dir: String, options: LodOptions, format: Option<String>) -> Result<()> {
+        self.data_manager.build_lod(input, output_dir, options, format).await
+    }
+    
+    pub async fn tile_splat(
+        &self,
+        input: String,
+        output_dir: String,
+        scheme: TilingScheme,
+        lod: LodOptions,
+        format: Option<String>,
+    ) -> Result<()> {
+        self.data_manager.tile_splat(input, output_dir, scheme, lod, format).await
+    }
+    
+    pub async fn transform_file(&self, input: String, output: String, transform: Similarity) -> Result<()> {
+        self.data_manager.transform_file(input, output, transform).await
+    }
+    
+    pub async fn align_cameras(&self, files: CameraSetFiles, reference: String, reference_format: Option<String>, options: AlignOptions) -> Result<()> {
+        self.data_manager.align_cameras(files, reference, reference_format, options).await
+    }
+    
+    pub async fn normalize_cameras(&self, files: CameraSetFiles, orient_up: bool, radius: f64) -> Result<()> {
+        self.data_manager.normalize_cameras(files, orient_up, radius).await
+    }
+    
+    pub async fn inspect_file(&self, input: String, input_format: Option<String>, json: bool) -> Result<()> {
+        self.data_manager.inspect_file(input, input_format, json).await
     }
     
     pub async fn execute_workflow(
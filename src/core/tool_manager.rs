@@ -1,6 +1,6 @@
 use crate::errors::{Result, HylaeanError};
 use crate::core::{ToolEntry, ToolCapabilities, InstallationMethod};
-use crate::integrations::{Integration, colmap, brush_app};
+use crate::integrations::{Integration, colmap, brush_app, skysplat};
 use sled::Db;
 use std::path::PathBuf;
 use std::process::Command;
@@ -575,6 +575,10 @@ impl ToolManager {
                 let brush = brush_app::BrushApp::new();
                 brush.run_command(command, &command_args.to_vec())
             }
+            "skysplat" => {
+                let skysplat = skysplat::SkySplat::new();
+                skysplat.run_command(command, command_args)
+            }
             _ => {
                 // Try to find the tool in the registry and run it generically
                 self.run_external_tool(name, args).await
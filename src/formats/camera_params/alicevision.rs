@@ -0,0 +1,292 @@
+//! AliceVision SfMData JSON (`.sfm`), as Meshroom writes `cameras.sfm`
+//!
+//! Numbers are usually written as strings. Views tie an image `path` to an intrinsic and a
+//! pose; poses hold the world-to-camera `rotation` (9 values in Eigen's column order) and
+//! the camera `center`, with OpenCV camera axes. Intrinsics give the focal length in pixels
+//! (`pxFocalLength`, before SfMData 1.2) or in millimetres over `sensorWidth`, measured
+//! along the larger image dimension, with `pixelRatio` = fx / fy. The principal point is
+//! absolute before 1.2.1 and an offset from the image center since. `structure` lists the
+//! landmarks with their color and per-view feature observations in pixels.
+
+use crate::errors::{Result, HylaeanError};
+use super::camera_model::{CameraModel, OpenCvDistortion};
+use super::colmap::{ColmapCamera, ColmapImage, ColmapPoint2D, ColmapPoint3D, TrackElement};
+use super::pose::Pose;
+use log::warn;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// A number or id, as a JSON number or string
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Scalar {
+    Number(serde_json::Number),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Scalars {
+    One(Scalar),
+    Many(Vec<Scalar>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SfmData {
+    #[serde(default)]
+    pub version: Vec<Scalar>,
+    #[serde(default)]
+    pub views: Vec<View>,
+    #[serde(default)]
+    pub intrinsics: Vec<Intrinsic>,
+    #[serde(default)]
+    pub poses: Vec<PoseEntry>,
+    #[serde(default)]
+    pub structure: Vec<Landmark>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct View {
+    pub view_id: Scalar,
+    pub pose_id: Scalar,
+    pub intrinsic_id: Scalar,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Intrinsic {
+    pub intrinsic_id: Scalar,
+    pub width: Scalar,
+    pub height: Scalar,
+    #[serde(rename = "type")]
+    pub model: String,
+    #[serde(default)]
+    pub px_focal_length: Option<Scalars>,
+    #[serde(default)]
+    pub focal_length: Option<Scalar>,
+    #[serde(default)]
+    pub sensor_width: Option<Scalar>,
+    #[serde(default)]
+    pub pixel_ratio: Option<Scalar>,
+    pub principal_point: Vec<Scalar>,
+    #[serde(default)]
+    pub distortion_params: Vec<Scalar>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoseEntry {
+    pub pose_id: Scalar,
+    pub pose: PoseTransform,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PoseTransform {
+    pub transform: Transform,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transform {
+    pub rotation: Vec<Scalar>,
+    pub center: Vec<Scalar>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Landmark {
+    #[serde(default)]
+    pub color: Vec<Scalar>,
+    #[serde(rename = "X")]
+    pub position: Vec<Scalar>,
+    #[serde(default)]
+    pub observations: Vec<Observation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Observation {
+    pub observation_id: Scalar,
+    pub x: Vec<Scalar>,
+}
+
+impl Scalar {
+    /// The value as written, for matching ids
+    pub fn key(&self) -> String {
+        match self {
+            Scalar::Number(number) => number.to_string(),
+            Scalar::Text(text) => text.trim().to_string(),
+        }
+    }
+
+    pub fn number(&self, what: &str) -> Result<f64> {
+        let value = match self {
+            Scalar::Number(number) => number.as_f64(),
+            Scalar::Text(text) => text.trim().parse().ok(),
+        };
+        value.ok_or_else(|| HylaeanError::ConfigError {
+            message: format!("AliceVision {} {:?} is not a number", what, self.key()),
+        })
+    }
+}
+
+fn numbers<const N: usize>(values: &[Scalar], what: &str) -> Result<[f64; N]> {
+    if values.len() != N {
+        return Err(HylaeanError::ConfigError {
+            message: format!("AliceVision {} has {} values, expected {}", what, values.len(), N),
+        });
+    }
+    let mut result = [0.0; N];
+    for (target, value) in result.iter_mut().zip(values) {
+        *target = value.number(what)?;
+    }
+    Ok(result)
+}
+
+impl SfmData {
+    pub fn read(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    /// Whether the SfMData version is at least `major.minor.patch`; files without one are old
+    fn version_at_least(&self, minimum: [u32; 3]) -> bool {
+        let version: Vec<u32> = self.version.iter().map(|part| part.key().parse().unwrap_or(0)).collect();
+        version.len() == 3 && version.as_slice() >= minimum.as_slice()
+    }
+
+    /// COLMAP cameras, images and points3D of the posed views. Image names are the view
+    /// paths relative to `base_dir` when they lie below it, their file names otherwise.
+    pub fn to_colmap(&self, base_dir: &Path) -> Result<(Vec<ColmapCamera>, Vec<ColmapImage>, Vec<ColmapPoint3D>)> {
+        let mut cameras = Vec::with_capacity(self.intrinsics.len());
+        let mut camera_ids = HashMap::new();
+        for intrinsic in &self.intrinsics {
+            let (model, width, height) = self.intrinsic_model(intrinsic)?;
+            let camera_id = cameras.len() as u32 + 1;
+            camera_ids.insert(intrinsic.intrinsic_id.key(), camera_id);
+            cameras.push(ColmapCamera::from_model(camera_id, width, height, &model));
+        }
+
+        let mut poses = HashMap::new();
+        for entry in &self.poses {
+            let transform = &entry.pose.transform;
+            // The world-to-camera rotation in column order is its transpose, camera-to-world, in row order
+            let values: [f64; 9] = numbers(&transform.rotation, "rotation")?;
+            let rotation = [0, 1, 2].map(|r| [values[r * 3], values[r * 3 + 1], values[r * 3 + 2]]);
+            poses.insert(entry.pose_id.key(), Pose::new(rotation, numbers(&transform.center, "center")?));
+        }
+
+        let mut images = Vec::new();
+        let mut image_index = HashMap::new();
+        for view in &self.views {
+            let Some(camera_to_world) = poses.get(&view.pose_id.key()) else { continue };
+            let camera_id = *camera_ids.get(&view.intrinsic_id.key()).ok_or_else(|| HylaeanError::ConfigError {
+                message: format!("AliceVision view {} refers to missing intrinsic {}", view.view_id.key(), view.intrinsic_id.key()),
+            })?;
+            let image_id = images.len() as u32 + 1;
+            image_index.insert(view.view_id.key(), images.len());
+            images.push(ColmapImage::new(image_id, camera_id, image_name(&view.path, base_dir), &camera_to_world.inverse()));
+        }
+        let unposed = self.views.len() - images.len();
+        if unposed > 0 {
+            warn!("Skipping {} AliceVision views without a pose", unposed);
+        }
+
+        let mut points = Vec::with_capacity(self.structure.len());
+        for landmark in &self.structure {
+            let point3d_id = points.len() as u64 + 1;
+            let mut track = Vec::with_capacity(landmark.observations.len());
+            for observation in &landmark.observations {
+                let Some(&index) = image_index.get(&observation.observation_id.key()) else { continue };
+                let [x, y] = numbers(&observation.x, "observation")?;
+                let image = &mut images[index];
+                track.push(TrackElement { image_id: image.image_id, point2d_idx: image.points2d.len() as u32 });
+                image.points2d.push(ColmapPoint2D { x, y, point3d_id: point3d_id as i64 });
+            }
+            if track.is_empty() {
+                continue;
+            }
+            let rgb = if landmark.color.is_empty() {
+                [0; 3]
+            } else {
+                numbers::<3>(&landmark.color, "color")?.map(|c| c.clamp(0.0, 255.0) as u8)
+            };
+            points.push(ColmapPoint3D {
+                point3d_id,
+                xyz: numbers(&landmark.position, "landmark")?,
+                rgb,
+                error: 0.0,
+                track,
+            });
+        }
+        Ok((cameras, images, points))
+    }
+
+    /// The COLMAP camera of an intrinsic and the image size it applies to
+    fn intrinsic_model(&self, intrinsic: &Intrinsic) -> Result<(CameraModel, u32, u32)> {
+        let width = intrinsic.width.number("width")?;
+        let height = intrinsic.height.number("height")?;
+        let pixel_ratio = match &intrinsic.pixel_ratio {
+            Some(ratio) => ratio.number("pixelRatio")?,
+            None => 1.0,
+        };
+        let (fx, fy) = match (&intrinsic.px_focal_length, &intrinsic.focal_length, &intrinsic.sensor_width) {
+            (Some(Scalars::One(focal)), _, _) => {
+                let focal = focal.number("pxFocalLength")?;
+                (focal, focal / pixel_ratio)
+            }
+            (Some(Scalars::Many(focal)), _, _) => {
+                let [fx, fy] = numbers(focal, "pxFocalLength")?;
+                (fx, fy)
+            }
+            (None, Some(focal), Some(sensor)) => {
+                let fx = focal.number("focalLength")? / sensor.number("sensorWidth")? * width.max(height);
+                (fx, fx / pixel_ratio)
+            }
+            _ => {
+                return Err(HylaeanError::ConfigError {
+                    message: format!("AliceVision intrinsic {} has no focal length", intrinsic.intrinsic_id.key()),
+                })
+            }
+        };
+        let [mut cx, mut cy] = numbers(&intrinsic.principal_point, "principalPoint")?;
+        if self.version_at_least([1, 2, 1]) {
+            cx += width / 2.0;
+            cy += height / 2.0;
+        }
+
+        let d = intrinsic.distortion_params.iter()
+            .map(|value| value.number("distortionParams"))
+            .collect::<Result<Vec<f64>>>()?;
+        let term = |i: usize| d.get(i).copied().unwrap_or(0.0);
+        let distortion = match intrinsic.model.as_str() {
+            "pinhole" => OpenCvDistortion::Standard(Vec::new()),
+            "radial1" => OpenCvDistortion::Standard(vec![term(0)]),
+            "radial3" => OpenCvDistortion::Standard(vec![term(0), term(1), 0.0, 0.0, term(2)]),
+            // k1, k2, k3, t1, t2
+            "brown" => OpenCvDistortion::Standard(vec![term(0), term(1), term(3), term(4), term(2)]),
+            "fisheye4" => OpenCvDistortion::Fisheye([term(0), term(1), term(2), term(3)]),
+            other => {
+                return Err(HylaeanError::UnsupportedFormat {
+                    format: format!("AliceVision {} camera (COLMAP has no equivalent)", other),
+                })
+            }
+        };
+        let model = CameraModel::from_opencv(fx, fy, cx, cy, &distortion)?;
+        Ok((model, width.round() as u32, height.round() as u32))
+    }
+}
+
+fn image_name(path: &str, base_dir: &Path) -> String {
+    let path = Path::new(path);
+    match path.strip_prefix(base_dir) {
+        Ok(relative) if path.is_absolute() == base_dir.is_absolute() && !base_dir.as_os_str().is_empty() => {
+            relative.to_string_lossy().into_owned()
+        }
+        _ => path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+    }
+}
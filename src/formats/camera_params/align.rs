@@ -0,0 +1,314 @@
+//! Aligning and normalizing camera sets
+//!
+//! Two reconstructions of the same images (a COLMAP rerun, ARKit poses next to SfM) live
+//! in different gauges: worlds related by an unknown similarity. [`align`] estimates it
+//! from the camera centers of images with matching names, with Umeyama's closed-form
+//! least-squares fit, optionally inside RANSAC to ignore badly registered images.
+//! [`normalize`] instead centers the cameras and scales them into a sphere, as nerfstudio
+//! does before training.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::math::{self, Matrix3};
+use crate::formats::splat_ops::transform::Similarity;
+use super::colmap::{ColmapImage, ColmapPoint3D};
+use super::pose::Pose;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// How [`align`] fits the transform
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlignOptions {
+    /// Estimate a scale; otherwise the transform is rigid
+    pub with_scale: bool,
+    pub ransac: Option<Ransac>,
+}
+
+impl Default for AlignOptions {
+    fn default() -> Self {
+        Self { with_scale: true, ransac: None }
+    }
+}
+
+/// RANSAC over minimal samples of three camera pairs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ransac {
+    /// Largest distance between an aligned camera center and its reference, in reference units
+    pub threshold: f64,
+    pub iterations: usize,
+}
+
+/// An estimated alignment and how well it fits
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment {
+    pub transform: Similarity,
+    /// Images present in both sets
+    pub matched: usize,
+    /// Matched images the final fit used
+    pub inliers: usize,
+    /// Root mean square distance between aligned and reference centers of the inliers
+    pub rms_error: f64,
+}
+
+/// Camera centers of the images in both sets, paired by image name. Names that differ
+/// only in their directory still pair when the file name is unique in both sets.
+pub fn matched_centers(source: &[ColmapImage], reference: &[ColmapImage]) -> (Vec<[f64; 3]>, Vec<[f64; 3]>) {
+    let file_name = |name: &str| Path::new(name).file_name().map(|n| n.to_string_lossy().into_owned());
+    let unique_file_names = |images: &[ColmapImage]| {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for name in images.iter().filter_map(|image| file_name(&image.name)) {
+            *counts.entry(name).or_default() += 1;
+        }
+        counts
+    };
+    let source_counts = unique_file_names(source);
+    let reference_counts = unique_file_names(reference);
+    let by_name: HashMap<&str, &ColmapImage> = reference.iter().map(|image| (image.name.as_str(), image)).collect();
+    let by_file_name: HashMap<String, &ColmapImage> = reference.iter()
+        .filter_map(|image| Some((file_name(&image.name)?, image)))
+        .filter(|(name, _)| reference_counts.get(name) == Some(&1))
+        .collect();
+
+    let mut from = Vec::new();
+    let mut to = Vec::new();
+    for image in source {
+        let matched = by_name.get(image.name.as_str()).copied().or_else(|| {
+            let name = file_name(&image.name)?;
+            if source_counts.get(&name) != Some(&1) {
+                return None;
+            }
+            by_file_name.get(&name).copied()
+        });
+        if let Some(other) = matched {
+            from.push(image.camera_to_world().translation);
+            to.push(other.camera_to_world().translation);
+        }
+    }
+    (from, to)
+}
+
+/// The similarity taking the `source` camera centers onto the `reference` ones
+pub fn align(source: &[[f64; 3]], reference: &[[f64; 3]], options: &AlignOptions) -> Result<Alignment> {
+    if source.len() < 3 {
+        return Err(HylaeanError::ConfigError {
+            message: format!("alignment needs at least 3 images in both camera sets, found {}", source.len()),
+        });
+    }
+    let inliers = match options.ransac {
+        Some(ransac) => ransac_inliers(source, reference, options.with_scale, &ransac)?,
+        None => (0..source.len()).collect(),
+    };
+
+    let pick = |points: &[[f64; 3]]| inliers.iter().map(|&i| points[i]).collect::<Vec<_>>();
+    let transform = umeyama(&pick(source), &pick(reference), options.with_scale)?;
+    let squared: f64 = inliers.iter().map(|&i| math::distance_squared(transform.transform_point(source[i]), reference[i])).sum();
+    Ok(Alignment {
+        matched: source.len(),
+        inliers: inliers.len(),
+        rms_error: (squared / inliers.len() as f64).sqrt(),
+        transform,
+    })
+}
+
+/// Indices of the largest consensus set; ties go to the smaller total error
+fn ransac_inliers(source: &[[f64; 3]], reference: &[[f64; 3]], with_scale: bool, ransac: &Ransac) -> Result<Vec<usize>> {
+    if !ransac.threshold.is_finite() || ransac.threshold <= 0.0 {
+        return Err(HylaeanError::ConfigError {
+            message: format!("the RANSAC threshold must be positive, got {}", ransac.threshold),
+        });
+    }
+    let threshold_squared = ransac.threshold * ransac.threshold;
+    // A fixed seed keeps runs reproducible
+    let mut random = SplitMix64(0x5eed_a11c_e5e7_0001);
+    let n = source.len() as u64;
+    let mut best: Option<(Vec<usize>, f64)> = None;
+    for _ in 0..ransac.iterations.max(1) {
+        let a = random.below(n);
+        let b = random.below(n - 1);
+        let b = if b >= a { b + 1 } else { b };
+        let mut c = random.below(n - 2);
+        for taken in [a.min(b), a.max(b)] {
+            if c >= taken {
+                c += 1;
+            }
+        }
+        let sample = [a, b, c].map(|i| i as usize);
+        // Collinear samples fix no rotation
+        let Ok(model) = umeyama(&sample.map(|i| source[i]), &sample.map(|i| reference[i]), with_scale) else {
+            continue;
+        };
+
+        let mut inliers = Vec::new();
+        let mut error = 0.0;
+        for (i, (from, to)) in source.iter().zip(reference).enumerate() {
+            let squared = math::distance_squared(model.transform_point(*from), *to);
+            if squared <= threshold_squared {
+                inliers.push(i);
+                error += squared;
+            }
+        }
+        let better = match &best {
+            Some((best_inliers, best_error)) => {
+                inliers.len() > best_inliers.len() || (inliers.len() == best_inliers.len() && error < *best_error)
+            }
+            None => true,
+        };
+        if better {
+            best = Some((inliers, error));
+        }
+    }
+
+    match best {
+        Some((inliers, _)) if inliers.len() >= 3 => Ok(inliers),
+        _ => Err(HylaeanError::ConfigError {
+            message: format!(
+                "RANSAC found no transform bringing 3 camera centers within {} of the reference",
+                ransac.threshold
+            ),
+        }),
+    }
+}
+
+/// Umeyama's least-squares similarity `p -> s R p + t` taking `source` onto `target`
+/// (rigid when `with_scale` is false). The points must not all lie on a line.
+pub fn umeyama(source: &[[f64; 3]], target: &[[f64; 3]], with_scale: bool) -> Result<Similarity> {
+    let n = source.len().min(target.len()) as f64;
+    let source_mean = math::mean(source);
+    let target_mean = math::mean(target);
+    let mut covariance = [[0.0; 3]; 3];
+    let mut variance = 0.0;
+    for (from, to) in source.iter().zip(target) {
+        let from = math::sub(*from, source_mean);
+        let to = math::sub(*to, target_mean);
+        for (row, t) in covariance.iter_mut().zip(to) {
+            for (value, f) in row.iter_mut().zip(from) {
+                *value += t * f / n;
+            }
+        }
+        variance += math::dot(from, from) / n;
+    }
+
+    // covariance = U D V^T from the eigenvectors V of covariance^T covariance, with
+    // u3 = u1 x u2 so that U is a rotation even when the points are coplanar
+    let (eigenvalues, vectors) = math::symmetric_eigen(&math::multiply(&math::transpose(&covariance), &covariance));
+    let mut order = [0, 1, 2];
+    order.sort_by(|a, b| eigenvalues[*b].total_cmp(&eigenvalues[*a]));
+    let singular = order.map(|i| eigenvalues[i].max(0.0).sqrt());
+    let v = order.map(|i| [vectors[0][i], vectors[1][i], vectors[2][i]]);
+    if !variance.is_finite() || variance <= 0.0 || singular[1] <= 1e-9 * singular[0] {
+        return Err(HylaeanError::ConfigError {
+            message: "the camera centers are collinear, which leaves the rotation undetermined".to_string(),
+        });
+    }
+    let u0 = math::apply(&covariance, v[0]).map(|x| x / singular[0]);
+    let u1 = math::apply(&covariance, v[1]).map(|x| x / singular[1]);
+    let u2 = math::cross(u0, u1);
+
+    // R = U diag(1, 1, det V) V^T is the best proper rotation; the third singular value
+    // enters the scale with the same sign
+    let handedness = math::dot(v[0], math::cross(v[1], v[2])).signum();
+    let third = math::dot(u2, math::apply(&covariance, v[2])) * handedness;
+    let mut rotation: Matrix3 = [[0.0; 3]; 3];
+    for (i, row) in rotation.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = u0[i] * v[0][j] + u1[i] * v[1][j] + handedness * u2[i] * v[2][j];
+        }
+    }
+    let scale = if with_scale { (singular[0] + singular[1] + third) / variance } else { 1.0 };
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(HylaeanError::ConfigError {
+            message: format!("the camera centers give a degenerate scale {}", scale),
+        });
+    }
+
+    let moved = math::apply(&rotation, source_mean);
+    Ok(Similarity {
+        rotation,
+        scale,
+        translation: [0, 1, 2].map(|i| target_mean[i] - scale * moved[i]),
+    })
+}
+
+/// The similarity that centers the cameras on their mean position and scales them so the
+/// farthest lies at `radius`, as nerfstudio's "poses" centering and pose scaling do. With
+/// `orient_up` it first rotates the mean camera up direction onto +z.
+pub fn normalize(images: &[ColmapImage], orient_up: bool, radius: f64) -> Result<Similarity> {
+    if !radius.is_finite() || radius <= 0.0 {
+        return Err(HylaeanError::ConfigError {
+            message: format!("the normalization radius must be positive, got {}", radius),
+        });
+    }
+    let poses: Vec<Pose> = images.iter().map(ColmapImage::camera_to_world).collect();
+    let centers: Vec<[f64; 3]> = poses.iter().map(|pose| pose.translation).collect();
+
+    let rotation = if orient_up {
+        // COLMAP cameras look along +z with y down, so up is their -y axis
+        let up = poses.iter().fold([0.0; 3], |sum, pose| [0, 1, 2].map(|i| sum[i] - pose.rotation[i][1]));
+        rotation_onto(up, [0.0, 0.0, 1.0])
+    } else {
+        Similarity::default()
+    };
+    let center = math::mean(&centers);
+    let farthest = centers.iter().map(|c| math::distance_squared(*c, center).sqrt()).fold(0.0, f64::max);
+    if farthest <= 0.0 {
+        return Err(HylaeanError::ConfigError {
+            message: "normalization needs cameras at two or more positions".to_string(),
+        });
+    }
+    Ok(Similarity::from_translation(center.map(|c| -c))
+        .then(&rotation)
+        .then(&Similarity::from_scale(radius / farthest)?))
+}
+
+/// Move the cameras and points of a COLMAP model by `transform`. Camera orientations turn
+/// with the world; intrinsics and keypoints are unchanged.
+pub fn transform_model(images: &mut [ColmapImage], points: &mut [ColmapPoint3D], transform: &Similarity) {
+    for image in images.iter_mut() {
+        let camera_to_world = image.camera_to_world();
+        let moved = Pose::new(
+            math::multiply(&transform.rotation, &camera_to_world.rotation),
+            transform.transform_point(camera_to_world.translation),
+        );
+        let world_to_camera = moved.inverse();
+        [image.qw, image.qx, image.qy, image.qz] = world_to_camera.quaternion();
+        [image.tx, image.ty, image.tz] = world_to_camera.translation;
+    }
+    for point in points.iter_mut() {
+        point.xyz = transform.transform_point(point.xyz);
+    }
+}
+
+/// The shortest rotation taking direction `from` onto `to`; the identity for a zero `from`
+fn rotation_onto(from: [f64; 3], to: [f64; 3]) -> Similarity {
+    let length = math::dot(from, from).sqrt();
+    if length <= 0.0 {
+        return Similarity::default();
+    }
+    let from = from.map(|x| x / length);
+    let cosine = math::dot(from, to);
+    let axis = math::cross(from, to);
+    let quaternion = if cosine < -1.0 + 1e-12 {
+        // Opposite directions: half a turn about any perpendicular axis
+        let other = if from[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+        let axis = math::cross(from, other);
+        let length = math::dot(axis, axis).sqrt();
+        [0.0, axis[0] / length, axis[1] / length, axis[2] / length]
+    } else {
+        [1.0 + cosine, axis[0], axis[1], axis[2]]
+    };
+    let norm = quaternion.iter().map(|q| q * q).sum::<f64>().sqrt();
+    Similarity { rotation: math::quaternion_to_matrix(quaternion.map(|q| q / norm)), ..Similarity::default() }
+}
+
+/// SplitMix64, enough to draw RANSAC samples without a dependency
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Uniform in `0..bound`
+    fn below(&mut self, bound: u64) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (((z ^ (z >> 31)) as u128 * bound as u128) >> 64) as u64
+    }
+}
@@ -0,0 +1,237 @@
+//! ARKit per-frame camera JSON, and the capture frames mobile app importers produce
+//!
+//! Every frame carries ARKit's `intrinsics` (3x3, in pixels of the captured image) and
+//! `cameraTransform`, the camera-to-world matrix with OpenGL camera axes (x right, y up,
+//! looking along -z) in a gravity-aligned, Y-up world. Matrices may be nested or flat, in
+//! rows or in simd's column order; the layout is told apart by where the `[0, ..., 0, 1]`
+//! row of camera matrices and rigid transforms lies. Frames come as a JSON array, an object
+//! with `frames`, or a directory with one JSON file per frame.
+//!
+//! World coordinates are kept as ARKit reports them, for every capture app.
+
+use crate::errors::{Result, HylaeanError};
+use super::camera_model::{CameraModel, OpenCvDistortion};
+use super::colmap::{ColmapCamera, ColmapImage};
+use super::image_size;
+use super::pose::{CameraAxes, Matrix4, Pose};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+const IMAGE_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
+/// A frame of a mobile capture in the internal camera model
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureFrame {
+    /// Image path, relative to the capture's image root
+    pub image: String,
+    /// Camera-to-world with OpenGL camera axes
+    pub camera_to_world: Pose,
+    pub model: CameraModel,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum MatrixValues {
+    Flat(Vec<f64>),
+    Nested(Vec<Vec<f64>>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Resolution {
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ArkitFrame {
+    #[serde(default, alias = "file_path", alias = "imageName")]
+    image: Option<String>,
+    intrinsics: MatrixValues,
+    #[serde(rename = "cameraTransform", alias = "camera_transform", alias = "transform")]
+    camera_transform: MatrixValues,
+    #[serde(default, rename = "imageResolution", alias = "image_resolution")]
+    image_resolution: Option<Resolution>,
+    #[serde(default)]
+    width: Option<u32>,
+    #[serde(default)]
+    height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum ArkitFile {
+    Frames(Vec<ArkitFrame>),
+    Wrapped { frames: Vec<ArkitFrame> },
+    Single(Box<ArkitFrame>),
+}
+
+/// Whether a JSON file, or a directory of JSON files, holds ARKit frames
+pub fn is_arkit(path: &Path) -> bool {
+    let file = if path.is_dir() {
+        match frame_files(path) {
+            Ok(files) if !files.is_empty() => files[0].clone(),
+            _ => return false,
+        }
+    } else {
+        path.to_path_buf()
+    };
+    let Ok(file) = File::open(file) else { return false };
+    let Ok(value) = serde_json::from_reader::<_, serde_json::Value>(BufReader::new(file)) else { return false };
+    let frame = match &value {
+        serde_json::Value::Array(frames) => frames.first(),
+        value => value.get("frames").and_then(|frames| frames.as_array()).and_then(|frames| frames.first()).or(Some(value)),
+    };
+    frame.is_some_and(|frame| {
+        frame.get("intrinsics").is_some()
+            && ["cameraTransform", "camera_transform", "transform"].iter().any(|key| frame.get(key).is_some())
+    })
+}
+
+/// Frames of a JSON file or a directory of per-frame files. Frames without an image name
+/// are named after their JSON file; missing image sizes are read from the image headers.
+pub fn read(path: &Path) -> Result<Vec<CaptureFrame>> {
+    let (base_dir, entries) = if path.is_dir() {
+        let mut entries = Vec::new();
+        for file in frame_files(path)? {
+            let frame: ArkitFrame = serde_json::from_reader(BufReader::new(File::open(&file)?))?;
+            entries.push((frame, Some(file)));
+        }
+        (path.to_path_buf(), entries)
+    } else {
+        let frames = match serde_json::from_reader(BufReader::new(File::open(path)?))? {
+            ArkitFile::Frames(frames) | ArkitFile::Wrapped { frames } => frames,
+            ArkitFile::Single(frame) => vec![*frame],
+        };
+        (path.parent().unwrap_or(Path::new(".")).to_path_buf(), frames.into_iter().map(|frame| (frame, None)).collect())
+    };
+
+    let transforms: Vec<Vec<f64>> = entries.iter().map(|(frame, _)| flatten(&frame.camera_transform)).collect();
+    let intrinsics: Vec<Vec<f64>> = entries.iter().map(|(frame, _)| flatten(&frame.intrinsics)).collect();
+    let transforms_by_rows = rows_first(&transforms, 4, "cameraTransform")?;
+    let intrinsics_by_rows = rows_first(&intrinsics, 3, "intrinsics")?;
+
+    let mut frames = Vec::with_capacity(entries.len());
+    for (((frame, file), transform), k) in entries.iter().zip(&transforms).zip(&intrinsics) {
+        let image = match (&frame.image, file) {
+            (Some(image), _) => image.clone(),
+            (None, Some(file)) => image_beside(&base_dir, file),
+            (None, None) => {
+                return Err(HylaeanError::ConfigError {
+                    message: format!("ARKit frame {} in {} names no image", frames.len(), path.display()),
+                })
+            }
+        };
+        let (width, height) = match (&frame.image_resolution, frame.width, frame.height) {
+            (Some(resolution), _, _) => (resolution.width, resolution.height),
+            (None, Some(width), Some(height)) => (width, height),
+            _ => {
+                let [width, height] = image_size::read_image_size(&base_dir.join(&image))?;
+                (width, height)
+            }
+        };
+
+        let m = rows::<4>(transform, transforms_by_rows);
+        let k = rows::<3>(k, intrinsics_by_rows);
+        frames.push(CaptureFrame {
+            image,
+            camera_to_world: Pose::from_matrix(&m),
+            model: pinhole(k[0][0], k[1][1], k[0][2], k[1][2])?,
+            width,
+            height,
+        });
+    }
+    Ok(frames)
+}
+
+/// COLMAP cameras and images; frames with the same intrinsics share a COLMAP camera
+pub fn to_colmap(frames: &[CaptureFrame]) -> (Vec<ColmapCamera>, Vec<ColmapImage>) {
+    let mut cameras: Vec<ColmapCamera> = Vec::new();
+    let mut images = Vec::with_capacity(frames.len());
+    for (i, frame) in frames.iter().enumerate() {
+        let camera = ColmapCamera::from_model(cameras.len() as u32 + 1, frame.width, frame.height, &frame.model);
+        let camera_id = match cameras.iter().find(|existing| {
+            (&existing.model, existing.width, existing.height, &existing.params) == (&camera.model, camera.width, camera.height, &camera.params)
+        }) {
+            Some(existing) => existing.camera_id,
+            None => {
+                cameras.push(camera);
+                cameras.len() as u32
+            }
+        };
+
+        let world_to_camera = frame.camera_to_world.change_camera_axes(CameraAxes::OpenGl, CameraAxes::OpenCv).inverse();
+        images.push(ColmapImage::new(i as u32 + 1, camera_id, frame.image.clone(), &world_to_camera));
+    }
+    (cameras, images)
+}
+
+/// ARKit cameras are undistorted pinholes
+pub(crate) fn pinhole(fx: f64, fy: f64, cx: f64, cy: f64) -> Result<CameraModel> {
+    CameraModel::from_opencv(fx, fy, cx, cy, &OpenCvDistortion::Standard(Vec::new()))
+}
+
+/// Whether the flat `n` x `n` matrices are stored by rows. Each must end in the
+/// homogeneous row when read by rows, or in the homogeneous column when read by columns;
+/// matrices that fit both, such as an identity rotation, follow the others.
+pub(crate) fn rows_first(matrices: &[Vec<f64>], n: usize, what: &str) -> Result<bool> {
+    let homogeneous = |values: &[f64]| values.iter().rev().enumerate().all(|(i, v)| (v - if i == 0 { 1.0 } else { 0.0 }).abs() < 1e-6);
+    let mut by_columns = false;
+    for matrix in matrices {
+        if matrix.len() != n * n {
+            return Err(HylaeanError::UnsupportedFormat {
+                format: format!("{} with {} values (expected {})", what, matrix.len(), n * n),
+            });
+        }
+        let last_column: Vec<f64> = (0..n).map(|i| matrix[i * n + n - 1]).collect();
+        match (homogeneous(&matrix[n * (n - 1)..]), homogeneous(&last_column)) {
+            (true, false) => return Ok(true),
+            (false, true) => by_columns = true,
+            (true, true) => {}
+            (false, false) => {
+                return Err(HylaeanError::UnsupportedFormat {
+                    format: format!("{} {:?} (neither its last row nor its last column is homogeneous)", what, matrix),
+                })
+            }
+        }
+    }
+    Ok(!by_columns)
+}
+
+/// The rows of a flat `N` x `N` matrix stored by rows or by columns
+pub(crate) fn rows<const N: usize>(values: &[f64], by_rows: bool) -> [[f64; N]; N] {
+    std::array::from_fn(|r| std::array::from_fn(|c| if by_rows { values[r * N + c] } else { values[c * N + r] }))
+}
+
+fn flatten(values: &MatrixValues) -> Vec<f64> {
+    match values {
+        MatrixValues::Flat(values) => values.clone(),
+        MatrixValues::Nested(nested) => nested.concat(),
+    }
+}
+
+fn frame_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().and_then(|s| s.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("json")))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// The image next to a per-frame JSON file, sharing its stem
+fn image_beside(dir: &Path, file: &Path) -> String {
+    let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    IMAGE_EXTENSIONS.iter()
+        .map(|ext| format!("{}.{}", stem, ext))
+        .find(|name| dir.join(name).is_file())
+        .unwrap_or_else(|| format!("{}.jpg", stem))
+}
+
+/// A 4x4 matrix from the 3x4 camera-to-world rows capture apps write
+pub(crate) fn homogeneous(rows: [[f64; 4]; 3]) -> Matrix4 {
+    [rows[0], rows[1], rows[2], [0.0, 0.0, 0.0, 1.0]]
+}
@@ -0,0 +1,201 @@
+//! Blender camera JSON
+//!
+//! One entry per camera object with the properties Blender itself uses: `lens` and
+//! `sensor_width`/`sensor_height` in millimetres, `sensor_fit`, `shift_x`/`shift_y` as
+//! fractions of the fitted image dimension, the scene's render resolution and pixel aspect,
+//! and `matrix_world`, the camera-to-world matrix in Blender's Z-up world with the camera
+//! looking along its local -Z (y up). NeRF synthetic `transform_matrix` entries are these
+//! same matrices, so world coordinates carry over unchanged between NeRF, COLMAP and Blender.
+//!
+//! Blender cameras are ideal pinholes: lens distortion is dropped with a warning.
+
+use crate::errors::{Result, HylaeanError};
+use super::camera_model::{CameraModel, OpenCvDistortion};
+use super::colmap::{ColmapCamera, ColmapImage};
+use super::pose::{CameraAxes, Matrix4, Pose};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Blender's default full-frame sensor width
+pub const DEFAULT_SENSOR_WIDTH: f64 = 36.0;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlenderCameras {
+    pub cameras: Vec<BlenderCamera>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlenderCamera {
+    /// Object name
+    pub name: String,
+    /// Image the camera took, as named in the reconstruction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Camera-to-world, row-major
+    pub matrix_world: Matrix4,
+    /// Focal length in millimetres
+    pub lens: f64,
+    #[serde(default = "default_sensor_width")]
+    pub sensor_width: f64,
+    #[serde(default = "default_sensor_height")]
+    pub sensor_height: f64,
+    /// `AUTO`, `HORIZONTAL` or `VERTICAL`
+    #[serde(default = "default_sensor_fit")]
+    pub sensor_fit: String,
+    #[serde(default)]
+    pub shift_x: f64,
+    #[serde(default)]
+    pub shift_y: f64,
+    pub resolution_x: u32,
+    pub resolution_y: u32,
+    #[serde(default = "default_pixel_aspect")]
+    pub pixel_aspect_x: f64,
+    #[serde(default = "default_pixel_aspect")]
+    pub pixel_aspect_y: f64,
+}
+
+fn default_sensor_width() -> f64 {
+    DEFAULT_SENSOR_WIDTH
+}
+
+fn default_sensor_height() -> f64 {
+    24.0
+}
+
+fn default_sensor_fit() -> String {
+    "AUTO".to_string()
+}
+
+fn default_pixel_aspect() -> f64 {
+    1.0
+}
+
+impl BlenderCameras {
+    pub fn read(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Whether a JSON file holds Blender cameras rather than NeRF frames
+    pub fn is_blender_cameras(path: &Path) -> bool {
+        let Ok(file) = File::open(path) else { return false };
+        let Ok(value) = serde_json::from_reader::<_, serde_json::Value>(BufReader::new(file)) else { return false };
+        value.get("cameras").and_then(|cameras| cameras.as_array()).is_some_and(|cameras| {
+            cameras.first().is_none_or(|camera| camera.get("lens").is_some() && camera.get("matrix_world").is_some())
+        })
+    }
+
+    /// Cameras for COLMAP images, named after the image files
+    pub fn from_colmap(cameras: &[ColmapCamera], images: &[ColmapImage]) -> Result<Self> {
+        let cameras: HashMap<u32, &ColmapCamera> = cameras.iter().map(|camera| (camera.camera_id, camera)).collect();
+        let mut converted = Vec::with_capacity(images.len());
+        for image in images {
+            let camera = cameras.get(&image.camera_id).ok_or_else(|| HylaeanError::ConfigError {
+                message: format!("COLMAP image {} refers to missing camera {}", image.name, image.camera_id),
+            })?;
+            let model = camera.camera_model()?;
+            let camera_to_world = image.camera_to_world().change_camera_axes(CameraAxes::OpenCv, CameraAxes::OpenGl);
+            let name = Path::new(&image.name).file_stem().and_then(|s| s.to_str()).unwrap_or(&image.name).to_string();
+            converted.push(BlenderCamera::from_model(name, Some(image.name.clone()), &model, camera.width, camera.height, &camera_to_world));
+        }
+        Ok(Self { cameras: converted })
+    }
+
+    /// COLMAP cameras and images; cameras with the same intrinsics share a COLMAP camera
+    pub fn to_colmap(&self) -> Result<(Vec<ColmapCamera>, Vec<ColmapImage>)> {
+        let mut cameras: Vec<ColmapCamera> = Vec::new();
+        let mut images = Vec::with_capacity(self.cameras.len());
+        for (i, blender) in self.cameras.iter().enumerate() {
+            let camera = ColmapCamera::from_model(cameras.len() as u32 + 1, blender.resolution_x, blender.resolution_y, &blender.to_model()?);
+            let camera_id = match cameras.iter().find(|existing| {
+                (&existing.model, existing.width, existing.height, &existing.params) == (&camera.model, camera.width, camera.height, &camera.params)
+            }) {
+                Some(existing) => existing.camera_id,
+                None => {
+                    cameras.push(camera);
+                    cameras.len() as u32
+                }
+            };
+
+            let world_to_camera = Pose::from_matrix(&blender.matrix_world)
+                .change_camera_axes(CameraAxes::OpenGl, CameraAxes::OpenCv)
+                .inverse();
+            let name = blender.image.clone().unwrap_or_else(|| blender.name.clone());
+            images.push(ColmapImage::new(i as u32 + 1, camera_id, name, &world_to_camera));
+        }
+        Ok((cameras, images))
+    }
+}
+
+impl BlenderCamera {
+    /// A horizontally fitted camera on a sensor of the default width; unequal focal lengths
+    /// become a pixel aspect, the principal point a lens shift
+    pub fn from_model(name: String, image: Option<String>, model: &CameraModel, width: u32, height: u32, camera_to_world: &Pose) -> Self {
+        let distorted = match model.opencv_distortion() {
+            Some(OpenCvDistortion::Standard(coefficients)) => coefficients.iter().any(|c| *c != 0.0),
+            Some(OpenCvDistortion::Fisheye(_)) | None => true,
+        };
+        if distorted {
+            warn!("Blender cameras have no lens distortion; dropping that of {} camera for {}", model.name(), name);
+        }
+
+        let [fx, fy] = model.focal_length();
+        let [cx, cy] = model.principal_point();
+        let (width_px, height_px) = (width as f64, height as f64);
+        // Blender's pixel aspect ratio, y over x
+        let aspect = fx / fy;
+        Self {
+            name,
+            image,
+            matrix_world: camera_to_world.to_matrix(),
+            lens: fx * DEFAULT_SENSOR_WIDTH / width_px,
+            sensor_width: DEFAULT_SENSOR_WIDTH,
+            sensor_height: DEFAULT_SENSOR_WIDTH * height_px / width_px,
+            sensor_fit: "HORIZONTAL".to_string(),
+            shift_x: (width_px / 2.0 - cx) / width_px,
+            shift_y: (cy - height_px / 2.0) * aspect / width_px,
+            resolution_x: width,
+            resolution_y: height,
+            pixel_aspect_x: 1.0,
+            pixel_aspect_y: aspect,
+        }
+    }
+
+    /// The pinhole camera Blender renders, following its sensor fit rules
+    pub fn to_model(&self) -> Result<CameraModel> {
+        let (width, height) = (self.resolution_x as f64, self.resolution_y as f64);
+        let aspect = self.pixel_aspect_y / self.pixel_aspect_x;
+        let horizontal = match self.sensor_fit.to_uppercase().as_str() {
+            "AUTO" => width * self.pixel_aspect_x >= height * self.pixel_aspect_y,
+            "HORIZONTAL" => true,
+            "VERTICAL" => false,
+            other => {
+                return Err(HylaeanError::UnsupportedFormat {
+                    format: format!("Blender sensor fit {}", other),
+                })
+            }
+        };
+        // AUTO always measures the sensor by its width, along the larger dimension
+        let sensor = if self.sensor_fit.eq_ignore_ascii_case("VERTICAL") { self.sensor_height } else { self.sensor_width };
+        let fitted = if horizontal { width } else { aspect * height };
+
+        let fx = self.lens * fitted / sensor;
+        CameraModel::from_opencv(
+            fx,
+            fx / aspect,
+            width / 2.0 - self.shift_x * fitted,
+            height / 2.0 + self.shift_y * fitted / aspect,
+            &OpenCvDistortion::Standard(Vec::new()),
+        )
+    }
+}
@@ -0,0 +1,194 @@
+//! Bundler v0.3 `bundle.out` reconstructions
+//!
+//! Every camera is a focal length `f`, radial terms `k1, k2`, and a world-to-camera
+//! rotation and translation with OpenGL camera axes (looking along -z, y up). A point
+//! projects to `f * (1 + k1 * r^2 + k2 * r^4) * p` with `p = -(x, y) / z` in camera
+//! coordinates, measured from the image center with y up: COLMAP's RADIAL model about
+//! the image center. Cameras with `f = 0` were not registered. Points list their color
+//! and the cameras, keypoints and positions they were seen at.
+//!
+//! Image names come from the list file Bundler reads its images from (`list.txt`), whose
+//! paths are relative to its own directory; image sizes come from the image headers.
+
+use crate::errors::{Result, HylaeanError};
+use super::camera_model::CameraModel;
+use super::colmap::{ColmapCamera, ColmapImage, ColmapPoint2D, ColmapPoint3D, TrackElement};
+use super::image_size;
+use super::pose::{CameraAxes, Pose};
+use log::warn;
+use std::path::{Path, PathBuf};
+
+pub const LIST_FILE: &str = "list.txt";
+
+/// A camera of `bundle.out`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundlerCamera {
+    pub focal: f64,
+    pub k1: f64,
+    pub k2: f64,
+    /// World-to-camera with OpenGL camera axes
+    pub world_to_camera: Pose,
+}
+
+/// A point of `bundle.out` with its views as (camera index, keypoint index, x, y)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundlerPoint {
+    pub position: [f64; 3],
+    pub color: [u8; 3],
+    pub views: Vec<(usize, u32, [f64; 2])>,
+}
+
+impl BundlerCamera {
+    pub fn is_registered(&self) -> bool {
+        self.focal != 0.0
+    }
+
+    /// Image position of a world point, from the image center with y up
+    pub fn project(&self, point: [f64; 3]) -> [f64; 2] {
+        let p = self.world_to_camera.transform_point(point);
+        let (x, y) = (-p[0] / p[2], -p[1] / p[2]);
+        let r2 = x * x + y * y;
+        let scale = self.focal * (1.0 + self.k1 * r2 + self.k2 * r2 * r2);
+        [scale * x, scale * y]
+    }
+}
+
+/// The list file of a bundle: `list.txt` next to it or in its parent, as Bundler's
+/// `bundle/bundle.out` layout has it, or `<stem>.list.txt`
+pub fn list_path(bundle_path: &Path) -> Option<PathBuf> {
+    let dir = bundle_path.parent().unwrap_or(Path::new("."));
+    let stem = bundle_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let mut candidates = vec![dir.join(format!("{}.list.txt", stem)), dir.join(LIST_FILE)];
+    if let Some(parent) = dir.parent() {
+        candidates.push(parent.join(LIST_FILE));
+    }
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
+
+pub fn read(path: &Path) -> Result<(Vec<BundlerCamera>, Vec<BundlerPoint>)> {
+    let text = std::fs::read_to_string(path)?;
+    if let Some(version) = text.lines().next().and_then(|line| line.strip_prefix("# Bundle file v")) {
+        if version.trim() != "0.3" {
+            return Err(HylaeanError::UnsupportedFormat {
+                format: format!("Bundler v{} file {} (only v0.3 is read)", version.trim(), path.display()),
+            });
+        }
+    }
+    let mut tokens = text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.split_whitespace());
+    let mut next = |what: &str| -> Result<f64> {
+        let token = tokens.next().ok_or_else(|| malformed(path, &format!("ends before {}", what)))?;
+        token.parse().map_err(|_| malformed(path, &format!("bad {} {:?}", what, token)))
+    };
+
+    let camera_count = next("camera count")? as usize;
+    let point_count = next("point count")? as usize;
+    let mut cameras = Vec::with_capacity(camera_count.min(1 << 16));
+    for _ in 0..camera_count {
+        let [focal, k1, k2] = [next("focal length")?, next("k1")?, next("k2")?];
+        let mut rotation = [[0.0; 3]; 3];
+        for value in rotation.as_flattened_mut() {
+            *value = next("rotation")?;
+        }
+        let translation = [next("translation")?, next("translation")?, next("translation")?];
+        cameras.push(BundlerCamera { focal, k1, k2, world_to_camera: Pose::new(rotation, translation) });
+    }
+
+    let mut points = Vec::with_capacity(point_count.min(1 << 20));
+    for _ in 0..point_count {
+        let position = [next("point")?, next("point")?, next("point")?];
+        let color = [next("color")?, next("color")?, next("color")?].map(|c| c.clamp(0.0, 255.0) as u8);
+        let view_count = next("view count")? as usize;
+        let mut views = Vec::with_capacity(view_count);
+        for _ in 0..view_count {
+            let camera = next("view camera")? as usize;
+            let key = next("view key")? as u32;
+            views.push((camera, key, [next("view x")?, next("view y")?]));
+        }
+        points.push(BundlerPoint { position, color, views });
+    }
+    Ok((cameras, points))
+}
+
+/// Image paths of a list file, one per camera; any focal length after the path is ignored
+pub fn read_list(path: &Path) -> Result<Vec<String>> {
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect())
+}
+
+/// COLMAP cameras, images and points3D of the registered cameras. Image sizes are read
+/// from the images, resolving `names` against `image_dir`.
+pub fn to_colmap(
+    cameras: &[BundlerCamera],
+    points: &[BundlerPoint],
+    names: &[String],
+    image_dir: &Path,
+) -> Result<(Vec<ColmapCamera>, Vec<ColmapImage>, Vec<ColmapPoint3D>)> {
+    if names.len() != cameras.len() {
+        return Err(HylaeanError::ConfigError {
+            message: format!("the Bundler list names {} images for {} cameras", names.len(), cameras.len()),
+        });
+    }
+
+    let mut colmap_cameras = Vec::new();
+    let mut images = Vec::new();
+    // Index into `images` of each Bundler camera, and its principal point
+    let mut registered: Vec<Option<(usize, [f64; 2])>> = vec![None; cameras.len()];
+    for (index, (camera, name)) in cameras.iter().zip(names).enumerate() {
+        if !camera.is_registered() {
+            continue;
+        }
+        let [width, height] = image_size::read_image_size(&image_dir.join(name))?;
+        let center = [width as f64 / 2.0, height as f64 / 2.0];
+        let id = images.len() as u32 + 1;
+        let model = CameraModel::Radial { f: camera.focal, cx: center[0], cy: center[1], k1: camera.k1, k2: camera.k2 };
+        colmap_cameras.push(ColmapCamera::from_model(id, width, height, &model));
+
+        let world_to_camera = camera.world_to_camera.inverse()
+            .change_camera_axes(CameraAxes::OpenGl, CameraAxes::OpenCv)
+            .inverse();
+        registered[index] = Some((images.len(), center));
+        images.push(ColmapImage::new(id, id, name.clone(), &world_to_camera));
+    }
+    if registered.iter().any(Option::is_none) {
+        warn!("Skipping {} unregistered Bundler cameras", registered.iter().filter(|r| r.is_none()).count());
+    }
+
+    let mut colmap_points = Vec::with_capacity(points.len());
+    for point in points {
+        let point3d_id = colmap_points.len() as u64 + 1;
+        let mut track = Vec::with_capacity(point.views.len());
+        let mut error_sum = 0.0;
+        for &(camera, _, [x, y]) in &point.views {
+            let Some(&Some((image, [cx, cy]))) = registered.get(camera) else { continue };
+            let [u, v] = cameras[camera].project(point.position);
+            error_sum += ((u - x).powi(2) + (v - y).powi(2)).sqrt();
+
+            // Bundler measures from the image center with y up, COLMAP from the top left corner
+            let image = &mut images[image];
+            track.push(TrackElement { image_id: image.image_id, point2d_idx: image.points2d.len() as u32 });
+            image.points2d.push(ColmapPoint2D { x: cx + x, y: cy - y, point3d_id: point3d_id as i64 });
+        }
+        if track.is_empty() {
+            continue;
+        }
+        colmap_points.push(ColmapPoint3D {
+            point3d_id,
+            xyz: point.position,
+            rgb: point.color,
+            error: error_sum / track.len() as f64,
+            track,
+        });
+    }
+    Ok((colmap_cameras, images, colmap_points))
+}
+
+fn malformed(path: &Path, detail: &str) -> HylaeanError {
+    HylaeanError::UnsupportedFormat {
+        format: format!("Bundler file {}: {}", path.display(), detail),
+    }
+}
@@ -0,0 +1,260 @@
+//! Typed COLMAP camera models
+//!
+//! COLMAP stores intrinsics as a flat parameter list whose meaning depends on the model
+//! name. [`CameraModel`] names every parameter, so conversions read focal lengths,
+//! principal points and distortion terms instead of indexing `params`. Parameter order
+//! follows `colmap/sensor/models.h`.
+
+use crate::errors::{Result, HylaeanError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraModel {
+    SimplePinhole { f: f64, cx: f64, cy: f64 },
+    Pinhole { fx: f64, fy: f64, cx: f64, cy: f64 },
+    SimpleRadial { f: f64, cx: f64, cy: f64, k: f64 },
+    Radial { f: f64, cx: f64, cy: f64, k1: f64, k2: f64 },
+    OpenCv { fx: f64, fy: f64, cx: f64, cy: f64, k1: f64, k2: f64, p1: f64, p2: f64 },
+    OpenCvFisheye { fx: f64, fy: f64, cx: f64, cy: f64, k1: f64, k2: f64, k3: f64, k4: f64 },
+    FullOpenCv {
+        fx: f64, fy: f64, cx: f64, cy: f64,
+        k1: f64, k2: f64, p1: f64, p2: f64, k3: f64, k4: f64, k5: f64, k6: f64,
+    },
+    /// Field-of-view model of Devernay and Faugeras
+    Fov { fx: f64, fy: f64, cx: f64, cy: f64, omega: f64 },
+    SimpleRadialFisheye { f: f64, cx: f64, cy: f64, k: f64 },
+    RadialFisheye { f: f64, cx: f64, cy: f64, k1: f64, k2: f64 },
+    ThinPrismFisheye {
+        fx: f64, fy: f64, cx: f64, cy: f64,
+        k1: f64, k2: f64, p1: f64, p2: f64, k3: f64, k4: f64, sx1: f64, sy1: f64,
+    },
+    /// Radial `k0..k5`, tangential `p0, p1` and thin prism `s0..s3` terms
+    RadTanThinPrismFisheye { fx: f64, fy: f64, cx: f64, cy: f64, k: [f64; 6], p: [f64; 2], s: [f64; 4] },
+}
+
+/// Distortion in the conventions of OpenCV's two camera models
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpenCvDistortion {
+    /// `cv::calibrateCamera` coefficients `k1, k2, p1, p2[, k3[, k4, k5, k6]]`
+    Standard(Vec<f64>),
+    /// `cv::fisheye` coefficients `k1, k2, k3, k4`
+    Fisheye([f64; 4]),
+}
+
+impl CameraModel {
+    /// Interpret COLMAP's `params` for the model called `model`
+    pub fn from_params(model: &str, params: &[f64]) -> Result<Self> {
+        let count_error = |expected: usize| HylaeanError::ConfigError {
+            message: format!("COLMAP {} camera needs {} parameters, got {}", model, expected, params.len()),
+        };
+        macro_rules! take {
+            ($count:expr) => {
+                <[f64; $count]>::try_from(params).map_err(|_| count_error($count))?
+            };
+        }
+
+        Ok(match model {
+            "SIMPLE_PINHOLE" => {
+                let [f, cx, cy] = take!(3);
+                CameraModel::SimplePinhole { f, cx, cy }
+            }
+            "PINHOLE" => {
+                let [fx, fy, cx, cy] = take!(4);
+                CameraModel::Pinhole { fx, fy, cx, cy }
+            }
+            "SIMPLE_RADIAL" => {
+                let [f, cx, cy, k] = take!(4);
+                CameraModel::SimpleRadial { f, cx, cy, k }
+            }
+            "RADIAL" => {
+                let [f, cx, cy, k1, k2] = take!(5);
+                CameraModel::Radial { f, cx, cy, k1, k2 }
+            }
+            "OPENCV" => {
+                let [fx, fy, cx, cy, k1, k2, p1, p2] = take!(8);
+                CameraModel::OpenCv { fx, fy, cx, cy, k1, k2, p1, p2 }
+            }
+            "OPENCV_FISHEYE" => {
+                let [fx, fy, cx, cy, k1, k2, k3, k4] = take!(8);
+                CameraModel::OpenCvFisheye { fx, fy, cx, cy, k1, k2, k3, k4 }
+            }
+            "FULL_OPENCV" => {
+                let [fx, fy, cx, cy, k1, k2, p1, p2, k3, k4, k5, k6] = take!(12);
+                CameraModel::FullOpenCv { fx, fy, cx, cy, k1, k2, p1, p2, k3, k4, k5, k6 }
+            }
+            "FOV" => {
+                let [fx, fy, cx, cy, omega] = take!(5);
+                CameraModel::Fov { fx, fy, cx, cy, omega }
+            }
+            "SIMPLE_RADIAL_FISHEYE" => {
+                let [f, cx, cy, k] = take!(4);
+                CameraModel::SimpleRadialFisheye { f, cx, cy, k }
+            }
+            "RADIAL_FISHEYE" => {
+                let [f, cx, cy, k1, k2] = take!(5);
+                CameraModel::RadialFisheye { f, cx, cy, k1, k2 }
+            }
+            "THIN_PRISM_FISHEYE" => {
+                let [fx, fy, cx, cy, k1, k2, p1, p2, k3, k4, sx1, sy1] = take!(12);
+                CameraModel::ThinPrismFisheye { fx, fy, cx, cy, k1, k2, p1, p2, k3, k4, sx1, sy1 }
+            }
+            "RAD_TAN_THIN_PRISM_FISHEYE" => {
+                let [fx, fy, cx, cy, k0, k1, k2, k3, k4, k5, p0, p1, s0, s1, s2, s3] = take!(16);
+                CameraModel::RadTanThinPrismFisheye {
+                    fx, fy, cx, cy,
+                    k: [k0, k1, k2, k3, k4, k5],
+                    p: [p0, p1],
+                    s: [s0, s1, s2, s3],
+                }
+            }
+            other => {
+                return Err(HylaeanError::UnsupportedFormat {
+                    format: format!("COLMAP camera model {}", other),
+                })
+            }
+        })
+    }
+
+    /// COLMAP's name for the model
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraModel::SimplePinhole { .. } => "SIMPLE_PINHOLE",
+            CameraModel::Pinhole { .. } => "PINHOLE",
+            CameraModel::SimpleRadial { .. } => "SIMPLE_RADIAL",
+            CameraModel::Radial { .. } => "RADIAL",
+            CameraModel::OpenCv { .. } => "OPENCV",
+            CameraModel::OpenCvFisheye { .. } => "OPENCV_FISHEYE",
+            CameraModel::FullOpenCv { .. } => "FULL_OPENCV",
+            CameraModel::Fov { .. } => "FOV",
+            CameraModel::SimpleRadialFisheye { .. } => "SIMPLE_RADIAL_FISHEYE",
+            CameraModel::RadialFisheye { .. } => "RADIAL_FISHEYE",
+            CameraModel::ThinPrismFisheye { .. } => "THIN_PRISM_FISHEYE",
+            CameraModel::RadTanThinPrismFisheye { .. } => "RAD_TAN_THIN_PRISM_FISHEYE",
+        }
+    }
+
+    /// COLMAP's flat parameter list, the inverse of [`CameraModel::from_params`]
+    pub fn params(&self) -> Vec<f64> {
+        match *self {
+            CameraModel::SimplePinhole { f, cx, cy } => vec![f, cx, cy],
+            CameraModel::Pinhole { fx, fy, cx, cy } => vec![fx, fy, cx, cy],
+            CameraModel::SimpleRadial { f, cx, cy, k } => vec![f, cx, cy, k],
+            CameraModel::Radial { f, cx, cy, k1, k2 } => vec![f, cx, cy, k1, k2],
+            CameraModel::OpenCv { fx, fy, cx, cy, k1, k2, p1, p2 } => vec![fx, fy, cx, cy, k1, k2, p1, p2],
+            CameraModel::OpenCvFisheye { fx, fy, cx, cy, k1, k2, k3, k4 } => vec![fx, fy, cx, cy, k1, k2, k3, k4],
+            CameraModel::FullOpenCv { fx, fy, cx, cy, k1, k2, p1, p2, k3, k4, k5, k6 } => {
+                vec![fx, fy, cx, cy, k1, k2, p1, p2, k3, k4, k5, k6]
+            }
+            CameraModel::Fov { fx, fy, cx, cy, omega } => vec![fx, fy, cx, cy, omega],
+            CameraModel::SimpleRadialFisheye { f, cx, cy, k } => vec![f, cx, cy, k],
+            CameraModel::RadialFisheye { f, cx, cy, k1, k2 } => vec![f, cx, cy, k1, k2],
+            CameraModel::ThinPrismFisheye { fx, fy, cx, cy, k1, k2, p1, p2, k3, k4, sx1, sy1 } => {
+                vec![fx, fy, cx, cy, k1, k2, p1, p2, k3, k4, sx1, sy1]
+            }
+            CameraModel::RadTanThinPrismFisheye { fx, fy, cx, cy, k, p, s } => {
+                let mut params = vec![fx, fy, cx, cy];
+                params.extend(k);
+                params.extend(p);
+                params.extend(s);
+                params
+            }
+        }
+    }
+
+    /// `[fx, fy]` in pixels; single-focal models report the same value twice
+    pub fn focal_length(&self) -> [f64; 2] {
+        match *self {
+            CameraModel::SimplePinhole { f, .. }
+            | CameraModel::SimpleRadial { f, .. }
+            | CameraModel::Radial { f, .. }
+            | CameraModel::SimpleRadialFisheye { f, .. }
+            | CameraModel::RadialFisheye { f, .. } => [f, f],
+            CameraModel::Pinhole { fx, fy, .. }
+            | CameraModel::OpenCv { fx, fy, .. }
+            | CameraModel::OpenCvFisheye { fx, fy, .. }
+            | CameraModel::FullOpenCv { fx, fy, .. }
+            | CameraModel::Fov { fx, fy, .. }
+            | CameraModel::ThinPrismFisheye { fx, fy, .. }
+            | CameraModel::RadTanThinPrismFisheye { fx, fy, .. } => [fx, fy],
+        }
+    }
+
+    /// `[cx, cy]` in pixels
+    pub fn principal_point(&self) -> [f64; 2] {
+        match *self {
+            CameraModel::SimplePinhole { cx, cy, .. }
+            | CameraModel::Pinhole { cx, cy, .. }
+            | CameraModel::SimpleRadial { cx, cy, .. }
+            | CameraModel::Radial { cx, cy, .. }
+            | CameraModel::OpenCv { cx, cy, .. }
+            | CameraModel::OpenCvFisheye { cx, cy, .. }
+            | CameraModel::FullOpenCv { cx, cy, .. }
+            | CameraModel::Fov { cx, cy, .. }
+            | CameraModel::SimpleRadialFisheye { cx, cy, .. }
+            | CameraModel::RadialFisheye { cx, cy, .. }
+            | CameraModel::ThinPrismFisheye { cx, cy, .. }
+            | CameraModel::RadTanThinPrismFisheye { cx, cy, .. } => [cx, cy],
+        }
+    }
+
+    /// Whether the model projects by the angle to the optical axis rather than its tangent
+    pub fn is_fisheye(&self) -> bool {
+        matches!(
+            self,
+            CameraModel::OpenCvFisheye { .. }
+                | CameraModel::SimpleRadialFisheye { .. }
+                | CameraModel::RadialFisheye { .. }
+                | CameraModel::ThinPrismFisheye { .. }
+                | CameraModel::RadTanThinPrismFisheye { .. }
+        )
+    }
+
+    /// The same lens in OpenCV's conventions, or `None` when OpenCV has no equivalent
+    /// (FOV and the thin prism fisheye models)
+    pub fn opencv_distortion(&self) -> Option<OpenCvDistortion> {
+        match *self {
+            CameraModel::SimplePinhole { .. } | CameraModel::Pinhole { .. } => {
+                Some(OpenCvDistortion::Standard(vec![0.0; 4]))
+            }
+            CameraModel::SimpleRadial { k, .. } => Some(OpenCvDistortion::Standard(vec![k, 0.0, 0.0, 0.0])),
+            CameraModel::Radial { k1, k2, .. } => Some(OpenCvDistortion::Standard(vec![k1, k2, 0.0, 0.0])),
+            CameraModel::OpenCv { k1, k2, p1, p2, .. } => Some(OpenCvDistortion::Standard(vec![k1, k2, p1, p2])),
+            CameraModel::FullOpenCv { k1, k2, p1, p2, k3, k4, k5, k6, .. } => {
+                Some(OpenCvDistortion::Standard(vec![k1, k2, p1, p2, k3, k4, k5, k6]))
+            }
+            CameraModel::OpenCvFisheye { k1, k2, k3, k4, .. } => Some(OpenCvDistortion::Fisheye([k1, k2, k3, k4])),
+            CameraModel::SimpleRadialFisheye { k, .. } => Some(OpenCvDistortion::Fisheye([k, 0.0, 0.0, 0.0])),
+            CameraModel::RadialFisheye { k1, k2, .. } => Some(OpenCvDistortion::Fisheye([k1, k2, 0.0, 0.0])),
+            CameraModel::Fov { .. } | CameraModel::ThinPrismFisheye { .. } | CameraModel::RadTanThinPrismFisheye { .. } => None,
+        }
+    }
+
+    /// The simplest COLMAP model that represents an OpenCV lens exactly: PINHOLE without
+    /// distortion, OPENCV for `k1, k2, p1, p2`, FULL_OPENCV beyond that and OPENCV_FISHEYE
+    /// for fisheye lenses. Rational terms past `k6` are not representable and are rejected.
+    pub fn from_opencv(fx: f64, fy: f64, cx: f64, cy: f64, distortion: &OpenCvDistortion) -> Result<Self> {
+        match distortion {
+            OpenCvDistortion::Fisheye([k1, k2, k3, k4]) => Ok(CameraModel::OpenCvFisheye {
+                fx, fy, cx, cy, k1: *k1, k2: *k2, k3: *k3, k4: *k4,
+            }),
+            OpenCvDistortion::Standard(coefficients) => {
+                if coefficients.len() > 8 && coefficients[8..].iter().any(|c| *c != 0.0) {
+                    return Err(HylaeanError::UnsupportedFormat {
+                        format: format!("OpenCV distortion with {} coefficients (COLMAP supports 8)", coefficients.len()),
+                    });
+                }
+                let mut c = [0.0; 8];
+                for (target, value) in c.iter_mut().zip(coefficients) {
+                    *target = *value;
+                }
+                let [k1, k2, p1, p2, k3, k4, k5, k6] = c;
+                Ok(if c[4..].iter().any(|v| *v != 0.0) {
+                    CameraModel::FullOpenCv { fx, fy, cx, cy, k1, k2, p1, p2, k3, k4, k5, k6 }
+                } else if c[..4].iter().any(|v| *v != 0.0) {
+                    CameraModel::OpenCv { fx, fy, cx, cy, k1, k2, p1, p2 }
+                } else {
+                    CameraModel::Pinhole { fx, fy, cx, cy }
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,742 @@
+//! COLMAP sparse models in the text (`cameras.txt`, `images.txt`, `points3D.txt`) and
+//! binary (`cameras.bin`, `images.bin`, `points3D.bin`) encodings
+//!
+//! The binary layout follows COLMAP's `read_write_model.py`: little-endian, with a
+//! `u64` record count at the start of each file. A sparse directory is read in
+//! whichever encoding it holds, preferring binary like COLMAP itself does.
+
+use crate::errors::{Result, HylaeanError};
+use super::camera_model::CameraModel;
+use super::pose::Pose;
+use crate::formats::ply::{self, Property, ScalarType};
+use crate::formats::point_cloud::stream::{self, PointChunk, PointSource, Schema};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColmapCamera {
+    pub camera_id: u32,
+    pub model: String,
+    pub width: u32,
+    pub height: u32,
+    pub params: Vec<f64>,
+}
+
+impl ColmapCamera {
+    pub fn from_model(camera_id: u32, width: u32, height: u32, model: &CameraModel) -> Self {
+        Self {
+            camera_id,
+            model: model.name().to_string(),
+            width,
+            height,
+            params: model.params(),
+        }
+    }
+
+    /// The typed model behind `model` and `params`
+    pub fn camera_model(&self) -> Result<CameraModel> {
+        CameraModel::from_params(&self.model, &self.params)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColmapImage {
+    pub image_id: u32,
+    pub qw: f64,
+    pub qx: f64,
+    pub qy: f64,
+    pub qz: f64,
+    pub tx: f64,
+    pub ty: f64,
+    pub tz: f64,
+    pub camera_id: u32,
+    pub name: String,
+    /// Keypoints of the image; `point3d_id` links triangulated ones to `points3D`
+    #[serde(default)]
+    pub points2d: Vec<ColmapPoint2D>,
+}
+
+impl ColmapImage {
+    /// An image without keypoints
+    pub fn new(image_id: u32, camera_id: u32, name: String, world_to_camera: &Pose) -> Self {
+        let [qw, qx, qy, qz] = world_to_camera.quaternion();
+        let [tx, ty, tz] = world_to_camera.translation;
+        Self { image_id, qw, qx, qy, qz, tx, ty, tz, camera_id, name, points2d: Vec::new() }
+    }
+
+    /// The stored pose, which maps world points into the (OpenCV-axes) camera frame
+    pub fn world_to_camera(&self) -> Pose {
+        Pose::from_quaternion([self.qw, self.qx, self.qy, self.qz], [self.tx, self.ty, self.tz])
+    }
+
+    /// Camera-to-world pose with OpenCV camera axes; its translation is the camera center
+    pub fn camera_to_world(&self) -> Pose {
+        self.world_to_camera().inverse()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColmapPoint2D {
+    pub x: f64,
+    pub y: f64,
+    /// `-1` when the keypoint is not triangulated
+    pub point3d_id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColmapPoint3D {
+    pub point3d_id: u64,
+    pub xyz: [f64; 3],
+    pub rgb: [u8; 3],
+    /// Mean reprojection error in pixels
+    pub error: f64,
+    pub track: Vec<TrackElement>,
+}
+
+/// One observation of a 3D point: keypoint `point2d_idx` of image `image_id`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackElement {
+    pub image_id: u32,
+    pub point2d_idx: u32,
+}
+
+/// On-disk encoding of a sparse model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelEncoding {
+    Text,
+    Binary,
+}
+
+impl ModelEncoding {
+    pub fn parse(encoding: &str) -> Result<Self> {
+        match encoding.to_lowercase().as_str() {
+            "text" | "txt" | "ascii" => Ok(ModelEncoding::Text),
+            "binary" | "bin" => Ok(ModelEncoding::Binary),
+            other => Err(HylaeanError::ConfigError {
+                message: format!("unknown COLMAP model encoding '{}' (expected text or binary)", other),
+            }),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ModelEncoding::Text => "txt",
+            ModelEncoding::Binary => "bin",
+        }
+    }
+}
+
+/// COLMAP camera model ids, names and parameter counts, as in `colmap/sensor/models.h`
+pub const CAMERA_MODELS: [(i32, &str, usize); 12] = [
+    (0, "SIMPLE_PINHOLE", 3),
+    (1, "PINHOLE", 4),
+    (2, "SIMPLE_RADIAL", 4),
+    (3, "RADIAL", 5),
+    (4, "OPENCV", 8),
+    (5, "OPENCV_FISHEYE", 8),
+    (6, "FULL_OPENCV", 12),
+    (7, "FOV", 5),
+    (8, "SIMPLE_RADIAL_FISHEYE", 4),
+    (9, "RADIAL_FISHEYE", 5),
+    (10, "THIN_PRISM_FISHEYE", 12),
+    (11, "RAD_TAN_THIN_PRISM_FISHEYE", 16),
+];
+
+fn model_by_id(id: i32) -> Result<(&'static str, usize)> {
+    CAMERA_MODELS.iter()
+        .find(|(model_id, _, _)| *model_id == id)
+        .map(|(_, name, count)| (*name, *count))
+        .ok_or_else(|| HylaeanError::UnsupportedFormat {
+            format: format!("COLMAP camera model id {}", id),
+        })
+}
+
+fn model_by_name(name: &str) -> Result<(i32, usize)> {
+    CAMERA_MODELS.iter()
+        .find(|(_, model_name, _)| *model_name == name)
+        .map(|(id, _, count)| (*id, *count))
+        .ok_or_else(|| HylaeanError::UnsupportedFormat {
+            format: format!("COLMAP camera model {}", name),
+        })
+}
+
+/// The sparse model directory for `path`, which may also name one of its files
+pub fn model_dir(path: &Path) -> PathBuf {
+    if path.is_file() {
+        path.parent().map(Path::to_path_buf).unwrap_or_default()
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Whether `path` is a sparse model directory or one of its files
+pub fn is_model(path: &Path) -> bool {
+    let dir = model_dir(path);
+    ["cameras.bin", "cameras.txt"].iter().any(|name| dir.join(name).is_file())
+}
+
+/// The encoding of the model in `dir`: binary when `cameras.bin` and `images.bin`
+/// exist, otherwise text
+pub fn detect_encoding(dir: &Path) -> Result<ModelEncoding> {
+    if dir.join("cameras.bin").is_file() && dir.join("images.bin").is_file() {
+        Ok(ModelEncoding::Binary)
+    } else if dir.join("cameras.txt").is_file() && dir.join("images.txt").is_file() {
+        Ok(ModelEncoding::Text)
+    } else {
+        Err(HylaeanError::InvalidPath {
+            path: format!("{} (no COLMAP cameras/images .bin or .txt)", dir.display()),
+        })
+    }
+}
+
+pub fn read_cameras(dir: &Path) -> Result<Vec<ColmapCamera>> {
+    match detect_encoding(dir)? {
+        ModelEncoding::Text => read_cameras_text(&dir.join("cameras.txt")),
+        ModelEncoding::Binary => read_cameras_binary(&dir.join("cameras.bin")),
+    }
+}
+
+pub fn read_images(dir: &Path) -> Result<Vec<ColmapImage>> {
+    match detect_encoding(dir)? {
+        ModelEncoding::Text => read_images_text(&dir.join("images.txt")),
+        ModelEncoding::Binary => read_images_binary(&dir.join("images.bin")),
+    }
+}
+
+pub fn write_cameras(dir: &Path, cameras: &[ColmapCamera], encoding: ModelEncoding) -> Result<()> {
+    let path = dir.join(format!("cameras.{}", encoding.extension()));
+    match encoding {
+        ModelEncoding::Text => write_cameras_text(&path, cameras),
+        ModelEncoding::Binary => write_cameras_binary(&path, cameras),
+    }
+}
+
+pub fn write_images(dir: &Path, images: &[ColmapImage], encoding: ModelEncoding) -> Result<()> {
+    let path = dir.join(format!("images.{}", encoding.extension()));
+    match encoding {
+        ModelEncoding::Text => write_images_text(&path, images),
+        ModelEncoding::Binary => write_images_binary(&path, images),
+    }
+}
+
+/// Whether the model in `dir` has a `points3D` file in `encoding`
+pub fn has_points3d(dir: &Path, encoding: ModelEncoding) -> bool {
+    dir.join(format!("points3D.{}", encoding.extension())).is_file()
+}
+
+pub fn read_points3d(dir: &Path) -> Result<Vec<ColmapPoint3D>> {
+    match detect_encoding(dir)? {
+        ModelEncoding::Text => read_points3d_text(&dir.join("points3D.txt")),
+        ModelEncoding::Binary => read_points3d_binary(&dir.join("points3D.bin")),
+    }
+}
+
+pub fn write_points3d(dir: &Path, points: &[ColmapPoint3D], encoding: ModelEncoding) -> Result<()> {
+    let path = dir.join(format!("points3D.{}", encoding.extension()));
+    match encoding {
+        ModelEncoding::Text => write_points3d_text(&path, points),
+        ModelEncoding::Binary => write_points3d_binary(&path, points),
+    }
+}
+
+/// Data lines of a COLMAP text file: comments are dropped, blank lines kept, since an
+/// image without keypoints has an empty second line
+fn data_lines(path: &Path) -> Result<Vec<String>> {
+    let reader = BufReader::new(open(path)?);
+    let mut lines = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.starts_with('#') {
+            lines.push(line);
+        }
+    }
+    Ok(lines)
+}
+
+pub fn read_cameras_text(path: &Path) -> Result<Vec<ColmapCamera>> {
+    let mut cameras = Vec::new();
+    for line in data_lines(path)? {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.is_empty() {
+            continue;
+        }
+        if parts.len() < 5 {
+            return Err(malformed(path, &line));
+        }
+        let params = parts[4..].iter()
+            .map(|value| value.parse::<f64>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| malformed(path, &line))?;
+        cameras.push(ColmapCamera {
+            camera_id: parts[0].parse().map_err(|_| malformed(path, &line))?,
+            model: parts[1].to_string(),
+            width: parts[2].parse().map_err(|_| malformed(path, &line))?,
+            height: parts[3].parse().map_err(|_| malformed(path, &line))?,
+            params,
+        });
+    }
+    Ok(cameras)
+}
+
+pub fn read_images_text(path: &Path) -> Result<Vec<ColmapImage>> {
+    let lines = data_lines(path)?;
+    let mut images = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = &lines[index];
+        index += 1;
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.is_empty() {
+            continue;
+        }
+        if parts.len() < 10 {
+            return Err(malformed(path, line));
+        }
+        let number = |i: usize| parts[i].parse::<f64>().map_err(|_| malformed(path, line));
+        let mut image = ColmapImage {
+            image_id: parts[0].parse().map_err(|_| malformed(path, line))?,
+            qw: number(1)?,
+            qx: number(2)?,
+            qy: number(3)?,
+            qz: number(4)?,
+            tx: number(5)?,
+            ty: number(6)?,
+            tz: number(7)?,
+            camera_id: parts[8].parse().map_err(|_| malformed(path, line))?,
+            // Names may contain spaces
+            name: parts[9..].join(" "),
+            points2d: Vec::new(),
+        };
+
+        if let Some(points_line) = lines.get(index) {
+            index += 1;
+            let values: Vec<&str> = points_line.split_whitespace().collect();
+            if !values.len().is_multiple_of(3) {
+                return Err(malformed(path, points_line));
+            }
+            for point in values.chunks_exact(3) {
+                image.points2d.push(ColmapPoint2D {
+                    x: point[0].parse().map_err(|_| malformed(path, points_line))?,
+                    y: point[1].parse().map_err(|_| malformed(path, points_line))?,
+                    point3d_id: point[2].parse().map_err(|_| malformed(path, points_line))?,
+                });
+            }
+        }
+        images.push(image);
+    }
+    Ok(images)
+}
+
+pub fn write_cameras_text(path: &Path, cameras: &[ColmapCamera]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "# Camera list with one line of data per camera:")?;
+    writeln!(file, "#   CAMERA_ID, MODEL, WIDTH, HEIGHT, PARAMS[]")?;
+    writeln!(file, "# Number of cameras: {}", cameras.len())?;
+    for camera in cameras {
+        write!(file, "{} {} {} {}", camera.camera_id, camera.model, camera.width, camera.height)?;
+        for param in &camera.params {
+            write!(file, " {}", param)?;
+        }
+        writeln!(file)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+pub fn write_images_text(path: &Path, images: &[ColmapImage]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "# Image list with two lines of data per image:")?;
+    writeln!(file, "#   IMAGE_ID, QW, QX, QY, QZ, TX, TY, TZ, CAMERA_ID, NAME")?;
+    writeln!(file, "#   POINTS2D[] as (X, Y, POINT3D_ID)")?;
+    writeln!(
+        file,
+        "# Number of images: {}, mean observations per image: {}",
+        images.len(),
+        mean(images.iter().map(|image| image.points2d.iter().filter(|point| point.point3d_id >= 0).count() as f64))
+    )?;
+    for image in images {
+        writeln!(
+            file,
+            "{} {} {} {} {} {} {} {} {} {}",
+            image.image_id, image.qw, image.qx, image.qy, image.qz,
+            image.tx, image.ty, image.tz, image.camera_id, image.name
+        )?;
+        for (i, point) in image.points2d.iter().enumerate() {
+            if i > 0 {
+                write!(file, " ")?;
+            }
+            write!(file, "{} {} {}", point.x, point.y, point.point3d_id)?;
+        }
+        writeln!(file)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+pub fn read_points3d_text(path: &Path) -> Result<Vec<ColmapPoint3D>> {
+    let mut points = Vec::new();
+    for line in data_lines(path)? {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.is_empty() {
+            continue;
+        }
+        if parts.len() < 8 || !(parts.len() - 8).is_multiple_of(2) {
+            return Err(malformed(path, &line));
+        }
+        let number = |i: usize| parts[i].parse::<f64>().map_err(|_| malformed(path, &line));
+        let channel = |i: usize| parts[i].parse::<u8>().map_err(|_| malformed(path, &line));
+        let track = parts[8..].chunks_exact(2)
+            .map(|element| {
+                Ok(TrackElement {
+                    image_id: element[0].parse().map_err(|_| malformed(path, &line))?,
+                    point2d_idx: element[1].parse().map_err(|_| malformed(path, &line))?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        points.push(ColmapPoint3D {
+            point3d_id: parts[0].parse().map_err(|_| malformed(path, &line))?,
+            xyz: [number(1)?, number(2)?, number(3)?],
+            rgb: [channel(4)?, channel(5)?, channel(6)?],
+            error: number(7)?,
+            track,
+        });
+    }
+    Ok(points)
+}
+
+pub fn write_points3d_text(path: &Path, points: &[ColmapPoint3D]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "# 3D point list with one line of data per point:")?;
+    writeln!(file, "#   POINT3D_ID, X, Y, Z, R, G, B, ERROR, TRACK[] as (IMAGE_ID, POINT2D_IDX)")?;
+    writeln!(
+        file,
+        "# Number of points: {}, mean track length: {}",
+        points.len(),
+        mean(points.iter().map(|point| point.track.len() as f64))
+    )?;
+    for point in points {
+        write!(
+            file,
+            "{} {} {} {} {} {} {} {}",
+            point.point3d_id, point.xyz[0], point.xyz[1], point.xyz[2],
+            point.rgb[0], point.rgb[1], point.rgb[2], point.error
+        )?;
+        for element in &point.track {
+            write!(file, " {} {}", element.image_id, element.point2d_idx)?;
+        }
+        writeln!(file)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+pub fn read_cameras_binary(path: &Path) -> Result<Vec<ColmapCamera>> {
+    let mut reader = BufReader::new(open(path)?);
+    let count = reader.read_u64::<LittleEndian>()?;
+    let mut cameras = Vec::with_capacity(count.min(1 << 16) as usize);
+    for _ in 0..count {
+        let camera_id = reader.read_i32::<LittleEndian>()?;
+        let (model, param_count) = model_by_id(reader.read_i32::<LittleEndian>()?)?;
+        let width = reader.read_u64::<LittleEndian>()?;
+        let height = reader.read_u64::<LittleEndian>()?;
+        let mut params = vec![0.0; param_count];
+        reader.read_f64_into::<LittleEndian>(&mut params)?;
+        cameras.push(ColmapCamera {
+            camera_id: camera_id as u32,
+            model: model.to_string(),
+            width: width as u32,
+            height: height as u32,
+            params,
+        });
+    }
+    Ok(cameras)
+}
+
+pub fn read_images_binary(path: &Path) -> Result<Vec<ColmapImage>> {
+    let mut reader = BufReader::new(open(path)?);
+    let count = reader.read_u64::<LittleEndian>()?;
+    let mut images = Vec::with_capacity(count.min(1 << 16) as usize);
+    for _ in 0..count {
+        let image_id = reader.read_i32::<LittleEndian>()? as u32;
+        let mut pose = [0.0; 7];
+        reader.read_f64_into::<LittleEndian>(&mut pose)?;
+        let camera_id = reader.read_i32::<LittleEndian>()? as u32;
+        let name = read_c_string(&mut reader)?;
+
+        let point_count = reader.read_u64::<LittleEndian>()?;
+        let mut points2d = Vec::with_capacity(point_count.min(1 << 20) as usize);
+        for _ in 0..point_count {
+            points2d.push(ColmapPoint2D {
+                x: reader.read_f64::<LittleEndian>()?,
+                y: reader.read_f64::<LittleEndian>()?,
+                point3d_id: reader.read_i64::<LittleEndian>()?,
+            });
+        }
+
+        let [qw, qx, qy, qz, tx, ty, tz] = pose;
+        images.push(ColmapImage { image_id, qw, qx, qy, qz, tx, ty, tz, camera_id, name, points2d });
+    }
+    Ok(images)
+}
+
+pub fn write_cameras_binary(path: &Path, cameras: &[ColmapCamera]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_u64::<LittleEndian>(cameras.len() as u64)?;
+    for camera in cameras {
+        let (model_id, param_count) = model_by_name(&camera.model)?;
+        if camera.params.len() != param_count {
+            return Err(HylaeanError::ConfigError {
+                message: format!(
+                    "camera {} ({}) has {} parameters, expected {}",
+                    camera.camera_id, camera.model, camera.params.len(), param_count
+                ),
+            });
+        }
+        file.write_i32::<LittleEndian>(camera.camera_id as i32)?;
+        file.write_i32::<LittleEndian>(model_id)?;
+        file.write_u64::<LittleEndian>(camera.width as u64)?;
+        file.write_u64::<LittleEndian>(camera.height as u64)?;
+        for param in &camera.params {
+            file.write_f64::<LittleEndian>(*param)?;
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
+
+pub fn write_images_binary(path: &Path, images: &[ColmapImage]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_u64::<LittleEndian>(images.len() as u64)?;
+    for image in images {
+        file.write_i32::<LittleEndian>(image.image_id as i32)?;
+        for value in [image.qw, image.qx, image.qy, image.qz, image.tx, image.ty, image.tz] {
+            file.write_f64::<LittleEndian>(value)?;
+        }
+        file.write_i32::<LittleEndian>(image.camera_id as i32)?;
+        file.write_all(image.name.as_bytes())?;
+        file.write_u8(0)?;
+        file.write_u64::<LittleEndian>(image.points2d.len() as u64)?;
+        for point in &image.points2d {
+            file.write_f64::<LittleEndian>(point.x)?;
+            file.write_f64::<LittleEndian>(point.y)?;
+            file.write_i64::<LittleEndian>(point.point3d_id)?;
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
+
+pub fn read_points3d_binary(path: &Path) -> Result<Vec<ColmapPoint3D>> {
+    let mut reader = BufReader::new(open(path)?);
+    let count = reader.read_u64::<LittleEndian>()?;
+    let mut points = Vec::with_capacity(count.min(1 << 24) as usize);
+    for _ in 0..count {
+        let point3d_id = reader.read_u64::<LittleEndian>()?;
+        let mut xyz = [0.0; 3];
+        reader.read_f64_into::<LittleEndian>(&mut xyz)?;
+        let mut rgb = [0u8; 3];
+        reader.read_exact(&mut rgb)?;
+        let error = reader.read_f64::<LittleEndian>()?;
+        let track_length = reader.read_u64::<LittleEndian>()?;
+        let mut track = Vec::with_capacity(track_length.min(1 << 16) as usize);
+        for _ in 0..track_length {
+            track.push(TrackElement {
+                image_id: reader.read_i32::<LittleEndian>()? as u32,
+                point2d_idx: reader.read_i32::<LittleEndian>()? as u32,
+            });
+        }
+        points.push(ColmapPoint3D { point3d_id, xyz, rgb, error, track });
+    }
+    Ok(points)
+}
+
+pub fn write_points3d_binary(path: &Path, points: &[ColmapPoint3D]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_u64::<LittleEndian>(points.len() as u64)?;
+    for point in points {
+        file.write_u64::<LittleEndian>(point.point3d_id)?;
+        for value in point.xyz {
+            file.write_f64::<LittleEndian>(value)?;
+        }
+        file.write_all(&point.rgb)?;
+        file.write_f64::<LittleEndian>(point.error)?;
+        file.write_u64::<LittleEndian>(point.track.len() as u64)?;
+        for element in &point.track {
+            file.write_i32::<LittleEndian>(element.image_id as i32)?;
+            file.write_i32::<LittleEndian>(element.point2d_idx as i32)?;
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// `points3D` as a colored point cloud for any streaming sink, laid out like the
+/// `points3D.ply` the reference 3DGS trainer writes (zero normals included), plus the
+/// reprojection `error` and `track_length` of each point
+pub struct Points3DSource {
+    points: Vec<ColmapPoint3D>,
+    schema: Schema,
+    next: usize,
+}
+
+impl Points3DSource {
+    pub fn new(points: Vec<ColmapPoint3D>) -> Self {
+        let mut schema: Schema = ["x", "y", "z", "nx", "ny", "nz"].iter()
+            .map(|name| (name.to_string(), ScalarType::Float))
+            .collect();
+        schema.extend(["red", "green", "blue"].iter().map(|name| (name.to_string(), ScalarType::UChar)));
+        schema.push(("error".to_string(), ScalarType::Float));
+        schema.push(("track_length".to_string(), ScalarType::UInt));
+        Self { points, schema, next: 0 }
+    }
+}
+
+impl PointSource for Points3DSource {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.points.len())
+    }
+
+    fn is_binary(&self) -> bool {
+        true
+    }
+
+    fn comments(&self) -> Vec<String> {
+        vec![format!("hylaeansplat COLMAP points3D ({} points)", self.points.len())]
+    }
+
+    fn read_chunk(&mut self, chunk: &mut PointChunk) -> Result<usize> {
+        chunk.clear();
+        while !chunk.is_full() && self.next < self.points.len() {
+            let point = &self.points[self.next];
+            let record = chunk.push_slot();
+            record.0.extend(point.xyz.map(|v| Property::Float(v as f32)));
+            record.0.extend([Property::Float(0.0), Property::Float(0.0), Property::Float(0.0)]);
+            record.0.extend(point.rgb.map(Property::UChar));
+            record.0.push(Property::Float(point.error as f32));
+            record.0.push(Property::UInt(point.track.len() as u32));
+            self.next += 1;
+        }
+        Ok(chunk.len())
+    }
+}
+
+/// Track-less 3D points from a point cloud with `x`, `y`, `z` and optionally `red`,
+/// `green`, `blue` (8-bit, or floats in 0..1); points are numbered from 1
+pub fn points_from_cloud(source: &mut dyn PointSource) -> Result<Vec<ColmapPoint3D>> {
+    let schema = source.schema().clone();
+    let column = |name: &str| schema.iter().position(|(column, _)| column == name);
+    let [Some(x), Some(y), Some(z)] = ["x", "y", "z"].map(column) else {
+        return Err(HylaeanError::UnsupportedFormat {
+            format: "point cloud without x, y and z attributes".to_string(),
+        });
+    };
+    let colors = ["red", "green", "blue"].map(|name| {
+        column(name).map(|index| {
+            let scale = if matches!(schema[index].1, ScalarType::Float | ScalarType::Double) { 255.0 } else { 1.0 };
+            (index, scale)
+        })
+    });
+
+    let mut points = Vec::with_capacity(source.point_count().unwrap_or(0));
+    let mut chunk = PointChunk::with_capacity(stream::DEFAULT_CHUNK_SIZE);
+    while source.read_chunk(&mut chunk)? > 0 {
+        for record in chunk.records() {
+            let value = |index: usize| ply::scalar_as_f64(&record.0[index]).unwrap_or(f64::NAN);
+            points.push(ColmapPoint3D {
+                point3d_id: points.len() as u64 + 1,
+                xyz: [value(x), value(y), value(z)],
+                rgb: colors.map(|color| match color {
+                    Some((index, scale)) => (value(index) * scale).round().clamp(0.0, 255.0) as u8,
+                    None => 128,
+                }),
+                error: 0.0,
+                track: Vec::new(),
+            });
+        }
+    }
+    Ok(points)
+}
+
+/// Summary statistics of a reconstruction, matching COLMAP's `model_analyzer`
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelStats {
+    pub cameras: usize,
+    pub images: usize,
+    pub points: usize,
+    /// Sum of all track lengths
+    pub observations: usize,
+    pub mean_track_length: f64,
+    /// Observations per image
+    pub mean_observations_per_image: f64,
+    /// Mean over points of their reprojection error, in pixels
+    pub mean_reprojection_error: f64,
+}
+
+pub fn model_stats(cameras: &[ColmapCamera], images: &[ColmapImage], points: &[ColmapPoint3D]) -> ModelStats {
+    let observations: usize = points.iter().map(|point| point.track.len()).sum();
+    ModelStats {
+        cameras: cameras.len(),
+        images: images.len(),
+        points: points.len(),
+        observations,
+        mean_track_length: mean(points.iter().map(|point| point.track.len() as f64)),
+        mean_observations_per_image: if images.is_empty() { 0.0 } else { observations as f64 / images.len() as f64 },
+        mean_reprojection_error: mean(points.iter().map(|point| point.error)),
+    }
+}
+
+impl fmt::Display for ModelStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = |f: &mut fmt::Formatter<'_>, label: &str, value: &dyn fmt::Display| {
+            writeln!(f, "  {:<29} {}", format!("{}:", label), value)
+        };
+        field(f, "Cameras", &self.cameras)?;
+        field(f, "Images", &self.images)?;
+        field(f, "Points", &self.points)?;
+        field(f, "Observations", &self.observations)?;
+        field(f, "Mean track length", &format!("{:.6}", self.mean_track_length))?;
+        field(f, "Mean observations per image", &format!("{:.6}", self.mean_observations_per_image))?;
+        field(f, "Mean reprojection error", &format!("{:.6}px", self.mean_reprojection_error))
+    }
+}
+
+fn mean<I: Iterator<Item = f64>>(values: I) -> f64 {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), value| (sum + value, count + 1));
+    if count == 0 { 0.0 } else { sum / count as f64 }
+}
+
+fn read_c_string<R: Read>(reader: &mut R) -> Result<String> {
+    let mut bytes = Vec::new();
+    loop {
+        match reader.read_u8()? {
+            0 => break,
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| HylaeanError::UnsupportedFormat {
+        format: "COLMAP image name that is not UTF-8".to_string(),
+    })
+}
+
+fn open(path: &Path) -> Result<File> {
+    if !path.is_file() {
+        return Err(HylaeanError::InvalidPath {
+            path: path.display().to_string(),
+        });
+    }
+    Ok(File::open(path)?)
+}
+
+fn malformed(path: &Path, line: &str) -> HylaeanError {
+    HylaeanError::UnsupportedFormat {
+        format: format!("malformed line in {}: '{}'", path.display(), line),
+    }
+}
@@ -0,0 +1,61 @@
+//! Image dimensions from PNG and JPEG headers, for camera files that omit them
+
+use crate::errors::{Result, HylaeanError};
+use byteorder::{BigEndian, ReadBytesExt};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// `[width, height]` of a PNG or JPEG image, reading only its header
+pub fn read_image_size(path: &Path) -> Result<[u32; 2]> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut signature = [0u8; 8];
+    reader.read_exact(&mut signature)?;
+
+    if signature == PNG_SIGNATURE {
+        // The IHDR chunk comes first: length, type, then width and height
+        reader.seek(SeekFrom::Start(16))?;
+        return Ok([reader.read_u32::<BigEndian>()?, reader.read_u32::<BigEndian>()?]);
+    }
+    if signature[..2] == [0xff, 0xd8] {
+        reader.seek(SeekFrom::Start(2))?;
+        return jpeg_size(&mut reader).ok_or_else(|| unsupported(path));
+    }
+    Err(unsupported(path))
+}
+
+/// Walk the JPEG markers up to the first start-of-frame segment
+fn jpeg_size<R: Read + Seek>(reader: &mut R) -> Option<[u32; 2]> {
+    loop {
+        if reader.read_u8().ok()? != 0xff {
+            return None;
+        }
+        let mut marker = reader.read_u8().ok()?;
+        while marker == 0xff {
+            marker = reader.read_u8().ok()?;
+        }
+        match marker {
+            // Standalone markers without a length
+            0x01 | 0xd0..=0xd7 => continue,
+            0xd9 | 0xda => return None,
+            _ => {}
+        }
+        let length = reader.read_u16::<BigEndian>().ok()?;
+        // SOF0..SOF15, except DHT (c4), JPG (c8) and DAC (cc)
+        if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let _precision = reader.read_u8().ok()?;
+            let height = reader.read_u16::<BigEndian>().ok()?;
+            let width = reader.read_u16::<BigEndian>().ok()?;
+            return Some([width as u32, height as u32]);
+        }
+        reader.seek(SeekFrom::Current(length as i64 - 2)).ok()?;
+    }
+}
+
+fn unsupported(path: &Path) -> HylaeanError {
+    HylaeanError::UnsupportedFormat {
+        format: format!("image {} (only PNG and JPEG sizes can be read)", path.display()),
+    }
+}
@@ -0,0 +1,227 @@
+//! LLFF `poses_bounds.npy`
+//!
+//! An N x 17 array with one row per image: a 3x5 matrix in row-major order, whose first
+//! four columns are the camera-to-world pose in (down, right, backwards) camera axes and
+//! whose last column is the image height, width and focal length, then the near and far
+//! scene depths. Rows follow the sorted file names in `images/`, as LLFF's `imgs2poses.py`
+//! writes them and every loader reads them back.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::npy::{self, NpyArray};
+use super::camera_model::{CameraModel, OpenCvDistortion};
+use super::colmap::{ColmapCamera, ColmapImage, ColmapPoint3D};
+use super::pose::{CameraAxes, Pose};
+use log::warn;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const POSES_BOUNDS_FILE: &str = "poses_bounds.npy";
+
+/// Percentiles of the visible point depths taken as the near and far bounds, as in `imgs2poses.py`
+const NEAR_PERCENTILE: f64 = 0.1;
+const FAR_PERCENTILE: f64 = 99.9;
+
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// One row of `poses_bounds.npy`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlffView {
+    /// Camera-to-world with LLFF camera axes
+    pub camera_to_world: Pose,
+    pub height: u32,
+    pub width: u32,
+    pub focal: f64,
+    pub near: f64,
+    pub far: f64,
+}
+
+/// `poses_bounds.npy` inside a scene directory, or the path itself when it names a file
+pub fn poses_bounds_path(path: &Path) -> PathBuf {
+    if path.is_dir() || path.extension().is_none() {
+        path.join(POSES_BOUNDS_FILE)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+pub fn read(path: &Path) -> Result<Vec<LlffView>> {
+    let array = npy::read(path)?;
+    if array.shape.len() != 2 || array.shape[1] != 17 {
+        return Err(HylaeanError::UnsupportedFormat {
+            format: format!("LLFF poses of shape {:?} in {} (expected N x 17)", array.shape, path.display()),
+        });
+    }
+    Ok(array
+        .rows()
+        .map(|row| {
+            let matrix = [0, 1, 2].map(|r| [row[r * 5], row[r * 5 + 1], row[r * 5 + 2], row[r * 5 + 3]]);
+            LlffView {
+                camera_to_world: Pose::from_matrix(&[matrix[0], matrix[1], matrix[2], [0.0, 0.0, 0.0, 1.0]]),
+                height: row[4].round() as u32,
+                width: row[9].round() as u32,
+                focal: row[14],
+                near: row[15],
+                far: row[16],
+            }
+        })
+        .collect())
+}
+
+pub fn write(path: &Path, views: &[LlffView]) -> Result<()> {
+    let mut data = Vec::with_capacity(views.len() * 17);
+    for view in views {
+        let matrix = view.camera_to_world.to_matrix();
+        let hwf = [view.height as f64, view.width as f64, view.focal];
+        for (row, value) in matrix.iter().take(3).zip(hwf) {
+            data.extend_from_slice(row);
+            data.push(value);
+        }
+        data.extend_from_slice(&[view.near, view.far]);
+    }
+    npy::write(path, &NpyArray::new(vec![views.len(), 17], data)?)
+}
+
+/// Names of the `count` images in `scene_dir/images`, sorted as the rows are. Without a
+/// matching directory the images are numbered instead.
+pub fn image_names(scene_dir: &Path, count: usize) -> Vec<String> {
+    let images_dir = scene_dir.join("images");
+    let mut names: Vec<String> = std::fs::read_dir(&images_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| {
+                    let extension = Path::new(name).extension().and_then(|e| e.to_str()).unwrap_or("");
+                    IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    if names.len() == count {
+        return names;
+    }
+    warn!(
+        "Found {} images in {} for {} LLFF poses; naming the images by index",
+        names.len(), images_dir.display(), count
+    );
+    (0..count).map(|i| format!("{:05}.png", i)).collect()
+}
+
+/// COLMAP cameras and images for LLFF views; views with the same size and focal length
+/// share a centered SIMPLE_PINHOLE camera
+pub fn to_colmap(views: &[LlffView], names: &[String]) -> (Vec<ColmapCamera>, Vec<ColmapImage>) {
+    let mut cameras: Vec<ColmapCamera> = Vec::new();
+    let mut images = Vec::with_capacity(views.len());
+    for (i, (view, name)) in views.iter().zip(names).enumerate() {
+        let model = CameraModel::SimplePinhole {
+            f: view.focal,
+            cx: view.width as f64 / 2.0,
+            cy: view.height as f64 / 2.0,
+        };
+        let camera = ColmapCamera::from_model(cameras.len() as u32 + 1, view.width, view.height, &model);
+        let camera_id = match cameras.iter().find(|existing| {
+            (existing.width, existing.height, &existing.params) == (camera.width, camera.height, &camera.params)
+        }) {
+            Some(existing) => existing.camera_id,
+            None => {
+                cameras.push(camera);
+                cameras.len() as u32
+            }
+        };
+
+        let world_to_camera = view.camera_to_world.change_camera_axes(CameraAxes::Llff, CameraAxes::OpenCv).inverse();
+        images.push(ColmapImage::new(i as u32 + 1, camera_id, name.clone(), &world_to_camera));
+    }
+    (cameras, images)
+}
+
+/// LLFF views of COLMAP images, sorted by name, with bounds from the depths of the points
+/// each image observes. Points without tracks count for every camera they lie in front of.
+pub fn from_colmap(cameras: &[ColmapCamera], images: &[ColmapImage], points: &[ColmapPoint3D]) -> Result<Vec<LlffView>> {
+    let cameras: HashMap<u32, &ColmapCamera> = cameras.iter().map(|camera| (camera.camera_id, camera)).collect();
+    let mut observed: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (index, point) in points.iter().enumerate() {
+        for element in &point.track {
+            observed.entry(element.image_id).or_default().push(index);
+        }
+    }
+
+    let mut sorted: Vec<&ColmapImage> = images.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut views = Vec::with_capacity(sorted.len());
+    let mut bounds = Vec::with_capacity(sorted.len());
+    for image in sorted {
+        let camera = cameras.get(&image.camera_id).ok_or_else(|| HylaeanError::ConfigError {
+            message: format!("COLMAP image {} refers to missing camera {}", image.name, image.camera_id),
+        })?;
+        let model = camera.camera_model()?;
+        check_representable(&model, camera);
+
+        let camera_to_world = image.camera_to_world();
+        let center = camera_to_world.translation;
+        let forward = [0, 1, 2].map(|i| camera_to_world.rotation[i][2]);
+        let depth = |point: &ColmapPoint3D| (0..3).map(|i| (point.xyz[i] - center[i]) * forward[i]).sum::<f64>();
+        let mut depths: Vec<f64> = match observed.get(&image.image_id) {
+            Some(indices) => indices.iter().map(|index| depth(&points[*index])).collect(),
+            None => points.iter().filter(|point| point.track.is_empty()).map(depth).filter(|z| *z > 0.0).collect(),
+        };
+        depths.retain(|z| z.is_finite());
+        depths.sort_by(f64::total_cmp);
+        bounds.push((!depths.is_empty()).then(|| (percentile(&depths, NEAR_PERCENTILE), percentile(&depths, FAR_PERCENTILE))));
+
+        views.push(LlffView {
+            camera_to_world: camera_to_world.change_camera_axes(CameraAxes::OpenCv, CameraAxes::Llff),
+            height: camera.height,
+            width: camera.width,
+            focal: model.focal_length()[0],
+            near: 0.0,
+            far: 0.0,
+        });
+    }
+
+    // Images that see no points take the bounds of the whole scene
+    let known: Vec<(f64, f64)> = bounds.iter().flatten().copied().collect();
+    if known.is_empty() && !views.is_empty() {
+        return Err(HylaeanError::ConfigError {
+            message: "LLFF near/far bounds need 3D points, but the cameras see none".to_string(),
+        });
+    }
+    let scene = known.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(near, far), (n, f)| (near.min(*n), far.max(*f)));
+    let missing = bounds.iter().filter(|bound| bound.is_none()).count();
+    if missing > 0 {
+        warn!("{} images observe no 3D points; using the scene bounds {:.3}..{:.3} for them", missing, scene.0, scene.1);
+    }
+    for (view, bound) in views.iter_mut().zip(bounds) {
+        (view.near, view.far) = bound.unwrap_or(scene);
+    }
+    Ok(views)
+}
+
+/// LLFF cameras are undistorted with one focal length and a centered principal point
+fn check_representable(model: &CameraModel, camera: &ColmapCamera) {
+    let [fx, fy] = model.focal_length();
+    if (fx - fy).abs() > 1e-6 * fx.abs() {
+        warn!("LLFF has one focal length; camera {} uses fx {} for fy {}", camera.camera_id, fx, fy);
+    }
+    let [cx, cy] = model.principal_point();
+    if (cx - camera.width as f64 / 2.0).abs() > 0.5 || (cy - camera.height as f64 / 2.0).abs() > 0.5 {
+        warn!("LLFF assumes a centered principal point; camera {} has ({}, {})", camera.camera_id, cx, cy);
+    }
+    let distorted = match model.opencv_distortion() {
+        Some(OpenCvDistortion::Standard(coefficients)) => coefficients.iter().any(|c| *c != 0.0),
+        Some(OpenCvDistortion::Fisheye(_)) | None => true,
+    };
+    if distorted {
+        warn!("LLFF expects undistorted images; dropping the {} distortion of camera {}", model.name(), camera.camera_id);
+    }
+}
+
+/// numpy's default (linear) percentile of sorted values
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = percent / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
@@ -0,0 +1,624 @@
+//! Camera parameter format handling and conversion
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::{DataFormat, CameraFormat, DatasetFormat, FormatConverter, PointCloudFormat};
+use crate::formats::ply::Encoding;
+use crate::formats::pcd::PcdData;
+use crate::formats::point_cloud::stream::{self, SinkOptions};
+use std::collections::HashMap;
+use std::path::Path;
+use log::{info, debug, warn};
+
+pub mod align;
+pub mod alicevision;
+pub mod arkit;
+pub mod blender;
+pub mod bundler;
+pub mod camera_model;
+pub mod colmap;
+pub mod image_size;
+pub mod llff;
+pub mod nerf;
+pub mod opencv;
+pub mod polycam;
+pub mod pose;
+pub mod record3d;
+
+pub use align::{AlignOptions, Alignment, Ransac};
+pub use alicevision::SfmData;
+pub use arkit::CaptureFrame;
+pub use blender::{BlenderCamera, BlenderCameras};
+pub use bundler::{BundlerCamera, BundlerPoint};
+pub use camera_model::{CameraModel, OpenCvDistortion};
+pub use llff::LlffView;
+pub use colmap::{ColmapCamera, ColmapImage, ColmapPoint2D, ColmapPoint3D, ModelEncoding};
+pub use nerf::{NeRFCamera, NeRFFrame, NeRFIntrinsics};
+pub use opencv::OpenCvCalibration;
+pub use pose::{CameraAxes, Pose};
+
+/// File name of the sparse point cloud written next to a nerfstudio `transforms.json`
+pub const NERFSTUDIO_PLY_FILE: &str = "sparse_pc.ply";
+
+pub struct CameraParamsConverter {
+    colmap_encoding: Option<ModelEncoding>,
+}
+
+impl FormatConverter for CameraParamsConverter {
+    fn can_convert(&self, from: &DataFormat, to: &DataFormat) -> bool {
+        matches!(
+            (from, to),
+            (DataFormat::CameraParameters(_), DataFormat::CameraParameters(_))
+                | (DataFormat::Dataset(DatasetFormat::LLFF), DataFormat::CameraParameters(_))
+                | (DataFormat::CameraParameters(_), DataFormat::Dataset(DatasetFormat::LLFF))
+        )
+    }
+    
+    fn convert(&self, input_path: &Path, output_path: &Path, from: &DataFormat, to: &DataFormat) -> Result<()> {
+        match (from, to) {
+            (DataFormat::CameraParameters(from_fmt), DataFormat::CameraParameters(to_fmt)) => {
+                self.convert_camera_params(input_path, output_path, from_fmt, to_fmt)
+            }
+            (DataFormat::Dataset(DatasetFormat::LLFF), DataFormat::CameraParameters(to_fmt)) => {
+                self.llff_to_camera_params(input_path, output_path, to_fmt)
+            }
+            (DataFormat::CameraParameters(from_fmt), DataFormat::Dataset(DatasetFormat::LLFF)) => {
+                self.camera_params_to_llff(input_path, output_path, from_fmt)
+            }
+            _ => Err(HylaeanError::ConversionFailed {
+                source_format: format!("{:?}", from),
+                target_format: format!("{:?}", to),
+            }),
+        }
+    }
+}
+
+impl CameraParamsConverter {
+    pub fn new() -> Self {
+        Self { colmap_encoding: None }
+    }
+    
+    /// Force the encoding of COLMAP output. By default COLMAP to COLMAP keeps the source
+    /// encoding and other sources are written as text.
+    pub fn with_colmap_encoding(mut self, encoding: ModelEncoding) -> Self {
+        self.colmap_encoding = Some(encoding);
+        self
+    }
+    
+    fn convert_camera_params(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        from_format: &CameraFormat,
+        to_format: &CameraFormat,
+    ) -> Result<()> {
+        info!("Converting camera parameters: {:?} -> {:?}", from_format, to_format);
+        
+        match (from_format, to_format) {
+            (CameraFormat::COLMAP, CameraFormat::COLMAP) => {
+                self.colmap_to_colmap(input_path, output_path)
+            }
+            (CameraFormat::COLMAP, CameraFormat::NeRF) => {
+                self.colmap_to_nerf(input_path, output_path)
+            }
+            (CameraFormat::NeRF, CameraFormat::COLMAP) => {
+                self.nerf_to_colmap(input_path, output_path)
+            }
+            (CameraFormat::COLMAP, CameraFormat::OpenCV) => {
+                self.colmap_to_opencv(input_path, output_path)
+            }
+            (CameraFormat::OpenCV, CameraFormat::COLMAP) => {
+                self.opencv_to_colmap(input_path, output_path)
+            }
+            (CameraFormat::COLMAP | CameraFormat::NeRF, CameraFormat::Blender) => {
+                self.camera_params_to_blender(input_path, output_path, from_format)
+            }
+            (CameraFormat::Blender, CameraFormat::COLMAP | CameraFormat::NeRF) => {
+                self.blender_to_camera_params(input_path, output_path, to_format)
+            }
+            (CameraFormat::Polycam | CameraFormat::Record3D | CameraFormat::ARKit, CameraFormat::COLMAP | CameraFormat::NeRF) => {
+                self.capture_to_camera_params(input_path, output_path, from_format, to_format)
+            }
+            (CameraFormat::Bundler | CameraFormat::AliceVision, CameraFormat::COLMAP | CameraFormat::NeRF) => {
+                self.reconstruction_to_camera_params(input_path, output_path, from_format, to_format)
+            }
+            _ => {
+                warn!("Conversion not implemented: {:?} -> {:?}", from_format, to_format);
+                Err(HylaeanError::ConversionFailed {
+                    source_format: format!("{:?}", from_format),
+                    target_format: format!("{:?}", to_format),
+                })
+            }
+        }
+    }
+    
+    fn colmap_to_colmap(&self, input_path: &Path, output_path: &Path) -> Result<()> {
+        debug!("Transcoding COLMAP model: {} -> {}", input_path.display(), output_path.display());
+        
+        let input_dir = colmap::model_dir(input_path);
+        let encoding = match self.colmap_encoding {
+            Some(encoding) => encoding,
+            None => colmap::detect_encoding(&input_dir)?,
+        };
+        let cameras = colmap::read_cameras(&input_dir)?;
+        let images = colmap::read_images(&input_dir)?;
+        
+        std::fs::create_dir_all(output_path)?;
+        colmap::write_cameras(output_path, &cameras, encoding)?;
+        colmap::write_images(output_path, &images, encoding)?;
+        if colmap::has_points3d(&input_dir, colmap::detect_encoding(&input_dir)?) {
+            let points = colmap::read_points3d(&input_dir)?;
+            colmap::write_points3d(output_path, &points, encoding)?;
+            info!("Wrote {} 3D points", points.len());
+        }
+        
+        info!("Wrote {:?} COLMAP model with {} cameras and {} images", encoding, cameras.len(), images.len());
+        Ok(())
+    }
+    
+    fn colmap_to_nerf(&self, input_path: &Path, output_path: &Path) -> Result<()> {
+        debug!("Converting COLMAP to NeRF format: {} -> {}", input_path.display(), output_path.display());
+        
+        // Read COLMAP cameras and images
+        let cameras = self.read_colmap_cameras(input_path)?;
+        let images = self.read_colmap_images(input_path)?;
+        
+        // Convert to NeRF format
+        let mut nerf_data = self.convert_colmap_to_nerf_data(&cameras, &images)?;
+        
+        let input_dir = colmap::model_dir(input_path);
+        if colmap::has_points3d(&input_dir, colmap::detect_encoding(&input_dir)?) {
+            self.write_nerf_points(output_path, colmap::read_points3d(&input_dir)?, &mut nerf_data)?;
+        }
+        
+        // Write NeRF JSON
+        nerf_data.write(output_path)?;
+        
+        info!("Converted COLMAP to NeRF format with {} frames", nerf_data.frames.len());
+        Ok(())
+    }
+    
+    fn nerf_to_colmap(&self, input_path: &Path, output_path: &Path) -> Result<()> {
+        debug!("Converting NeRF to COLMAP format: {} -> {}", input_path.display(), output_path.display());
+        
+        // Read NeRF JSON
+        let nerf_data = NeRFCamera::read(input_path)?;
+        let base_dir = input_path.parent().unwrap_or(Path::new("."));
+        
+        // Convert to COLMAP format
+        let (cameras, images) = self.convert_nerf_to_colmap_data(&nerf_data, base_dir)?;
+        
+        let points = self.read_nerf_points(&nerf_data, base_dir)?;
+        
+        // Write COLMAP files
+        self.write_colmap_cameras(output_path, &cameras)?;
+        self.write_colmap_images(output_path, &images)?;
+        colmap::write_points3d(output_path, &points, self.colmap_encoding.unwrap_or(ModelEncoding::Text))?;
+        
+        info!("Converted NeRF to COLMAP format with {} cameras and {} images", cameras.len(), images.len());
+        Ok(())
+    }
+    
+    /// The sparse points go next to transforms.json, as nerfstudio's ns-process-data does
+    fn write_nerf_points(&self, output_path: &Path, points: Vec<ColmapPoint3D>, nerf_data: &mut NeRFCamera) -> Result<()> {
+        let ply_path = output_path.with_file_name(NERFSTUDIO_PLY_FILE);
+        let mut source = colmap::Points3DSource::new(points);
+        let options = SinkOptions { ply_encoding: Encoding::BinaryLittleEndian, pcd_data: PcdData::Binary };
+        let sink = stream::create_sink(&ply_path, &PointCloudFormat::PLY, &source, options)?;
+        let written = stream::pump(&mut source, sink, stream::DEFAULT_CHUNK_SIZE)?;
+        info!("Wrote {} sparse points to {}", written, ply_path.display());
+        nerf_data.ply_file_path = Some(NERFSTUDIO_PLY_FILE.to_string());
+        Ok(())
+    }
+    
+    /// The sparse points referenced by `ply_file_path`, in COLMAP's world; nerfstudio
+    /// transformed them with the poses, so `applied_transform` is undone here too
+    fn read_nerf_points(&self, nerf_data: &NeRFCamera, base_dir: &Path) -> Result<Vec<ColmapPoint3D>> {
+        let Some(ply_file) = &nerf_data.ply_file_path else {
+            return Ok(Vec::new());
+        };
+        let ply_path = base_dir.join(ply_file);
+        if !ply_path.is_file() {
+            warn!("Point cloud {} not found; continuing without 3D points", ply_path.display());
+            return Ok(Vec::new());
+        }
+        
+        let mut source = stream::open_source(&ply_path, &PointCloudFormat::PLY)?;
+        let mut points = colmap::points_from_cloud(source.as_mut())?;
+        let to_colmap = nerf_data.applied_transform().inverse();
+        for point in &mut points {
+            point.xyz = to_colmap.transform_point(point.xyz);
+        }
+        Ok(points)
+    }
+    
+    fn llff_to_camera_params(&self, input_path: &Path, output_path: &Path, to_format: &CameraFormat) -> Result<()> {
+        debug!("Converting LLFF to {:?} format: {} -> {}", to_format, input_path.display(), output_path.display());
+        
+        let poses_path = llff::poses_bounds_path(input_path);
+        let views = llff::read(&poses_path)?;
+        let scene_dir = poses_path.parent().unwrap_or(Path::new("."));
+        let (cameras, images) = llff::to_colmap(&views, &llff::image_names(scene_dir, views.len()));
+        
+        match to_format {
+            CameraFormat::COLMAP => {
+                // LLFF keeps no points; an empty points3D completes the model
+                self.write_colmap_cameras(output_path, &cameras)?;
+                self.write_colmap_images(output_path, &images)?;
+                colmap::write_points3d(output_path, &[], self.colmap_encoding.unwrap_or(ModelEncoding::Text))?;
+            }
+            CameraFormat::NeRF => {
+                self.convert_colmap_to_nerf_data(&cameras, &images)?.write(output_path)?;
+            }
+            _ => {
+                return Err(HylaeanError::ConversionFailed {
+                    source_format: "LLFF".to_string(),
+                    target_format: format!("{:?}", to_format),
+                })
+            }
+        }
+        
+        info!("Converted {} LLFF poses to {:?} format", views.len(), to_format);
+        Ok(())
+    }
+    
+    fn camera_params_to_llff(&self, input_path: &Path, output_path: &Path, from_format: &CameraFormat) -> Result<()> {
+        debug!("Converting {:?} to LLFF format: {} -> {}", from_format, input_path.display(), output_path.display());
+        
+        let (cameras, images, points) = match from_format {
+            CameraFormat::COLMAP => {
+                let input_dir = colmap::model_dir(input_path);
+                let points = if colmap::has_points3d(&input_dir, colmap::detect_encoding(&input_dir)?) {
+                    colmap::read_points3d(&input_dir)?
+                } else {
+                    Vec::new()
+                };
+                (colmap::read_cameras(&input_dir)?, colmap::read_images(&input_dir)?, points)
+            }
+            CameraFormat::NeRF => {
+                let nerf_data = NeRFCamera::read(input_path)?;
+                let base_dir = input_path.parent().unwrap_or(Path::new("."));
+                let (cameras, images) = self.convert_nerf_to_colmap_data(&nerf_data, base_dir)?;
+                (cameras, images, self.read_nerf_points(&nerf_data, base_dir)?)
+            }
+            _ => {
+                return Err(HylaeanError::ConversionFailed {
+                    source_format: format!("{:?}", from_format),
+                    target_format: "LLFF".to_string(),
+                })
+            }
+        };
+        
+        let views = llff::from_colmap(&cameras, &images, &points)?;
+        let poses_path = llff::poses_bounds_path(output_path);
+        if let Some(parent) = poses_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        llff::write(&poses_path, &views)?;
+        
+        info!("Wrote {} LLFF poses, in image name order, to {}", views.len(), poses_path.display());
+        Ok(())
+    }
+    
+    fn colmap_to_opencv(&self, input_path: &Path, output_path: &Path) -> Result<()> {
+        debug!("Converting COLMAP to OpenCV format: {} -> {}", input_path.display(), output_path.display());
+        
+        let cameras = self.read_colmap_cameras(input_path)?;
+        let images = self.read_colmap_images(input_path)?;
+        
+        // One FileStorage file per camera, holding the poses of the images it took
+        let camera_ids: Vec<u32> = cameras.iter().map(|camera| camera.camera_id).collect();
+        let paths = opencv::calibration_paths(output_path, &camera_ids);
+        if let Some(parent) = paths.first().and_then(|path| path.parent()).filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        for (camera, path) in cameras.iter().zip(&paths) {
+            let views = images.iter()
+                .filter(|image| image.camera_id == camera.camera_id)
+                .map(|image| (image.name.clone(), image.world_to_camera()))
+                .collect();
+            let calibration = OpenCvCalibration::from_model(&camera.camera_model()?, camera.width, camera.height, views);
+            calibration.write(path)?;
+            debug!("Wrote camera {} to {}", camera.camera_id, path.display());
+        }
+        
+        info!("Converted {} COLMAP cameras and {} images to OpenCV format", cameras.len(), images.len());
+        Ok(())
+    }
+    
+    fn opencv_to_colmap(&self, input_path: &Path, output_path: &Path) -> Result<()> {
+        debug!("Converting OpenCV to COLMAP format: {} -> {}", input_path.display(), output_path.display());
+        
+        // A single calibration file, or a rig directory with one file per camera
+        let files = opencv::calibration_files(input_path)?;
+        if files.is_empty() {
+            return Err(HylaeanError::ConfigError {
+                message: format!("no OpenCV calibration files (.yml, .yaml, .xml) in {}", input_path.display()),
+            });
+        }
+        
+        let mut cameras = Vec::with_capacity(files.len());
+        let mut images = Vec::new();
+        for (index, file) in files.iter().enumerate() {
+            let calibration = OpenCvCalibration::read(file)?;
+            let camera_id = index as u32 + 1;
+            cameras.push(ColmapCamera::from_model(camera_id, calibration.image_width, calibration.image_height, &calibration.to_model()?));
+            for (name, world_to_camera) in calibration.views {
+                images.push(ColmapImage::new(images.len() as u32 + 1, camera_id, name, &world_to_camera));
+            }
+        }
+        
+        self.write_colmap_cameras(output_path, &cameras)?;
+        self.write_colmap_images(output_path, &images)?;
+        colmap::write_points3d(output_path, &[], self.colmap_encoding.unwrap_or(ModelEncoding::Text))?;
+        
+        info!("Converted OpenCV calibrations to COLMAP format with {} cameras and {} images", cameras.len(), images.len());
+        Ok(())
+    }
+    
+    fn camera_params_to_blender(&self, input_path: &Path, output_path: &Path, from_format: &CameraFormat) -> Result<()> {
+        debug!("Converting {:?} to Blender cameras: {} -> {}", from_format, input_path.display(), output_path.display());
+        
+        let (cameras, images) = if let CameraFormat::NeRF = from_format {
+            let nerf_data = NeRFCamera::read(input_path)?;
+            self.convert_nerf_to_colmap_data(&nerf_data, input_path.parent().unwrap_or(Path::new(".")))?
+        } else {
+            (self.read_colmap_cameras(input_path)?, self.read_colmap_images(input_path)?)
+        };
+        
+        let blender = BlenderCameras::from_colmap(&cameras, &images)?;
+        blender.write(output_path)?;
+        
+        info!("Converted {} images to Blender cameras", blender.cameras.len());
+        Ok(())
+    }
+    
+    fn blender_to_camera_params(&self, input_path: &Path, output_path: &Path, to_format: &CameraFormat) -> Result<()> {
+        debug!("Converting Blender cameras to {:?}: {} -> {}", to_format, input_path.display(), output_path.display());
+        
+        let blender = BlenderCameras::read(input_path)?;
+        let (cameras, images) = blender.to_colmap()?;
+        
+        if let CameraFormat::NeRF = to_format {
+            self.convert_colmap_to_nerf_data(&cameras, &images)?.write(output_path)?;
+        } else {
+            self.write_colmap_cameras(output_path, &cameras)?;
+            self.write_colmap_images(output_path, &images)?;
+            colmap::write_points3d(output_path, &[], self.colmap_encoding.unwrap_or(ModelEncoding::Text))?;
+        }
+        
+        info!("Converted {} Blender cameras to {:?} format", blender.cameras.len(), to_format);
+        Ok(())
+    }
+    
+    fn capture_to_camera_params(&self, input_path: &Path, output_path: &Path, from_format: &CameraFormat, to_format: &CameraFormat) -> Result<()> {
+        debug!("Converting {:?} capture to {:?}: {} -> {}", from_format, to_format, input_path.display(), output_path.display());
+        
+        let frames = self.read_capture(input_path, from_format)?;
+        let (cameras, images) = arkit::to_colmap(&frames);
+        
+        let image_root = if let CameraFormat::NeRF = to_format {
+            self.convert_colmap_to_nerf_data(&cameras, &images)?.write(output_path)?;
+            output_path.parent().unwrap_or(Path::new(".")).to_path_buf()
+        } else {
+            // Captures have no sparse points; an empty points3D completes the model
+            self.write_colmap_cameras(output_path, &cameras)?;
+            self.write_colmap_images(output_path, &images)?;
+            colmap::write_points3d(output_path, &[], self.colmap_encoding.unwrap_or(ModelEncoding::Text))?;
+            output_path.to_path_buf()
+        };
+        
+        // Frames packed in an .r3d archive are unpacked where the image names resolve
+        if record3d::is_archive(input_path) {
+            let extracted = record3d::extract_frames(input_path, &image_root)?;
+            info!("Extracted {} Record3D frames to {}", extracted, image_root.join(record3d::FRAMES_DIR).display());
+        }
+        
+        info!("Converted {} {:?} frames to {:?} format with {} cameras", frames.len(), from_format, to_format, cameras.len());
+        Ok(())
+    }
+    
+    fn reconstruction_to_camera_params(&self, input_path: &Path, output_path: &Path, from_format: &CameraFormat, to_format: &CameraFormat) -> Result<()> {
+        debug!("Converting {:?} reconstruction to {:?}: {} -> {}", from_format, to_format, input_path.display(), output_path.display());
+        
+        let (cameras, images, points) = self.read_reconstruction(input_path, from_format)?;
+        let point_count = points.len();
+        self.write_model(output_path, to_format, &cameras, &images, points)?;
+        
+        info!("Converted {:?} reconstruction to {:?} format with {} images and {} points", from_format, to_format, images.len(), point_count);
+        Ok(())
+    }
+    
+    /// Cameras, images and points3D of any camera format that converts to COLMAP, in
+    /// COLMAP's world: nerfstudio's `applied_transform` is undone
+    pub fn read_model(&self, input_path: &Path, format: &CameraFormat) -> Result<(Vec<ColmapCamera>, Vec<ColmapImage>, Vec<ColmapPoint3D>)> {
+        match format {
+            CameraFormat::COLMAP => {
+                let input_dir = colmap::model_dir(input_path);
+                let points = if colmap::has_points3d(&input_dir, colmap::detect_encoding(&input_dir)?) {
+                    colmap::read_points3d(&input_dir)?
+                } else {
+                    Vec::new()
+                };
+                Ok((colmap::read_cameras(&input_dir)?, colmap::read_images(&input_dir)?, points))
+            }
+            CameraFormat::NeRF => {
+                let nerf_data = NeRFCamera::read(input_path)?;
+                let base_dir = input_path.parent().unwrap_or(Path::new("."));
+                let (cameras, images) = self.convert_nerf_to_colmap_data(&nerf_data, base_dir)?;
+                Ok((cameras, images, self.read_nerf_points(&nerf_data, base_dir)?))
+            }
+            CameraFormat::Blender => {
+                let (cameras, images) = BlenderCameras::read(input_path)?.to_colmap()?;
+                Ok((cameras, images, Vec::new()))
+            }
+            CameraFormat::Polycam | CameraFormat::Record3D | CameraFormat::ARKit => {
+                let (cameras, images) = arkit::to_colmap(&self.read_capture(input_path, format)?);
+                Ok((cameras, images, Vec::new()))
+            }
+            CameraFormat::Bundler | CameraFormat::AliceVision => self.read_reconstruction(input_path, format),
+            _ => Err(HylaeanError::UnsupportedFormat {
+                format: format!("{:?} camera set (COLMAP, NeRF, Blender, mobile captures, Bundler and AliceVision are read)", format),
+            }),
+        }
+    }
+    
+    /// Write a COLMAP model, or a NeRF transforms file with the points next to it
+    pub fn write_model(
+        &self,
+        output_path: &Path,
+        format: &CameraFormat,
+        cameras: &[ColmapCamera],
+        images: &[ColmapImage],
+        points: Vec<ColmapPoint3D>,
+    ) -> Result<()> {
+        match format {
+            CameraFormat::COLMAP => {
+                self.write_colmap_cameras(output_path, cameras)?;
+                self.write_colmap_images(output_path, images)?;
+                colmap::write_points3d(output_path, &points, self.colmap_encoding.unwrap_or(ModelEncoding::Text))
+            }
+            CameraFormat::NeRF => {
+                let mut nerf_data = self.convert_colmap_to_nerf_data(cameras, images)?;
+                if !points.is_empty() {
+                    self.write_nerf_points(output_path, points, &mut nerf_data)?;
+                }
+                nerf_data.write(output_path)
+            }
+            _ => Err(HylaeanError::UnsupportedFormat {
+                format: format!("{:?} camera set output (COLMAP and NeRF are written)", format),
+            }),
+        }
+    }
+    
+    fn read_capture(&self, input_path: &Path, format: &CameraFormat) -> Result<Vec<CaptureFrame>> {
+        match format {
+            CameraFormat::Polycam => polycam::read(input_path),
+            CameraFormat::Record3D => record3d::read(input_path),
+            _ => arkit::read(input_path),
+        }
+    }
+    
+    fn read_reconstruction(&self, input_path: &Path, format: &CameraFormat) -> Result<(Vec<ColmapCamera>, Vec<ColmapImage>, Vec<ColmapPoint3D>)> {
+        if let CameraFormat::Bundler = format {
+            let list = bundler::list_path(input_path).ok_or_else(|| HylaeanError::ConfigError {
+                message: format!("no {} with the image names of {}", bundler::LIST_FILE, input_path.display()),
+            })?;
+            let (bundler_cameras, bundler_points) = bundler::read(input_path)?;
+            let image_dir = list.parent().unwrap_or(Path::new("."));
+            bundler::to_colmap(&bundler_cameras, &bundler_points, &bundler::read_list(&list)?, image_dir)
+        } else {
+            SfmData::read(input_path)?.to_colmap(input_path.parent().unwrap_or(Path::new(".")))
+        }
+    }
+    
+    fn read_colmap_cameras(&self, base_path: &Path) -> Result<Vec<ColmapCamera>> {
+        colmap::read_cameras(&colmap::model_dir(base_path))
+    }
+    
+    fn read_colmap_images(&self, base_path: &Path) -> Result<Vec<ColmapImage>> {
+        colmap::read_images(&colmap::model_dir(base_path))
+    }
+    
+    fn convert_colmap_to_nerf_data(&self, cameras: &[ColmapCamera], images: &[ColmapImage]) -> Result<NeRFCamera> {
+        let mut frames = Vec::new();
+        
+        let mut intrinsics = HashMap::new();
+        for camera in cameras {
+            let model = camera.camera_model()?;
+            intrinsics.insert(camera.camera_id, (model, NeRFIntrinsics::from_model(&model, camera.width, camera.height)));
+        }
+        
+        // The first image's camera is written at the top level, where instant-ngp also reads
+        // it; frames taken with other cameras carry their own intrinsics
+        let main_camera = images.first().map(|image| image.camera_id)
+            .or_else(|| cameras.first().map(|camera| camera.camera_id))
+            .ok_or_else(|| HylaeanError::ConversionFailed {
+                source_format: "COLMAP".to_string(),
+                target_format: "NeRF".to_string(),
+            })?;
+        let camera_of = |camera_id: u32| intrinsics.get(&camera_id).ok_or_else(|| HylaeanError::ConfigError {
+            message: format!("COLMAP image refers to missing camera {}", camera_id),
+        });
+        let (main_model, main_intrinsics) = camera_of(main_camera)?;
+        let [fx, fy] = main_model.focal_length();
+        let (width, height) = (main_intrinsics.w.unwrap_or(0) as f64, main_intrinsics.h.unwrap_or(0) as f64);
+        
+        for image in images {
+            // COLMAP stores world-to-camera in OpenCV axes, NeRF camera-to-world in OpenGL axes
+            let camera_to_world = image.camera_to_world().change_camera_axes(CameraAxes::OpenCv, CameraAxes::OpenGl);
+            
+            let frame = NeRFFrame {
+                file_path: image.name.clone(),
+                rotation: None,
+                transform_matrix: camera_to_world.to_matrix(),
+                intrinsics: if image.camera_id == main_camera {
+                    NeRFIntrinsics::default()
+                } else {
+                    camera_of(image.camera_id)?.1.overriding(main_intrinsics)
+                },
+                colmap_im_id: Some(image.image_id),
+            };
+            
+            frames.push(frame);
+        }
+        
+        Ok(NeRFCamera {
+            camera_angle_x: Some(2.0 * (width / (2.0 * fx)).atan()),
+            camera_angle_y: Some(2.0 * (height / (2.0 * fy)).atan()),
+            intrinsics: main_intrinsics.clone(),
+            applied_transform: None,
+            ply_file_path: None,
+            frames,
+        })
+    }
+    
+    fn convert_nerf_to_colmap_data(&self, nerf_data: &NeRFCamera, base_dir: &Path) -> Result<(Vec<ColmapCamera>, Vec<ColmapImage>)> {
+        let mut cameras: Vec<ColmapCamera> = Vec::new();
+        let mut images = Vec::new();
+        
+        // Poses are in nerfstudio's world; applied_transform maps COLMAP's world to it
+        let to_colmap = nerf_data.applied_transform().inverse();
+        
+        // COLMAP image ids are kept when every frame has a distinct one
+        let mut ids: Vec<Option<u32>> = nerf_data.frames.iter().map(|frame| frame.colmap_im_id).collect();
+        let mut distinct = ids.iter().flatten().copied().collect::<Vec<_>>();
+        distinct.sort_unstable();
+        distinct.dedup();
+        if distinct.len() != ids.len() {
+            ids = (1..=ids.len() as u32).map(Some).collect();
+        }
+        
+        for (frame, image_id) in nerf_data.frames.iter().zip(ids.into_iter().flatten()) {
+            // Frames sharing intrinsics and image size share a camera
+            let (model, width, height) = nerf_data.frame_camera(frame, base_dir)?;
+            let camera = ColmapCamera::from_model(cameras.len() as u32 + 1, width, height, &model);
+            let camera_id = match cameras.iter().find(|existing| {
+                (&existing.model, existing.width, existing.height, &existing.params) == (&camera.model, width, height, &camera.params)
+            }) {
+                Some(existing) => existing.camera_id,
+                None => {
+                    cameras.push(camera);
+                    cameras.len() as u32
+                }
+            };
+            
+            let world_to_camera = Pose::from_matrix(&frame.transform_matrix)
+                .then(&to_colmap)
+                .change_camera_axes(CameraAxes::OpenGl, CameraAxes::OpenCv)
+                .inverse();
+            images.push(ColmapImage::new(image_id, camera_id, frame.file_path.clone(), &world_to_camera));
+        }
+        
+        Ok((cameras, images))
+    }
+    
+    fn write_colmap_cameras(&self, base_path: &Path, cameras: &[ColmapCamera]) -> Result<()> {
+        std::fs::create_dir_all(base_path)?;
+        colmap::write_cameras(base_path, cameras, self.colmap_encoding.unwrap_or(ModelEncoding::Text))
+    }
+    
+    fn write_colmap_images(&self, base_path: &Path, images: &[ColmapImage]) -> Result<()> {
+        std::fs::create_dir_all(base_path)?;
+        colmap::write_images(base_path, images, self.colmap_encoding.unwrap_or(ModelEncoding::Text))
+    }
+}
@@ -0,0 +1,267 @@
+//! NeRF `transforms.json` in the instant-ngp and nerfstudio dialects
+//!
+//! Both dialects list frames with camera-to-world matrices in OpenGL camera axes.
+//! instant-ngp describes the camera by `camera_angle_x` and optional `fl_x`, ..., `is_fisheye`;
+//! nerfstudio adds `camera_model`, lets every frame override the intrinsics, and records
+//! the world transform applied after COLMAP (`applied_transform`) and the sparse point
+//! cloud (`ply_file_path`).
+
+use crate::errors::{Result, HylaeanError};
+use super::camera_model::{CameraModel, OpenCvDistortion};
+use super::image_size;
+use super::pose::{Matrix4, Pose};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Camera intrinsics, at the top level or as per-frame overrides
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NeRFIntrinsics {
+    /// nerfstudio's model name: `PINHOLE`, `OPENCV`, `OPENCV_FISHEYE`, ...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fl_x: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fl_y: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cx: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cy: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub w: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub h: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub k1: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub k2: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub k3: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub k4: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p1: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p2: Option<f64>,
+    /// instant-ngp's flag for `cv::fisheye` distortion terms
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_fisheye: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NeRFCamera {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_angle_x: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_angle_y: Option<f64>,
+    #[serde(flatten)]
+    pub intrinsics: NeRFIntrinsics,
+    /// World transform (3x4) applied to the COLMAP poses and points by nerfstudio
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_transform: Option<[[f64; 4]; 3]>,
+    /// Sparse point cloud, relative to the transforms file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ply_file_path: Option<String>,
+    pub frames: Vec<NeRFFrame>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeRFFrame {
+    pub file_path: String,
+    /// Written by the Blender synthetic datasets; unused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<f64>,
+    /// Camera-to-world with OpenGL camera axes
+    pub transform_matrix: Matrix4,
+    #[serde(flatten)]
+    pub intrinsics: NeRFIntrinsics,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colmap_im_id: Option<u32>,
+}
+
+impl NeRFCamera {
+    pub fn read(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// `applied_transform` as a pose; the identity when absent
+    pub fn applied_transform(&self) -> Pose {
+        match &self.applied_transform {
+            Some(rows) => Pose::from_matrix(&[rows[0], rows[1], rows[2], [0.0, 0.0, 0.0, 1.0]]),
+            None => Pose::default(),
+        }
+    }
+
+    /// Intrinsics of `frame`: its own values over the top-level ones
+    pub fn frame_intrinsics(&self, frame: &NeRFFrame) -> NeRFIntrinsics {
+        frame.intrinsics.or(&self.intrinsics)
+    }
+
+    /// The COLMAP camera of `frame` and the image size it applies to. Missing sizes are
+    /// read from the image header, resolving `file_path` against `base_dir`.
+    pub fn frame_camera(&self, frame: &NeRFFrame, base_dir: &Path) -> Result<(CameraModel, u32, u32)> {
+        let intrinsics = self.frame_intrinsics(frame);
+        let (width, height) = match (intrinsics.w, intrinsics.h) {
+            (Some(width), Some(height)) => (width, height),
+            _ => {
+                let [width, height] = frame_image_size(base_dir, &frame.file_path)?;
+                (width, height)
+            }
+        };
+        let model = intrinsics.to_model(width, height, self.camera_angle_x, self.camera_angle_y)?;
+        Ok((model, width, height))
+    }
+}
+
+impl NeRFIntrinsics {
+    /// Fields of `self`, falling back to `defaults` where unset
+    pub fn or(&self, defaults: &NeRFIntrinsics) -> NeRFIntrinsics {
+        NeRFIntrinsics {
+            camera_model: self.camera_model.clone().or_else(|| defaults.camera_model.clone()),
+            fl_x: self.fl_x.or(defaults.fl_x),
+            fl_y: self.fl_y.or(defaults.fl_y),
+            cx: self.cx.or(defaults.cx),
+            cy: self.cy.or(defaults.cy),
+            w: self.w.or(defaults.w),
+            h: self.h.or(defaults.h),
+            k1: self.k1.or(defaults.k1),
+            k2: self.k2.or(defaults.k2),
+            k3: self.k3.or(defaults.k3),
+            k4: self.k4.or(defaults.k4),
+            p1: self.p1.or(defaults.p1),
+            p2: self.p2.or(defaults.p2),
+            is_fisheye: self.is_fisheye.or(defaults.is_fisheye),
+        }
+    }
+
+    /// Per-frame overrides under top-level `defaults` that resolve to `self`: distortion
+    /// terms the defaults set and `self` lacks are written as zero
+    pub fn overriding(&self, defaults: &NeRFIntrinsics) -> NeRFIntrinsics {
+        let zero = |own: Option<f64>, default: Option<f64>| own.or(default.map(|_| 0.0));
+        NeRFIntrinsics {
+            k1: zero(self.k1, defaults.k1),
+            k2: zero(self.k2, defaults.k2),
+            k3: zero(self.k3, defaults.k3),
+            k4: zero(self.k4, defaults.k4),
+            p1: zero(self.p1, defaults.p1),
+            p2: zero(self.p2, defaults.p2),
+            is_fisheye: self.is_fisheye.or(defaults.is_fisheye.map(|_| false)),
+            ..self.clone()
+        }
+    }
+
+    /// Intrinsics of a COLMAP camera, in nerfstudio's names plus instant-ngp's fisheye flag
+    pub fn from_model(model: &CameraModel, width: u32, height: u32) -> Self {
+        let [fx, fy] = model.focal_length();
+        let [cx, cy] = model.principal_point();
+        let mut intrinsics = NeRFIntrinsics {
+            fl_x: Some(fx),
+            fl_y: Some(fy),
+            cx: Some(cx),
+            cy: Some(cy),
+            w: Some(width),
+            h: Some(height),
+            ..NeRFIntrinsics::default()
+        };
+        match model.opencv_distortion() {
+            Some(OpenCvDistortion::Standard(coefficients)) => {
+                if coefficients.iter().any(|c| *c != 0.0) {
+                    intrinsics.camera_model = Some("OPENCV".to_string());
+                    intrinsics.k1 = Some(coefficients[0]);
+                    intrinsics.k2 = Some(coefficients[1]);
+                    intrinsics.p1 = Some(coefficients[2]);
+                    intrinsics.p2 = Some(coefficients[3]);
+                    intrinsics.k3 = coefficients.get(4).copied();
+                } else {
+                    intrinsics.camera_model = Some("PINHOLE".to_string());
+                }
+                if coefficients.iter().skip(5).any(|c| *c != 0.0) {
+                    warn!("NeRF has no rational distortion terms; dropping k4, k5 and k6 of a {} camera", model.name());
+                }
+            }
+            Some(OpenCvDistortion::Fisheye([k1, k2, k3, k4])) => {
+                intrinsics.camera_model = Some("OPENCV_FISHEYE".to_string());
+                intrinsics.k1 = Some(k1);
+                intrinsics.k2 = Some(k2);
+                intrinsics.k3 = Some(k3);
+                intrinsics.k4 = Some(k4);
+                intrinsics.is_fisheye = Some(true);
+            }
+            None => {
+                warn!("NeRF cannot represent {} distortion; writing the camera without it", model.name());
+                if model.is_fisheye() {
+                    intrinsics.camera_model = Some("OPENCV_FISHEYE".to_string());
+                    intrinsics.is_fisheye = Some(true);
+                } else {
+                    intrinsics.camera_model = Some("PINHOLE".to_string());
+                }
+            }
+        }
+        intrinsics
+    }
+
+    pub fn is_fisheye(&self) -> bool {
+        self.is_fisheye.unwrap_or(false) || self.camera_model.as_deref() == Some("OPENCV_FISHEYE")
+    }
+
+    /// The COLMAP camera for an image of `width` x `height`. Focal lengths fall back to
+    /// instant-ngp's field of view angles, the principal point to the image center.
+    pub fn to_model(&self, width: u32, height: u32, camera_angle_x: Option<f64>, camera_angle_y: Option<f64>) -> Result<CameraModel> {
+        if let Some(model @ ("EQUIRECTANGULAR" | "OMNIDIRECTIONALSTEREO_L" | "OMNIDIRECTIONALSTEREO_R")) = self.camera_model.as_deref() {
+            return Err(HylaeanError::UnsupportedFormat {
+                format: format!("nerfstudio {} camera (COLMAP has no equivalent)", model),
+            });
+        }
+
+        let focal = |size: u32, angle: f64| size as f64 / (2.0 * (angle / 2.0).tan());
+        let from_x = self.fl_x.or_else(|| camera_angle_x.map(|angle| focal(width, angle)));
+        let from_y = self.fl_y.or_else(|| camera_angle_y.map(|angle| focal(height, angle)));
+        let (fx, fy) = match (from_x, from_y) {
+            (Some(fx), Some(fy)) => (fx, fy),
+            (Some(f), None) | (None, Some(f)) => (f, f),
+            (None, None) => {
+                return Err(HylaeanError::ConfigError {
+                    message: "NeRF camera has no focal length (fl_x, fl_y or camera_angle_x)".to_string(),
+                })
+            }
+        };
+        let cx = self.cx.unwrap_or(width as f64 / 2.0);
+        let cy = self.cy.unwrap_or(height as f64 / 2.0);
+
+        let coefficient = |value: Option<f64>| value.unwrap_or(0.0);
+        let distortion = if self.is_fisheye() {
+            OpenCvDistortion::Fisheye([self.k1, self.k2, self.k3, self.k4].map(coefficient))
+        } else {
+            if coefficient(self.k4) != 0.0 {
+                warn!("Dropping k4 of a perspective NeRF camera; COLMAP's OpenCV models have no r^8 term");
+            }
+            OpenCvDistortion::Standard([self.k1, self.k2, self.p1, self.p2, self.k3].map(coefficient).to_vec())
+        };
+        CameraModel::from_opencv(fx, fy, cx, cy, &distortion)
+    }
+}
+
+/// Size of a frame's image; Blender synthetic datasets omit the `.png` extension
+fn frame_image_size(base_dir: &Path, file_path: &str) -> Result<[u32; 2]> {
+    let path = base_dir.join(file_path);
+    let candidates = [path.clone(), base_dir.join(format!("{}.png", file_path))];
+    match candidates.iter().find(|candidate| candidate.is_file()) {
+        Some(image) => image_size::read_image_size(image),
+        None => Err(HylaeanError::ConfigError {
+            message: format!(
+                "the image size of {} is unknown: set w and h in the transforms file, or put the image at {}",
+                file_path, path.display()
+            ),
+        }),
+    }
+}
@@ -0,0 +1,530 @@
+//! OpenCV `cv::FileStorage` calibrations in YAML and XML
+//!
+//! A file describes one camera the way `calibrateCamera` samples save it: `image_width`,
+//! `image_height`, `camera_matrix`, `distortion_coefficients` and, for `cv::fisheye`,
+//! `fisheye_model: 1`. Extrinsics are world-to-camera rotation vectors and translations:
+//! `rvec`/`tvec` for a single view or an N x 6 `extrinsic_parameters` matrix, with the
+//! view names in `image_name`/`image_names`. A rig is a directory of such files.
+//!
+//! Only what calibration files use is parsed: top-level scalars, string sequences and
+//! `opencv-matrix` nodes. Nested maps are skipped.
+
+use crate::errors::{Result, HylaeanError};
+use super::camera_model::{CameraModel, OpenCvDistortion};
+use super::pose::Pose;
+use log::warn;
+use std::path::{Path, PathBuf};
+
+pub const EXTENSIONS: [&str; 3] = ["yml", "yaml", "xml"];
+
+/// A FileStorage node
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Number(f64),
+    Text(String),
+    /// `opencv-matrix` in row-major order
+    Matrix { rows: usize, cols: usize, data: Vec<f64> },
+    Seq(Vec<Node>),
+}
+
+/// Top-level nodes of a FileStorage document, in file order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileStorage {
+    pub nodes: Vec<(String, Node)>,
+}
+
+impl FileStorage {
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let trimmed = text.trim_start();
+        if trimmed.starts_with("<?xml") || trimmed.starts_with("<opencv_storage") {
+            parse_xml(&text, path)
+        } else {
+            parse_yaml(&text, path)
+        }
+    }
+
+    /// Write as XML for `.xml` paths, YAML otherwise
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let is_xml = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("xml"));
+        let text = if is_xml { self.to_xml() } else { self.to_yaml() };
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        self.nodes.iter().find(|(name, _)| name == key).map(|(_, node)| node)
+    }
+
+    pub fn insert(&mut self, key: &str, node: Node) {
+        self.nodes.push((key.to_string(), node));
+    }
+
+    /// A number, or the single element of a 1x1 matrix
+    pub fn number(&self, key: &str) -> Option<f64> {
+        match self.values(key)?.as_slice() {
+            [value] => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Numbers of a matrix or sequence node, in row-major order
+    pub fn values(&self, key: &str) -> Option<Vec<f64>> {
+        match self.get(key)? {
+            Node::Matrix { data, .. } => Some(data.clone()),
+            Node::Seq(items) => items.iter().map(|item| match item {
+                Node::Number(value) => Some(*value),
+                _ => None,
+            }).collect(),
+            Node::Number(value) => Some(vec![*value]),
+            Node::Text(_) => None,
+        }
+    }
+
+    /// Strings of a text node or sequence
+    pub fn strings(&self, key: &str) -> Option<Vec<String>> {
+        let text = |node: &Node| match node {
+            Node::Text(text) => Some(text.clone()),
+            Node::Number(value) => Some(value.to_string()),
+            _ => None,
+        };
+        match self.get(key)? {
+            Node::Seq(items) => items.iter().map(text).collect(),
+            node => text(node).map(|single| vec![single]),
+        }
+    }
+
+    fn to_yaml(&self) -> String {
+        let mut out = String::from("%YAML:1.0\n---\n");
+        for (key, node) in &self.nodes {
+            match node {
+                Node::Matrix { rows, cols, data } => {
+                    out.push_str(&format!("{}: !!opencv-matrix\n   rows: {}\n   cols: {}\n   dt: d\n   data: [ ", key, rows, cols));
+                    out.push_str(&data.iter().map(|value| format_number(*value)).collect::<Vec<_>>().join(", "));
+                    out.push_str(" ]\n");
+                }
+                Node::Seq(items) => {
+                    let items: Vec<String> = items.iter().map(yaml_scalar).collect();
+                    out.push_str(&format!("{}: [ {} ]\n", key, items.join(", ")));
+                }
+                scalar => out.push_str(&format!("{}: {}\n", key, yaml_scalar(scalar))),
+            }
+        }
+        out
+    }
+
+    fn to_xml(&self) -> String {
+        let mut out = String::from("<?xml version=\"1.0\"?>\n<opencv_storage>\n");
+        for (key, node) in &self.nodes {
+            match node {
+                Node::Matrix { rows, cols, data } => {
+                    out.push_str(&format!(
+                        "<{} type_id=\"opencv-matrix\">\n  <rows>{}</rows>\n  <cols>{}</cols>\n  <dt>d</dt>\n  <data>\n    ",
+                        key, rows, cols
+                    ));
+                    out.push_str(&data.iter().map(|value| format_number(*value)).collect::<Vec<_>>().join(" "));
+                    out.push_str(&format!("</data></{}>\n", key));
+                }
+                Node::Seq(items) => {
+                    let items: Vec<String> = items.iter().map(xml_scalar).collect();
+                    out.push_str(&format!("<{}>\n  {}</{}>\n", key, items.join(" "), key));
+                }
+                scalar => out.push_str(&format!("<{}>{}</{}>\n", key, xml_scalar(scalar), key)),
+            }
+        }
+        out.push_str("</opencv_storage>\n");
+        out
+    }
+}
+
+/// One camera's calibration and the views it was posed in
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenCvCalibration {
+    pub image_width: u32,
+    pub image_height: u32,
+    pub camera_matrix: [[f64; 3]; 3],
+    pub distortion: OpenCvDistortion,
+    /// View names with their world-to-camera poses
+    pub views: Vec<(String, Pose)>,
+}
+
+impl OpenCvCalibration {
+    /// The calibration of a COLMAP camera. Models OpenCV has no distortion for are written
+    /// without it, with a warning.
+    pub fn from_model(model: &CameraModel, width: u32, height: u32, views: Vec<(String, Pose)>) -> Self {
+        let [fx, fy] = model.focal_length();
+        let [cx, cy] = model.principal_point();
+        let distortion = model.opencv_distortion().unwrap_or_else(|| {
+            warn!("OpenCV cannot represent {} distortion; writing the camera without it", model.name());
+            OpenCvDistortion::Standard(vec![0.0; 5])
+        });
+        Self {
+            image_width: width,
+            image_height: height,
+            camera_matrix: [[fx, 0.0, cx], [0.0, fy, cy], [0.0, 0.0, 1.0]],
+            distortion,
+            views,
+        }
+    }
+
+    pub fn to_model(&self) -> Result<CameraModel> {
+        let k = &self.camera_matrix;
+        if k[0][1] != 0.0 {
+            warn!("Dropping the camera matrix skew {}; COLMAP cameras have none", k[0][1]);
+        }
+        CameraModel::from_opencv(k[0][0], k[1][1], k[0][2], k[1][2], &self.distortion)
+    }
+
+    /// Read a calibration; unnamed views are named after the file
+    pub fn read(path: &Path) -> Result<Self> {
+        let storage = FileStorage::read(path)?;
+        let missing = |key: &str| HylaeanError::ConfigError {
+            message: format!("OpenCV calibration {} has no {}", path.display(), key),
+        };
+
+        let camera_matrix = storage.values("camera_matrix").filter(|values| values.len() == 9).ok_or_else(|| missing("3x3 camera_matrix"))?;
+        let image_width = storage.number("image_width").ok_or_else(|| missing("image_width"))?;
+        let image_height = storage.number("image_height").ok_or_else(|| missing("image_height"))?;
+        let coefficients = storage.values("distortion_coefficients").unwrap_or_default();
+        let distortion = if storage.number("fisheye_model").is_some_and(|flag| flag != 0.0) {
+            let mut fisheye = [0.0; 4];
+            for (target, value) in fisheye.iter_mut().zip(&coefficients) {
+                *target = *value;
+            }
+            OpenCvDistortion::Fisheye(fisheye)
+        } else {
+            OpenCvDistortion::Standard(coefficients)
+        };
+
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("view");
+        let mut poses = Vec::new();
+        if let Some(extrinsics) = storage.values("extrinsic_parameters") {
+            for row in extrinsics.chunks_exact(6) {
+                poses.push(Pose::from_rotation_vector([row[0], row[1], row[2]], [row[3], row[4], row[5]]));
+            }
+        } else if let (Some(rvec), Some(tvec)) = (storage.values("rvec"), storage.values("tvec")) {
+            if let (Ok(rvec), Ok(tvec)) = (<[f64; 3]>::try_from(rvec), <[f64; 3]>::try_from(tvec)) {
+                poses.push(Pose::from_rotation_vector(rvec, tvec));
+            } else {
+                return Err(missing("3-element rvec and tvec"));
+            }
+        }
+        let names = storage.strings("image_names").or_else(|| storage.strings("image_name"))
+            .filter(|names| names.len() == poses.len())
+            .unwrap_or_else(|| (0..poses.len()).map(|i| format!("{}_{:04}.png", stem, i)).collect());
+
+        Ok(Self {
+            image_width: image_width as u32,
+            image_height: image_height as u32,
+            camera_matrix: [0, 1, 2].map(|r| [camera_matrix[r * 3], camera_matrix[r * 3 + 1], camera_matrix[r * 3 + 2]]),
+            distortion,
+            views: names.into_iter().zip(poses).collect(),
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut storage = FileStorage::default();
+        storage.insert("image_width", Node::Number(self.image_width as f64));
+        storage.insert("image_height", Node::Number(self.image_height as f64));
+        storage.insert("camera_matrix", Node::Matrix { rows: 3, cols: 3, data: self.camera_matrix.as_flattened().to_vec() });
+        let coefficients = match &self.distortion {
+            OpenCvDistortion::Standard(coefficients) => coefficients.clone(),
+            OpenCvDistortion::Fisheye(coefficients) => coefficients.to_vec(),
+        };
+        storage.insert("distortion_coefficients", Node::Matrix { rows: 1, cols: coefficients.len(), data: coefficients });
+        if matches!(self.distortion, OpenCvDistortion::Fisheye(_)) {
+            storage.insert("fisheye_model", Node::Number(1.0));
+        }
+
+        match self.views.as_slice() {
+            [] => {}
+            [(name, pose)] => {
+                storage.insert("image_name", Node::Text(name.clone()));
+                storage.insert("rvec", Node::Matrix { rows: 3, cols: 1, data: pose.rotation_vector().to_vec() });
+                storage.insert("tvec", Node::Matrix { rows: 3, cols: 1, data: pose.translation.to_vec() });
+            }
+            views => {
+                storage.insert("image_names", Node::Seq(views.iter().map(|(name, _)| Node::Text(name.clone())).collect()));
+                let data = views.iter().flat_map(|(_, pose)| pose.rotation_vector().into_iter().chain(pose.translation)).collect();
+                storage.insert("extrinsic_parameters", Node::Matrix { rows: views.len(), cols: 6, data });
+            }
+        }
+        storage.write(path)
+    }
+}
+
+/// The calibration files of a rig directory in name order, or the single file given
+pub fn calibration_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|file| file.is_file() && is_calibration_file(file))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+pub fn is_calibration_file(path: &Path) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    EXTENSIONS.contains(&extension.to_lowercase().as_str())
+}
+
+/// Where each camera's calibration goes: `output` itself for a single camera, numbered
+/// siblings for several, or `camera_<id>.yml` files when `output` is a directory
+pub fn calibration_paths(output: &Path, camera_ids: &[u32]) -> Vec<PathBuf> {
+    if output.is_dir() || output.extension().is_none() {
+        return camera_ids.iter().map(|id| output.join(format!("camera_{}.yml", id))).collect();
+    }
+    if let [_] = camera_ids {
+        return vec![output.to_path_buf()];
+    }
+    let stem = output.file_stem().and_then(|s| s.to_str()).unwrap_or("camera");
+    let extension = output.extension().and_then(|e| e.to_str()).unwrap_or("yml");
+    camera_ids.iter().map(|id| output.with_file_name(format!("{}_{}.{}", stem, id, extension))).collect()
+}
+
+/// Shortest text that reads back as the same double, always with a decimal point or exponent
+fn format_number(value: f64) -> String {
+    if value.is_nan() {
+        ".Nan".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { ".Inf" } else { "-.Inf" }.to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+fn yaml_scalar(node: &Node) -> String {
+    match node {
+        Node::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => format!("{}", *value as i64),
+        Node::Number(value) => format_number(*value),
+        Node::Text(text) => format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")),
+        _ => String::new(),
+    }
+}
+
+fn xml_scalar(node: &Node) -> String {
+    match node {
+        Node::Text(text) => format!("\"{}\"", text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")),
+        other => yaml_scalar(other),
+    }
+}
+
+fn parse_yaml(text: &str, path: &Path) -> Result<FileStorage> {
+    let mut storage = FileStorage::default();
+    let lines: Vec<&str> = text.lines().map(strip_yaml_comment).collect();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if line.trim().is_empty() || line.starts_with('%') || line.starts_with("---") || line.starts_with("...") {
+            continue;
+        }
+        // A top-level key and the indented lines that continue it
+        let start = i;
+        while i < lines.len() && (lines[i].starts_with([' ', '\t', '-']) || lines[i].trim().is_empty()) {
+            i += 1;
+        }
+        let continuation = &lines[start..i];
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(malformed(path, &format!("expected `key: value`, found `{}`", line)));
+        };
+        let key = key.trim().trim_matches('"').to_string();
+        let value = value.trim();
+
+        let node = if value.starts_with("!!opencv-matrix") {
+            parse_yaml_matrix(continuation).ok_or_else(|| malformed(path, &format!("bad matrix {}", key)))?
+        } else if value.starts_with('[') {
+            let joined = std::iter::once(value).chain(continuation.iter().map(|line| line.trim())).collect::<Vec<_>>().join(" ");
+            Node::Seq(split_flow(&joined).iter().map(|item| scalar(item)).collect())
+        } else if value.is_empty() && continuation.iter().any(|line| line.trim_start().starts_with('-')) {
+            Node::Seq(
+                continuation
+                    .iter()
+                    .filter_map(|line| line.trim_start().strip_prefix('-'))
+                    .map(|item| scalar(item.trim()))
+                    .collect(),
+            )
+        } else if value.is_empty() || value.starts_with("!!") || value.starts_with('{') {
+            // Nested maps and other types are not part of a calibration
+            continue;
+        } else {
+            scalar(value)
+        };
+        storage.nodes.push((key, node));
+    }
+    Ok(storage)
+}
+
+fn parse_yaml_matrix(lines: &[&str]) -> Option<Node> {
+    let mut rows = None;
+    let mut cols = None;
+    let mut data = String::new();
+    let mut in_data = false;
+    for line in lines {
+        let line = line.trim();
+        if in_data {
+            data.push(' ');
+            data.push_str(line);
+        } else if let Some(value) = line.strip_prefix("rows:") {
+            rows = value.trim().parse::<usize>().ok();
+        } else if let Some(value) = line.strip_prefix("cols:") {
+            cols = value.trim().parse::<usize>().ok();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push_str(value);
+            in_data = true;
+        }
+    }
+    matrix(rows?, cols?, &split_flow(&data))
+}
+
+/// Items of a `[a, b, c]` flow sequence, or of whitespace-separated XML text
+fn split_flow(text: &str) -> Vec<String> {
+    let inner = text.trim().trim_start_matches('[').trim_end_matches(']');
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            '\\' if quoted => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            ',' | ' ' | '\t' | '\n' | '\r' if !quoted => {
+                if !current.is_empty() {
+                    items.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        items.push(current);
+    }
+    items
+}
+
+/// A scalar: quoted text, a number, or bare text
+fn scalar(text: &str) -> Node {
+    let text = text.trim();
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        return Node::Text(text[1..text.len() - 1].replace("\\\"", "\"").replace("\\\\", "\\"));
+    }
+    if text.len() >= 2 && text.starts_with('\'') && text.ends_with('\'') {
+        return Node::Text(text[1..text.len() - 1].to_string());
+    }
+    match parse_number(text) {
+        Some(value) => Node::Number(value),
+        None => Node::Text(text.to_string()),
+    }
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    match text.to_lowercase().as_str() {
+        ".nan" => Some(f64::NAN),
+        ".inf" => Some(f64::INFINITY),
+        "-.inf" => Some(f64::NEG_INFINITY),
+        _ => text.parse::<f64>().ok(),
+    }
+}
+
+fn matrix(rows: usize, cols: usize, items: &[String]) -> Option<Node> {
+    let data = items.iter().map(|item| parse_number(item)).collect::<Option<Vec<f64>>>()?;
+    (data.len() == rows * cols).then_some(Node::Matrix { rows, cols, data })
+}
+
+fn strip_yaml_comment(line: &str) -> &str {
+    if line.trim_start().starts_with('#') {
+        return "";
+    }
+    match line.find(" #") {
+        Some(index) if !line[..index].contains('"') => &line[..index],
+        _ => line,
+    }
+}
+
+fn parse_xml(text: &str, path: &Path) -> Result<FileStorage> {
+    let root = xml_elements(text)
+        .into_iter()
+        .find(|element| element.0 == "opencv_storage")
+        .ok_or_else(|| malformed(path, "no <opencv_storage> element"))?;
+
+    let mut storage = FileStorage::default();
+    for (key, attributes, inner) in xml_elements(&root.2) {
+        let node = if attributes.contains("opencv-matrix") {
+            let children = xml_elements(&inner);
+            let child = |name: &str| children.iter().find(|child| child.0 == name).map(|child| child.2.trim().to_string());
+            let rows = child("rows").and_then(|rows| rows.parse().ok());
+            let cols = child("cols").and_then(|cols| cols.parse().ok());
+            let data = split_flow(&child("data").unwrap_or_default());
+            match (rows, cols) {
+                (Some(rows), Some(cols)) => matrix(rows, cols, &data).ok_or_else(|| malformed(path, &format!("bad matrix {}", key)))?,
+                _ => return Err(malformed(path, &format!("matrix {} without rows and cols", key))),
+            }
+        } else if inner.contains('<') {
+            // Nested maps and sequences of maps are not part of a calibration
+            continue;
+        } else {
+            let mut items: Vec<Node> = split_flow(&unescape_xml(&inner)).iter().map(|item| scalar(item)).collect();
+            if items.len() == 1 {
+                items.remove(0)
+            } else {
+                Node::Seq(items)
+            }
+        };
+        storage.nodes.push((key, node));
+    }
+    Ok(storage)
+}
+
+/// `(name, attributes, content)` of the elements directly in `text`, skipping the XML
+/// declaration and comments
+fn xml_elements(text: &str) -> Vec<(String, String, String)> {
+    let mut elements = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.split_once("-->").map_or("", |(_, tail)| tail);
+            continue;
+        }
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest = rest.split_once('>').map_or("", |(_, tail)| tail);
+            continue;
+        }
+        let Some(end) = rest.find('>') else { break };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('/') {
+            continue;
+        }
+        let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        if let Some(name) = name.strip_suffix('/').or(attributes.ends_with('/').then_some(name)) {
+            elements.push((name.to_string(), attributes.trim_end_matches('/').to_string(), String::new()));
+            continue;
+        }
+        let closing = format!("</{}>", name);
+        let Some(close) = rest.find(&closing) else { break };
+        elements.push((name.to_string(), attributes.to_string(), rest[..close].to_string()));
+        rest = &rest[close + closing.len()..];
+    }
+    elements
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+fn malformed(path: &Path, detail: &str) -> HylaeanError {
+    HylaeanError::UnsupportedFormat {
+        format: format!("OpenCV FileStorage {}: {}", path.display(), detail),
+    }
+}
@@ -0,0 +1,103 @@
+//! Polycam raw data exports
+//!
+//! The `keyframes/` directory of an export holds one camera JSON per keyframe, named after
+//! the image: `fx`, `fy`, `cx`, `cy`, `width`, `height` and the 3x4 camera-to-world matrix
+//! `t_00` ... `t_23` from ARKit (OpenGL camera axes). Polycam's bundle-adjusted
+//! `corrected_cameras/` and `corrected_images/` are preferred over the raw `cameras/` and
+//! `images/`, as nerfstudio's `ns-process-data polycam` does.
+
+use crate::errors::{Result, HylaeanError};
+use super::arkit::{self, CaptureFrame};
+use super::pose::Pose;
+use log::warn;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+const IMAGE_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
+#[derive(Debug, Clone, Deserialize)]
+struct PolycamCamera {
+    fx: f64,
+    fy: f64,
+    cx: f64,
+    cy: f64,
+    width: u32,
+    height: u32,
+    t_00: f64,
+    t_01: f64,
+    t_02: f64,
+    t_03: f64,
+    t_10: f64,
+    t_11: f64,
+    t_12: f64,
+    t_13: f64,
+    t_20: f64,
+    t_21: f64,
+    t_22: f64,
+    t_23: f64,
+}
+
+/// The `keyframes/` directory of an export, given the export or `keyframes/` itself
+pub fn keyframes_dir(path: &Path) -> PathBuf {
+    if path.join("keyframes").is_dir() {
+        path.join("keyframes")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Whether a directory is a Polycam raw data export
+pub fn is_export(path: &Path) -> bool {
+    let keyframes = keyframes_dir(path);
+    (keyframes.join("cameras").is_dir() || keyframes.join("corrected_cameras").is_dir())
+        && (keyframes.join("images").is_dir() || keyframes.join("corrected_images").is_dir())
+}
+
+/// Frames in keyframe name order; image names are relative to `keyframes/`
+pub fn read(path: &Path) -> Result<Vec<CaptureFrame>> {
+    let keyframes = keyframes_dir(path);
+    let (cameras_dir, images_dir) = if keyframes.join("corrected_cameras").is_dir() {
+        ("corrected_cameras", "corrected_images")
+    } else {
+        warn!("{} has no corrected_cameras; using the raw ARKit poses", keyframes.display());
+        ("cameras", "images")
+    };
+    let images_dir = if keyframes.join(images_dir).is_dir() { images_dir } else { "images" };
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(keyframes.join(cameras_dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("json"))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        return Err(HylaeanError::ConfigError {
+            message: format!("no keyframe cameras in {}", keyframes.join(cameras_dir).display()),
+        });
+    }
+
+    let mut frames = Vec::with_capacity(files.len());
+    for file in files {
+        let camera: PolycamCamera = serde_json::from_reader(BufReader::new(File::open(&file)?))?;
+        let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let image = IMAGE_EXTENSIONS.iter()
+            .map(|ext| format!("{}/{}.{}", images_dir, stem, ext))
+            .find(|name| keyframes.join(name).is_file())
+            .unwrap_or_else(|| format!("{}/{}.jpg", images_dir, stem));
+
+        let rows = [
+            [camera.t_00, camera.t_01, camera.t_02, camera.t_03],
+            [camera.t_10, camera.t_11, camera.t_12, camera.t_13],
+            [camera.t_20, camera.t_21, camera.t_22, camera.t_23],
+        ];
+        frames.push(CaptureFrame {
+            image,
+            camera_to_world: Pose::from_matrix(&arkit::homogeneous(rows)),
+            model: arkit::pinhole(camera.fx, camera.fy, camera.cx, camera.cy)?,
+            width: camera.width,
+            height: camera.height,
+        });
+    }
+    Ok(frames)
+}
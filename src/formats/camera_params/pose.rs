@@ -0,0 +1,156 @@
+//! Rigid camera poses and axis conventions
+//!
+//! COLMAP stores world-to-camera rotations and translations with OpenCV camera axes
+//! (x right, y down, looking along +z). NeRF and nerfstudio store camera-to-world
+//! matrices with OpenGL camera axes (x right, y up, looking along -z); Blender cameras
+//! use the OpenGL axes too, in a Z-up world. LLFF's `poses_bounds.npy` stores
+//! camera-to-world matrices with (down, right, backwards) camera axes. Bundler stores
+//! world-to-camera poses with OpenGL camera axes; AliceVision stores world-to-camera
+//! rotations and camera centers with OpenCV camera axes.
+
+use crate::formats::math::{self, Matrix3, IDENTITY};
+
+pub type Matrix4 = [[f64; 4]; 4];
+
+/// Axes of a camera's local frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraAxes {
+    /// x right, y down, looking along +z (COLMAP, OpenCV)
+    OpenCv,
+    /// x right, y up, looking along -z (NeRF, nerfstudio, Blender)
+    OpenGl,
+    /// x down, y right, looking along -z (LLFF)
+    Llff,
+}
+
+impl CameraAxes {
+    /// Maps camera coordinates in these axes to OpenCV camera coordinates
+    fn to_opencv(self) -> Matrix3 {
+        match self {
+            CameraAxes::OpenCv => IDENTITY,
+            CameraAxes::OpenGl => [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, -1.0]],
+            CameraAxes::Llff => [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+        }
+    }
+}
+
+/// Rigid transform `p -> rotation * p + translation`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub rotation: Matrix3,
+    pub translation: [f64; 3],
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self { rotation: IDENTITY, translation: [0.0; 3] }
+    }
+}
+
+impl Pose {
+    pub fn new(rotation: Matrix3, translation: [f64; 3]) -> Self {
+        Self { rotation, translation }
+    }
+
+    /// From a (w, x, y, z) quaternion of any length; a zero quaternion is the identity
+    pub fn from_quaternion(q: [f64; 4], translation: [f64; 3]) -> Self {
+        let norm = q.iter().map(|v| v * v).sum::<f64>().sqrt();
+        let rotation = if norm > 0.0 && norm.is_finite() {
+            math::quaternion_to_matrix(q.map(|v| v / norm))
+        } else {
+            IDENTITY
+        };
+        Self { rotation, translation }
+    }
+
+    /// Unit (w, x, y, z) quaternion of the rotation, with w >= 0
+    pub fn quaternion(&self) -> [f64; 4] {
+        math::matrix_to_quaternion(&self.rotation)
+    }
+
+    /// From an OpenCV rotation vector (axis times angle, as `cv::Rodrigues` takes it)
+    pub fn from_rotation_vector(rvec: [f64; 3], translation: [f64; 3]) -> Self {
+        let angle = rvec.iter().map(|v| v * v).sum::<f64>().sqrt();
+        // sin(angle / 2) / angle tends to 1/2 for small angles
+        let scale = if angle > 1e-12 { (angle / 2.0).sin() / angle } else { 0.5 };
+        let q = [(angle / 2.0).cos(), rvec[0] * scale, rvec[1] * scale, rvec[2] * scale];
+        Self { rotation: math::quaternion_to_matrix(q), translation }
+    }
+
+    /// OpenCV rotation vector of the rotation, with an angle in [0, pi]
+    pub fn rotation_vector(&self) -> [f64; 3] {
+        let [w, x, y, z] = self.quaternion();
+        let sine = (x * x + y * y + z * z).sqrt();
+        let angle = 2.0 * sine.atan2(w);
+        let scale = if sine > 1e-12 { angle / sine } else { 2.0 / w };
+        [x * scale, y * scale, z * scale]
+    }
+
+    /// From a homogeneous matrix whose upper-left block is a rotation up to noise or scale;
+    /// the block is replaced by its nearest rotation
+    pub fn from_matrix(matrix: &Matrix4) -> Self {
+        let block = [0, 1, 2].map(|i| [matrix[i][0], matrix[i][1], matrix[i][2]]);
+        Self {
+            rotation: nearest_rotation(&block),
+            translation: [matrix[0][3], matrix[1][3], matrix[2][3]],
+        }
+    }
+
+    pub fn to_matrix(&self) -> Matrix4 {
+        let mut matrix = [[0.0; 4]; 4];
+        for (i, row) in matrix.iter_mut().take(3).enumerate() {
+            row[..3].copy_from_slice(&self.rotation[i]);
+            row[3] = self.translation[i];
+        }
+        matrix[3][3] = 1.0;
+        matrix
+    }
+
+    pub fn inverse(&self) -> Self {
+        let rotation = math::transpose(&self.rotation);
+        let translation = math::apply(&rotation, self.translation).map(|v| -v);
+        Self { rotation, translation }
+    }
+
+    /// `self` followed by `next`
+    pub fn then(&self, next: &Pose) -> Self {
+        Self {
+            rotation: math::multiply(&next.rotation, &self.rotation),
+            translation: next.transform_point(self.translation),
+        }
+    }
+
+    pub fn transform_point(&self, point: [f64; 3]) -> [f64; 3] {
+        let rotated = math::apply(&self.rotation, point);
+        [0, 1, 2].map(|i| rotated[i] + self.translation[i])
+    }
+
+    /// Re-express a camera-to-world pose whose camera frame uses `from` axes in `to` axes.
+    /// The camera center and viewing direction are unchanged.
+    pub fn change_camera_axes(&self, from: CameraAxes, to: CameraAxes) -> Self {
+        let change = math::multiply(&math::transpose(&from.to_opencv()), &to.to_opencv());
+        Self {
+            rotation: math::multiply(&self.rotation, &change),
+            translation: self.translation,
+        }
+    }
+}
+
+/// The rotation closest to `m` in the Frobenius norm (the orthogonal polar factor, with
+/// the sign of the least significant axis flipped if needed to keep a proper rotation)
+pub fn nearest_rotation(m: &Matrix3) -> Matrix3 {
+    // m = U S V^T; the polar factor U V^T = m V S^-1 V^T, from the eigenvectors of m^T m
+    let (eigenvalues, v) = math::symmetric_eigen(&math::multiply(&math::transpose(m), m));
+    if !eigenvalues.iter().all(|value| *value > 1e-24) {
+        return IDENTITY;
+    }
+    let mv = math::multiply(m, &v);
+    let mut u = [0, 1, 2].map(|i| [0, 1, 2].map(|j| mv[i][j] / eigenvalues[j].sqrt()));
+    if math::determinant(&u) < 0.0 {
+        let smallest = (0..3).min_by(|a, b| eigenvalues[*a].total_cmp(&eigenvalues[*b])).unwrap_or(2);
+        for row in u.iter_mut() {
+            row[smallest] = -row[smallest];
+        }
+    }
+    math::multiply(&u, &math::transpose(&v))
+}
@@ -0,0 +1,124 @@
+//! Record3D captures
+//!
+//! An `.r3d` file is a zip archive of `metadata` JSON and `rgbd/<i>.jpg` frames, and an
+//! exported capture is the same files in a directory. The metadata holds the image size
+//! `w` x `h`, the 3x3 camera matrix `K` in column order and one ARKit pose per frame as
+//! `[qx, qy, qz, qw, tx, ty, tz]`, camera-to-world with OpenGL camera axes.
+
+use crate::errors::{Result, HylaeanError};
+use super::arkit::{self, CaptureFrame};
+use super::pose::Pose;
+use log::debug;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+pub const METADATA_FILE: &str = "metadata";
+
+/// Directory of the color frames, which is also the prefix of their image names
+pub const FRAMES_DIR: &str = "rgbd";
+
+#[derive(Debug, Clone, Deserialize)]
+struct Metadata {
+    w: u32,
+    h: u32,
+    #[serde(rename = "K")]
+    k: Vec<f64>,
+    poses: Vec<[f64; 7]>,
+}
+
+/// Whether a path is an `.r3d` archive, an exported capture or its metadata file
+pub fn is_capture(path: &Path) -> bool {
+    if is_archive(path) {
+        return true;
+    }
+    let Some(metadata) = metadata_path(path) else { return false };
+    let Ok(file) = File::open(metadata) else { return false };
+    serde_json::from_reader::<_, Metadata>(BufReader::new(file)).is_ok()
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file() && path.extension().and_then(|s| s.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("r3d"))
+}
+
+/// Frames in capture order, named `rgbd/<i>.jpg`
+pub fn read(path: &Path) -> Result<Vec<CaptureFrame>> {
+    let metadata: Metadata = if is_archive(path) {
+        let mut archive = open_archive(path)?;
+        let entry = archive.by_name(METADATA_FILE).map_err(|error| match error {
+            ZipError::FileNotFound => HylaeanError::ConfigError {
+                message: format!("Record3D capture {} has no {}", path.display(), METADATA_FILE),
+            },
+            error => zip_error(path, error),
+        })?;
+        serde_json::from_reader(BufReader::new(entry))?
+    } else {
+        let metadata = metadata_path(path).ok_or_else(|| HylaeanError::ConfigError {
+            message: format!("no Record3D {} file in {}", METADATA_FILE, path.display()),
+        })?;
+        serde_json::from_reader(BufReader::new(File::open(metadata)?))?
+    };
+
+    let by_rows = arkit::rows_first(std::slice::from_ref(&metadata.k), 3, "Record3D K")?;
+    let k = arkit::rows::<3>(&metadata.k, by_rows);
+    let model = arkit::pinhole(k[0][0], k[1][1], k[0][2], k[1][2])?;
+    Ok(metadata.poses.iter().enumerate().map(|(i, [qx, qy, qz, qw, tx, ty, tz])| CaptureFrame {
+        image: format!("{}/{}.jpg", FRAMES_DIR, i),
+        camera_to_world: Pose::from_quaternion([*qw, *qx, *qy, *qz], [*tx, *ty, *tz]),
+        model,
+        width: metadata.w,
+        height: metadata.h,
+    }).collect())
+}
+
+/// Unpack the color frames of an `.r3d` archive into `dir/rgbd/`, streaming each entry
+/// to its file so no frame is held in memory
+pub fn extract_frames(archive_path: &Path, dir: &Path) -> Result<usize> {
+    let mut archive = open_archive(archive_path)?;
+    std::fs::create_dir_all(dir.join(FRAMES_DIR))?;
+    let mut extracted = 0;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|error| zip_error(archive_path, error))?;
+        let name = entry.name().to_string();
+        if !(name.starts_with(&format!("{}/", FRAMES_DIR)) && name.ends_with(".jpg")) {
+            continue;
+        }
+        // Entry names are written below `dir`; none may climb out of it
+        if name.split('/').any(|part| part == "..") {
+            continue;
+        }
+        let path = dir.join(&name);
+        let mut file = BufWriter::new(File::create(&path)?);
+        std::io::copy(&mut entry, &mut file)?;
+        file.flush()?;
+        debug!("Extracted {}", path.display());
+        extracted += 1;
+    }
+    Ok(extracted)
+}
+
+fn open_archive(path: &Path) -> Result<ZipArchive<BufReader<File>>> {
+    ZipArchive::new(BufReader::new(File::open(path)?)).map_err(|error| zip_error(path, error))
+}
+
+fn zip_error(path: &Path, error: ZipError) -> HylaeanError {
+    match error {
+        ZipError::Io(error) => HylaeanError::IoError(error),
+        error => HylaeanError::UnsupportedFormat {
+            format: format!("zip archive {}: {}", path.display(), error),
+        },
+    }
+}
+
+/// The metadata file of an exported capture, or the path itself when it names one
+fn metadata_path(path: &Path) -> Option<PathBuf> {
+    let candidates = if path.is_dir() {
+        vec![path.join(METADATA_FILE), path.join(format!("{}.json", METADATA_FILE))]
+    } else {
+        vec![path.to_path_buf()]
+    };
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
@@ -0,0 +1,340 @@
+//! 3D Gaussian splat model and INRIA-style PLY reading/writing
+//!
+//! The layout follows the original `gaussian_splatting` exporter: position, optional
+//! normals, `f_dc_*`, `f_rest_*` (channel-major), `opacity` (logit), `scale_*` (log)
+//! and `rot_*` (w, x, y, z). Unrecognised vertex properties are carried through untouched.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::ply::{self, Encoding, Header, PlyReader, PlyRecord, PropertyType, ScalarType};
+use log::{debug, info, warn};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Zeroth-order SH basis constant, used to map DC coefficients to RGB
+pub const SH_C0: f32 = 0.282_094_8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gaussian {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub sh_dc: [f32; 3],
+    /// Higher-order SH coefficients in file order: all of R, then G, then B
+    pub sh_rest: Vec<f32>,
+    pub opacity: f32,
+    pub scale: [f32; 3],
+    pub rotation: [f32; 4],
+    pub extra: Vec<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GaussianCloud {
+    pub gaussians: Vec<Gaussian>,
+    pub sh_degree: u32,
+    pub has_normals: bool,
+    pub extra_properties: Vec<(String, ScalarType)>,
+    pub comments: Vec<String>,
+}
+
+/// Where each known attribute lives in a vertex record
+struct VertexLayout {
+    position: [usize; 3],
+    normal: Option<[usize; 3]>,
+    sh_dc: [usize; 3],
+    sh_rest: Vec<usize>,
+    opacity: usize,
+    scale: [usize; 3],
+    rotation: [usize; 4],
+    extra: Vec<usize>,
+}
+
+impl Gaussian {
+    pub fn new(sh_degree: u32) -> Self {
+        Self {
+            position: [0.0; 3],
+            normal: [0.0; 3],
+            sh_dc: [0.0; 3],
+            sh_rest: vec![0.0; sh_rest_len(sh_degree)],
+            opacity: 0.0,
+            scale: [0.0; 3],
+            rotation: [1.0, 0.0, 0.0, 0.0],
+            extra: Vec::new(),
+        }
+    }
+
+    /// Linear opacity in [0, 1]
+    pub fn alpha(&self) -> f32 {
+        sigmoid(self.opacity)
+    }
+
+    /// Linear (exponentiated) scale along each local axis
+    pub fn linear_scale(&self) -> [f32; 3] {
+        [self.scale[0].exp(), self.scale[1].exp(), self.scale[2].exp()]
+    }
+
+    /// Base color evaluated from the DC term, clamped to [0, 1]
+    pub fn base_color(&self) -> [f32; 3] {
+        let mut color = [0.0; 3];
+        for (c, dc) in color.iter_mut().zip(self.sh_dc) {
+            *c = (0.5 + SH_C0 * dc).clamp(0.0, 1.0);
+        }
+        color
+    }
+}
+
+impl GaussianCloud {
+    pub fn new(sh_degree: u32) -> Self {
+        Self {
+            gaussians: Vec::new(),
+            sh_degree,
+            has_normals: true,
+            extra_properties: Vec::new(),
+            comments: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.gaussians.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gaussians.is_empty()
+    }
+
+    /// Truncate the higher-order SH coefficients to `sh_degree`, or zero-pad them up to it
+    pub fn set_sh_degree(&mut self, sh_degree: u32) {
+        if sh_degree == self.sh_degree {
+            return;
+        }
+        let old_len = sh_rest_len(self.sh_degree) / 3;
+        let new_len = sh_rest_len(sh_degree) / 3;
+        let kept = old_len.min(new_len);
+        for gaussian in &mut self.gaussians {
+            let mut rest = vec![0.0; 3 * new_len];
+            for channel in 0..3 {
+                let source = &gaussian.sh_rest[channel * old_len..channel * old_len + kept];
+                rest[channel * new_len..channel * new_len + kept].copy_from_slice(source);
+            }
+            gaussian.sh_rest = rest;
+        }
+        self.sh_degree = sh_degree;
+    }
+
+    /// True when the header's vertex element carries the attributes of a trained splat
+    pub fn is_gaussian_header(header: &Header) -> bool {
+        header.elements.get("vertex")
+            .map(|vertex| {
+                ["f_dc_0", "opacity", "scale_0", "rot_0"].iter()
+                    .all(|name| vertex.properties.contains_key(*name))
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn read_ply(path: &Path) -> Result<Self> {
+        debug!("Reading Gaussian PLY: {}", path.display());
+
+        let mut reader = PlyReader::open(path)?;
+        let header = reader.header().clone();
+        if !Self::is_gaussian_header(&header) {
+            return Err(HylaeanError::UnsupportedFormat {
+                format: format!("{} is not a Gaussian splat PLY", path.display()),
+            });
+        }
+
+        let mut cloud = Self::new(0);
+        cloud.comments = header.comments.clone();
+
+        for (name, element_def) in &header.elements {
+            if name != "vertex" {
+                warn!("Skipping non-vertex PLY element '{}' ({} records)", name, element_def.count);
+                reader.skip_element(element_def)?;
+                continue;
+            }
+
+            let layout = cloud.resolve_layout(element_def)?;
+            cloud.gaussians.reserve(element_def.count.min(1 << 20));
+
+            let mut record = PlyRecord::default();
+            for _ in 0..element_def.count {
+                reader.read_record(element_def, &mut record)?;
+                cloud.gaussians.push(layout.decode(&record));
+            }
+        }
+
+        info!(
+            "Read {} Gaussians (SH degree {}) from {}",
+            cloud.len(), cloud.sh_degree, path.display()
+        );
+        Ok(cloud)
+    }
+
+    pub fn write_ply(&self, path: &Path, encoding: Encoding) -> Result<()> {
+        debug!("Writing Gaussian PLY: {}", path.display());
+
+        let rest_len = sh_rest_len(self.sh_degree);
+        if let Some(bad) = self.gaussians.iter().find(|g| g.sh_rest.len() != rest_len) {
+            return Err(HylaeanError::Unknown {
+                message: format!(
+                    "Gaussian has {} f_rest coefficients, SH degree {} requires {}",
+                    bad.sh_rest.len(), self.sh_degree, rest_len
+                ),
+            });
+        }
+
+        let mut header = Header::new();
+        header.encoding = encoding;
+        header.comments = self.comments.clone();
+        let vertex = ply::element_def("vertex", self.len(), &self.property_names());
+        header.elements.insert("vertex".to_string(), vertex.clone());
+
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        ply::write_header(&mut writer, &header)?;
+
+        let mut record = PlyRecord::default();
+        for gaussian in &self.gaussians {
+            self.encode(gaussian, &mut record);
+            ply::write_record(&mut writer, encoding, &vertex, &record)?;
+        }
+        writer.flush()?;
+
+        info!("Wrote {} Gaussians to {}", self.len(), path.display());
+        Ok(())
+    }
+
+    /// Vertex property names and types in INRIA order, followed by any extras
+    pub fn property_names(&self) -> Vec<(String, ScalarType)> {
+        let mut names: Vec<String> = vec!["x".into(), "y".into(), "z".into()];
+        if self.has_normals {
+            names.extend(["nx".into(), "ny".into(), "nz".into()]);
+        }
+        names.extend((0..3).map(|i| format!("f_dc_{}", i)));
+        names.extend((0..sh_rest_len(self.sh_degree)).map(|i| format!("f_rest_{}", i)));
+        names.push("opacity".into());
+        names.extend((0..3).map(|i| format!("scale_{}", i)));
+        names.extend((0..4).map(|i| format!("rot_{}", i)));
+
+        let mut properties: Vec<(String, ScalarType)> = names.into_iter()
+            .map(|name| (name, ScalarType::Float))
+            .collect();
+        properties.extend(self.extra_properties.iter().cloned());
+        properties
+    }
+
+    fn encode(&self, gaussian: &Gaussian, record: &mut PlyRecord) {
+        use ply::Property::Float;
+
+        record.0.clear();
+        record.0.extend(gaussian.position.iter().map(|v| Float(*v)));
+        if self.has_normals {
+            record.0.extend(gaussian.normal.iter().map(|v| Float(*v)));
+        }
+        record.0.extend(gaussian.sh_dc.iter().map(|v| Float(*v)));
+        record.0.extend(gaussian.sh_rest.iter().map(|v| Float(*v)));
+        record.0.push(Float(gaussian.opacity));
+        record.0.extend(gaussian.scale.iter().map(|v| Float(*v)));
+        record.0.extend(gaussian.rotation.iter().map(|v| Float(*v)));
+        for ((_, scalar_type), value) in self.extra_properties.iter().zip(&gaussian.extra) {
+            record.0.push(ply::scalar_from_f64(scalar_type, *value));
+        }
+    }
+
+    fn resolve_layout(&mut self, element_def: &ply::ElementDef) -> Result<VertexLayout> {
+        let names: Vec<&str> = element_def.properties.keys().map(|k| k.as_str()).collect();
+        let find = |name: &str| -> Result<usize> {
+            names.iter().position(|n| *n == name).ok_or_else(|| HylaeanError::UnsupportedFormat {
+                format: format!("Gaussian PLY is missing property '{}'", name),
+            })
+        };
+
+        let mut rest: Vec<(usize, usize)> = Vec::new();
+        let mut extra = Vec::new();
+        for (index, (name, property_def)) in element_def.properties.iter().enumerate() {
+            if let Some(n) = name.strip_prefix("f_rest_").and_then(|n| n.parse::<usize>().ok()) {
+                rest.push((n, index));
+                continue;
+            }
+            let known = matches!(
+                name.as_str(),
+                "x" | "y" | "z" | "nx" | "ny" | "nz" | "f_dc_0" | "f_dc_1" | "f_dc_2"
+                    | "opacity" | "scale_0" | "scale_1" | "scale_2"
+                    | "rot_0" | "rot_1" | "rot_2" | "rot_3"
+            );
+            if !known {
+                match &property_def.data_type {
+                    PropertyType::Scalar(scalar_type) => {
+                        self.extra_properties.push((name.clone(), scalar_type.clone()));
+                        extra.push(index);
+                    }
+                    PropertyType::List(_, _) => {
+                        warn!("Dropping list property '{}' from Gaussian PLY", name);
+                    }
+                }
+            }
+        }
+
+        rest.sort_unstable();
+        if rest.iter().enumerate().any(|(i, (n, _))| i != *n) {
+            return Err(HylaeanError::UnsupportedFormat {
+                format: "Gaussian PLY f_rest_* properties are not contiguous".to_string(),
+            });
+        }
+        self.sh_degree = sh_degree_from_rest_len(rest.len()).ok_or_else(|| HylaeanError::UnsupportedFormat {
+            format: format!("{} f_rest_* coefficients do not match any SH degree", rest.len()),
+        })?;
+
+        let normal = match (find("nx"), find("ny"), find("nz")) {
+            (Ok(nx), Ok(ny), Ok(nz)) => Some([nx, ny, nz]),
+            _ => None,
+        };
+        self.has_normals = normal.is_some();
+
+        Ok(VertexLayout {
+            position: [find("x")?, find("y")?, find("z")?],
+            normal,
+            sh_dc: [find("f_dc_0")?, find("f_dc_1")?, find("f_dc_2")?],
+            sh_rest: rest.into_iter().map(|(_, index)| index).collect(),
+            opacity: find("opacity")?,
+            scale: [find("scale_0")?, find("scale_1")?, find("scale_2")?],
+            rotation: [find("rot_0")?, find("rot_1")?, find("rot_2")?, find("rot_3")?],
+            extra,
+        })
+    }
+}
+
+impl VertexLayout {
+    fn decode(&self, record: &PlyRecord) -> Gaussian {
+        let value = |index: usize| ply::scalar_as_f64(&record.0[index]).unwrap_or(0.0);
+        let float = |index: usize| value(index) as f32;
+
+        Gaussian {
+            position: self.position.map(float),
+            normal: self.normal.map(|n| n.map(float)).unwrap_or([0.0; 3]),
+            sh_dc: self.sh_dc.map(float),
+            sh_rest: self.sh_rest.iter().map(|i| float(*i)).collect(),
+            opacity: float(self.opacity),
+            scale: self.scale.map(float),
+            rotation: self.rotation.map(float),
+            extra: self.extra.iter().map(|i| value(*i)).collect(),
+        }
+    }
+}
+
+/// Number of `f_rest_*` coefficients for an SH degree (three color channels)
+pub fn sh_rest_len(sh_degree: u32) -> usize {
+    let coeffs = (sh_degree as usize + 1).pow(2);
+    3 * (coeffs - 1)
+}
+
+pub fn sh_degree_from_rest_len(rest_len: usize) -> Option<u32> {
+    (0..=8).find(|degree| sh_rest_len(*degree) == rest_len)
+}
+
+pub fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+pub fn inverse_sigmoid(y: f32) -> f32 {
+    let y = y.clamp(1e-6, 1.0 - 1e-6);
+    (y / (1.0 - y)).ln()
+}
@@ -0,0 +1,535 @@
+//! Format-independent summaries of point cloud and splat files
+//!
+//! Streamable formats are read chunk by chunk, so inspecting a huge scan costs one pass
+//! and constant memory. `.splat`, `.ksplat` and SPZ files are decoded whole; they are
+//! compact by design.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::PointCloudFormat;
+use crate::formats::gaussian::{self, GaussianCloud};
+use crate::formats::las::LasReader;
+use crate::formats::pcd::{self, PcdField};
+use crate::formats::ply::{self, Property, PropertyType, ScalarType};
+use crate::formats::point_cloud::stream::{self, PointChunk, Schema};
+use crate::formats::{splat, spz};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Opacity bins over linear alpha
+const OPACITY_EDGES: [f64; 11] = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0];
+/// Scale bins over the largest linear axis, one per decade
+const SCALE_EDGES: [f64; 9] = [1e-6, 1e-5, 1e-4, 1e-3, 1e-2, 1e-1, 1.0, 10.0, 100.0];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReport {
+    pub path: String,
+    pub format: String,
+    pub encoding: Option<String>,
+    pub elements: Vec<ElementSchema>,
+    /// Format-specific header values, such as the LAS point format or SPZ version
+    pub details: BTreeMap<String, String>,
+    pub point_count: u64,
+    pub bounds: Option<Bounds>,
+    pub centroid: Option<[f64; 3]>,
+    pub has_color: bool,
+    pub has_normals: bool,
+    pub sh_degree: Option<u32>,
+    pub opacity_histogram: Option<Histogram>,
+    pub scale_histogram: Option<Histogram>,
+    pub nan_count: u64,
+    pub inf_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ElementSchema {
+    pub name: String,
+    pub count: u64,
+    pub properties: Vec<PropertySchema>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertySchema {
+    pub name: String,
+    pub data_type: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Bounds {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+/// `counts[i]` holds values in `[edges[i], edges[i + 1])`; values outside the edges
+/// are counted in the first or last bin.
+#[derive(Debug, Clone, Serialize)]
+pub struct Histogram {
+    pub edges: Vec<f64>,
+    pub counts: Vec<u64>,
+}
+
+impl Histogram {
+    fn new(edges: &[f64]) -> Self {
+        Self {
+            edges: edges.to_vec(),
+            counts: vec![0; edges.len() - 1],
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        let bin = self.edges[1..self.edges.len() - 1].iter()
+            .take_while(|edge| value >= **edge)
+            .count();
+        self.counts[bin] += 1;
+    }
+}
+
+/// Summarize a point cloud or splat file
+pub fn inspect(path: &Path, format: &PointCloudFormat) -> Result<FileReport> {
+    let mut report = FileReport {
+        path: path.display().to_string(),
+        format: format!("{:?}", format),
+        encoding: None,
+        elements: Vec::new(),
+        details: BTreeMap::new(),
+        point_count: 0,
+        bounds: None,
+        centroid: None,
+        has_color: false,
+        has_normals: false,
+        sh_degree: None,
+        opacity_histogram: None,
+        scale_histogram: None,
+        nan_count: 0,
+        inf_count: 0,
+    };
+    let mut stats = Stats::default();
+
+    match format {
+        PointCloudFormat::PLY => {
+            let header = ply::read_header(path)?;
+            report.encoding = Some(header.encoding.to_string());
+            for (name, element_def) in &header.elements {
+                report.elements.push(ElementSchema {
+                    name: name.clone(),
+                    count: element_def.count as u64,
+                    properties: element_def.properties.iter()
+                        .map(|(name, property_def)| PropertySchema {
+                            name: name.clone(),
+                            data_type: match &property_def.data_type {
+                                PropertyType::Scalar(scalar_type) => scalar_type_name(scalar_type).to_string(),
+                                PropertyType::List(index_type, scalar_type) => format!(
+                                    "list {} {}", scalar_type_name(index_type), scalar_type_name(scalar_type)
+                                ),
+                            },
+                        })
+                        .collect(),
+                });
+            }
+            if !header.comments.is_empty() {
+                report.details.insert("comments".to_string(), header.comments.join("; "));
+            }
+            stats.read_stream(path, format)?;
+        }
+        PointCloudFormat::PCD => {
+            let header = pcd::read_header(path)?;
+            report.encoding = Some(header.data.to_string());
+            report.elements.push(ElementSchema {
+                name: "points".to_string(),
+                count: header.points as u64,
+                properties: header.fields.iter().map(pcd_property).collect(),
+            });
+            report.details.insert("version".to_string(), header.version.clone());
+            report.details.insert("width x height".to_string(), format!("{} x {}", header.width, header.height));
+            report.details.insert(
+                "viewpoint".to_string(),
+                header.viewpoint.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(" "),
+            );
+            stats.read_stream(path, format)?;
+        }
+        PointCloudFormat::XYZ => {
+            report.encoding = Some("ascii".to_string());
+            stats.read_stream(path, format)?;
+            report.elements.push(ElementSchema {
+                name: "points".to_string(),
+                count: stats.count,
+                properties: schema_properties(&stats.schema),
+            });
+        }
+        PointCloudFormat::LAZ => {
+            let mut reader = LasReader::open(path)?;
+            let header = reader.header().clone();
+            report.encoding = Some(if header.compressed { "laz" } else { "las" }.to_string());
+            report.elements.push(ElementSchema {
+                name: "points".to_string(),
+                count: header.point_count,
+                properties: las_properties(header.has_gps_time(), header.has_color(), header.has_nir()),
+            });
+            report.details.insert("version".to_string(), format!("{}.{}", header.version.0, header.version.1));
+            report.details.insert("point format".to_string(), header.point_format.to_string());
+            report.details.insert("scale".to_string(), format!("{:?}", header.scale));
+            report.details.insert("offset".to_string(), format!("{:?}", header.offset));
+            report.details.insert("generating software".to_string(), header.generating_software.clone());
+            report.details.insert("VLRs".to_string(), header.vlrs.len().to_string());
+            stats.has_color = header.has_color();
+
+            while let Some(point) = reader.read_point()? {
+                stats.add_position(point.position);
+                stats.count_value(point.gps_time);
+            }
+        }
+        PointCloudFormat::Splat | PointCloudFormat::KSplat | PointCloudFormat::Spz => {
+            let cloud = match format {
+                PointCloudFormat::Spz => {
+                    let header = spz::read_header(path)?;
+                    report.encoding = Some(format!("spz v{}", header.version));
+                    report.details.insert("fractional bits".to_string(), header.fractional_bits.to_string());
+                    report.details.insert("antialiased".to_string(), header.antialiased().to_string());
+                    spz::read_spz(path)?
+                }
+                PointCloudFormat::KSplat => {
+                    report.encoding = Some("ksplat".to_string());
+                    splat::read_ksplat(path)?
+                }
+                _ => {
+                    report.encoding = Some("binary".to_string());
+                    splat::read_splat(path)?
+                }
+            };
+            report.elements.push(ElementSchema {
+                name: "gaussians".to_string(),
+                count: cloud.len() as u64,
+                properties: schema_properties(&cloud.property_names()),
+            });
+            stats.add_cloud(&cloud);
+        }
+        other => {
+            return Err(HylaeanError::UnsupportedFormat {
+                format: format!("{:?} (inspect)", other),
+            });
+        }
+    }
+
+    report.point_count = stats.count;
+    report.nan_count = stats.nan;
+    report.inf_count = stats.inf;
+    report.has_color = stats.has_color;
+    report.has_normals = stats.has_normals;
+    report.sh_degree = stats.sh_degree;
+    if stats.positions > 0 {
+        report.bounds = Some(Bounds { min: stats.min, max: stats.max });
+        report.centroid = Some(stats.sum.map(|sum| sum / stats.positions as f64));
+    }
+    if stats.opacity.counts.iter().sum::<u64>() > 0 {
+        report.opacity_histogram = Some(stats.opacity);
+        report.scale_histogram = Some(stats.scale);
+    }
+    Ok(report)
+}
+
+/// Running totals shared by every format
+struct Stats {
+    schema: Schema,
+    count: u64,
+    positions: u64,
+    min: [f64; 3],
+    max: [f64; 3],
+    sum: [f64; 3],
+    nan: u64,
+    inf: u64,
+    has_color: bool,
+    has_normals: bool,
+    sh_degree: Option<u32>,
+    opacity: Histogram,
+    scale: Histogram,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            schema: Schema::new(),
+            count: 0,
+            positions: 0,
+            min: [f64::INFINITY; 3],
+            max: [f64::NEG_INFINITY; 3],
+            sum: [0.0; 3],
+            nan: 0,
+            inf: 0,
+            has_color: false,
+            has_normals: false,
+            sh_degree: None,
+            opacity: Histogram::new(&OPACITY_EDGES),
+            scale: Histogram::new(&SCALE_EDGES),
+        }
+    }
+}
+
+impl Stats {
+    fn read_stream(&mut self, path: &Path, format: &PointCloudFormat) -> Result<()> {
+        let mut source = stream::open_source(path, format)?;
+        self.schema = source.schema().clone();
+
+        let column = |name: &str| self.schema.iter().position(|(key, _)| key == name);
+        let columns = |names: [&str; 3]| -> Option<[usize; 3]> {
+            Some([column(names[0])?, column(names[1])?, column(names[2])?])
+        };
+        let position = columns(["x", "y", "z"]);
+        let opacity = column("opacity");
+        let scale = columns(["scale_0", "scale_1", "scale_2"]);
+
+        self.has_color = columns(["red", "green", "blue"]).is_some() || column("f_dc_0").is_some();
+        self.has_normals = columns(["nx", "ny", "nz"]).is_some();
+        if column("f_dc_0").is_some() {
+            let rest = self.schema.iter().filter(|(name, _)| name.starts_with("f_rest_")).count();
+            self.sh_degree = gaussian::sh_degree_from_rest_len(rest);
+        }
+
+        let mut chunk = PointChunk::with_capacity(stream::DEFAULT_CHUNK_SIZE);
+        while source.read_chunk(&mut chunk)? > 0 {
+            for record in chunk.records() {
+                let value = |index: usize| ply::scalar_as_f64(&record.0[index]).unwrap_or(0.0);
+                for property in &record.0 {
+                    match property {
+                        Property::Float(v) => self.count_value(*v as f64),
+                        Property::Double(v) => self.count_value(*v),
+                        _ => {}
+                    }
+                }
+                match position {
+                    Some(position) => self.add_position(position.map(value)),
+                    None => self.count += 1,
+                }
+                if let (Some(opacity), Some(scale)) = (opacity, scale) {
+                    self.add_gaussian(value(opacity), scale.map(value));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_cloud(&mut self, cloud: &GaussianCloud) {
+        self.has_color = true;
+        self.has_normals = cloud.has_normals;
+        self.sh_degree = Some(cloud.sh_degree);
+
+        for gaussian in &cloud.gaussians {
+            let values = gaussian.position.iter()
+                .chain(&gaussian.sh_dc)
+                .chain(&gaussian.sh_rest)
+                .chain(&gaussian.scale)
+                .chain(&gaussian.rotation)
+                .chain(std::iter::once(&gaussian.opacity));
+            for value in values {
+                self.count_value(*value as f64);
+            }
+            self.add_position(gaussian.position.map(|v| v as f64));
+            self.add_gaussian(gaussian.opacity as f64, gaussian.scale.map(|v| v as f64));
+        }
+    }
+
+    /// Count a point; non-finite positions are left out of the bounds and centroid
+    fn add_position(&mut self, position: [f64; 3]) {
+        self.count += 1;
+        if position.iter().all(|v| v.is_finite()) {
+            self.positions += 1;
+            for (axis, value) in position.into_iter().enumerate() {
+                self.min[axis] = self.min[axis].min(value);
+                self.max[axis] = self.max[axis].max(value);
+                self.sum[axis] += value;
+            }
+        }
+    }
+
+    /// Record logit opacity and log scales, as stored by 3DGS trainers
+    fn add_gaussian(&mut self, opacity: f64, log_scale: [f64; 3]) {
+        self.opacity.add(gaussian::sigmoid(opacity as f32) as f64);
+        let largest = log_scale.iter().fold(f64::NEG_INFINITY, |max, v| max.max(*v));
+        self.scale.add(largest.exp());
+    }
+
+    fn count_value(&mut self, value: f64) {
+        if value.is_nan() {
+            self.nan += 1;
+        } else if value.is_infinite() {
+            self.inf += 1;
+        }
+    }
+}
+
+impl fmt::Display for FileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.path)?;
+        write_field(f, "Format", &self.format)?;
+        if let Some(encoding) = &self.encoding {
+            write_field(f, "Encoding", encoding)?;
+        }
+        for (name, value) in &self.details {
+            write_field(f, &capitalize(name), value)?;
+        }
+        write_field(f, "Points", &self.point_count)?;
+        if let (Some(bounds), Some(centroid)) = (&self.bounds, &self.centroid) {
+            write_field(f, "Bounds min", &format_vector(&bounds.min))?;
+            write_field(f, "Bounds max", &format_vector(&bounds.max))?;
+            write_field(f, "Centroid", &format_vector(centroid))?;
+        }
+        write_field(f, "Color", &yes_no(self.has_color))?;
+        write_field(f, "Normals", &yes_no(self.has_normals))?;
+        if let Some(sh_degree) = self.sh_degree {
+            write_field(f, "SH degree", &sh_degree)?;
+        }
+        write_field(f, "NaN values", &self.nan_count)?;
+        write_field(f, "Inf values", &self.inf_count)?;
+
+        for element in &self.elements {
+            writeln!(f, "  Element {} ({}):", element.name, element.count)?;
+            write_properties(f, &element.properties)?;
+        }
+
+        if let Some(histogram) = &self.opacity_histogram {
+            writeln!(f, "  Opacity:")?;
+            write_histogram(f, histogram, |edge| format!("{:.1}", edge))?;
+        }
+        if let Some(histogram) = &self.scale_histogram {
+            writeln!(f, "  Largest scale:")?;
+            write_histogram(f, histogram, |edge| format!("{:.0e}", edge))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_field(f: &mut fmt::Formatter<'_>, label: &str, value: &dyn fmt::Display) -> fmt::Result {
+    writeln!(f, "  {:<21} {}", format!("{}:", label), value)
+}
+
+/// List properties, folding numbered runs such as `f_rest_0` .. `f_rest_44` into one line
+fn write_properties(f: &mut fmt::Formatter<'_>, properties: &[PropertySchema]) -> fmt::Result {
+    let numbered = |property: &PropertySchema| -> Option<(String, usize)> {
+        let (prefix, index) = property.name.rsplit_once('_')?;
+        Some((prefix.to_string(), index.parse().ok()?))
+    };
+
+    let mut i = 0;
+    while i < properties.len() {
+        let mut end = i + 1;
+        if let Some((prefix, first)) = numbered(&properties[i]) {
+            while end < properties.len()
+                && properties[end].data_type == properties[i].data_type
+                && numbered(&properties[end]) == Some((prefix.clone(), first + end - i))
+            {
+                end += 1;
+            }
+        }
+
+        if end - i > 3 {
+            writeln!(
+                f,
+                "    {:<12} {} .. {} ({})",
+                properties[i].data_type, properties[i].name, properties[end - 1].name, end - i
+            )?;
+        } else {
+            end = i + 1;
+            writeln!(f, "    {:<12} {}", properties[i].data_type, properties[i].name)?;
+        }
+        i = end;
+    }
+    Ok(())
+}
+
+fn write_histogram(f: &mut fmt::Formatter<'_>, histogram: &Histogram, label: impl Fn(f64) -> String) -> fmt::Result {
+    const BAR_WIDTH: u64 = 40;
+    let total = histogram.counts.iter().sum::<u64>().max(1);
+    let peak = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    for (i, count) in histogram.counts.iter().enumerate() {
+        let range = format!("{}..{}", label(histogram.edges[i]), label(histogram.edges[i + 1]));
+        let line = format!(
+            "    {:<14} {:>10} {:>5.1}% {}",
+            range,
+            count,
+            100.0 * *count as f64 / total as f64,
+            "#".repeat((count * BAR_WIDTH / peak) as usize)
+        );
+        writeln!(f, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+fn schema_properties(schema: &Schema) -> Vec<PropertySchema> {
+    schema.iter()
+        .map(|(name, scalar_type)| PropertySchema {
+            name: name.clone(),
+            data_type: scalar_type_name(scalar_type).to_string(),
+        })
+        .collect()
+}
+
+/// PCD fields as `<TYPE><SIZE>`, with the element count when it is not 1
+fn pcd_property(field: &PcdField) -> PropertySchema {
+    let data_type = if field.count == 1 {
+        format!("{}{}", field.kind, field.size)
+    } else {
+        format!("{}{} x{}", field.kind, field.size, field.count)
+    };
+    PropertySchema {
+        name: field.name.clone(),
+        data_type,
+    }
+}
+
+/// The LAS point record fields present for a point format
+fn las_properties(gps_time: bool, color: bool, nir: bool) -> Vec<PropertySchema> {
+    let mut properties = vec![
+        ("x", "int (scaled)"), ("y", "int (scaled)"), ("z", "int (scaled)"),
+        ("intensity", "ushort"), ("return_number", "bits"), ("number_of_returns", "bits"),
+        ("classification", "uchar"), ("scan_angle", "char/short"), ("user_data", "uchar"),
+        ("point_source_id", "ushort"),
+    ];
+    if gps_time {
+        properties.push(("gps_time", "double"));
+    }
+    if color {
+        properties.extend([("red", "ushort"), ("green", "ushort"), ("blue", "ushort")]);
+    }
+    if nir {
+        properties.push(("nir", "ushort"));
+    }
+    properties.into_iter()
+        .map(|(name, data_type)| PropertySchema {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+        })
+        .collect()
+}
+
+/// PLY spelling of a scalar type
+fn scalar_type_name(scalar_type: &ScalarType) -> &'static str {
+    match scalar_type {
+        ScalarType::Char => "char",
+        ScalarType::UChar => "uchar",
+        ScalarType::Short => "short",
+        ScalarType::UShort => "ushort",
+        ScalarType::Int => "int",
+        ScalarType::UInt => "uint",
+        ScalarType::Float => "float",
+        ScalarType::Double => "double",
+    }
+}
+
+fn format_vector(v: &[f64; 3]) -> String {
+    format!("{:.4} {:.4} {:.4}", v[0], v[1], v[2])
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,597 @@
+//! ASPRS LAS 1.2-1.4 point clouds, with LAZ handled through LASzip
+//!
+//! Point data formats 0-3 and 6-8 are read and written natively. LAZ point data is
+//! compressed and decompressed record by record with the `laz` crate, a port of the
+//! LASzip codec, so LAZ needs no external tools or temporary files.
+
+use crate::errors::{Result, HylaeanError};
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+use laz::{LasZipCompressor, LasZipDecompressor, LasZipError, LazVlr, LazVlrBuilder};
+use log::{debug, warn};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const SIGNATURE: &[u8; 4] = b"LASF";
+const VLR_HEADER_SIZE: usize = 54;
+/// LASzip marks compressed point data by setting the top bit of the format byte
+const LASZIP_FORMAT_FLAG: u8 = 0x80;
+const GENERATING_SOFTWARE: &str = "Hylaean Splat";
+
+/// A variable length record, kept verbatim so CRS and metadata survive a round trip
+#[derive(Debug, Clone)]
+pub struct LasVlr {
+    pub user_id: String,
+    pub record_id: u16,
+    pub description: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LasHeader {
+    pub version: (u8, u8),
+    pub point_format: u8,
+    pub point_record_length: u16,
+    pub point_count: u64,
+    pub points_by_return: [u64; 15],
+    pub scale: [f64; 3],
+    pub offset: [f64; 3],
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+    pub file_source_id: u16,
+    pub global_encoding: u16,
+    pub system_identifier: String,
+    pub generating_software: String,
+    pub compressed: bool,
+    pub vlrs: Vec<LasVlr>,
+    offset_to_point_data: u32,
+}
+
+impl LasHeader {
+    /// Header for a new file; the LAS version follows from the point format
+    pub fn new(point_format: u8, scale: [f64; 3], offset: [f64; 3]) -> Result<Self> {
+        let point_record_length = standard_record_length(point_format)?;
+        Ok(Self {
+            version: if point_format >= 6 { (1, 4) } else { (1, 2) },
+            point_format,
+            point_record_length,
+            point_count: 0,
+            points_by_return: [0; 15],
+            scale,
+            offset,
+            min: [0.0; 3],
+            max: [0.0; 3],
+            file_source_id: 0,
+            // Formats 6-10 must declare their CRS as WKT
+            global_encoding: if point_format >= 6 { 0x10 } else { 0 },
+            system_identifier: "OTHER".to_string(),
+            generating_software: GENERATING_SOFTWARE.to_string(),
+            compressed: false,
+            vlrs: Vec::new(),
+            offset_to_point_data: 0,
+        })
+    }
+
+    pub fn has_gps_time(&self) -> bool {
+        matches!(self.point_format, 1 | 3 | 6..=8)
+    }
+
+    pub fn has_color(&self) -> bool {
+        matches!(self.point_format, 2 | 3 | 7 | 8)
+    }
+
+    pub fn has_nir(&self) -> bool {
+        self.point_format == 8
+    }
+
+    fn header_size(&self) -> u16 {
+        match self.version.1 {
+            0..=2 => 227,
+            3 => 235,
+            _ => 375,
+        }
+    }
+
+    fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let mut bytes = vec![0u8; 227];
+        reader.read_exact(&mut bytes).map_err(|_| invalid("file is shorter than the LAS header"))?;
+        if &bytes[0..4] != SIGNATURE {
+            return Err(invalid("missing LASF signature"));
+        }
+
+        let version = (bytes[24], bytes[25]);
+        if version.0 != 1 || !(0..=4).contains(&version.1) {
+            return Err(invalid(&format!("unsupported version {}.{}", version.0, version.1)));
+        }
+
+        let header_size = LittleEndian::read_u16(&bytes[94..96]) as usize;
+        if header_size > bytes.len() {
+            bytes.resize(header_size, 0);
+            reader.read_exact(&mut bytes[227..]).map_err(|_| invalid("truncated header"))?;
+        }
+
+        let raw_format = bytes[104];
+        let f64_at = |i: usize| LittleEndian::read_f64(&bytes[i..i + 8]);
+        let mut header = Self {
+            version,
+            point_format: raw_format & 0x3f,
+            point_record_length: LittleEndian::read_u16(&bytes[105..107]),
+            point_count: LittleEndian::read_u32(&bytes[107..111]) as u64,
+            points_by_return: [0; 15],
+            scale: [f64_at(131), f64_at(139), f64_at(147)],
+            offset: [f64_at(155), f64_at(163), f64_at(171)],
+            max: [f64_at(179), f64_at(195), f64_at(211)],
+            min: [f64_at(187), f64_at(203), f64_at(219)],
+            file_source_id: LittleEndian::read_u16(&bytes[4..6]),
+            global_encoding: LittleEndian::read_u16(&bytes[6..8]),
+            system_identifier: fixed_string(&bytes[26..58]),
+            generating_software: fixed_string(&bytes[58..90]),
+            // LASzip flags compressed files by setting the top bits of the format byte
+            compressed: raw_format & 0xc0 != 0,
+            vlrs: Vec::new(),
+            offset_to_point_data: LittleEndian::read_u32(&bytes[96..100]),
+        };
+        for (i, count) in header.points_by_return.iter_mut().take(5).enumerate() {
+            *count = LittleEndian::read_u32(&bytes[111 + i * 4..115 + i * 4]) as u64;
+        }
+        if version.1 >= 4 && header_size >= 375 {
+            header.point_count = LittleEndian::read_u64(&bytes[247..255]);
+            for (i, count) in header.points_by_return.iter_mut().enumerate() {
+                *count = LittleEndian::read_u64(&bytes[255 + i * 8..263 + i * 8]);
+            }
+            if LittleEndian::read_u32(&bytes[243..247]) > 0 {
+                warn!("LAS extended VLRs after the point data are not carried over");
+            }
+        }
+
+        let standard = standard_record_length(header.point_format)?;
+        if header.point_record_length < standard {
+            return Err(invalid(&format!(
+                "point format {} needs {} bytes per record, header declares {}",
+                header.point_format, standard, header.point_record_length
+            )));
+        }
+
+        reader.seek(SeekFrom::Start(header_size as u64))?;
+        let vlr_count = LittleEndian::read_u32(&bytes[100..104]);
+        for _ in 0..vlr_count {
+            let mut vlr_header = [0u8; VLR_HEADER_SIZE];
+            reader.read_exact(&mut vlr_header).map_err(|_| invalid("truncated VLR"))?;
+            let length = LittleEndian::read_u16(&vlr_header[20..22]) as usize;
+            let mut data = vec![0u8; length];
+            reader.read_exact(&mut data).map_err(|_| invalid("truncated VLR"))?;
+            header.vlrs.push(LasVlr {
+                user_id: fixed_string(&vlr_header[2..18]),
+                record_id: LittleEndian::read_u16(&vlr_header[18..20]),
+                description: fixed_string(&vlr_header[22..54]),
+                data,
+            });
+        }
+
+        Ok(header)
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        if self.version.1 < 4 && self.point_count > u32::MAX as u64 {
+            return Err(too_many_points(self.version));
+        }
+        let mut bytes = vec![0u8; self.header_size() as usize];
+        bytes[0..4].copy_from_slice(SIGNATURE);
+        LittleEndian::write_u16(&mut bytes[4..6], self.file_source_id);
+        LittleEndian::write_u16(&mut bytes[6..8], self.global_encoding);
+        bytes[24] = self.version.0;
+        bytes[25] = self.version.1;
+        put_fixed_string(&mut bytes[26..58], &self.system_identifier);
+        put_fixed_string(&mut bytes[58..90], &self.generating_software);
+        LittleEndian::write_u16(&mut bytes[94..96], self.header_size());
+        LittleEndian::write_u32(&mut bytes[96..100], self.offset_to_point_data);
+        LittleEndian::write_u32(&mut bytes[100..104], self.vlrs.len() as u32);
+        bytes[104] = if self.compressed { self.point_format | LASZIP_FORMAT_FLAG } else { self.point_format };
+        LittleEndian::write_u16(&mut bytes[105..107], self.point_record_length);
+
+        // Legacy counts are zero when they cannot represent the file
+        let legacy = self.point_format < 6 && self.point_count <= u32::MAX as u64;
+        if legacy {
+            LittleEndian::write_u32(&mut bytes[107..111], self.point_count as u32);
+            for (i, count) in self.points_by_return.iter().take(5).enumerate() {
+                LittleEndian::write_u32(&mut bytes[111 + i * 4..115 + i * 4], *count as u32);
+            }
+        }
+
+        let mut put_f64 = |i: usize, v: f64| LittleEndian::write_f64(&mut bytes[i..i + 8], v);
+        for axis in 0..3 {
+            put_f64(131 + axis * 8, self.scale[axis]);
+            put_f64(155 + axis * 8, self.offset[axis]);
+            put_f64(179 + axis * 16, self.max[axis]);
+            put_f64(187 + axis * 16, self.min[axis]);
+        }
+
+        if self.version.1 >= 4 {
+            LittleEndian::write_u64(&mut bytes[247..255], self.point_count);
+            for (i, count) in self.points_by_return.iter().enumerate() {
+                LittleEndian::write_u64(&mut bytes[255 + i * 8..263 + i * 8], *count);
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// One point record, with the union of the fields of formats 0-3 and 6-8
+#[derive(Debug, Clone, Default)]
+pub struct LasPoint {
+    pub position: [f64; 3],
+    pub intensity: u16,
+    pub return_number: u8,
+    pub number_of_returns: u8,
+    pub classification: u8,
+    /// Synthetic, key-point, withheld and (formats 6+) overlap bits
+    pub classification_flags: u8,
+    pub scanner_channel: u8,
+    pub scan_direction: bool,
+    pub edge_of_flight_line: bool,
+    /// Scan angle in degrees
+    pub scan_angle: f32,
+    pub user_data: u8,
+    pub point_source_id: u16,
+    pub gps_time: f64,
+    pub color: [u16; 3],
+    pub nir: u16,
+}
+
+/// Where point records come from: the file itself, or the LASzip decoder over it
+enum PointSource {
+    Las(BufReader<File>),
+    Laz(Box<LasZipDecompressor<'static, BufReader<File>>>),
+}
+
+/// Sequential reader over the points of a LAS or LAZ file
+pub struct LasReader {
+    source: PointSource,
+    header: LasHeader,
+    remaining: u64,
+    record: Vec<u8>,
+}
+
+impl LasReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut header = LasHeader::read(&mut reader)?;
+        reader.seek(SeekFrom::Start(header.offset_to_point_data as u64))?;
+
+        let source = if header.compressed {
+            let vlr = header.vlrs.iter()
+                .find(|vlr| is_laszip_vlr(vlr))
+                .ok_or_else(|| invalid("compressed point data without a LASzip VLR"))?;
+            let vlr = LazVlr::from_buffer(&vlr.data).map_err(laz_error)?;
+            PointSource::Laz(Box::new(LasZipDecompressor::new(reader, vlr).map_err(laz_error)?))
+        } else {
+            PointSource::Las(reader)
+        };
+        header.vlrs.retain(|vlr| !is_laszip_vlr(vlr));
+
+        let standard = standard_record_length(header.point_format)?;
+        if header.point_record_length > standard {
+            debug!(
+                "Ignoring {} extra bytes per LAS point record",
+                header.point_record_length - standard
+            );
+        }
+
+        Ok(Self {
+            source,
+            remaining: header.point_count,
+            record: vec![0u8; header.point_record_length as usize],
+            header,
+        })
+    }
+
+    pub fn header(&self) -> &LasHeader {
+        &self.header
+    }
+
+    /// Start over from the first point, e.g. for a second pass
+    pub fn rewind(&mut self) -> Result<()> {
+        match &mut self.source {
+            PointSource::Las(reader) => {
+                reader.seek(SeekFrom::Start(self.header.offset_to_point_data as u64))?;
+            }
+            PointSource::Laz(decompressor) => decompressor.seek(0).map_err(laz_error)?,
+        }
+        self.remaining = self.header.point_count;
+        Ok(())
+    }
+
+    /// Read the next point, or `None` once all declared points have been read
+    pub fn read_point(&mut self) -> Result<Option<LasPoint>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let read = match &mut self.source {
+            PointSource::Las(reader) => reader.read_exact(&mut self.record),
+            PointSource::Laz(decompressor) => decompressor.decompress_one(&mut self.record),
+        };
+        read.map_err(|_| invalid("point data ended before the declared point count"))?;
+        self.remaining -= 1;
+        Ok(Some(decode_point(&self.header, &self.record)))
+    }
+}
+
+/// Where point records go: straight to the file, or through the LASzip encoder
+enum PointSink {
+    Las(BufWriter<File>),
+    Laz(Box<LasZipCompressor<'static, BufWriter<File>>>),
+}
+
+/// Streaming LAS writer; a `.laz` path is compressed with LASzip as points arrive
+pub struct LasWriter {
+    sink: PointSink,
+    header: LasHeader,
+    record: Vec<u8>,
+}
+
+impl LasWriter {
+    pub fn create(path: &Path, mut header: LasHeader) -> Result<Self> {
+        if let Some(vlr) = header.vlrs.iter().find(|vlr| vlr.data.len() > u16::MAX as usize) {
+            return Err(invalid(&format!(
+                "VLR {} {} holds {} bytes, more than the {} a VLR can",
+                vlr.user_id, vlr.record_id, vlr.data.len(), u16::MAX
+            )));
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        header.compressed = is_laz_path(path);
+        header.point_count = 0;
+        header.points_by_return = [0; 15];
+        header.min = [f64::INFINITY; 3];
+        header.max = [f64::NEG_INFINITY; 3];
+        header.point_record_length = standard_record_length(header.point_format)?;
+        header.vlrs.retain(|vlr| !is_laszip_vlr(vlr));
+        let laz_vlr = if header.compressed {
+            let vlr = LazVlrBuilder::default()
+                .with_point_format(header.point_format, 0)
+                .map_err(laz_error)?
+                .build();
+            let mut data = Vec::new();
+            vlr.write_to(&mut data)?;
+            header.vlrs.push(LasVlr {
+                user_id: LazVlr::USER_ID.to_string(),
+                record_id: LazVlr::RECORD_ID,
+                description: LazVlr::DESCRIPTION.to_string(),
+                data,
+            });
+            Some(vlr)
+        } else {
+            None
+        };
+        header.offset_to_point_data = header.header_size() as u32
+            + header.vlrs.iter().map(|vlr| (VLR_HEADER_SIZE + vlr.data.len()) as u32).sum::<u32>();
+
+        writer.write_all(&header.to_bytes()?)?;
+        for vlr in &header.vlrs {
+            writer.write_u16::<LittleEndian>(0)?;
+            let mut fixed = [0u8; 16];
+            put_fixed_string(&mut fixed, &vlr.user_id);
+            writer.write_all(&fixed)?;
+            writer.write_u16::<LittleEndian>(vlr.record_id)?;
+            writer.write_u16::<LittleEndian>(vlr.data.len() as u16)?;
+            let mut fixed = [0u8; 32];
+            put_fixed_string(&mut fixed, &vlr.description);
+            writer.write_all(&fixed)?;
+            writer.write_all(&vlr.data)?;
+        }
+
+        let sink = match laz_vlr {
+            Some(vlr) => PointSink::Laz(Box::new(LasZipCompressor::new(writer, vlr).map_err(laz_error)?)),
+            None => PointSink::Las(writer),
+        };
+        Ok(Self {
+            sink,
+            record: vec![0u8; header.point_record_length as usize],
+            header,
+        })
+    }
+
+    pub fn write_point(&mut self, point: &LasPoint) -> Result<()> {
+        // Fail on the point that does not fit rather than after writing the rest
+        if self.header.version.1 < 4 && self.header.point_count == u32::MAX as u64 {
+            return Err(too_many_points(self.header.version));
+        }
+        encode_point(&self.header, point, &mut self.record)?;
+        match &mut self.sink {
+            PointSink::Las(writer) => writer.write_all(&self.record)?,
+            PointSink::Laz(compressor) => compressor.compress_one(&self.record)?,
+        }
+
+        let header = &mut self.header;
+        header.point_count += 1;
+        if (1..=15).contains(&point.return_number) {
+            header.points_by_return[point.return_number as usize - 1] += 1;
+        }
+        for axis in 0..3 {
+            header.min[axis] = header.min[axis].min(point.position[axis]);
+            header.max[axis] = header.max[axis].max(point.position[axis]);
+        }
+        Ok(())
+    }
+
+    /// Patch the header with the final count and bounds; returns the number of points
+    pub fn finish(mut self) -> Result<u64> {
+        if self.header.point_count == 0 {
+            self.header.min = [0.0; 3];
+            self.header.max = [0.0; 3];
+        }
+        let mut writer = match self.sink {
+            PointSink::Las(writer) => writer,
+            PointSink::Laz(mut compressor) => {
+                // Flushes the last chunk and appends the chunk table
+                compressor.done()?;
+                compressor.into_inner()
+            }
+        };
+        writer.seek(SeekFrom::Start(0))?;
+        writer.write_all(&self.header.to_bytes()?)?;
+        writer.flush()?;
+        Ok(self.header.point_count)
+    }
+}
+
+pub fn read_header(path: &Path) -> Result<LasHeader> {
+    let mut reader = BufReader::new(File::open(path)?);
+    LasHeader::read(&mut reader)
+}
+
+pub fn is_laz_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("laz"))
+}
+
+/// Record size of a point format without extra bytes
+fn standard_record_length(point_format: u8) -> Result<u16> {
+    match point_format {
+        0 => Ok(20),
+        1 => Ok(28),
+        2 => Ok(26),
+        3 => Ok(34),
+        6 => Ok(30),
+        7 => Ok(36),
+        8 => Ok(38),
+        other => Err(invalid(&format!("point data format {} is not supported", other))),
+    }
+}
+
+fn decode_point(header: &LasHeader, bytes: &[u8]) -> LasPoint {
+    let mut point = LasPoint::default();
+    for axis in 0..3 {
+        let raw = LittleEndian::read_i32(&bytes[axis * 4..axis * 4 + 4]);
+        point.position[axis] = raw as f64 * header.scale[axis] + header.offset[axis];
+    }
+    point.intensity = LittleEndian::read_u16(&bytes[12..14]);
+
+    let extras = if header.point_format >= 6 {
+        point.return_number = bytes[14] & 0x0f;
+        point.number_of_returns = bytes[14] >> 4;
+        point.classification_flags = bytes[15] & 0x0f;
+        point.scanner_channel = (bytes[15] >> 4) & 0x03;
+        point.scan_direction = bytes[15] & 0x40 != 0;
+        point.edge_of_flight_line = bytes[15] & 0x80 != 0;
+        point.classification = bytes[16];
+        point.user_data = bytes[17];
+        point.scan_angle = LittleEndian::read_i16(&bytes[18..20]) as f32 * 0.006;
+        point.point_source_id = LittleEndian::read_u16(&bytes[20..22]);
+        point.gps_time = LittleEndian::read_f64(&bytes[22..30]);
+        30
+    } else {
+        point.return_number = bytes[14] & 0x07;
+        point.number_of_returns = (bytes[14] >> 3) & 0x07;
+        point.scan_direction = bytes[14] & 0x40 != 0;
+        point.edge_of_flight_line = bytes[14] & 0x80 != 0;
+        point.classification = bytes[15] & 0x1f;
+        point.classification_flags = bytes[15] >> 5;
+        point.scan_angle = bytes[16] as i8 as f32;
+        point.user_data = bytes[17];
+        point.point_source_id = LittleEndian::read_u16(&bytes[18..20]);
+        if header.has_gps_time() {
+            point.gps_time = LittleEndian::read_f64(&bytes[20..28]);
+            28
+        } else {
+            20
+        }
+    };
+
+    if header.has_color() {
+        for (c, value) in point.color.iter_mut().enumerate() {
+            *value = LittleEndian::read_u16(&bytes[extras + c * 2..extras + c * 2 + 2]);
+        }
+    }
+    if header.has_nir() {
+        point.nir = LittleEndian::read_u16(&bytes[extras + 6..extras + 8]);
+    }
+    point
+}
+
+fn encode_point(header: &LasHeader, point: &LasPoint, bytes: &mut [u8]) -> Result<()> {
+    let mut cursor = &mut bytes[..];
+    for axis in 0..3 {
+        let raw = ((point.position[axis] - header.offset[axis]) / header.scale[axis]).round();
+        if raw < i32::MIN as f64 || raw > i32::MAX as f64 {
+            return Err(invalid(&format!(
+                "coordinate {} does not fit the header scale {} and offset {}",
+                point.position[axis], header.scale[axis], header.offset[axis]
+            )));
+        }
+        cursor.write_i32::<LittleEndian>(raw as i32)?;
+    }
+    cursor.write_u16::<LittleEndian>(point.intensity)?;
+
+    if header.point_format >= 6 {
+        cursor.write_u8((point.return_number & 0x0f) | (point.number_of_returns << 4))?;
+        cursor.write_u8(
+            (point.classification_flags & 0x0f)
+                | (point.scanner_channel & 0x03) << 4
+                | (point.scan_direction as u8) << 6
+                | (point.edge_of_flight_line as u8) << 7,
+        )?;
+        cursor.write_u8(point.classification)?;
+        cursor.write_u8(point.user_data)?;
+        cursor.write_i16::<LittleEndian>((point.scan_angle / 0.006).round() as i16)?;
+        cursor.write_u16::<LittleEndian>(point.point_source_id)?;
+        cursor.write_f64::<LittleEndian>(point.gps_time)?;
+    } else {
+        cursor.write_u8(
+            (point.return_number.min(7))
+                | point.number_of_returns.min(7) << 3
+                | (point.scan_direction as u8) << 6
+                | (point.edge_of_flight_line as u8) << 7,
+        )?;
+        cursor.write_u8(point.classification.min(31) | (point.classification_flags & 0x07) << 5)?;
+        cursor.write_i8(point.scan_angle.round().clamp(-90.0, 90.0) as i8)?;
+        cursor.write_u8(point.user_data)?;
+        cursor.write_u16::<LittleEndian>(point.point_source_id)?;
+        if header.has_gps_time() {
+            cursor.write_f64::<LittleEndian>(point.gps_time)?;
+        }
+    }
+
+    if header.has_color() {
+        for value in point.color {
+            cursor.write_u16::<LittleEndian>(value)?;
+        }
+    }
+    if header.has_nir() {
+        cursor.write_u16::<LittleEndian>(point.nir)?;
+    }
+    Ok(())
+}
+
+fn is_laszip_vlr(vlr: &LasVlr) -> bool {
+    vlr.user_id == LazVlr::USER_ID && vlr.record_id == LazVlr::RECORD_ID
+}
+
+fn laz_error(error: LasZipError) -> HylaeanError {
+    invalid(&format!("LASzip: {}", error))
+}
+
+fn fixed_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim_end().to_string()
+}
+
+fn put_fixed_string(bytes: &mut [u8], value: &str) {
+    let len = value.len().min(bytes.len());
+    bytes[..len].copy_from_slice(&value.as_bytes()[..len]);
+}
+
+/// LAS 1.2 counts points in 32 bits; formats 6 and up write LAS 1.4, which uses 64
+fn too_many_points(version: (u8, u8)) -> HylaeanError {
+    invalid(&format!(
+        "LAS {}.{} holds at most {} points; use point format 6 or higher for more",
+        version.0, version.1, u32::MAX
+    ))
+}
+
+fn invalid(message: &str) -> HylaeanError {
+    HylaeanError::UnsupportedFormat {
+        format: format!("LAS: {}", message),
+    }
+}
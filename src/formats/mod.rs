@@ -2,6 +2,16 @@
 
 pub mod point_cloud;
 pub mod camera_params;
+pub mod ply;
+pub mod gaussian;
+pub mod splat;
+pub mod spz;
+pub mod las;
+pub mod pcd;
+pub mod inspect;
+pub mod npy;
+pub mod math;
+pub mod splat_ops;
 
 use crate::errors::{Result, HylaeanError};
 use serde::{Deserialize, Serialize};
@@ -20,6 +30,9 @@ pub enum PointCloudFormat {
     PCD,
     XYZ,
     LAZ,
+    Splat,
+    KSplat,
+    Spz,
     Custom(String),
 }
 
@@ -29,6 +42,11 @@ pub enum CameraFormat {
     NeRF,
     OpenCV,
     Blender,
+    Polycam,
+    Record3D,
+    ARKit,
+    Bundler,
+    AliceVision,
     Custom(String),
 }
 
@@ -46,6 +64,29 @@ pub trait FormatConverter {
 }
 
 pub fn detect_format(path: &Path) -> Result<DataFormat> {
+    // COLMAP sparse models are directories of cameras/images files in either encoding;
+    // LLFF scenes are directories holding poses_bounds.npy, rigs directories of OpenCV
+    // calibrations; mobile captures are directories of app exports or per-frame ARKit JSON
+    if path.is_dir() {
+        return if camera_params::colmap::is_model(path) {
+            Ok(DataFormat::CameraParameters(CameraFormat::COLMAP))
+        } else if path.join(camera_params::llff::POSES_BOUNDS_FILE).is_file() {
+            Ok(DataFormat::Dataset(DatasetFormat::LLFF))
+        } else if camera_params::polycam::is_export(path) {
+            Ok(DataFormat::CameraParameters(CameraFormat::Polycam))
+        } else if camera_params::record3d::is_capture(path) {
+            Ok(DataFormat::CameraParameters(CameraFormat::Record3D))
+        } else if camera_params::arkit::is_arkit(path) {
+            Ok(DataFormat::CameraParameters(CameraFormat::ARKit))
+        } else if camera_params::opencv::calibration_files(path)?.iter().any(|file| file.is_file()) {
+            Ok(DataFormat::CameraParameters(CameraFormat::OpenCV))
+        } else {
+            Err(HylaeanError::UnsupportedFormat {
+                format: format!("directory {}", path.display()),
+            })
+        };
+    }
+    
     let extension = path.extension()
         .and_then(|s| s.to_str())
         .unwrap_or("");
@@ -54,7 +95,10 @@ pub fn detect_format(path: &Path) -> Result<DataFormat> {
         "ply" => Ok(DataFormat::PointCloud(PointCloudFormat::PLY)),
         "pcd" => Ok(DataFormat::PointCloud(PointCloudFormat::PCD)),
         "xyz" => Ok(DataFormat::PointCloud(PointCloudFormat::XYZ)),
-        "laz" => Ok(DataFormat::PointCloud(PointCloudFormat::LAZ)),
+        "las" | "laz" => Ok(DataFormat::PointCloud(PointCloudFormat::LAZ)),
+        "splat" => Ok(DataFormat::PointCloud(PointCloudFormat::Splat)),
+        "ksplat" => Ok(DataFormat::PointCloud(PointCloudFormat::KSplat)),
+        "spz" => Ok(DataFormat::PointCloud(PointCloudFormat::Spz)),
         "txt" => {
             // Could be camera parameters or point cloud
             if looks_like_camera_params(path)? {
@@ -63,7 +107,17 @@ pub fn detect_format(path: &Path) -> Result<DataFormat> {
                 Ok(DataFormat::PointCloud(PointCloudFormat::XYZ))
             }
         }
+        "json" if camera_params::BlenderCameras::is_blender_cameras(path) => Ok(DataFormat::CameraParameters(CameraFormat::Blender)),
+        "json" if camera_params::record3d::is_capture(path) => Ok(DataFormat::CameraParameters(CameraFormat::Record3D)),
+        "json" if camera_params::arkit::is_arkit(path) => Ok(DataFormat::CameraParameters(CameraFormat::ARKit)),
         "json" => Ok(DataFormat::CameraParameters(CameraFormat::NeRF)),
+        "out" => Ok(DataFormat::CameraParameters(CameraFormat::Bundler)),
+        "sfm" => Ok(DataFormat::CameraParameters(CameraFormat::AliceVision)),
+        "r3d" => Ok(DataFormat::CameraParameters(CameraFormat::Record3D)),
+        "" if camera_params::record3d::is_capture(path) => Ok(DataFormat::CameraParameters(CameraFormat::Record3D)),
+        "npy" => Ok(DataFormat::Dataset(DatasetFormat::LLFF)),
+        "yml" | "yaml" | "xml" => Ok(DataFormat::CameraParameters(CameraFormat::OpenCV)),
+        "bin" if camera_params::colmap::is_model(path) => Ok(DataFormat::CameraParameters(CameraFormat::COLMAP)),
         _ => Err(HylaeanError::UnsupportedFormat {
             format: extension.to_string(),
         }),
@@ -79,7 +133,7 @@ fn looks_like_camera_params(path: &Path) -> Result<bool> {
     
     for line in reader.lines().take(5) {
         let line = line?;
-        if line.contains("# Camera list") || line.contains("# Image list") {
+        if line.contains("# Camera list") || line.contains("# Image list") || line.contains("# 3D point list") {
             return Ok(true);
         }
     }
@@ -92,11 +146,20 @@ pub fn parse_format(format_str: &str) -> Result<DataFormat> {
         "ply" => Ok(DataFormat::PointCloud(PointCloudFormat::PLY)),
         "pcd" => Ok(DataFormat::PointCloud(PointCloudFormat::PCD)),
         "xyz" => Ok(DataFormat::PointCloud(PointCloudFormat::XYZ)),
-        "laz" => Ok(DataFormat::PointCloud(PointCloudFormat::LAZ)),
+        "las" | "laz" => Ok(DataFormat::PointCloud(PointCloudFormat::LAZ)),
+        "splat" => Ok(DataFormat::PointCloud(PointCloudFormat::Splat)),
+        "ksplat" => Ok(DataFormat::PointCloud(PointCloudFormat::KSplat)),
+        "spz" => Ok(DataFormat::PointCloud(PointCloudFormat::Spz)),
         "colmap" => Ok(DataFormat::CameraParameters(CameraFormat::COLMAP)),
         "nerf" => Ok(DataFormat::CameraParameters(CameraFormat::NeRF)),
         "opencv" => Ok(DataFormat::CameraParameters(CameraFormat::OpenCV)),
         "blender" => Ok(DataFormat::CameraParameters(CameraFormat::Blender)),
+        "polycam" => Ok(DataFormat::CameraParameters(CameraFormat::Polycam)),
+        "record3d" | "r3d" => Ok(DataFormat::CameraParameters(CameraFormat::Record3D)),
+        "arkit" => Ok(DataFormat::CameraParameters(CameraFormat::ARKit)),
+        "bundler" => Ok(DataFormat::CameraParameters(CameraFormat::Bundler)),
+        "alicevision" | "meshroom" => Ok(DataFormat::CameraParameters(CameraFormat::AliceVision)),
+        "llff" => Ok(DataFormat::Dataset(DatasetFormat::LLFF)),
         _ => Err(HylaeanError::UnsupportedFormat {
             format: format_str.to_string(),
         }),
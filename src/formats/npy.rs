@@ -0,0 +1,156 @@
+//! Minimal NumPy `.npy` support for floating point arrays
+//!
+//! Reads format versions 1 to 3 with `f4` or `f8` data of either byte order and either
+//! memory order; always writes version 1.0, little-endian `f8`, C order, which is what
+//! `np.save` produces for the camera arrays we exchange (LLFF `poses_bounds.npy`).
+
+use crate::errors::{Result, HylaeanError};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+/// A dense array in C (row-major) order
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpyArray {
+    pub shape: Vec<usize>,
+    pub data: Vec<f64>,
+}
+
+impl NpyArray {
+    pub fn new(shape: Vec<usize>, data: Vec<f64>) -> Result<Self> {
+        if shape.iter().product::<usize>() != data.len() {
+            return Err(HylaeanError::ConfigError {
+                message: format!("array of shape {:?} cannot hold {} values", shape, data.len()),
+            });
+        }
+        Ok(Self { shape, data })
+    }
+
+    /// Rows of a 2-D array
+    pub fn rows(&self) -> std::slice::Chunks<'_, f64> {
+        self.data.chunks(self.shape.get(1).copied().unwrap_or(1).max(1))
+    }
+}
+
+pub fn read(path: &Path) -> Result<NpyArray> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; 6];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(malformed(path, "missing the \\x93NUMPY magic"));
+    }
+    let major = reader.read_u8()?;
+    let _minor = reader.read_u8()?;
+    let header_len = match major {
+        1 => reader.read_u16::<LittleEndian>()? as usize,
+        2 | 3 => reader.read_u32::<LittleEndian>()? as usize,
+        _ => return Err(malformed(path, &format!("unknown format version {}", major))),
+    };
+    let mut header = vec![0u8; header_len];
+    reader.read_exact(&mut header)?;
+    let header = String::from_utf8_lossy(&header);
+
+    let descr = dict_value(&header, "descr").ok_or_else(|| malformed(path, "no descr in header"))?;
+    let descr = descr.trim_matches(|c| c == '\'' || c == '"');
+    let fortran_order = dict_value(&header, "fortran_order").ok_or_else(|| malformed(path, "no fortran_order in header"))? == "True";
+    let shape_text = dict_value(&header, "shape").ok_or_else(|| malformed(path, "no shape in header"))?;
+    let shape = shape_text
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.parse::<usize>().map_err(|_| malformed(path, &format!("bad shape {}", shape_text))))
+        .collect::<Result<Vec<_>>>()?;
+
+    let count = shape.iter().product::<usize>();
+    let (width, big_endian) = match descr {
+        "<f8" => (8, false),
+        ">f8" => (8, true),
+        "<f4" => (4, false),
+        ">f4" => (4, true),
+        other => {
+            return Err(HylaeanError::UnsupportedFormat {
+                format: format!("npy dtype {} (only f4 and f8 are read)", other),
+            })
+        }
+    };
+    let mut bytes = vec![0u8; count * width];
+    reader.read_exact(&mut bytes)?;
+    let mut data: Vec<f64> = bytes
+        .chunks_exact(width)
+        .map(|value| match (width, big_endian) {
+            (8, false) => LittleEndian::read_f64(value),
+            (8, true) => BigEndian::read_f64(value),
+            (_, false) => LittleEndian::read_f32(value) as f64,
+            (_, true) => BigEndian::read_f32(value) as f64,
+        })
+        .collect();
+
+    if fortran_order && shape.len() > 1 {
+        data = fortran_to_c(&data, &shape);
+    }
+    Ok(NpyArray { shape, data })
+}
+
+pub fn write(path: &Path, array: &NpyArray) -> Result<()> {
+    let shape = match array.shape.as_slice() {
+        [single] => format!("({},)", single),
+        dims => format!("({})", dims.iter().map(|dim| dim.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}", shape);
+    // The data starts 64-byte aligned; the header ends with a newline
+    let unpadded = MAGIC.len() + 2 + 2 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in &array.data {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The text of `key`'s value in the header's Python dict literal
+fn dict_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key)).or_else(|| header.find(&format!("\"{}\"", key)))?;
+    let rest = header[start + key.len() + 2..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else {
+        rest.find([',', '}'])?
+    };
+    Some(rest[..end].trim())
+}
+
+/// Reorder column-major data into row-major
+fn fortran_to_c(data: &[f64], shape: &[usize]) -> Vec<f64> {
+    let mut reordered = vec![0.0; data.len()];
+    let mut index = vec![0usize; shape.len()];
+    for value in data {
+        // The first index varies fastest in Fortran order
+        let offset = index.iter().zip(shape).fold(0, |offset, (i, dim)| offset * dim + i);
+        reordered[offset] = *value;
+        for (i, dim) in index.iter_mut().zip(shape) {
+            *i += 1;
+            if *i < *dim {
+                break;
+            }
+            *i = 0;
+        }
+    }
+    reordered
+}
+
+fn malformed(path: &Path, detail: &str) -> HylaeanError {
+    HylaeanError::UnsupportedFormat {
+        format: format!("npy file {}: {}", path.display(), detail),
+    }
+}
@@ -0,0 +1,727 @@
+//! Point Cloud Library PCD v0.7 files
+//!
+//! All three DATA modes are supported: `ascii`, `binary` (little-endian records) and
+//! `binary_compressed` (LZF-compressed, field-major columns). Values are carried as PLY
+//! `Property` scalars so records can move between the two formats without reshaping.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::ply::{self, PlyRecord, Property, ScalarType};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Width reserved for WIDTH and POINTS values that are patched after writing
+const COUNT_WIDTH: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcdData {
+    Ascii,
+    Binary,
+    BinaryCompressed,
+}
+
+impl fmt::Display for PcdData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcdData::Ascii => write!(f, "ascii"),
+            PcdData::Binary => write!(f, "binary"),
+            PcdData::BinaryCompressed => write!(f, "binary_compressed"),
+        }
+    }
+}
+
+pub fn parse_data(data_str: &str) -> Result<PcdData> {
+    match data_str.to_lowercase().as_str() {
+        "ascii" => Ok(PcdData::Ascii),
+        "binary" => Ok(PcdData::Binary),
+        "binary_compressed" => Ok(PcdData::BinaryCompressed),
+        _ => Err(HylaeanError::UnsupportedFormat {
+            format: format!("PCD DATA {}", data_str),
+        }),
+    }
+}
+
+/// One `FIELDS` entry with its `SIZE`, `TYPE` and `COUNT`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcdField {
+    pub name: String,
+    pub size: usize,
+    pub kind: char,
+    pub count: usize,
+}
+
+impl PcdField {
+    pub fn new(name: &str, scalar_type: &ScalarType) -> Self {
+        let (kind, size) = match scalar_type {
+            ScalarType::Char => ('I', 1),
+            ScalarType::UChar => ('U', 1),
+            ScalarType::Short => ('I', 2),
+            ScalarType::UShort => ('U', 2),
+            ScalarType::Int => ('I', 4),
+            ScalarType::UInt => ('U', 4),
+            ScalarType::Float => ('F', 4),
+            ScalarType::Double => ('F', 8),
+        };
+        Self { name: name.to_string(), size, kind, count: 1 }
+    }
+
+    /// The PLY scalar type holding this field; 64-bit integers widen to double
+    pub fn scalar_type(&self) -> ScalarType {
+        match (self.kind, self.size) {
+            ('I', 1) => ScalarType::Char,
+            ('U', 1) => ScalarType::UChar,
+            ('I', 2) => ScalarType::Short,
+            ('U', 2) => ScalarType::UShort,
+            ('I', 4) => ScalarType::Int,
+            ('U', 4) => ScalarType::UInt,
+            ('F', 4) => ScalarType::Float,
+            _ => ScalarType::Double,
+        }
+    }
+
+    /// PCL pads structs with fields named `_`, which carry no data
+    pub fn is_padding(&self) -> bool {
+        self.name == "_"
+    }
+
+    fn validate(&self) -> Result<()> {
+        let valid = match self.kind {
+            'I' | 'U' => matches!(self.size, 1 | 2 | 4 | 8),
+            'F' => matches!(self.size, 4 | 8),
+            _ => false,
+        };
+        if valid && self.count > 0 {
+            Ok(())
+        } else {
+            Err(invalid(&format!(
+                "field '{}' has unsupported TYPE {} SIZE {} COUNT {}",
+                self.name, self.kind, self.size, self.count
+            )))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PcdHeader {
+    pub version: String,
+    pub fields: Vec<PcdField>,
+    pub width: usize,
+    pub height: usize,
+    pub viewpoint: [f64; 7],
+    pub points: usize,
+    pub data: PcdData,
+}
+
+impl PcdHeader {
+    /// Unorganized cloud of `points` points
+    pub fn new(fields: Vec<PcdField>, points: usize, data: PcdData) -> Self {
+        Self {
+            version: "0.7".to_string(),
+            fields,
+            width: points,
+            height: 1,
+            viewpoint: [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0],
+            points,
+            data,
+        }
+    }
+
+    /// Number of scalar values per point, counting every `COUNT` element
+    pub fn values_per_point(&self) -> usize {
+        self.fields.iter().map(|field| field.count).sum()
+    }
+
+    /// Bytes per point in the binary layouts
+    pub fn point_size(&self) -> usize {
+        self.fields.iter().map(|field| field.size * field.count).sum()
+    }
+
+    /// Index of a field's first value within a flattened record
+    pub fn value_index(&self, name: &str) -> Option<usize> {
+        let mut index = 0;
+        for field in &self.fields {
+            if field.name == name {
+                return Some(index);
+            }
+            index += field.count;
+        }
+        None
+    }
+
+    /// Flattened record indices of the `x`, `y`, `z` fields
+    pub fn position_indices(&self) -> Result<[usize; 3]> {
+        let mut indices = [0; 3];
+        for (index, axis) in indices.iter_mut().zip(["x", "y", "z"]) {
+            *index = self.value_index(axis)
+                .ok_or_else(|| invalid(&format!("no '{}' field", axis)))?;
+        }
+        Ok(indices)
+    }
+
+    fn read<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let mut version = "0.7".to_string();
+        let mut names = Vec::new();
+        let mut sizes = Vec::new();
+        let mut kinds = Vec::new();
+        let mut counts = Vec::new();
+        let mut width = None;
+        let mut height = 1;
+        let mut viewpoint = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+        let mut points = None;
+        let mut line = String::new();
+
+        let data = loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(invalid("header ended without a DATA line"));
+            }
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let mut tokens = trimmed.split_whitespace();
+            let keyword = tokens.next().unwrap_or("").to_uppercase();
+            let values: Vec<&str> = tokens.collect();
+            let number = |value: Option<&&str>| {
+                value.and_then(|v| v.parse::<usize>().ok())
+                    .ok_or_else(|| invalid(&format!("malformed {} line", keyword)))
+            };
+
+            match keyword.as_str() {
+                "VERSION" => version = values.first().unwrap_or(&"0.7").to_string(),
+                "FIELDS" | "COLUMNS" => names = values.iter().map(|v| v.to_string()).collect(),
+                "SIZE" => sizes = values.iter().map(|v| number(Some(v))).collect::<Result<_>>()?,
+                "TYPE" => kinds = values.iter().map(|v| v.chars().next().unwrap_or('?').to_ascii_uppercase()).collect(),
+                "COUNT" => counts = values.iter().map(|v| number(Some(v))).collect::<Result<_>>()?,
+                "WIDTH" => width = Some(number(values.first())?),
+                "HEIGHT" => height = number(values.first())?,
+                "POINTS" => points = Some(number(values.first())?),
+                "VIEWPOINT" => {
+                    for (slot, value) in viewpoint.iter_mut().zip(&values) {
+                        *slot = value.parse().map_err(|_| invalid("malformed VIEWPOINT line"))?;
+                    }
+                }
+                "DATA" => break parse_data(values.first().unwrap_or(&""))?,
+                other => return Err(invalid(&format!("unknown header keyword {}", other))),
+            }
+        };
+
+        if counts.is_empty() {
+            counts = vec![1; names.len()];
+        }
+        if names.is_empty() || sizes.len() != names.len() || kinds.len() != names.len() || counts.len() != names.len() {
+            return Err(invalid("FIELDS, SIZE, TYPE and COUNT lines disagree"));
+        }
+
+        let fields: Vec<PcdField> = names.into_iter()
+            .zip(sizes).zip(kinds).zip(counts)
+            .map(|(((name, size), kind), count)| PcdField { name, size, kind, count })
+            .collect();
+        for field in &fields {
+            field.validate()?;
+        }
+
+        let width = width.ok_or_else(|| invalid("missing WIDTH"))?;
+        Ok(Self {
+            version,
+            fields,
+            width,
+            height,
+            viewpoint,
+            points: points.unwrap_or(width * height),
+            data,
+        })
+    }
+
+    /// Render the header text. A nonzero `count_width` pads WIDTH and POINTS so they
+    /// can be overwritten in place once the real count is known.
+    fn render(&self, count_width: usize) -> String {
+        let join = |values: Vec<String>| values.join(" ");
+        let mut text = String::new();
+        text += &format!("# .PCD v{} - Point Cloud Data file format\n", self.version);
+        text += &format!("VERSION {}\n", self.version);
+        text += &format!("FIELDS {}\n", join(self.fields.iter().map(|f| f.name.clone()).collect()));
+        text += &format!("SIZE {}\n", join(self.fields.iter().map(|f| f.size.to_string()).collect()));
+        text += &format!("TYPE {}\n", join(self.fields.iter().map(|f| f.kind.to_string()).collect()));
+        text += &format!("COUNT {}\n", join(self.fields.iter().map(|f| f.count.to_string()).collect()));
+        text += &format!("WIDTH {:<1$}\n", self.width, count_width);
+        text += &format!("HEIGHT {}\n", self.height);
+        text += &format!("VIEWPOINT {}\n", join(self.viewpoint.iter().map(|v| v.to_string()).collect()));
+        text += &format!("POINTS {:<1$}\n", self.points, count_width);
+        text += &format!("DATA {}\n", self.data);
+        text
+    }
+}
+
+/// Sequential reader over the points of a PCD file
+pub struct PcdReader<R: BufRead> {
+    reader: R,
+    header: PcdHeader,
+    line: String,
+    // Decompressed field-major payload of a binary_compressed file
+    columns: Option<Vec<u8>>,
+    next_point: usize,
+}
+
+impl PcdReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: BufRead> PcdReader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let header = PcdHeader::read(&mut reader)?;
+        Ok(Self {
+            reader,
+            header,
+            line: String::new(),
+            columns: None,
+            next_point: 0,
+        })
+    }
+
+    pub fn header(&self) -> &PcdHeader {
+        &self.header
+    }
+
+    /// Read the next point as flattened values in field order.
+    ///
+    /// Returns `false` once all `POINTS` have been read.
+    pub fn read_record(&mut self, record: &mut PlyRecord) -> Result<bool> {
+        if self.next_point >= self.header.points {
+            return Ok(false);
+        }
+        record.0.clear();
+
+        match self.header.data {
+            PcdData::Ascii => {
+                self.line.clear();
+                loop {
+                    if self.reader.read_line(&mut self.line)? == 0 {
+                        return Err(truncated());
+                    }
+                    if !self.line.trim().is_empty() {
+                        break;
+                    }
+                    self.line.clear();
+                }
+                let mut tokens = self.line.split_whitespace();
+                for field in &self.header.fields {
+                    for _ in 0..field.count {
+                        let token = tokens.next().ok_or_else(truncated)?;
+                        record.0.push(parse_ascii_value(field, token)?);
+                    }
+                }
+            }
+            PcdData::Binary => {
+                for field in &self.header.fields {
+                    for _ in 0..field.count {
+                        record.0.push(read_binary_value(&mut self.reader, field).map_err(|_| truncated())?);
+                    }
+                }
+            }
+            PcdData::BinaryCompressed => {
+                if self.columns.is_none() {
+                    self.columns = Some(read_compressed_payload(&mut self.reader, &self.header)?);
+                }
+                let columns = self.columns.as_deref().unwrap_or_default();
+                let points = self.header.points;
+                let mut column_start = 0;
+                for field in &self.header.fields {
+                    let stride = field.size * field.count;
+                    let mut value = &columns[column_start + self.next_point * stride..];
+                    for _ in 0..field.count {
+                        record.0.push(read_binary_value(&mut value, field).map_err(|_| truncated())?);
+                    }
+                    column_start += stride * points;
+                }
+            }
+        }
+
+        self.next_point += 1;
+        Ok(true)
+    }
+}
+
+/// Streaming PCD writer; `binary_compressed` output is buffered until `finish`
+pub struct PcdWriter<W: Write + Seek> {
+    writer: W,
+    header: PcdHeader,
+    // One buffer per field for binary_compressed output
+    columns: Vec<Vec<u8>>,
+    written: usize,
+    // Offsets of the padded WIDTH and POINTS values when the count is patched on finish
+    count_offsets: Vec<u64>,
+}
+
+impl PcdWriter<BufWriter<File>> {
+    pub fn create(path: &Path, header: PcdHeader) -> Result<Self> {
+        let file = File::create(path)?;
+        Self::new(BufWriter::new(file), header)
+    }
+
+    /// Create a writer for an unknown number of points; `finish` fills in the count
+    pub fn create_unsized(path: &Path, mut header: PcdHeader) -> Result<Self> {
+        header.width = 0;
+        header.points = 0;
+        let file = File::create(path)?;
+        Self::with_count_width(BufWriter::new(file), header, COUNT_WIDTH)
+    }
+}
+
+impl<W: Write + Seek> PcdWriter<W> {
+    pub fn new(writer: W, header: PcdHeader) -> Result<Self> {
+        Self::with_count_width(writer, header, 0)
+    }
+
+    fn with_count_width(mut writer: W, header: PcdHeader, count_width: usize) -> Result<Self> {
+        for field in &header.fields {
+            field.validate()?;
+        }
+        let text = header.render(count_width);
+        writer.write_all(text.as_bytes())?;
+        let count_offsets = if count_width > 0 {
+            ["\nWIDTH ", "\nPOINTS "].iter()
+                .filter_map(|key| text.find(key).map(|at| (at + key.len()) as u64))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let columns = match header.data {
+            PcdData::BinaryCompressed => header.fields.iter()
+                .map(|field| Vec::with_capacity(field.size * field.count * header.points))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            writer,
+            header,
+            columns,
+            written: 0,
+            count_offsets,
+        })
+    }
+
+    pub fn header(&self) -> &PcdHeader {
+        &self.header
+    }
+
+    /// Write one point given as flattened values in field order
+    pub fn write_record(&mut self, record: &PlyRecord) -> Result<()> {
+        if record.0.len() != self.header.values_per_point() {
+            return Err(HylaeanError::Unknown {
+                message: format!(
+                    "PCD record has {} values, fields declare {}",
+                    record.0.len(), self.header.values_per_point()
+                ),
+            });
+        }
+
+        let mut values = record.0.iter();
+        match self.header.data {
+            PcdData::Ascii => {
+                let mut first = true;
+                for field in &self.header.fields {
+                    for value in values.by_ref().take(field.count) {
+                        if !first {
+                            write!(self.writer, " ")?;
+                        }
+                        first = false;
+                        write_ascii_value(&mut self.writer, field, value)?;
+                    }
+                }
+                writeln!(self.writer)?;
+            }
+            PcdData::Binary => {
+                for field in &self.header.fields {
+                    for value in values.by_ref().take(field.count) {
+                        write_binary_value(&mut self.writer, field, value)?;
+                    }
+                }
+            }
+            PcdData::BinaryCompressed => {
+                for (field, column) in self.header.fields.iter().zip(&mut self.columns) {
+                    for value in values.by_ref().take(field.count) {
+                        write_binary_value(column, field, value)?;
+                    }
+                }
+            }
+        }
+
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Flush the output, checking that exactly `POINTS` records were written
+    pub fn finish(mut self) -> Result<usize> {
+        let unsized_count = !self.count_offsets.is_empty();
+        if unsized_count {
+            self.header.width = self.written;
+            self.header.points = self.written;
+        }
+        if self.written != self.header.points {
+            return Err(HylaeanError::Unknown {
+                message: format!(
+                    "PCD header declares {} points but {} were written",
+                    self.header.points, self.written
+                ),
+            });
+        }
+
+        if self.header.data == PcdData::BinaryCompressed {
+            let payload = self.columns.concat();
+            let compressed = lzf_compress(&payload);
+            self.writer.write_u32::<LittleEndian>(compressed.len() as u32)?;
+            self.writer.write_u32::<LittleEndian>(payload.len() as u32)?;
+            self.writer.write_all(&compressed)?;
+        }
+        if unsized_count {
+            let count = format!("{:<1$}", self.written, COUNT_WIDTH);
+            for offset in &self.count_offsets {
+                self.writer.seek(SeekFrom::Start(*offset))?;
+                self.writer.write_all(count.as_bytes())?;
+            }
+        }
+        self.writer.flush()?;
+        Ok(self.written)
+    }
+}
+
+pub fn read_header(path: &Path) -> Result<PcdHeader> {
+    Ok(PcdReader::open(path)?.header)
+}
+
+/// Pack 8-bit channels the way PCL stores `rgb`/`rgba`: 0xAARRGGBB reinterpreted as a float
+pub fn pack_rgba([r, g, b, a]: [u8; 4]) -> f32 {
+    f32::from_bits(u32::from_be_bytes([a, r, g, b]))
+}
+
+pub fn unpack_rgba(packed: &Property) -> [u8; 4] {
+    let bits = match packed {
+        Property::Float(v) => v.to_bits(),
+        Property::UInt(v) => *v,
+        Property::Int(v) => *v as u32,
+        other => ply::scalar_as_f64(other).unwrap_or(0.0) as u32,
+    };
+    let [a, r, g, b] = bits.to_be_bytes();
+    [r, g, b, a]
+}
+
+fn parse_ascii_value(field: &PcdField, token: &str) -> Result<Property> {
+    let malformed = || invalid(&format!("malformed value '{}' for field '{}'", token, field.name));
+    if is_packed_color(field) {
+        // Current PCL writes packed colors as integers, older releases as floats
+        if let Ok(bits) = token.parse::<u32>() {
+            return Ok(Property::Float(f32::from_bits(bits)));
+        }
+    }
+    Ok(match field.scalar_type() {
+        ScalarType::Char => Property::Char(token.parse().map_err(|_| malformed())?),
+        ScalarType::UChar => Property::UChar(token.parse().map_err(|_| malformed())?),
+        ScalarType::Short => Property::Short(token.parse().map_err(|_| malformed())?),
+        ScalarType::UShort => Property::UShort(token.parse().map_err(|_| malformed())?),
+        ScalarType::Int => Property::Int(token.parse().map_err(|_| malformed())?),
+        ScalarType::UInt => Property::UInt(token.parse().map_err(|_| malformed())?),
+        ScalarType::Float => Property::Float(token.parse().map_err(|_| malformed())?),
+        ScalarType::Double => Property::Double(token.parse().map_err(|_| malformed())?),
+    })
+}
+
+fn read_binary_value<R: Read>(reader: &mut R, field: &PcdField) -> std::io::Result<Property> {
+    Ok(match (field.kind, field.size) {
+        ('I', 1) => Property::Char(reader.read_i8()?),
+        ('U', 1) => Property::UChar(reader.read_u8()?),
+        ('I', 2) => Property::Short(reader.read_i16::<LittleEndian>()?),
+        ('U', 2) => Property::UShort(reader.read_u16::<LittleEndian>()?),
+        ('I', 4) => Property::Int(reader.read_i32::<LittleEndian>()?),
+        ('U', 4) => Property::UInt(reader.read_u32::<LittleEndian>()?),
+        ('F', 4) => Property::Float(reader.read_f32::<LittleEndian>()?),
+        ('I', 8) => Property::Double(reader.read_i64::<LittleEndian>()? as f64),
+        ('U', 8) => Property::Double(reader.read_u64::<LittleEndian>()? as f64),
+        _ => Property::Double(reader.read_f64::<LittleEndian>()?),
+    })
+}
+
+/// Write a value as the field's binary type. Matching variants are written bit for bit,
+/// which keeps packed `rgb` floats intact even when their bits spell a NaN.
+fn write_binary_value<W: Write>(writer: &mut W, field: &PcdField, value: &Property) -> Result<()> {
+    match (field.kind, field.size, value) {
+        ('F', 4, Property::Float(v)) => writer.write_u32::<LittleEndian>(v.to_bits())?,
+        ('F', 8, Property::Double(v)) => writer.write_u64::<LittleEndian>(v.to_bits())?,
+        _ => {
+            let v = ply::scalar_as_f64(value).ok_or_else(|| HylaeanError::Unknown {
+                message: format!("PCD field '{}' cannot hold {:?}", field.name, value),
+            })?;
+            match (field.kind, field.size) {
+                ('I', 1) => writer.write_i8(v as i8)?,
+                ('U', 1) => writer.write_u8(v as u8)?,
+                ('I', 2) => writer.write_i16::<LittleEndian>(v as i16)?,
+                ('U', 2) => writer.write_u16::<LittleEndian>(v as u16)?,
+                ('I', 4) => writer.write_i32::<LittleEndian>(v as i32)?,
+                ('U', 4) => writer.write_u32::<LittleEndian>(v as u32)?,
+                ('I', 8) => writer.write_i64::<LittleEndian>(v as i64)?,
+                ('U', 8) => writer.write_u64::<LittleEndian>(v as u64)?,
+                ('F', 4) => writer.write_f32::<LittleEndian>(v as f32)?,
+                _ => writer.write_f64::<LittleEndian>(v)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_ascii_value<W: Write>(writer: &mut W, field: &PcdField, value: &Property) -> Result<()> {
+    // PCL writes packed colors as their unsigned integer bits in ASCII files
+    if is_packed_color(field) {
+        let [r, g, b, a] = unpack_rgba(value);
+        write!(writer, "{}", u32::from_be_bytes([a, r, g, b]))?;
+        return Ok(());
+    }
+    match value {
+        Property::Float(v) if field.kind == 'F' => write!(writer, "{}", v)?,
+        Property::Double(v) if field.kind == 'F' => write!(writer, "{}", v)?,
+        _ => {
+            let v = ply::scalar_as_f64(value).unwrap_or(0.0);
+            match field.kind {
+                'F' => write!(writer, "{}", v)?,
+                _ => write!(writer, "{}", v.round() as i64)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_packed_color(field: &PcdField) -> bool {
+    field.kind == 'F' && field.size == 4 && matches!(field.name.as_str(), "rgb" | "rgba")
+}
+
+fn read_compressed_payload<R: Read>(reader: &mut R, header: &PcdHeader) -> Result<Vec<u8>> {
+    let compressed_size = reader.read_u32::<LittleEndian>().map_err(|_| truncated())? as usize;
+    let uncompressed_size = reader.read_u32::<LittleEndian>().map_err(|_| truncated())? as usize;
+    let expected = header.point_size() * header.points;
+    if uncompressed_size != expected {
+        return Err(invalid(&format!(
+            "compressed payload expands to {} bytes, fields need {}",
+            uncompressed_size, expected
+        )));
+    }
+
+    let mut compressed = vec![0u8; compressed_size];
+    reader.read_exact(&mut compressed).map_err(|_| truncated())?;
+    lzf_decompress(&compressed, uncompressed_size)
+}
+
+/// LZF compression as implemented by liblzf, which PCL uses for `binary_compressed`
+pub fn lzf_compress(input: &[u8]) -> Vec<u8> {
+    const HASH_BITS: u32 = 14;
+    const MAX_OFFSET: usize = 1 << 13;
+    const MAX_MATCH: usize = 264;
+
+    let mut output = Vec::with_capacity(input.len() / 2 + 16);
+    let mut table = vec![usize::MAX; 1 << HASH_BITS];
+    let mut literal_start = 0;
+    let mut ip = 0;
+
+    while ip + 2 < input.len() {
+        let key = u32::from_be_bytes([0, input[ip], input[ip + 1], input[ip + 2]]);
+        let slot = (key.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize;
+        let candidate = table[slot];
+        table[slot] = ip;
+
+        if candidate != usize::MAX
+            && ip - candidate <= MAX_OFFSET
+            && input[candidate..candidate + 3] == input[ip..ip + 3]
+        {
+            flush_literals(&mut output, &input[literal_start..ip]);
+
+            let max_len = (input.len() - ip).min(MAX_MATCH);
+            let mut len = 3;
+            while len < max_len && input[candidate + len] == input[ip + len] {
+                len += 1;
+            }
+
+            let offset = ip - candidate - 1;
+            let encoded_len = len - 2;
+            if encoded_len < 7 {
+                output.push((encoded_len << 5) as u8 | (offset >> 8) as u8);
+            } else {
+                output.push((7 << 5) | (offset >> 8) as u8);
+                output.push((encoded_len - 7) as u8);
+            }
+            output.push(offset as u8);
+
+            ip += len;
+            literal_start = ip;
+        } else {
+            ip += 1;
+        }
+    }
+
+    flush_literals(&mut output, &input[literal_start..]);
+    output
+}
+
+pub fn lzf_decompress(input: &[u8], output_len: usize) -> Result<Vec<u8>> {
+    let corrupt = || invalid("corrupt LZF stream");
+    let mut output = Vec::with_capacity(output_len);
+    let mut ip = 0;
+
+    while ip < input.len() {
+        let control = input[ip] as usize;
+        ip += 1;
+
+        if control < 32 {
+            let len = control + 1;
+            let literal = input.get(ip..ip + len).ok_or_else(corrupt)?;
+            output.extend_from_slice(literal);
+            ip += len;
+        } else {
+            let mut len = control >> 5;
+            if len == 7 {
+                len += *input.get(ip).ok_or_else(corrupt)? as usize;
+                ip += 1;
+            }
+            let low = *input.get(ip).ok_or_else(corrupt)? as usize;
+            ip += 1;
+
+            let distance = ((control & 0x1f) << 8) + low + 1;
+            let start = output.len().checked_sub(distance).ok_or_else(corrupt)?;
+            // Back-references may overlap the bytes they produce
+            for i in 0..len + 2 {
+                output.push(output[start + i]);
+            }
+        }
+
+        if output.len() > output_len {
+            return Err(corrupt());
+        }
+    }
+
+    if output.len() != output_len {
+        return Err(corrupt());
+    }
+    Ok(output)
+}
+
+fn flush_literals(output: &mut Vec<u8>, literals: &[u8]) {
+    for chunk in literals.chunks(32) {
+        output.push((chunk.len() - 1) as u8);
+        output.extend_from_slice(chunk);
+    }
+}
+
+fn truncated() -> HylaeanError {
+    invalid("point data ended before the declared point count")
+}
+
+fn invalid(message: &str) -> HylaeanError {
+    HylaeanError::UnsupportedFormat {
+        format: format!("PCD: {}", message),
+    }
+}
@@ -0,0 +1,509 @@
+//! Header-driven PLY reading and writing
+//!
+//! Headers and ASCII element lines are parsed with `ply-rs`; binary payloads are
+//! decoded record by record so large splat files never need a second in-memory copy.
+
+use crate::errors::{Result, HylaeanError};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+use ply_rs::parser::Parser;
+use ply_rs::ply::PropertyAccess;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+pub use ply_rs::ply::{Addable, ElementDef, Encoding, Header, Property, PropertyDef, PropertyType, ScalarType};
+
+/// A single element record with values stored in header property order
+#[derive(Debug, Clone, Default)]
+pub struct PlyRecord(pub Vec<Property>);
+
+impl PropertyAccess for PlyRecord {
+    fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    fn set_property(&mut self, _property_name: String, property: Property) {
+        // ply-rs calls this in header order, so the position is all we need
+        self.0.push(property);
+    }
+}
+
+/// Sequential reader over the elements of a PLY file
+pub struct PlyReader<R: BufRead> {
+    reader: R,
+    header: Header,
+    parser: Parser<PlyRecord>,
+    line: String,
+}
+
+impl PlyReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: BufRead> PlyReader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let parser = Parser::<PlyRecord>::new();
+        let header = parser.read_header(&mut reader)?;
+
+        Ok(Self {
+            reader,
+            header,
+            parser,
+            line: String::new(),
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Read the next record of `element_def` into `record`, reusing its allocation.
+    ///
+    /// Elements must be consumed in header order, `count` records each.
+    pub fn read_record(&mut self, element_def: &ElementDef, record: &mut PlyRecord) -> Result<()> {
+        record.0.clear();
+        match self.header.encoding {
+            Encoding::Ascii => {
+                self.line.clear();
+                if self.reader.read_line(&mut self.line)? == 0 {
+                    return Err(truncated(&element_def.name));
+                }
+                *record = self.parser.read_ascii_element(&self.line, element_def)?;
+            }
+            Encoding::BinaryLittleEndian => {
+                read_binary_record::<_, LittleEndian>(&mut self.reader, element_def, record)?;
+            }
+            Encoding::BinaryBigEndian => {
+                read_binary_record::<_, BigEndian>(&mut self.reader, element_def, record)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip over all records of `element_def`
+    pub fn skip_element(&mut self, element_def: &ElementDef) -> Result<()> {
+        let mut record = PlyRecord::default();
+        for _ in 0..element_def.count {
+            self.read_record(element_def, &mut record)?;
+        }
+        Ok(())
+    }
+}
+
+/// Visit the `x`, `y`, `z` values of every vertex, skipping all other elements.
+///
+/// Returns the number of vertices visited.
+pub fn for_each_vertex_position<F>(path: &Path, mut visit: F) -> Result<usize>
+where
+    F: FnMut(&[Property; 3]) -> Result<()>,
+{
+    let mut reader = PlyReader::open(path)?;
+    let header = reader.header().clone();
+    let mut visited = 0;
+
+    for (name, element_def) in &header.elements {
+        if name != "vertex" {
+            reader.skip_element(element_def)?;
+            continue;
+        }
+
+        let columns = position_columns(element_def)?;
+        let mut record = PlyRecord::default();
+        for _ in 0..element_def.count {
+            reader.read_record(element_def, &mut record)?;
+            let position = columns.map(|column| record.0[column].clone());
+            visit(&position)?;
+            visited += 1;
+        }
+    }
+
+    Ok(visited)
+}
+
+/// Indices of the `x`, `y`, `z` scalar properties within an element
+pub fn position_columns(element_def: &ElementDef) -> Result<[usize; 3]> {
+    let mut columns = [0; 3];
+    for (column, axis) in columns.iter_mut().zip(["x", "y", "z"]) {
+        *column = element_def.properties.iter()
+            .position(|(name, property_def)| {
+                name == axis && matches!(property_def.data_type, PropertyType::Scalar(_))
+            })
+            .ok_or_else(|| HylaeanError::UnsupportedFormat {
+                format: format!("PLY element '{}' has no scalar '{}' property", element_def.name, axis),
+            })?;
+    }
+    Ok(columns)
+}
+
+/// Writer for plain `x y z` float vertex PLY files
+pub struct PlyPointWriter<W: Write> {
+    writer: W,
+    encoding: Encoding,
+    element: ElementDef,
+    record: PlyRecord,
+    written: usize,
+}
+
+impl PlyPointWriter<BufWriter<File>> {
+    pub fn create(path: &Path, encoding: Encoding, count: usize) -> Result<Self> {
+        let file = File::create(path)?;
+        Self::new(BufWriter::new(file), encoding, count)
+    }
+}
+
+impl<W: Write> PlyPointWriter<W> {
+    pub fn new(mut writer: W, encoding: Encoding, count: usize) -> Result<Self> {
+        let properties: Vec<(String, ScalarType)> = ["x", "y", "z"].iter()
+            .map(|axis| (axis.to_string(), ScalarType::Float))
+            .collect();
+        let element = element_def("vertex", count, &properties);
+
+        let mut header = Header::new();
+        header.encoding = encoding;
+        header.elements.add(element.clone());
+        write_header(&mut writer, &header)?;
+
+        Ok(Self {
+            writer,
+            encoding,
+            element,
+            record: PlyRecord::default(),
+            written: 0,
+        })
+    }
+
+    pub fn write_point(&mut self, position: [f32; 3]) -> Result<()> {
+        self.record.0.clear();
+        self.record.0.extend(position.iter().map(|v| Property::Float(*v)));
+        write_record(&mut self.writer, self.encoding, &self.element, &self.record)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Flush the output, checking that exactly the declared number of points was written
+    pub fn finish(mut self) -> Result<usize> {
+        self.writer.flush()?;
+        if self.written != self.element.count {
+            return Err(HylaeanError::Unknown {
+                message: format!(
+                    "PLY header declares {} vertices but {} were written",
+                    self.element.count, self.written
+                ),
+            });
+        }
+        Ok(self.written)
+    }
+}
+
+/// A fully loaded PLY file: the header plus the records of every element, in file order
+#[derive(Debug, Clone)]
+pub struct PlyFile {
+    pub header: Header,
+    pub elements: Vec<(String, Vec<PlyRecord>)>,
+}
+
+impl PlyFile {
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut reader = PlyReader::open(path)?;
+        let header = reader.header().clone();
+        let mut elements = Vec::new();
+
+        for (name, element_def) in &header.elements {
+            // The count comes from the header, so only trust it as far as the data backs it
+            let mut records = Vec::with_capacity(element_def.count.min(1 << 20));
+            for _ in 0..element_def.count {
+                let mut record = PlyRecord::default();
+                reader.read_record(element_def, &mut record)?;
+                records.push(record);
+            }
+            elements.push((name.clone(), records));
+        }
+
+        Ok(Self { header, elements })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        write_header(&mut writer, &self.header)?;
+
+        for (name, records) in &self.elements {
+            let element_def = self.header.elements.get(name).ok_or_else(|| HylaeanError::Unknown {
+                message: format!("PLY element '{}' has no header definition", name),
+            })?;
+            for record in records {
+                write_record(&mut writer, self.header.encoding, element_def, record)?;
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn element(&self, name: &str) -> Option<(&ElementDef, &[PlyRecord])> {
+        let element_def = self.header.elements.get(name)?;
+        let records = self.elements.iter()
+            .find(|(element_name, _)| element_name == name)
+            .map(|(_, records)| records.as_slice())?;
+        Some((element_def, records))
+    }
+}
+
+pub fn read_header(path: &Path) -> Result<Header> {
+    Ok(PlyReader::open(path)?.header)
+}
+
+pub fn write_header<W: Write>(writer: &mut W, header: &Header) -> Result<()> {
+    ply_rs::writer::Writer::<PlyRecord>::new().write_header(writer, header)?;
+    Ok(())
+}
+
+/// Encode one record of `element_def` according to `encoding`
+pub fn write_record<W: Write>(writer: &mut W, encoding: Encoding, element_def: &ElementDef, record: &PlyRecord) -> Result<()> {
+    if record.0.len() != element_def.properties.len() {
+        return Err(HylaeanError::Unknown {
+            message: format!(
+                "PLY element '{}' expects {} properties, record has {}",
+                element_def.name, element_def.properties.len(), record.0.len()
+            ),
+        });
+    }
+
+    match encoding {
+        Encoding::Ascii => {
+            for (i, value) in record.0.iter().enumerate() {
+                if i > 0 {
+                    write!(writer, " ")?;
+                }
+                write_ascii_property(writer, value)?;
+            }
+            writeln!(writer)?;
+        }
+        Encoding::BinaryLittleEndian => {
+            for ((_, property_def), value) in element_def.properties.iter().zip(&record.0) {
+                write_binary_property::<_, LittleEndian>(writer, &property_def.data_type, value)?;
+            }
+        }
+        Encoding::BinaryBigEndian => {
+            for ((_, property_def), value) in element_def.properties.iter().zip(&record.0) {
+                write_binary_property::<_, BigEndian>(writer, &property_def.data_type, value)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_binary_record<R: Read, B: ByteOrder>(reader: &mut R, element_def: &ElementDef, record: &mut PlyRecord) -> Result<()> {
+    for (_, property_def) in &element_def.properties {
+        let value = match &property_def.data_type {
+            PropertyType::Scalar(scalar_type) => read_binary_scalar::<_, B>(reader, scalar_type)
+                .map_err(|_| truncated(&element_def.name))?,
+            PropertyType::List(index_type, scalar_type) => {
+                let len = read_binary_scalar::<_, B>(reader, index_type)
+                    .ok()
+                    .and_then(|index| scalar_as_f64(&index))
+                    .ok_or_else(|| truncated(&element_def.name))? as usize;
+                read_binary_list::<_, B>(reader, scalar_type, len)
+                    .map_err(|_| truncated(&element_def.name))?
+            }
+        };
+        record.0.push(value);
+    }
+    Ok(())
+}
+
+fn read_binary_scalar<R: Read, B: ByteOrder>(reader: &mut R, scalar_type: &ScalarType) -> std::io::Result<Property> {
+    Ok(match scalar_type {
+        ScalarType::Char => Property::Char(reader.read_i8()?),
+        ScalarType::UChar => Property::UChar(reader.read_u8()?),
+        ScalarType::Short => Property::Short(reader.read_i16::<B>()?),
+        ScalarType::UShort => Property::UShort(reader.read_u16::<B>()?),
+        ScalarType::Int => Property::Int(reader.read_i32::<B>()?),
+        ScalarType::UInt => Property::UInt(reader.read_u32::<B>()?),
+        ScalarType::Float => Property::Float(reader.read_f32::<B>()?),
+        ScalarType::Double => Property::Double(reader.read_f64::<B>()?),
+    })
+}
+
+fn read_binary_list<R: Read, B: ByteOrder>(reader: &mut R, scalar_type: &ScalarType, len: usize) -> std::io::Result<Property> {
+    macro_rules! list {
+        ($variant:ident, $read:expr) => {{
+            let mut values = Vec::with_capacity(len.min(1 << 16));
+            for _ in 0..len {
+                values.push($read?);
+            }
+            Property::$variant(values)
+        }};
+    }
+
+    Ok(match scalar_type {
+        ScalarType::Char => list!(ListChar, reader.read_i8()),
+        ScalarType::UChar => list!(ListUChar, reader.read_u8()),
+        ScalarType::Short => list!(ListShort, reader.read_i16::<B>()),
+        ScalarType::UShort => list!(ListUShort, reader.read_u16::<B>()),
+        ScalarType::Int => list!(ListInt, reader.read_i32::<B>()),
+        ScalarType::UInt => list!(ListUInt, reader.read_u32::<B>()),
+        ScalarType::Float => list!(ListFloat, reader.read_f32::<B>()),
+        ScalarType::Double => list!(ListDouble, reader.read_f64::<B>()),
+    })
+}
+
+/// Write a property as PLY ASCII text: scalars as-is, lists length-prefixed
+pub fn write_ascii_property<W: Write>(writer: &mut W, value: &Property) -> Result<()> {
+    fn list<W: Write, T: std::fmt::Display>(writer: &mut W, values: &[T]) -> std::io::Result<()> {
+        write!(writer, "{}", values.len())?;
+        for value in values {
+            write!(writer, " {}", value)?;
+        }
+        Ok(())
+    }
+
+    match value {
+        Property::Char(v) => write!(writer, "{}", v)?,
+        Property::UChar(v) => write!(writer, "{}", v)?,
+        Property::Short(v) => write!(writer, "{}", v)?,
+        Property::UShort(v) => write!(writer, "{}", v)?,
+        Property::Int(v) => write!(writer, "{}", v)?,
+        Property::UInt(v) => write!(writer, "{}", v)?,
+        Property::Float(v) => write!(writer, "{}", v)?,
+        Property::Double(v) => write!(writer, "{}", v)?,
+        Property::ListChar(v) => list(writer, v)?,
+        Property::ListUChar(v) => list(writer, v)?,
+        Property::ListShort(v) => list(writer, v)?,
+        Property::ListUShort(v) => list(writer, v)?,
+        Property::ListInt(v) => list(writer, v)?,
+        Property::ListUInt(v) => list(writer, v)?,
+        Property::ListFloat(v) => list(writer, v)?,
+        Property::ListDouble(v) => list(writer, v)?,
+    }
+    Ok(())
+}
+
+fn write_binary_property<W: Write, B: ByteOrder>(writer: &mut W, data_type: &PropertyType, value: &Property) -> Result<()> {
+    match data_type {
+        PropertyType::Scalar(_) => write_binary_scalar::<_, B>(writer, value),
+        PropertyType::List(index_type, _) => {
+            let len = list_len(value);
+            write_binary_scalar::<_, B>(writer, &scalar_from_f64(index_type, len as f64))?;
+
+            macro_rules! items {
+                ($values:expr, $write:ident) => {
+                    for v in $values {
+                        writer.$write::<B>(*v)?;
+                    }
+                };
+            }
+
+            match value {
+                Property::ListChar(values) => for v in values { writer.write_i8(*v)?; },
+                Property::ListUChar(values) => writer.write_all(values)?,
+                Property::ListShort(values) => items!(values, write_i16),
+                Property::ListUShort(values) => items!(values, write_u16),
+                Property::ListInt(values) => items!(values, write_i32),
+                Property::ListUInt(values) => items!(values, write_u32),
+                Property::ListFloat(values) => items!(values, write_f32),
+                Property::ListDouble(values) => items!(values, write_f64),
+                _ => return Err(type_mismatch(value)),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn write_binary_scalar<W: Write, B: ByteOrder>(writer: &mut W, value: &Property) -> Result<()> {
+    match value {
+        Property::Char(v) => writer.write_i8(*v)?,
+        Property::UChar(v) => writer.write_u8(*v)?,
+        Property::Short(v) => writer.write_i16::<B>(*v)?,
+        Property::UShort(v) => writer.write_u16::<B>(*v)?,
+        Property::Int(v) => writer.write_i32::<B>(*v)?,
+        Property::UInt(v) => writer.write_u32::<B>(*v)?,
+        Property::Float(v) => writer.write_f32::<B>(*v)?,
+        Property::Double(v) => writer.write_f64::<B>(*v)?,
+        _ => return Err(type_mismatch(value)),
+    }
+    Ok(())
+}
+
+fn list_len(value: &Property) -> usize {
+    match value {
+        Property::ListChar(v) => v.len(),
+        Property::ListUChar(v) => v.len(),
+        Property::ListShort(v) => v.len(),
+        Property::ListUShort(v) => v.len(),
+        Property::ListInt(v) => v.len(),
+        Property::ListUInt(v) => v.len(),
+        Property::ListFloat(v) => v.len(),
+        Property::ListDouble(v) => v.len(),
+        _ => 0,
+    }
+}
+
+fn truncated(element: &str) -> HylaeanError {
+    HylaeanError::Unknown {
+        message: format!("PLY payload ended early while reading element '{}'", element),
+    }
+}
+
+fn type_mismatch(value: &Property) -> HylaeanError {
+    HylaeanError::Unknown {
+        message: format!("PLY value {:?} does not match its property declaration", value),
+    }
+}
+
+/// Widen any scalar property to `f64`; lists yield `None`
+pub fn scalar_as_f64(value: &Property) -> Option<f64> {
+    match value {
+        Property::Char(v) => Some(*v as f64),
+        Property::UChar(v) => Some(*v as f64),
+        Property::Short(v) => Some(*v as f64),
+        Property::UShort(v) => Some(*v as f64),
+        Property::Int(v) => Some(*v as f64),
+        Property::UInt(v) => Some(*v as f64),
+        Property::Float(v) => Some(*v as f64),
+        Property::Double(v) => Some(*v),
+        _ => None,
+    }
+}
+
+/// Narrow an `f64` back into a scalar property of the given type
+pub fn scalar_from_f64(scalar_type: &ScalarType, value: f64) -> Property {
+    match scalar_type {
+        ScalarType::Char => Property::Char(value as i8),
+        ScalarType::UChar => Property::UChar(value as u8),
+        ScalarType::Short => Property::Short(value as i16),
+        ScalarType::UShort => Property::UShort(value as u16),
+        ScalarType::Int => Property::Int(value as i32),
+        ScalarType::UInt => Property::UInt(value as u32),
+        ScalarType::Float => Property::Float(value as f32),
+        ScalarType::Double => Property::Double(value),
+    }
+}
+
+/// Build an element definition from `(name, type)` pairs
+pub fn element_def(name: &str, count: usize, properties: &[(String, ScalarType)]) -> ElementDef {
+    let mut element = ElementDef::new(name.to_string());
+    element.count = count;
+    for (property_name, scalar_type) in properties {
+        element.properties.add(PropertyDef::new(
+            property_name.clone(),
+            PropertyType::Scalar(scalar_type.clone()),
+        ));
+    }
+    element
+}
+
+pub fn parse_encoding(encoding_str: &str) -> Result<Encoding> {
+    match encoding_str.to_lowercase().as_str() {
+        "ascii" => Ok(Encoding::Ascii),
+        "binary" | "binary_little_endian" => Ok(Encoding::BinaryLittleEndian),
+        "binary_big_endian" => Ok(Encoding::BinaryBigEndian),
+        _ => Err(HylaeanError::UnsupportedFormat {
+            format: encoding_str.to_string(),
+        }),
+    }
+}
@@ -0,0 +1,216 @@
+//! Point cloud format handling and conversion
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::{DataFormat, PointCloudFormat, FormatConverter};
+use crate::formats::splat_ops;
+use crate::formats::las::{LasHeader, LasReader, LasWriter};
+use crate::formats::pcd::{PcdData, PcdReader, PcdWriter};
+use crate::formats::ply::{self, Encoding, PlyReader, PlyRecord, ScalarType};
+use std::path::Path;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use log::{info, debug};
+
+pub mod stream;
+pub mod tiles;
+
+use stream::{LasSource, PointSource, SinkOptions};
+
+/// Whether a format stores Gaussian splats
+fn is_gaussian(format: &PointCloudFormat) -> bool {
+    matches!(
+        format,
+        PointCloudFormat::PLY | PointCloudFormat::Splat | PointCloudFormat::KSplat | PointCloudFormat::Spz
+    )
+}
+
+#[derive(Default)]
+pub struct PointCloudConverter {
+    ply_encoding: Option<Encoding>,
+    pcd_data: Option<PcdData>,
+}
+
+impl FormatConverter for PointCloudConverter {
+    fn can_convert(&self, from: &DataFormat, to: &DataFormat) -> bool {
+        matches!(
+            (from, to),
+            (DataFormat::PointCloud(_), DataFormat::PointCloud(_))
+        )
+    }
+    
+    fn convert(&self, input_path: &Path, output_path: &Path, from: &DataFormat, to: &DataFormat) -> Result<()> {
+        match (from, to) {
+            (DataFormat::PointCloud(from_fmt), DataFormat::PointCloud(to_fmt)) => {
+                self.convert_point_cloud(input_path, output_path, from_fmt, to_fmt)
+            }
+            _ => Err(HylaeanError::ConversionFailed {
+                source_format: format!("{:?}", from),
+                target_format: format!("{:?}", to),
+            }),
+        }
+    }
+}
+
+impl PointCloudConverter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Force the encoding of PLY output. By default PLY to PLY keeps the source encoding.
+    pub fn with_ply_encoding(mut self, encoding: Encoding) -> Self {
+        self.ply_encoding = Some(encoding);
+        self
+    }
+    
+    /// Force the DATA mode of PCD output. By default binary sources produce binary PCD.
+    pub fn with_pcd_data(mut self, data: PcdData) -> Self {
+        self.pcd_data = Some(data);
+        self
+    }
+    
+    fn convert_point_cloud(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        from_format: &PointCloudFormat,
+        to_format: &PointCloudFormat,
+    ) -> Result<()> {
+        info!("Converting point cloud: {:?} -> {:?}", from_format, to_format);
+        
+        match (from_format, to_format) {
+            (PointCloudFormat::PLY, PointCloudFormat::PLY) => {
+                self.ply_to_ply(input_path, output_path)
+            }
+            (from, to) if is_gaussian(from) && is_gaussian(to) => {
+                self.convert_gaussians(input_path, output_path)
+            }
+            (PointCloudFormat::LAZ, PointCloudFormat::LAZ) => {
+                self.las_to_las(input_path, output_path)
+            }
+            (PointCloudFormat::PCD, PointCloudFormat::PCD) => {
+                self.pcd_to_pcd(input_path, output_path)
+            }
+            (from, to) if stream::is_streamable(from) && stream::is_streamable(to) => {
+                self.stream_points(input_path, output_path, from, to)
+            }
+            _ => Err(HylaeanError::ConversionFailed {
+                source_format: format!("{:?}", from_format),
+                target_format: format!("{:?}", to_format),
+            }),
+        }
+    }
+    
+    /// Re-encode every element of a PLY file, one record at a time
+    fn ply_to_ply(&self, input_path: &Path, output_path: &Path) -> Result<()> {
+        debug!("Re-encoding PLY: {} -> {}", input_path.display(), output_path.display());
+        
+        let mut reader = PlyReader::open(input_path)?;
+        let mut header = reader.header().clone();
+        let encoding = self.ply_encoding.unwrap_or(header.encoding);
+        header.encoding = encoding;
+        
+        let mut output_file = BufWriter::new(File::create(output_path)?);
+        ply::write_header(&mut output_file, &header)?;
+        
+        let mut record = PlyRecord::default();
+        let mut record_count = 0;
+        for (_, element_def) in &header.elements {
+            for _ in 0..element_def.count {
+                reader.read_record(element_def, &mut record)?;
+                ply::write_record(&mut output_file, encoding, element_def, &record)?;
+            }
+            record_count += element_def.count;
+        }
+        output_file.flush()?;
+        
+        info!("Re-encoded {} PLY records as {}", record_count, encoding);
+        Ok(())
+    }
+    
+    /// Convert between any two Gaussian formats (PLY, `.splat`, `.ksplat` and SPZ) in memory
+    fn convert_gaussians(&self, input_path: &Path, output_path: &Path) -> Result<()> {
+        debug!("Converting Gaussian scene: {} -> {}", input_path.display(), output_path.display());
+        
+        let (cloud, encoding) = splat_ops::load_cloud(input_path)?;
+        splat_ops::save_cloud(&cloud, output_path, self.ply_encoding.unwrap_or(encoding))?;
+        
+        info!("Converted {} Gaussians to {}", cloud.len(), output_path.display());
+        Ok(())
+    }
+    
+    /// Copy points between LAS and LAZ, keeping the point format, scale, offset and VLRs
+    fn las_to_las(&self, input_path: &Path, output_path: &Path) -> Result<()> {
+        debug!("Converting LAS to LAS: {} -> {}", input_path.display(), output_path.display());
+        
+        let mut reader = LasReader::open(input_path)?;
+        let source = reader.header();
+        let mut header = LasHeader::new(source.point_format, source.scale, source.offset)?;
+        header.file_source_id = source.file_source_id;
+        header.global_encoding = source.global_encoding;
+        header.system_identifier = source.system_identifier.clone();
+        header.vlrs = source.vlrs.clone();
+        
+        let mut writer = LasWriter::create(output_path, header)?;
+        while let Some(point) = reader.read_point()? {
+            writer.write_point(&point)?;
+        }
+        let point_count = writer.finish()?;
+        
+        info!("Copied {} LAS points to {}", point_count, output_path.display());
+        Ok(())
+    }
+    
+    /// Re-encode a PCD file, keeping its fields and viewpoint
+    fn pcd_to_pcd(&self, input_path: &Path, output_path: &Path) -> Result<()> {
+        debug!("Re-encoding PCD: {} -> {}", input_path.display(), output_path.display());
+        
+        let mut reader = PcdReader::open(input_path)?;
+        let mut header = reader.header().clone();
+        header.data = self.pcd_data.unwrap_or(header.data);
+        let data = header.data;
+        
+        let mut writer = PcdWriter::create(output_path, header)?;
+        let mut record = PlyRecord::default();
+        while reader.read_record(&mut record)? {
+            writer.write_record(&record)?;
+        }
+        let point_count = writer.finish()?;
+        
+        info!("Re-encoded {} PCD points as {}", point_count, data);
+        Ok(())
+    }
+    
+    /// Convert between PLY, XYZ, PCD and LAS/LAZ through the chunked pipeline in [`stream`]
+    fn stream_points(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        from_format: &PointCloudFormat,
+        to_format: &PointCloudFormat,
+    ) -> Result<()> {
+        debug!(
+            "Streaming {:?} to {:?}: {} -> {}",
+            from_format, to_format, input_path.display(), output_path.display()
+        );
+        
+        let mut source: Box<dyn PointSource> = match (from_format, to_format) {
+            // 3DGS trainers read PLY positions as float
+            (PointCloudFormat::LAZ, PointCloudFormat::PLY) => {
+                Box::new(LasSource::open(input_path, ScalarType::Float)?)
+            }
+            _ => stream::open_source(input_path, from_format)?,
+        };
+        let defaults = SinkOptions::matching(source.as_ref());
+        let options = SinkOptions {
+            ply_encoding: self.ply_encoding.unwrap_or(defaults.ply_encoding),
+            pcd_data: self.pcd_data.unwrap_or(defaults.pcd_data),
+        };
+        let fields = source.schema().iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(" ");
+        
+        let sink = stream::create_sink(output_path, to_format, source.as_ref(), options)?;
+        let point_count = stream::pump(source.as_mut(), sink, stream::DEFAULT_CHUNK_SIZE)?;
+        
+        info!("Converted {} points from {:?} to {:?} (fields: {})", point_count, from_format, to_format, fields);
+        Ok(())
+    }
+}
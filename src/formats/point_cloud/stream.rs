@@ -0,0 +1,920 @@
+//! Chunked, constant-memory point cloud pipeline
+//!
+//! A [`PointSource`] decodes points into a reusable [`PointChunk`] and a [`PointSink`]
+//! encodes them, so converting between PLY, XYZ, PCD and LAS/LAZ holds one chunk in
+//! memory regardless of file size. Points are carried as PLY-style vertex records
+//! (`x y z`, `nx ny nz`, `red green blue` and any extra scalars). Two exceptions buffer
+//! more by the nature of the format: LZF-compressed PCD is one field-major block, and
+//! LAZ decodes a chunk of LASzip records at a time.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::PointCloudFormat;
+use crate::formats::las::{LasHeader, LasPoint, LasReader, LasWriter};
+use crate::formats::pcd::{self, PcdData, PcdField, PcdHeader, PcdReader, PcdWriter};
+use crate::formats::ply::{self, Addable, ElementDef, Encoding, PlyReader, PlyRecord, Property, PropertyType, ScalarType};
+use log::{info, warn};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Points per chunk; small enough to stay cache- and memory-friendly, large enough to amortize dispatch
+pub const DEFAULT_CHUNK_SIZE: usize = 65_536;
+
+/// Per-point attributes as `(name, type)` pairs, in record order
+pub type Schema = Vec<(String, ScalarType)>;
+
+/// Width reserved for PLY vertex counts that are patched after writing
+const COUNT_WIDTH: usize = 20;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A reusable batch of point records; allocations survive between chunks
+#[derive(Debug, Default)]
+pub struct PointChunk {
+    records: Vec<PlyRecord>,
+    len: usize,
+    capacity: usize,
+}
+
+impl PointChunk {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            records: Vec::with_capacity(capacity),
+            len: 0,
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len >= self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn records(&self) -> &[PlyRecord] {
+        &self.records[..self.len]
+    }
+
+    pub fn records_mut(&mut self) -> &mut [PlyRecord] {
+        &mut self.records[..self.len]
+    }
+
+    /// Claim the next record slot, cleared but keeping its allocation
+    pub fn push_slot(&mut self) -> &mut PlyRecord {
+        if self.len == self.records.len() {
+            self.records.push(PlyRecord::default());
+        }
+        self.len += 1;
+        let record = &mut self.records[self.len - 1];
+        record.0.clear();
+        record
+    }
+}
+
+pub trait PointSource {
+    fn schema(&self) -> &Schema;
+
+    /// Number of points, when the format declares it up front
+    fn point_count(&self) -> Option<usize>;
+
+    /// Whether the source is a binary encoding, used to pick a matching output default
+    fn is_binary(&self) -> bool;
+
+    /// Provenance notes carried into outputs that have room for them
+    fn comments(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Clear `chunk` and fill it up to capacity; returns the number of points read, 0 at the end
+    fn read_chunk(&mut self, chunk: &mut PointChunk) -> Result<usize>;
+}
+
+pub trait PointSink {
+    fn write_chunk(&mut self, chunk: &PointChunk) -> Result<()>;
+
+    /// Flush and finalize headers; returns the number of points written
+    fn finish(self: Box<Self>) -> Result<usize>;
+}
+
+/// Output settings for [`create_sink`]
+#[derive(Debug, Clone, Copy)]
+pub struct SinkOptions {
+    pub ply_encoding: Encoding,
+    pub pcd_data: PcdData,
+}
+
+impl SinkOptions {
+    /// Binary output for binary sources, ASCII for ASCII ones
+    pub fn matching(source: &dyn PointSource) -> Self {
+        let binary = source.is_binary();
+        Self {
+            ply_encoding: if binary { Encoding::BinaryLittleEndian } else { Encoding::Ascii },
+            pcd_data: if binary { PcdData::Binary } else { PcdData::Ascii },
+        }
+    }
+}
+
+/// Whether a format has a streaming source and sink
+pub fn is_streamable(format: &PointCloudFormat) -> bool {
+    matches!(
+        format,
+        PointCloudFormat::PLY | PointCloudFormat::XYZ | PointCloudFormat::PCD | PointCloudFormat::LAZ
+    )
+}
+
+pub fn open_source(path: &Path, format: &PointCloudFormat) -> Result<Box<dyn PointSource>> {
+    match format {
+        PointCloudFormat::PLY => Ok(Box::new(PlySource::open(path)?)),
+        PointCloudFormat::XYZ => Ok(Box::new(XyzSource::open(path)?)),
+        PointCloudFormat::PCD => Ok(Box::new(PcdSource::open(path)?)),
+        PointCloudFormat::LAZ => Ok(Box::new(LasSource::open(path, ScalarType::Double)?)),
+        other => Err(not_streamable(other)),
+    }
+}
+
+/// Open a sink for the points of `source`, sized up front when the source knows its count
+pub fn create_sink(
+    path: &Path,
+    format: &PointCloudFormat,
+    source: &dyn PointSource,
+    options: SinkOptions,
+) -> Result<Box<dyn PointSink>> {
+    let schema = source.schema();
+    let point_count = source.point_count();
+    match format {
+        PointCloudFormat::PLY => Ok(Box::new(PlySink::create(path, schema, point_count, &source.comments(), options.ply_encoding)?)),
+        PointCloudFormat::XYZ => Ok(Box::new(XyzSink::create(path, schema)?)),
+        PointCloudFormat::PCD => Ok(Box::new(PcdSink::create(path, schema, point_count, options.pcd_data)?)),
+        PointCloudFormat::LAZ => Ok(Box::new(LasSink::create(path, schema)?)),
+        other => Err(not_streamable(other)),
+    }
+}
+
+/// Move every point from `source` to `sink`, logging progress; returns the number of points
+pub fn pump(source: &mut dyn PointSource, sink: Box<dyn PointSink>, chunk_size: usize) -> Result<usize> {
+    pump_map(source, sink, chunk_size, |_| Ok(()))
+}
+
+/// Like [`pump`], but lets `map` edit each chunk in place before it is written
+pub fn pump_map<F>(source: &mut dyn PointSource, mut sink: Box<dyn PointSink>, chunk_size: usize, mut map: F) -> Result<usize>
+where
+    F: FnMut(&mut PointChunk) -> Result<()>,
+{
+    let total = source.point_count();
+    let mut chunk = PointChunk::with_capacity(chunk_size);
+    let started = Instant::now();
+    let mut last_report = started;
+    let mut moved = 0usize;
+
+    while source.read_chunk(&mut chunk)? > 0 {
+        map(&mut chunk)?;
+        sink.write_chunk(&chunk)?;
+        moved += chunk.len();
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            last_report = Instant::now();
+            let rate = moved as f64 / started.elapsed().as_secs_f64();
+            match total {
+                Some(total) if total > 0 => info!(
+                    "{} / {} points ({:.1}%), {:.2} M points/s",
+                    moved, total, 100.0 * moved as f64 / total as f64, rate / 1.0e6
+                ),
+                _ => info!("{} points, {:.2} M points/s", moved, rate / 1.0e6),
+            }
+        }
+    }
+
+    let written = sink.finish()?;
+    let seconds = started.elapsed().as_secs_f64();
+    info!(
+        "Streamed {} points in {:.1}s ({:.2} M points/s)",
+        written, seconds, written as f64 / seconds.max(1e-9) / 1.0e6
+    );
+    Ok(written)
+}
+
+/// Vertex element of a PLY file; other elements are skipped
+struct PlySource {
+    reader: PlyReader<BufReader<File>>,
+    element: ElementDef,
+    schema: Schema,
+    // Scalar columns to keep when the vertex element also has list properties
+    columns: Option<Vec<usize>>,
+    record: PlyRecord,
+    remaining: usize,
+}
+
+impl PlySource {
+    fn open(path: &Path) -> Result<Self> {
+        let mut reader = PlyReader::open(path)?;
+        let header = reader.header().clone();
+
+        for (name, element_def) in &header.elements {
+            if name != "vertex" {
+                reader.skip_element(element_def)?;
+                continue;
+            }
+
+            let mut schema = Vec::new();
+            let mut columns = Vec::new();
+            for (index, (property_name, property_def)) in element_def.properties.iter().enumerate() {
+                match &property_def.data_type {
+                    PropertyType::Scalar(scalar_type) => {
+                        schema.push((property_name.clone(), scalar_type.clone()));
+                        columns.push(index);
+                    }
+                    PropertyType::List(..) => warn!("Skipping PLY list property '{}'", property_name),
+                }
+            }
+            let all_scalar = columns.len() == element_def.properties.len();
+
+            return Ok(Self {
+                reader,
+                element: element_def.clone(),
+                schema,
+                columns: (!all_scalar).then_some(columns),
+                record: PlyRecord::default(),
+                remaining: element_def.count,
+            });
+        }
+
+        Err(HylaeanError::UnsupportedFormat {
+            format: "PLY without a vertex element".to_string(),
+        })
+    }
+}
+
+impl PointSource for PlySource {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.element.count)
+    }
+
+    fn is_binary(&self) -> bool {
+        self.reader.header().encoding != Encoding::Ascii
+    }
+
+    fn read_chunk(&mut self, chunk: &mut PointChunk) -> Result<usize> {
+        chunk.clear();
+        while !chunk.is_full() && self.remaining > 0 {
+            match &self.columns {
+                None => self.reader.read_record(&self.element, chunk.push_slot())?,
+                Some(columns) => {
+                    self.reader.read_record(&self.element, &mut self.record)?;
+                    let slot = chunk.push_slot();
+                    slot.0.extend(columns.iter().map(|column| self.record.0[*column].clone()));
+                }
+            }
+            self.remaining -= 1;
+        }
+        Ok(chunk.len())
+    }
+}
+
+/// Whitespace-separated `x y z` lines, with 8-bit `r g b` when the first point has them;
+/// further columns are ignored
+struct XyzSource {
+    reader: BufReader<File>,
+    path: PathBuf,
+    schema: Schema,
+    colors: bool,
+    line: String,
+}
+
+impl XyzSource {
+    fn open(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 && is_xyz_skippable(&line) {
+            line.clear();
+        }
+        let colors = line.split_whitespace().skip(3).take(3)
+            .filter(|v| v.parse::<u8>().is_ok())
+            .count() == 3;
+        reader.seek(SeekFrom::Start(0))?;
+
+        let mut schema = position_schema(ScalarType::Float);
+        if colors {
+            schema.extend(["red", "green", "blue"].map(|c| (c.to_string(), ScalarType::UChar)));
+        }
+        Ok(Self {
+            reader,
+            path: path.to_path_buf(),
+            schema,
+            colors,
+            line,
+        })
+    }
+}
+
+impl PointSource for XyzSource {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        None
+    }
+
+    fn is_binary(&self) -> bool {
+        false
+    }
+
+    fn read_chunk(&mut self, chunk: &mut PointChunk) -> Result<usize> {
+        chunk.clear();
+        while !chunk.is_full() {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                break;
+            }
+            if is_xyz_skippable(&self.line) {
+                continue;
+            }
+
+            let mut values = self.line.split_whitespace();
+            let position = parse_position(values.by_ref(), &self.path)?;
+            let slot = chunk.push_slot();
+            slot.0.extend(position.iter().map(|v| Property::Float(*v)));
+            if self.colors {
+                for _ in 0..3 {
+                    let channel = values.next().and_then(|v| v.parse::<u8>().ok())
+                        .ok_or_else(|| malformed_point(&self.path))?;
+                    slot.0.push(Property::UChar(channel));
+                }
+            }
+        }
+        Ok(chunk.len())
+    }
+}
+
+/// Where a schema value comes from in a flattened PCD record
+enum PcdInput {
+    Value(usize),
+    Channel(usize, usize),
+}
+
+/// PCD fields as PLY-style attributes: `rgb`/`rgba` unpacked, PCL normal names mapped
+struct PcdSource {
+    reader: PcdReader<BufReader<File>>,
+    schema: Schema,
+    inputs: Vec<PcdInput>,
+    record: PlyRecord,
+}
+
+impl PcdSource {
+    fn open(path: &Path) -> Result<Self> {
+        let reader = PcdReader::open(path)?;
+        let mut schema = Vec::new();
+        let mut inputs = Vec::new();
+        let mut index = 0;
+
+        for field in &reader.header().fields {
+            if field.is_padding() {
+                // Padding carries no data
+            } else if matches!(field.name.as_str(), "rgb" | "rgba") && field.size == 4 && field.count == 1 {
+                let channels: &[&str] = if field.name == "rgba" {
+                    &["red", "green", "blue", "alpha"]
+                } else {
+                    &["red", "green", "blue"]
+                };
+                for (channel, name) in channels.iter().enumerate() {
+                    schema.push((name.to_string(), ScalarType::UChar));
+                    inputs.push(PcdInput::Channel(index, channel));
+                }
+            } else if field.count == 1 {
+                schema.push((ply_property_name(&field.name).to_string(), field.scalar_type()));
+                inputs.push(PcdInput::Value(index));
+            } else {
+                for i in 0..field.count {
+                    schema.push((format!("{}_{}", field.name, i), field.scalar_type()));
+                    inputs.push(PcdInput::Value(index + i));
+                }
+            }
+            index += field.count;
+        }
+
+        Ok(Self {
+            reader,
+            schema,
+            inputs,
+            record: PlyRecord::default(),
+        })
+    }
+}
+
+impl PointSource for PcdSource {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.reader.header().points)
+    }
+
+    fn is_binary(&self) -> bool {
+        self.reader.header().data != PcdData::Ascii
+    }
+
+    fn read_chunk(&mut self, chunk: &mut PointChunk) -> Result<usize> {
+        chunk.clear();
+        while !chunk.is_full() && self.reader.read_record(&mut self.record)? {
+            let record = &self.record;
+            chunk.push_slot().0.extend(self.inputs.iter().map(|input| match input {
+                PcdInput::Value(index) => record.0[*index].clone(),
+                PcdInput::Channel(index, channel) => Property::UChar(pcd::unpack_rgba(&record.0[*index])[*channel]),
+            }));
+        }
+        Ok(chunk.len())
+    }
+}
+
+/// LAS points in the 3DGS initialization layout (`x y z nx ny nz red green blue`)
+/// plus intensity and classification.
+///
+/// Files without RGB get gray levels from intensity. Opening makes one extra pass over
+/// the points to find the color range, since many files store 8-bit RGB in 16-bit fields.
+pub struct LasSource {
+    reader: LasReader,
+    schema: Schema,
+    color_scale: f32,
+    single_precision: bool,
+}
+
+impl LasSource {
+    /// `position_type` is `Float` for training inputs, `Double` to keep full georeferenced precision
+    pub fn open(path: &Path, position_type: ScalarType) -> Result<Self> {
+        let mut reader = LasReader::open(path)?;
+        let has_color = reader.header().has_color();
+
+        let mut max_value = 0u16;
+        while let Some(point) = reader.read_point()? {
+            let value = if has_color { *point.color.iter().max().unwrap_or(&0) } else { point.intensity };
+            max_value = max_value.max(value);
+        }
+        reader.rewind()?;
+        let color_scale = if has_color && max_value <= 255 { 1.0 } else { 255.0 / max_value.max(1) as f32 };
+
+        let extent = reader.header().min.iter().chain(&reader.header().max)
+            .fold(0.0f64, |max, v| max.max(v.abs()));
+        let single_precision = position_type == ScalarType::Float;
+        if single_precision && extent > 1.0e5 {
+            warn!(
+                "LAS coordinates reach {:.0}; single-precision positions lose detail this far from the origin",
+                extent
+            );
+        }
+
+        let mut schema = position_schema(position_type);
+        schema.extend([
+            ("nx", ScalarType::Float), ("ny", ScalarType::Float), ("nz", ScalarType::Float),
+            ("red", ScalarType::UChar), ("green", ScalarType::UChar), ("blue", ScalarType::UChar),
+            ("intensity", ScalarType::UShort), ("classification", ScalarType::UChar),
+        ].iter().map(|(name, scalar_type)| (name.to_string(), scalar_type.clone())));
+
+        Ok(Self {
+            reader,
+            schema,
+            color_scale,
+            single_precision,
+        })
+    }
+}
+
+impl PointSource for LasSource {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.reader.header().point_count as usize)
+    }
+
+    fn is_binary(&self) -> bool {
+        true
+    }
+
+    fn comments(&self) -> Vec<String> {
+        let header = self.reader.header();
+        vec![format!(
+            "converted from LAS {}.{} point format {}",
+            header.version.0, header.version.1, header.point_format
+        )]
+    }
+
+    fn read_chunk(&mut self, chunk: &mut PointChunk) -> Result<usize> {
+        chunk.clear();
+        let has_color = self.reader.header().has_color();
+        while !chunk.is_full() {
+            let Some(point) = self.reader.read_point()? else {
+                break;
+            };
+            let channels = if has_color { point.color } else { [point.intensity; 3] };
+            let rgb = channels.map(|c| (c as f32 * self.color_scale).round().min(255.0) as u8);
+
+            let slot = chunk.push_slot();
+            if self.single_precision {
+                slot.0.extend(point.position.iter().map(|v| Property::Float(*v as f32)));
+            } else {
+                slot.0.extend(point.position.iter().map(|v| Property::Double(*v)));
+            }
+            slot.0.extend([Property::Float(0.0), Property::Float(0.0), Property::Float(0.0)]);
+            slot.0.extend(rgb.iter().map(|c| Property::UChar(*c)));
+            slot.0.push(Property::UShort(point.intensity));
+            slot.0.push(Property::UChar(point.classification));
+        }
+        Ok(chunk.len())
+    }
+}
+
+/// A single `vertex` element; an unknown count is written padded and patched on finish
+struct PlySink {
+    writer: BufWriter<File>,
+    element: ElementDef,
+    encoding: Encoding,
+    count_offset: Option<u64>,
+    written: usize,
+}
+
+impl PlySink {
+    fn create(
+        path: &Path,
+        schema: &Schema,
+        point_count: Option<usize>,
+        comments: &[String],
+        encoding: Encoding,
+    ) -> Result<Self> {
+        let element = ply::element_def("vertex", point_count.unwrap_or(0), schema);
+        let mut header = ply::Header::new();
+        header.encoding = encoding;
+        header.comments.extend_from_slice(comments);
+        header.elements.add(element.clone());
+
+        let mut text = Vec::new();
+        ply::write_header(&mut text, &header)?;
+        let mut count_offset = None;
+        if point_count.is_none() {
+            let key = b"element vertex ";
+            let at = text.windows(key.len()).position(|window| window == key)
+                .map(|at| at + key.len())
+                .ok_or_else(|| HylaeanError::Unknown {
+                    message: "PLY header has no vertex element line".to_string(),
+                })?;
+            // Replace the "0" placeholder with a fixed-width field
+            text.splice(at..at + 1, format!("{:<1$}", 0, COUNT_WIDTH).bytes());
+            count_offset = Some(at as u64);
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&text)?;
+        Ok(Self {
+            writer,
+            element,
+            encoding,
+            count_offset,
+            written: 0,
+        })
+    }
+}
+
+impl PointSink for PlySink {
+    fn write_chunk(&mut self, chunk: &PointChunk) -> Result<()> {
+        for record in chunk.records() {
+            ply::write_record(&mut self.writer, self.encoding, &self.element, record)?;
+        }
+        self.written += chunk.len();
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<usize> {
+        match self.count_offset {
+            Some(offset) => {
+                self.writer.seek(SeekFrom::Start(offset))?;
+                self.writer.write_all(format!("{:<1$}", self.written, COUNT_WIDTH).as_bytes())?;
+            }
+            None if self.written != self.element.count => {
+                return Err(HylaeanError::Unknown {
+                    message: format!(
+                        "PLY header declares {} vertices but {} were written",
+                        self.element.count, self.written
+                    ),
+                });
+            }
+            None => {}
+        }
+        self.writer.flush()?;
+        Ok(self.written)
+    }
+}
+
+/// `x y z` lines, followed by 8-bit `r g b` when the schema has colors
+struct XyzSink {
+    writer: BufWriter<File>,
+    columns: [usize; 3],
+    colors: Option<[usize; 3]>,
+    written: usize,
+}
+
+impl XyzSink {
+    fn create(path: &Path, schema: &Schema) -> Result<Self> {
+        let column = |name: &str| schema.iter().position(|(key, _)| key == name);
+        let colors = match (column("red"), column("green"), column("blue")) {
+            (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+            _ => None,
+        };
+
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            columns: position_columns(schema)?,
+            colors,
+            written: 0,
+        })
+    }
+}
+
+impl PointSink for XyzSink {
+    fn write_chunk(&mut self, chunk: &PointChunk) -> Result<()> {
+        for record in chunk.records() {
+            for (i, column) in self.columns.iter().enumerate() {
+                if i > 0 {
+                    write!(self.writer, " ")?;
+                }
+                ply::write_ascii_property(&mut self.writer, &record.0[*column])?;
+            }
+            if let Some(colors) = self.colors {
+                for column in colors {
+                    write!(self.writer, " {}", color_to_u8(&record.0[column]))?;
+                }
+            }
+            writeln!(self.writer)?;
+        }
+        self.written += chunk.len();
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<usize> {
+        self.writer.flush()?;
+        Ok(self.written)
+    }
+}
+
+/// Where a PCD field's value comes from in a schema record
+enum PcdOutput {
+    Value(usize),
+    Rgba([usize; 3], Option<usize>),
+}
+
+/// Every attribute as a PCD field, with `red green blue [alpha]` packed into `rgb`/`rgba`
+struct PcdSink {
+    writer: PcdWriter<BufWriter<File>>,
+    outputs: Vec<PcdOutput>,
+    point: PlyRecord,
+}
+
+impl PcdSink {
+    fn create(path: &Path, schema: &Schema, point_count: Option<usize>, data: PcdData) -> Result<Self> {
+        let column = |name: &str| schema.iter().position(|(key, _)| key == name);
+        let color = match (column("red"), column("green"), column("blue")) {
+            (Some(r), Some(g), Some(b)) => Some(([r, g, b], column("alpha"))),
+            _ => None,
+        };
+
+        let mut fields = Vec::new();
+        let mut outputs = Vec::new();
+        for (index, (name, scalar_type)) in schema.iter().enumerate() {
+            match color {
+                Some((channels, alpha)) if channels.contains(&index) || alpha == Some(index) => {
+                    if index == channels[0] {
+                        let name = if alpha.is_some() { "rgba" } else { "rgb" };
+                        fields.push(PcdField::new(name, &ScalarType::Float));
+                        outputs.push(PcdOutput::Rgba(channels, alpha));
+                    }
+                }
+                _ => {
+                    fields.push(PcdField::new(pcd_field_name(name), scalar_type));
+                    outputs.push(PcdOutput::Value(index));
+                }
+            }
+        }
+
+        let header = PcdHeader::new(fields, point_count.unwrap_or(0), data);
+        let writer = match point_count {
+            Some(_) => PcdWriter::create(path, header)?,
+            None => PcdWriter::create_unsized(path, header)?,
+        };
+        Ok(Self {
+            writer,
+            outputs,
+            point: PlyRecord::default(),
+        })
+    }
+}
+
+impl PointSink for PcdSink {
+    fn write_chunk(&mut self, chunk: &PointChunk) -> Result<()> {
+        for record in chunk.records() {
+            self.point.0.clear();
+            self.point.0.extend(self.outputs.iter().map(|output| match output {
+                PcdOutput::Value(index) => record.0[*index].clone(),
+                PcdOutput::Rgba(channels, alpha) => {
+                    let channel = |index: usize| color_to_u8(&record.0[index]);
+                    let a = alpha.map(channel).unwrap_or(255);
+                    Property::Float(pcd::pack_rgba([channel(channels[0]), channel(channels[1]), channel(channels[2]), a]))
+                }
+            }));
+            self.writer.write_record(&self.point)?;
+        }
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<usize> {
+        self.writer.finish()
+    }
+}
+
+/// LAS point format 0, or 2 when the schema has colors. The offset comes from the first
+/// chunk, so the writer is opened lazily.
+struct LasSink {
+    path: PathBuf,
+    writer: Option<LasWriter>,
+    positions: [usize; 3],
+    colors: Option<[usize; 3]>,
+    intensity: Option<usize>,
+    classification: Option<usize>,
+}
+
+impl LasSink {
+    fn create(path: &Path, schema: &Schema) -> Result<Self> {
+        let column = |name: &str| schema.iter().position(|(key, _)| key == name);
+        let colors = match (column("red"), column("green"), column("blue")) {
+            (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+            _ => None,
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: None,
+            positions: position_columns(schema)?,
+            colors,
+            intensity: column("intensity"),
+            classification: column("classification"),
+        })
+    }
+
+    fn open_writer(&self, min: [f64; 3]) -> Result<LasWriter> {
+        let point_format = if self.colors.is_some() { 2 } else { 0 };
+        LasWriter::create(&self.path, LasHeader::new(point_format, LAS_SCALE, las_offset(min))?)
+    }
+}
+
+impl PointSink for LasSink {
+    fn write_chunk(&mut self, chunk: &PointChunk) -> Result<()> {
+        let value = |record: &PlyRecord, column: usize| ply::scalar_as_f64(&record.0[column]).unwrap_or(0.0);
+
+        if self.writer.is_none() {
+            let mut min = [f64::INFINITY; 3];
+            for record in chunk.records() {
+                for (axis, column) in self.positions.iter().enumerate() {
+                    min[axis] = min[axis].min(value(record, *column));
+                }
+            }
+            self.writer = Some(self.open_writer(min)?);
+        }
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+
+        for record in chunk.records() {
+            let mut point = LasPoint {
+                position: self.positions.map(|column| value(record, column)),
+                return_number: 1,
+                number_of_returns: 1,
+                ..LasPoint::default()
+            };
+            if let Some(colors) = self.colors {
+                point.color = colors.map(|column| color_to_u16(&record.0[column]));
+            }
+            if let Some(column) = self.intensity {
+                point.intensity = value(record, column).clamp(0.0, u16::MAX as f64) as u16;
+            }
+            if let Some(column) = self.classification {
+                point.classification = value(record, column).clamp(0.0, 31.0) as u8;
+            }
+            writer.write_point(&point)?;
+        }
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<usize> {
+        let writer = match self.writer {
+            Some(writer) => writer,
+            None => self.open_writer([0.0; 3])?,
+        };
+        Ok(writer.finish()? as usize)
+    }
+}
+
+fn position_schema(scalar_type: ScalarType) -> Schema {
+    ["x", "y", "z"].iter().map(|axis| (axis.to_string(), scalar_type.clone())).collect()
+}
+
+fn position_columns(schema: &Schema) -> Result<[usize; 3]> {
+    let mut columns = [0; 3];
+    for (column, axis) in columns.iter_mut().zip(["x", "y", "z"]) {
+        *column = schema.iter().position(|(name, _)| name == axis)
+            .ok_or_else(|| HylaeanError::UnsupportedFormat {
+                format: format!("point cloud without an '{}' attribute", axis),
+            })?;
+    }
+    Ok(columns)
+}
+
+/// PLY and PCL spell normals differently
+const NORMAL_NAMES: [(&str, &str); 3] = [("nx", "normal_x"), ("ny", "normal_y"), ("nz", "normal_z")];
+
+fn pcd_field_name(ply_name: &str) -> &str {
+    NORMAL_NAMES.iter()
+        .find(|(ply, _)| *ply == ply_name)
+        .map_or(ply_name, |(_, pcd)| pcd)
+}
+
+fn ply_property_name(pcd_name: &str) -> &str {
+    NORMAL_NAMES.iter()
+        .find(|(_, pcd)| *pcd == pcd_name)
+        .map_or(pcd_name, |(ply, _)| ply)
+}
+
+/// Narrow a color channel to 8 bits; float channels are taken as 0..1
+fn color_to_u8(value: &Property) -> u8 {
+    match value {
+        Property::UChar(v) => *v,
+        Property::UShort(v) => (*v >> 8) as u8,
+        other => {
+            let v = ply::scalar_as_f64(other).unwrap_or(0.0);
+            (v.clamp(0.0, 1.0) * 255.0).round() as u8
+        }
+    }
+}
+
+/// Widen a color channel to the 16-bit range LAS expects
+fn color_to_u16(value: &Property) -> u16 {
+    match value {
+        Property::UChar(v) => *v as u16 * 257,
+        Property::UShort(v) => *v,
+        other => {
+            let v = ply::scalar_as_f64(other).unwrap_or(0.0);
+            (v.clamp(0.0, 1.0) * u16::MAX as f64).round() as u16
+        }
+    }
+}
+
+/// Millimeter precision, the common choice for aerial lidar
+const LAS_SCALE: [f64; 3] = [0.001; 3];
+
+/// Offset that keeps quantized coordinates small: the minimum, rounded down to a kilometer
+fn las_offset(min: [f64; 3]) -> [f64; 3] {
+    min.map(|v| if v.is_finite() { (v / 1000.0).floor() * 1000.0 } else { 0.0 })
+}
+
+fn parse_position<'a, I>(mut values: I, input_path: &Path) -> Result<[f32; 3]>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut position = [0.0; 3];
+    for coordinate in position.iter_mut() {
+        *coordinate = values.next()
+            .and_then(|v| v.parse::<f32>().ok())
+            .ok_or_else(|| malformed_point(input_path))?;
+    }
+    Ok(position)
+}
+
+fn malformed_point(input_path: &Path) -> HylaeanError {
+    HylaeanError::Unknown {
+        message: format!("Malformed point in {}", input_path.display()),
+    }
+}
+
+/// Blank lines and `#` comments carry no point
+fn is_xyz_skippable(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+fn not_streamable(format: &PointCloudFormat) -> HylaeanError {
+    HylaeanError::UnsupportedFormat {
+        format: format!("{:?} has no streaming point reader/writer", format),
+    }
+}
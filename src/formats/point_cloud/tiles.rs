@@ -0,0 +1,260 @@
+//! Spatial tiling of splat scenes for streaming viewers
+//!
+//! A scene is split into a fixed grid or an adaptive octree of tiles, each written as a
+//! separate splat file with optional coarser LOD levels. `tileset.json` records every
+//! tile's region, tight bounds, Gaussian count and level files, so a viewer can decide
+//! what to fetch from the index alone.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::gaussian::GaussianCloud;
+use crate::formats::ply::Encoding;
+use crate::formats::splat_ops::{self, lod::{self, LodOptions}};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// File name of the tileset index inside the output directory
+pub const TILESET_FILE: &str = "tileset.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TilingScheme {
+    /// Cubic cells of edge `tile_size`, aligned to the origin
+    Grid { tile_size: f64 },
+    /// Cells split into octants until they hold at most `max_gaussians` or reach `max_depth`
+    Octree { max_gaussians: usize, max_depth: u32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tileset {
+    pub source: String,
+    pub scheme: TilingScheme,
+    pub sh_degree: u32,
+    pub gaussians: usize,
+    pub bounds: TileBounds,
+    pub tiles: Vec<TileEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TileBounds {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileEntry {
+    pub id: String,
+    /// Octree depth (0 is the root); absent for grid tiles
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub depth: Option<u32>,
+    /// The cell of space the tile covers
+    pub region: TileBounds,
+    /// Tight bounds of the tile's Gaussian centers
+    pub bounds: TileBounds,
+    pub gaussians: usize,
+    /// Level 0 is the full tile, further levels are progressively coarser
+    pub levels: Vec<TileLevel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileLevel {
+    pub level: usize,
+    /// File name, relative to the tileset index
+    pub file: String,
+    pub gaussians: usize,
+}
+
+/// Where the Gaussians of one tile sit in the source cloud
+struct Cell {
+    id: String,
+    depth: Option<u32>,
+    region: TileBounds,
+    members: Vec<usize>,
+}
+
+/// Split `cloud` into tiles written to `output_dir` as `tile_<id>[_lod<N>].<extension>`,
+/// followed by the tileset index
+pub fn tile_cloud(
+    cloud: &GaussianCloud,
+    source: &Path,
+    output_dir: &Path,
+    scheme: TilingScheme,
+    lod: &LodOptions,
+    extension: &str,
+    encoding: Encoding,
+) -> Result<Tileset> {
+    lod.validate()?;
+    let finite: Vec<usize> = (0..cloud.len())
+        .filter(|index| cloud.gaussians[*index].position.iter().all(|v| v.is_finite()))
+        .collect();
+    if finite.len() < cloud.len() {
+        warn!("Dropping {} Gaussians with non-finite positions", cloud.len() - finite.len());
+    }
+    let bounds = bounds_of(finite.iter().map(|index| cloud.gaussians[*index].position)).ok_or_else(|| HylaeanError::ConfigError {
+        message: "cannot tile a scene without finite Gaussian positions".to_string(),
+    })?;
+
+    let cells = match scheme {
+        TilingScheme::Grid { tile_size } => {
+            if !(tile_size > 0.0 && tile_size.is_finite()) {
+                return Err(HylaeanError::ConfigError {
+                    message: format!("tile size must be positive, got {}", tile_size),
+                });
+            }
+            grid_cells(cloud, finite, tile_size)
+        }
+        TilingScheme::Octree { max_gaussians, max_depth } => {
+            if max_gaussians == 0 {
+                return Err(HylaeanError::ConfigError {
+                    message: "octree tiles need room for at least one Gaussian".to_string(),
+                });
+            }
+            let mut octree = Octree { cloud, max_gaussians, max_depth, cells: Vec::new() };
+            octree.split(finite, "r".to_string(), 0, cube_around(&bounds));
+            octree.cells
+        }
+    };
+
+    std::fs::create_dir_all(output_dir)?;
+    let mut tileset = Tileset {
+        source: source.display().to_string(),
+        scheme,
+        sh_degree: cloud.sh_degree,
+        gaussians: 0,
+        bounds,
+        tiles: Vec::with_capacity(cells.len()),
+    };
+    for cell in cells {
+        let mut tile = GaussianCloud {
+            gaussians: cell.members.iter().map(|index| cloud.gaussians[*index].clone()).collect(),
+            sh_degree: cloud.sh_degree,
+            has_normals: cloud.has_normals,
+            extra_properties: cloud.extra_properties.clone(),
+            comments: cloud.comments.clone(),
+        };
+        tile.comments.push(format!("hylaeansplat tile {} of {}", cell.id, source.display()));
+
+        let mut levels = Vec::with_capacity(lod.levels);
+        for level in 0..lod.levels {
+            let mut reduced = tile.clone();
+            let file = if level == 0 {
+                format!("tile_{}.{}", cell.id, extension)
+            } else {
+                lod::reduce(&mut reduced, lod.target(tile.len(), level), lod.method)?;
+                reduced.comments.push(format!("hylaeansplat LOD level {}", level));
+                format!("tile_{}_lod{}.{}", cell.id, level, extension)
+            };
+            splat_ops::save_cloud(&reduced, &output_dir.join(&file), encoding)?;
+            levels.push(TileLevel { level, file, gaussians: reduced.len() });
+        }
+
+        tileset.gaussians += tile.len();
+        tileset.tiles.push(TileEntry {
+            id: cell.id,
+            depth: cell.depth,
+            region: cell.region,
+            bounds: bounds_of(tile.gaussians.iter().map(|gaussian| gaussian.position)).unwrap_or(cell.region),
+            gaussians: tile.len(),
+            levels,
+        });
+    }
+
+    let index_path = output_dir.join(TILESET_FILE);
+    serde_json::to_writer_pretty(BufWriter::new(File::create(&index_path)?), &tileset)?;
+    info!(
+        "Split {} Gaussians into {} tiles: {}",
+        tileset.gaussians, tileset.tiles.len(), index_path.display()
+    );
+    Ok(tileset)
+}
+
+/// Read a tileset index written by [`tile_cloud`]
+pub fn read_tileset(path: &Path) -> Result<Tileset> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+fn grid_cells(cloud: &GaussianCloud, members: Vec<usize>, tile_size: f64) -> Vec<Cell> {
+    let mut cells: BTreeMap<[i64; 3], Vec<usize>> = BTreeMap::new();
+    for index in members {
+        let key = cloud.gaussians[index].position.map(|v| (v as f64 / tile_size).floor() as i64);
+        cells.entry(key).or_default().push(index);
+    }
+    cells.into_iter()
+        .map(|(key, members)| Cell {
+            id: format!("{}_{}_{}", key[0], key[1], key[2]),
+            depth: None,
+            region: TileBounds {
+                min: key.map(|k| k as f64 * tile_size),
+                max: key.map(|k| (k + 1) as f64 * tile_size),
+            },
+            members,
+        })
+        .collect()
+}
+
+/// Octants are numbered by their x, y and z halves as bits 0, 1 and 2; a tile id is the
+/// path of octant digits from the root `r`
+struct Octree<'a> {
+    cloud: &'a GaussianCloud,
+    max_gaussians: usize,
+    max_depth: u32,
+    cells: Vec<Cell>,
+}
+
+impl Octree<'_> {
+    fn split(&mut self, members: Vec<usize>, id: String, depth: u32, region: TileBounds) {
+        if members.is_empty() {
+            return;
+        }
+        if members.len() <= self.max_gaussians || depth >= self.max_depth {
+            self.cells.push(Cell { id, depth: Some(depth), region, members });
+            return;
+        }
+
+        let center = [0, 1, 2].map(|axis| 0.5 * (region.min[axis] + region.max[axis]));
+        let mut octants: [Vec<usize>; 8] = Default::default();
+        for index in members {
+            let position = self.cloud.gaussians[index].position;
+            let octant = (0..3)
+                .filter(|axis| position[*axis] as f64 >= center[*axis])
+                .map(|axis| 1 << axis)
+                .sum::<usize>();
+            octants[octant].push(index);
+        }
+        for (octant, members) in octants.into_iter().enumerate() {
+            let upper = |axis: usize| octant & (1 << axis) != 0;
+            let child = TileBounds {
+                min: [0, 1, 2].map(|axis| if upper(axis) { center[axis] } else { region.min[axis] }),
+                max: [0, 1, 2].map(|axis| if upper(axis) { region.max[axis] } else { center[axis] }),
+            };
+            self.split(members, format!("{}{}", id, octant), depth + 1, child);
+        }
+    }
+}
+
+fn bounds_of<I: IntoIterator<Item = [f32; 3]>>(positions: I) -> Option<TileBounds> {
+    let mut bounds: Option<TileBounds> = None;
+    for position in positions.into_iter().map(|position| position.map(f64::from)) {
+        let current = bounds.get_or_insert(TileBounds { min: position, max: position });
+        for (axis, value) in position.into_iter().enumerate() {
+            current.min[axis] = current.min[axis].min(value);
+            current.max[axis] = current.max[axis].max(value);
+        }
+    }
+    bounds
+}
+
+/// Smallest cube sharing the center of `bounds` that contains it, so octree cells stay cubic
+fn cube_around(bounds: &TileBounds) -> TileBounds {
+    let half = (0..3).map(|axis| bounds.max[axis] - bounds.min[axis]).fold(0.0, f64::max) * 0.5;
+    // Pad so points on the max faces fall strictly inside
+    let half = half * (1.0 + 1e-9) + 1e-9;
+    let center = [0, 1, 2].map(|axis| 0.5 * (bounds.min[axis] + bounds.max[axis]));
+    TileBounds {
+        min: center.map(|c| c - half),
+        max: center.map(|c| c + half),
+    }
+}
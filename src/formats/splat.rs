@@ -0,0 +1,447 @@
+//! Web viewer splat formats: antimatter15 `.splat` and GaussianSplats3D `.ksplat`
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::gaussian::{self, Gaussian, GaussianCloud, SH_C0};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use log::{debug, info, warn};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Bytes per splat in the antimatter15 format
+pub const SPLAT_RECORD_SIZE: usize = 32;
+
+const KSPLAT_HEADER_SIZE: usize = 4096;
+const KSPLAT_SECTION_HEADER_SIZE: usize = 1024;
+const KSPLAT_COMPRESSION_LEVEL: u16 = 1;
+const KSPLAT_SCALE_RANGE: u32 = 32767;
+const KSPLAT_BUCKET_SIZE: usize = 256;
+const KSPLAT_BLOCK_SIZE: f32 = 5.0;
+const KSPLAT_MAX_SH_DEGREE: u32 = 2;
+const KSPLAT_DEFAULT_SH_RANGE: f32 = 3.0;
+
+/// Write an antimatter15 `.splat` file, most important splats first
+pub fn write_splat(cloud: &GaussianCloud, path: &Path) -> Result<()> {
+    debug!("Writing .splat: {}", path.display());
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    for gaussian in sorted_by_importance(cloud) {
+        for v in gaussian.position {
+            writer.write_f32::<LittleEndian>(v)?;
+        }
+        for v in gaussian.linear_scale() {
+            writer.write_f32::<LittleEndian>(v)?;
+        }
+        writer.write_all(&rgba_bytes(gaussian))?;
+        for v in normalized_rotation(gaussian) {
+            writer.write_u8(unit_to_u8(v))?;
+        }
+    }
+    writer.flush()?;
+
+    info!("Wrote {} splats to {}", cloud.len(), path.display());
+    Ok(())
+}
+
+/// Read an antimatter15 `.splat` file as a degree-0 Gaussian cloud
+pub fn read_splat(path: &Path) -> Result<GaussianCloud> {
+    debug!("Reading .splat: {}", path.display());
+
+    let length = std::fs::metadata(path)?.len() as usize;
+    if !length.is_multiple_of(SPLAT_RECORD_SIZE) {
+        return Err(HylaeanError::UnsupportedFormat {
+            format: format!("{} is not a multiple of {} bytes", path.display(), SPLAT_RECORD_SIZE),
+        });
+    }
+
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    let mut cloud = GaussianCloud::new(0);
+    cloud.has_normals = false;
+    cloud.gaussians.reserve(length / SPLAT_RECORD_SIZE);
+
+    for _ in 0..length / SPLAT_RECORD_SIZE {
+        let mut gaussian = Gaussian::new(0);
+        for v in gaussian.position.iter_mut() {
+            *v = reader.read_f32::<LittleEndian>()?;
+        }
+        for v in gaussian.scale.iter_mut() {
+            *v = reader.read_f32::<LittleEndian>()?.max(f32::MIN_POSITIVE).ln();
+        }
+        let mut rgba = [0u8; 4];
+        reader.read_exact(&mut rgba)?;
+        set_rgba(&mut gaussian, rgba);
+        let mut rotation = [0u8; 4];
+        reader.read_exact(&mut rotation)?;
+        gaussian.rotation = rotation.map(|r| (r as f32 - 128.0) / 128.0);
+        cloud.gaussians.push(gaussian);
+    }
+
+    info!("Read {} splats from {}", cloud.len(), path.display());
+    Ok(cloud)
+}
+
+/// Write a GaussianSplats3D `.ksplat` file (format 0.1, compression level 1).
+///
+/// Splats are grouped into buckets of up to 256 that share a block of the scene, so
+/// positions can be stored as 16-bit offsets from the bucket center.
+pub fn write_ksplat(cloud: &GaussianCloud, path: &Path) -> Result<()> {
+    debug!("Writing .ksplat: {}", path.display());
+
+    let sh_degree = cloud.sh_degree.min(KSPLAT_MAX_SH_DEGREE);
+    if cloud.sh_degree > sh_degree {
+        warn!(
+            ".ksplat supports SH degree {} at most; dropping higher bands of degree {} input",
+            KSPLAT_MAX_SH_DEGREE, cloud.sh_degree
+        );
+    }
+    let coeffs_per_channel = (sh_degree as usize + 1).pow(2) - 1;
+    let source_coeffs_per_channel = (cloud.sh_degree as usize + 1).pow(2) - 1;
+    let bytes_per_splat = 24 + coeffs_per_channel * 3 * 2;
+
+    let buckets = partition_buckets(cloud);
+    let (full, partial): (Vec<_>, Vec<_>) = buckets.into_iter()
+        .partition(|bucket| bucket.members.len() == KSPLAT_BUCKET_SIZE);
+    let splat_count = cloud.len() as u32;
+    let scene_center = scene_center(cloud);
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    // Main header
+    let mut header = [0u8; KSPLAT_HEADER_SIZE];
+    header[0] = 0; // version major
+    header[1] = 1; // version minor
+    put_u32(&mut header, 1, 1); // max section count
+    put_u32(&mut header, 2, 1); // section count
+    put_u32(&mut header, 3, splat_count); // max splat count
+    put_u32(&mut header, 4, splat_count); // splat count
+    header[20..22].copy_from_slice(&KSPLAT_COMPRESSION_LEVEL.to_le_bytes());
+    for (i, v) in scene_center.iter().enumerate() {
+        put_f32(&mut header, 6 + i, *v);
+    }
+    put_f32(&mut header, 9, -KSPLAT_DEFAULT_SH_RANGE / 2.0);
+    put_f32(&mut header, 10, KSPLAT_DEFAULT_SH_RANGE / 2.0);
+    writer.write_all(&header)?;
+
+    // Section header
+    let mut section = [0u8; KSPLAT_SECTION_HEADER_SIZE];
+    put_u32(&mut section, 0, splat_count);
+    put_u32(&mut section, 1, splat_count);
+    put_u32(&mut section, 2, KSPLAT_BUCKET_SIZE as u32);
+    put_u32(&mut section, 3, (full.len() + partial.len()) as u32);
+    put_f32(&mut section, 4, KSPLAT_BLOCK_SIZE);
+    section[20..22].copy_from_slice(&12u16.to_le_bytes()); // bucket storage: center as 3 x f32
+    put_u32(&mut section, 6, KSPLAT_SCALE_RANGE);
+    put_u32(&mut section, 8, full.len() as u32);
+    put_u32(&mut section, 9, partial.len() as u32);
+    section[40..42].copy_from_slice(&(sh_degree as u16).to_le_bytes());
+    writer.write_all(&section)?;
+
+    // Bucket metadata, then bucket centers
+    for bucket in &partial {
+        writer.write_u32::<LittleEndian>(bucket.members.len() as u32)?;
+    }
+    for bucket in full.iter().chain(&partial) {
+        for v in bucket.center {
+            writer.write_f32::<LittleEndian>(v)?;
+        }
+    }
+
+    // Splat data, in bucket order
+    let half_block = KSPLAT_BLOCK_SIZE / 2.0;
+    let scale_factor = KSPLAT_SCALE_RANGE as f32 / half_block;
+    for bucket in full.iter().chain(&partial) {
+        for &index in &bucket.members {
+            let gaussian = &cloud.gaussians[index];
+            for (v, center) in gaussian.position.iter().zip(bucket.center) {
+                let offset = ((v - center) * scale_factor).round() + KSPLAT_SCALE_RANGE as f32;
+                writer.write_u16::<LittleEndian>(offset.clamp(0.0, (2 * KSPLAT_SCALE_RANGE + 1) as f32) as u16)?;
+            }
+            for v in gaussian.linear_scale() {
+                writer.write_u16::<LittleEndian>(f32_to_f16(v))?;
+            }
+            for v in normalized_rotation(gaussian) {
+                writer.write_u16::<LittleEndian>(f32_to_f16(v))?;
+            }
+            writer.write_all(&rgba_bytes(gaussian))?;
+            // Coefficients interleaved per band: r, g, b of coefficient 0, then coefficient 1, ...
+            for k in 0..coeffs_per_channel {
+                for channel in 0..3 {
+                    let value = gaussian.sh_rest[channel * source_coeffs_per_channel + k];
+                    writer.write_u16::<LittleEndian>(f32_to_f16(value))?;
+                }
+            }
+        }
+    }
+    writer.flush()?;
+
+    info!(
+        "Wrote {} splats to {} ({} bytes per splat, SH degree {})",
+        cloud.len(), path.display(), bytes_per_splat, sh_degree
+    );
+    Ok(())
+}
+
+/// Read a GaussianSplats3D `.ksplat` file (format 0.1, compression level 0 or 1).
+///
+/// Splats come back in file order, which is bucket order rather than the order they
+/// were written in; SH bands are zero-padded to the highest degree of any section.
+pub fn read_ksplat(path: &Path) -> Result<GaussianCloud> {
+    debug!("Reading .ksplat: {}", path.display());
+
+    let data = std::fs::read(path)?;
+    let truncated = || HylaeanError::UnsupportedFormat {
+        format: format!("{} is truncated", path.display()),
+    };
+    let bytes = |offset: usize, len: usize| data.get(offset..offset + len).ok_or_else(truncated);
+    let u16_at = |offset: usize| bytes(offset, 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let u32_at = |offset: usize| bytes(offset, 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    let f32_at = |offset: usize| u32_at(offset).map(f32::from_bits);
+
+    let (major, minor) = (*data.first().ok_or_else(truncated)?, *data.get(1).ok_or_else(truncated)?);
+    let compression_level = u16_at(20)?;
+    if (major, minor) != (0, 1) || compression_level > 1 {
+        return Err(HylaeanError::UnsupportedFormat {
+            format: format!(".ksplat version {}.{} with compression level {}", major, minor, compression_level),
+        });
+    }
+    let section_count = u32_at(8)? as usize;
+
+    let mut sections = Vec::new();
+    for index in 0..section_count {
+        let offset = KSPLAT_HEADER_SIZE + index * KSPLAT_SECTION_HEADER_SIZE;
+        sections.push(KsplatSection {
+            splat_count: u32_at(offset)? as usize,
+            max_splat_count: u32_at(offset + 4)? as usize,
+            bucket_size: u32_at(offset + 8)? as usize,
+            bucket_count: u32_at(offset + 12)? as usize,
+            block_size: f32_at(offset + 16)?,
+            bucket_storage_size: u16_at(offset + 20)? as usize,
+            scale_range: u32_at(offset + 24)?,
+            full_bucket_count: u32_at(offset + 32)? as usize,
+            partial_bucket_count: u32_at(offset + 36)? as usize,
+            sh_degree: u16_at(offset + 40)? as u32,
+        });
+    }
+
+    let sh_degree = sections.iter().map(|section| section.sh_degree).max().unwrap_or(0);
+    let mut cloud = GaussianCloud::new(sh_degree);
+    cloud.has_normals = false;
+    let rest_per_channel = gaussian::sh_rest_len(sh_degree) / 3;
+
+    let mut offset = KSPLAT_HEADER_SIZE + section_count * KSPLAT_SECTION_HEADER_SIZE;
+    for section in &sections {
+        let coeffs_per_channel = (section.sh_degree as usize + 1).pow(2) - 1;
+        let (value_size, color_offset) = if compression_level == 0 { (4, 40) } else { (2, 20) };
+        let bytes_per_splat = color_offset + 4 + coeffs_per_channel * 3 * value_size;
+        let value_at = |offset: usize| -> Result<f32> {
+            if compression_level == 0 { f32_at(offset) } else { u16_at(offset).map(f16_to_f32) }
+        };
+
+        // Full buckets hold `bucket_size` splats each, partial ones list their own lengths
+        let mut bucket_lengths = vec![section.bucket_size; section.full_bucket_count];
+        for i in 0..section.partial_bucket_count {
+            bucket_lengths.push(u32_at(offset + i * 4)? as usize);
+        }
+        offset += section.partial_bucket_count * 4;
+        let mut centers = Vec::with_capacity(section.bucket_count.min(1 << 16));
+        for i in 0..section.bucket_count {
+            let center = offset + i * section.bucket_storage_size;
+            centers.push([f32_at(center)?, f32_at(center + 4)?, f32_at(center + 8)?]);
+        }
+        offset += section.bucket_count * section.bucket_storage_size;
+
+        let position_step = section.block_size / 2.0 / section.scale_range as f32;
+        let mut buckets = centers.iter().zip(&bucket_lengths)
+            .flat_map(|(center, length)| std::iter::repeat_n(*center, *length));
+        for i in 0..section.splat_count {
+            let record = offset + i * bytes_per_splat;
+            let mut gaussian = Gaussian::new(sh_degree);
+            if compression_level == 0 {
+                for (axis, v) in gaussian.position.iter_mut().enumerate() {
+                    *v = f32_at(record + axis * 4)?;
+                }
+            } else {
+                let center = buckets.next().ok_or_else(truncated)?;
+                for (axis, v) in gaussian.position.iter_mut().enumerate() {
+                    let quantized = u16_at(record + axis * 2)? as f32 - section.scale_range as f32;
+                    *v = quantized * position_step + center[axis];
+                }
+            }
+            for (axis, v) in gaussian.scale.iter_mut().enumerate() {
+                *v = value_at(record + (3 + axis) * value_size)?.max(f32::MIN_POSITIVE).ln();
+            }
+            for (axis, v) in gaussian.rotation.iter_mut().enumerate() {
+                *v = value_at(record + (6 + axis) * value_size)?;
+            }
+            let color = bytes(record + color_offset, 4)?;
+            set_rgba(&mut gaussian, [color[0], color[1], color[2], color[3]]);
+            for k in 0..coeffs_per_channel {
+                for channel in 0..3 {
+                    let value = record + color_offset + 4 + (k * 3 + channel) * value_size;
+                    gaussian.sh_rest[channel * rest_per_channel + k] = value_at(value)?;
+                }
+            }
+            cloud.gaussians.push(gaussian);
+        }
+        offset += section.max_splat_count * bytes_per_splat;
+    }
+
+    info!("Read {} splats (SH degree {}) from {}", cloud.len(), sh_degree, path.display());
+    Ok(cloud)
+}
+
+struct KsplatSection {
+    splat_count: usize,
+    max_splat_count: usize,
+    bucket_size: usize,
+    bucket_count: usize,
+    block_size: f32,
+    bucket_storage_size: usize,
+    scale_range: u32,
+    full_bucket_count: usize,
+    partial_bucket_count: usize,
+    sh_degree: u32,
+}
+
+struct Bucket {
+    center: [f32; 3],
+    members: Vec<usize>,
+}
+
+/// Group splats by block of the scene grid, then split each block into buckets of at most 256
+fn partition_buckets(cloud: &GaussianCloud) -> Vec<Bucket> {
+    let mut blocks: BTreeMap<[i64; 3], Vec<usize>> = BTreeMap::new();
+    for (index, gaussian) in cloud.gaussians.iter().enumerate() {
+        let key = gaussian.position.map(|v| (v / KSPLAT_BLOCK_SIZE).floor() as i64);
+        blocks.entry(key).or_default().push(index);
+    }
+
+    let mut buckets = Vec::new();
+    for (key, members) in blocks {
+        let center = key.map(|k| (k as f32 + 0.5) * KSPLAT_BLOCK_SIZE);
+        for chunk in members.chunks(KSPLAT_BUCKET_SIZE) {
+            buckets.push(Bucket {
+                center,
+                members: chunk.to_vec(),
+            });
+        }
+    }
+    buckets
+}
+
+fn sorted_by_importance(cloud: &GaussianCloud) -> Vec<&Gaussian> {
+    let importance = |g: &Gaussian| (g.scale[0] + g.scale[1] + g.scale[2]).exp() * g.alpha();
+    let mut sorted: Vec<&Gaussian> = cloud.gaussians.iter().collect();
+    sorted.sort_by(|a, b| importance(b).total_cmp(&importance(a)));
+    sorted
+}
+
+fn scene_center(cloud: &GaussianCloud) -> [f32; 3] {
+    if cloud.is_empty() {
+        return [0.0; 3];
+    }
+    let mut sum = [0.0f64; 3];
+    for gaussian in &cloud.gaussians {
+        for (s, v) in sum.iter_mut().zip(gaussian.position) {
+            *s += v as f64;
+        }
+    }
+    sum.map(|s| (s / cloud.len() as f64) as f32)
+}
+
+fn rgba_bytes(gaussian: &Gaussian) -> [u8; 4] {
+    let [r, g, b] = gaussian.base_color();
+    [r, g, b, gaussian.alpha()].map(|v| (v * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+fn set_rgba(gaussian: &mut Gaussian, rgba: [u8; 4]) {
+    for (dc, c) in gaussian.sh_dc.iter_mut().zip(rgba) {
+        *dc = (c as f32 / 255.0 - 0.5) / SH_C0;
+    }
+    gaussian.opacity = gaussian::inverse_sigmoid(rgba[3] as f32 / 255.0);
+}
+
+fn normalized_rotation(gaussian: &Gaussian) -> [f32; 4] {
+    let norm = gaussian.rotation.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        gaussian.rotation.map(|v| v / norm)
+    } else {
+        [1.0, 0.0, 0.0, 0.0]
+    }
+}
+
+fn unit_to_u8(v: f32) -> u8 {
+    (v * 128.0 + 128.0).clamp(0.0, 255.0) as u8
+}
+
+fn put_u32(buffer: &mut [u8], index: usize, value: u32) {
+    buffer[index * 4..index * 4 + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_f32(buffer: &mut [u8], index: usize, value: f32) {
+    buffer[index * 4..index * 4 + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// IEEE 754 binary16 conversion with round-to-nearest-even
+pub(crate) fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x007f_ffff;
+
+    if exponent == 0xff {
+        // Infinity or NaN
+        let nan = if mantissa != 0 { 0x0200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    if half_exponent <= 0 {
+        if half_exponent < -10 {
+            return sign;
+        }
+        // Subnormal half: shift in the implicit leading bit
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - half_exponent) as u32;
+        let half_mantissa = mantissa >> shift;
+        let remainder = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round_up = remainder > halfway || (remainder == halfway && half_mantissa & 1 == 1);
+        return sign | (half_mantissa + round_up as u32) as u16;
+    }
+
+    let half_mantissa = mantissa >> 13;
+    let remainder = mantissa & 0x1fff;
+    let round_up = remainder > 0x1000 || (remainder == 0x1000 && half_mantissa & 1 == 1);
+    // A mantissa carry rolls over into the exponent, which is the correct result
+    (sign as u32 | ((half_exponent as u32) << 10) | half_mantissa) as u16 + round_up as u16
+}
+
+/// Widen an IEEE 754 binary16 value to `f32`
+pub(crate) fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x03ff) as u32;
+
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            // Subnormal half: renormalize into the f32 range
+            let shift = mantissa.leading_zeros() - 21;
+            let mantissa = (mantissa << shift) & 0x03ff;
+            sign | ((127 - 15 + 1 - shift) << 23) | (mantissa << 13)
+        }
+        0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
@@ -0,0 +1,66 @@
+//! Baking splat colors into plain point clouds
+//!
+//! [`GaussianSource`] presents a [`GaussianCloud`] as a colored point cloud, so any
+//! streaming sink (PLY, PCD, XYZ, LAS/LAZ) can write Gaussian centers with the DC term
+//! of their spherical harmonics evaluated to 8-bit RGB.
+
+use crate::errors::Result;
+use crate::formats::gaussian::GaussianCloud;
+use crate::formats::ply::{Property, ScalarType};
+use crate::formats::point_cloud::stream::{PointChunk, PointSource, Schema};
+
+pub struct GaussianSource {
+    cloud: GaussianCloud,
+    schema: Schema,
+    alpha: bool,
+    next: usize,
+}
+
+impl GaussianSource {
+    /// With `alpha`, linear opacity is written as an 8-bit `alpha` channel too
+    pub fn new(cloud: GaussianCloud, alpha: bool) -> Self {
+        let mut schema: Schema = ["x", "y", "z"].iter().map(|axis| (axis.to_string(), ScalarType::Float)).collect();
+        let channels: &[&str] = if alpha { &["red", "green", "blue", "alpha"] } else { &["red", "green", "blue"] };
+        schema.extend(channels.iter().map(|channel| (channel.to_string(), ScalarType::UChar)));
+        Self { cloud, schema, alpha, next: 0 }
+    }
+}
+
+impl PointSource for GaussianSource {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.cloud.len())
+    }
+
+    fn is_binary(&self) -> bool {
+        true
+    }
+
+    fn comments(&self) -> Vec<String> {
+        let mut comments = self.cloud.comments.clone();
+        comments.push(format!("hylaeansplat baked {} Gaussians to RGB from the SH DC term", self.cloud.len()));
+        comments
+    }
+
+    fn read_chunk(&mut self, chunk: &mut PointChunk) -> Result<usize> {
+        chunk.clear();
+        while !chunk.is_full() && self.next < self.cloud.len() {
+            let gaussian = &self.cloud.gaussians[self.next];
+            let record = chunk.push_slot();
+            record.0.extend(gaussian.position.map(Property::Float));
+            record.0.extend(gaussian.base_color().map(|c| Property::UChar(to_u8(c))));
+            if self.alpha {
+                record.0.push(Property::UChar(to_u8(gaussian.alpha())));
+            }
+            self.next += 1;
+        }
+        Ok(chunk.len())
+    }
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,260 @@
+//! Voxel-grid and importance downsampling of splats and point clouds
+//!
+//! Gaussians that share a voxel are merged into one by opacity-weighted moment
+//! matching: the merged mean and covariance are the weighted mean and covariance of the
+//! mixture, colors and other attributes are weighted averages, and opacity keeps the
+//! cell's integrated density without exceeding what compositing all of them would give.
+//! Plain point clouds are streamed in and reduced to one averaged point per voxel.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::PointCloudFormat;
+use crate::formats::gaussian::{inverse_sigmoid, Gaussian, GaussianCloud};
+use crate::formats::math::{self, Matrix3};
+use crate::formats::ply::{self, ScalarType};
+use crate::formats::point_cloud::stream::{self, PointChunk, PointSource, Schema, SinkOptions};
+use super::edit;
+use log::{debug, info};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Floor of each coordinate divided by `voxel_size`; `None` for non-finite positions
+fn voxel_key(position: [f64; 3], voxel_size: f64) -> Option<[i64; 3]> {
+    position.iter().all(|v| v.is_finite())
+        .then(|| position.map(|v| (v / voxel_size).floor() as i64))
+}
+
+/// Number of occupied voxels at `voxel_size`
+pub fn occupied_voxels(cloud: &GaussianCloud, voxel_size: f64) -> usize {
+    let mut cells = HashSet::new();
+    for gaussian in &cloud.gaussians {
+        if let Some(key) = voxel_key(gaussian.position.map(f64::from), voxel_size) {
+            cells.insert(key);
+        }
+    }
+    cells.len()
+}
+
+/// Merge all Gaussians sharing a voxel of edge `voxel_size`; returns the number removed.
+/// Gaussians with non-finite positions are dropped.
+pub fn voxel_downsample(cloud: &mut GaussianCloud, voxel_size: f32) -> Result<usize> {
+    if !(voxel_size > 0.0 && voxel_size.is_finite()) {
+        return Err(HylaeanError::ConfigError {
+            message: format!("voxel size must be positive, got {}", voxel_size),
+        });
+    }
+
+    let before = cloud.len();
+    let mut cells: HashMap<[i64; 3], usize> = HashMap::new();
+    let mut groups: Vec<Vec<Gaussian>> = Vec::new();
+    for gaussian in cloud.gaussians.drain(..) {
+        let Some(key) = voxel_key(gaussian.position.map(f64::from), voxel_size as f64) else {
+            continue;
+        };
+        let group = *cells.entry(key).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(gaussian);
+    }
+
+    cloud.gaussians = groups.into_iter().map(merge_gaussians).collect();
+    debug!("Voxel size {}: {} Gaussians in {} cells", voxel_size, before, cloud.len());
+    Ok(before - cloud.len())
+}
+
+/// Keep the `count` most important Gaussians (opacity times volume)
+pub fn importance_downsample(cloud: &mut GaussianCloud, count: usize) -> usize {
+    edit::keep_largest(cloud, count)
+}
+
+/// Moment-match a group of Gaussians with one
+pub fn merge_gaussians(mut group: Vec<Gaussian>) -> Gaussian {
+    if group.len() <= 1 {
+        return group.pop().unwrap_or_else(|| Gaussian::new(0));
+    }
+
+    let mut total = 0.0;
+    let mut mean = [0.0f64; 3];
+    let mut second: Matrix3 = [[0.0; 3]; 3];
+    let mut mass = 0.0;
+    let mut transmittance = 1.0;
+    for gaussian in &group {
+        let alpha = gaussian.alpha() as f64;
+        let weight = alpha.max(1e-6);
+        let position = gaussian.position.map(f64::from);
+        let covariance = covariance(gaussian);
+        for i in 0..3 {
+            mean[i] += weight * position[i];
+            for j in 0..3 {
+                second[i][j] += weight * (covariance[i][j] + position[i] * position[j]);
+            }
+        }
+        total += weight;
+        mass += alpha * volume(gaussian);
+        transmittance *= 1.0 - alpha;
+    }
+
+    let mean = mean.map(|v| v / total);
+    let mut merged_covariance = second;
+    for i in 0..3 {
+        for j in 0..3 {
+            merged_covariance[i][j] = second[i][j] / total - mean[i] * mean[j];
+        }
+    }
+    let (variances, axes) = math::symmetric_eigen(&merged_covariance);
+    let sigma = variances.map(|v| v.max(1e-18).sqrt());
+    let alpha = (mass / (sigma[0] * sigma[1] * sigma[2])).min(1.0 - transmittance);
+
+    let average = |values: &dyn Fn(&Gaussian) -> f64| -> f64 {
+        group.iter().map(|g| g.alpha().max(1e-6) as f64 * values(g)).sum::<f64>() / total
+    };
+    let mut merged = group[0].clone();
+    merged.position = mean.map(|v| v as f32);
+    merged.scale = sigma.map(|s| s.ln() as f32);
+    merged.rotation = math::matrix_to_quaternion(&axes).map(|v| v as f32);
+    merged.opacity = inverse_sigmoid(alpha as f32);
+    for channel in 0..3 {
+        merged.sh_dc[channel] = average(&|g| g.sh_dc[channel] as f64) as f32;
+        merged.normal[channel] = average(&|g| g.normal[channel] as f64) as f32;
+    }
+    for index in 0..merged.sh_rest.len() {
+        merged.sh_rest[index] = average(&|g| g.sh_rest[index] as f64) as f32;
+    }
+    for index in 0..merged.extra.len() {
+        merged.extra[index] = average(&|g| g.extra[index]);
+    }
+    merged
+}
+
+fn covariance(gaussian: &Gaussian) -> Matrix3 {
+    let q = gaussian.rotation.map(f64::from);
+    let norm = q.iter().map(|v| v * v).sum::<f64>().sqrt();
+    let rotation = if norm > 0.0 {
+        math::quaternion_to_matrix(q.map(|v| v / norm))
+    } else {
+        math::quaternion_to_matrix([1.0, 0.0, 0.0, 0.0])
+    };
+    let variances = gaussian.scale.map(|s| (2.0 * s as f64).exp());
+    let mut covariance = [[0.0; 3]; 3];
+    for (i, row) in covariance.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| rotation[i][k] * variances[k] * rotation[j][k]).sum();
+        }
+    }
+    covariance
+}
+
+/// Product of the standard deviations; proportional to the ellipsoid volume
+fn volume(gaussian: &Gaussian) -> f64 {
+    gaussian.scale.iter().map(|s| *s as f64).sum::<f64>().exp()
+}
+
+/// Stream a plain point cloud into one point per voxel, averaging every attribute
+/// (integer attributes are rounded). Returns the number of points written.
+pub fn voxel_downsample_points(
+    input_path: &Path,
+    from_format: &PointCloudFormat,
+    output_path: &Path,
+    to_format: &PointCloudFormat,
+    voxel_size: f32,
+) -> Result<usize> {
+    if !(voxel_size > 0.0 && voxel_size.is_finite()) {
+        return Err(HylaeanError::ConfigError {
+            message: format!("voxel size must be positive, got {}", voxel_size),
+        });
+    }
+
+    let mut source = stream::open_source(input_path, from_format)?;
+    let schema = source.schema().clone();
+    let positions = ["x", "y", "z"].map(|axis| schema.iter().position(|(name, _)| name == axis));
+    let [Some(x), Some(y), Some(z)] = positions else {
+        return Err(HylaeanError::UnsupportedFormat {
+            format: "point cloud without x, y and z attributes".to_string(),
+        });
+    };
+
+    let mut cells: HashMap<[i64; 3], usize> = HashMap::new();
+    let mut sums: Vec<f64> = Vec::new();
+    let mut counts: Vec<u32> = Vec::new();
+    let mut chunk = PointChunk::with_capacity(stream::DEFAULT_CHUNK_SIZE);
+    let mut read = 0;
+    while source.read_chunk(&mut chunk)? > 0 {
+        for record in chunk.records() {
+            let value = |column: usize| ply::scalar_as_f64(&record.0[column]).unwrap_or(f64::NAN);
+            let Some(key) = voxel_key([value(x), value(y), value(z)], voxel_size as f64) else {
+                continue;
+            };
+            let cell = *cells.entry(key).or_insert_with(|| {
+                counts.push(0);
+                sums.resize(sums.len() + schema.len(), 0.0);
+                counts.len() - 1
+            });
+            counts[cell] += 1;
+            for (column, sum) in sums[cell * schema.len()..(cell + 1) * schema.len()].iter_mut().enumerate() {
+                *sum += value(column);
+            }
+        }
+        read += chunk.len();
+    }
+    debug!("Voxel size {}: {} points in {} cells", voxel_size, read, counts.len());
+
+    let mut cells = VoxelSource {
+        comments: vec![format!("hylaeansplat voxel downsampled {} points at voxel size {}", read, voxel_size)],
+        schema,
+        sums,
+        counts,
+        next: 0,
+        binary: source.is_binary(),
+    };
+    let sink = stream::create_sink(output_path, to_format, &cells, SinkOptions::matching(source.as_ref()))?;
+    let written = stream::pump(&mut cells, sink, stream::DEFAULT_CHUNK_SIZE)?;
+    info!("Voxel downsampled {} points to {}", read, written);
+    Ok(written)
+}
+
+/// Averaged voxel points, replayed as a point source
+struct VoxelSource {
+    schema: Schema,
+    comments: Vec<String>,
+    sums: Vec<f64>,
+    counts: Vec<u32>,
+    next: usize,
+    binary: bool,
+}
+
+impl PointSource for VoxelSource {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.counts.len())
+    }
+
+    fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    fn comments(&self) -> Vec<String> {
+        self.comments.clone()
+    }
+
+    fn read_chunk(&mut self, chunk: &mut PointChunk) -> Result<usize> {
+        chunk.clear();
+        let width = self.schema.len();
+        while !chunk.is_full() && self.next < self.counts.len() {
+            let count = self.counts[self.next] as f64;
+            let sums = &self.sums[self.next * width..(self.next + 1) * width];
+            let record = chunk.push_slot();
+            record.0.extend(self.schema.iter().zip(sums).map(|((_, scalar_type), sum)| {
+                let mean = sum / count;
+                match scalar_type {
+                    ScalarType::Float | ScalarType::Double => ply::scalar_from_f64(scalar_type, mean),
+                    _ => ply::scalar_from_f64(scalar_type, mean.round()),
+                }
+            }));
+            self.next += 1;
+        }
+        Ok(chunk.len())
+    }
+}
@@ -0,0 +1,219 @@
+//! Cropping, filtering and pruning of Gaussians
+//!
+//! Each operation removes Gaussians in place and returns how many it removed, so the
+//! caller can record an [`EditSummary`](super::EditSummary) step per operation.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::gaussian::{Gaussian, GaussianCloud};
+use crate::formats::math;
+use super::kdtree::KdTree;
+use log::debug;
+
+/// A region of space for cropping
+#[derive(Debug, Clone, PartialEq)]
+pub enum Region {
+    /// Axis-aligned box
+    Box { min: [f32; 3], max: [f32; 3] },
+    /// Box with its own orientation; `rotation` is a unit quaternion (w, x, y, z)
+    OrientedBox { center: [f32; 3], half_extents: [f32; 3], rotation: [f32; 4] },
+    Sphere { center: [f32; 3], radius: f32 },
+}
+
+impl Region {
+    /// Axis-aligned box from `min_x, min_y, min_z, max_x, max_y, max_z`
+    pub fn from_box_values(values: &[f32]) -> Result<Self> {
+        let v = expect_values(values, 6, "box")?;
+        let min = [v[0].min(v[3]), v[1].min(v[4]), v[2].min(v[5])];
+        let max = [v[0].max(v[3]), v[1].max(v[4]), v[2].max(v[5])];
+        Ok(Region::Box { min, max })
+    }
+
+    /// Oriented box from `cx, cy, cz, hx, hy, hz, qw, qx, qy, qz`
+    pub fn from_oriented_box_values(values: &[f32]) -> Result<Self> {
+        let v = expect_values(values, 10, "oriented box")?;
+        let rotation = normalize_quaternion([v[6], v[7], v[8], v[9]]).ok_or_else(|| HylaeanError::ConfigError {
+            message: "oriented box rotation must be a non-zero quaternion".to_string(),
+        })?;
+        Ok(Region::OrientedBox {
+            center: [v[0], v[1], v[2]],
+            half_extents: [v[3].abs(), v[4].abs(), v[5].abs()],
+            rotation,
+        })
+    }
+
+    /// Sphere from `cx, cy, cz, radius`
+    pub fn from_sphere_values(values: &[f32]) -> Result<Self> {
+        let v = expect_values(values, 4, "sphere")?;
+        Ok(Region::Sphere {
+            center: [v[0], v[1], v[2]],
+            radius: v[3].abs(),
+        })
+    }
+
+    pub fn contains(&self, point: [f32; 3]) -> bool {
+        match self {
+            Region::Box { min, max } => (0..3).all(|axis| point[axis] >= min[axis] && point[axis] <= max[axis]),
+            Region::OrientedBox { center, half_extents, rotation } => {
+                let offset = [point[0] - center[0], point[1] - center[1], point[2] - center[2]];
+                let local = rotate_inverse(*rotation, offset);
+                (0..3).all(|axis| local[axis].abs() <= half_extents[axis])
+            }
+            Region::Sphere { center, radius } => {
+                let distance2: f32 = (0..3).map(|axis| (point[axis] - center[axis]).powi(2)).sum();
+                distance2 <= radius * radius
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Region::Box { min, max } => format!("box {:?}..{:?}", min, max),
+            Region::OrientedBox { center, half_extents, .. } => {
+                format!("oriented box at {:?}, half extents {:?}", center, half_extents)
+            }
+            Region::Sphere { center, radius } => format!("sphere at {:?}, radius {}", center, radius),
+        }
+    }
+}
+
+/// One step of an edit pipeline
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditOperation {
+    Crop { region: Region, invert: bool },
+    MinOpacity(f32),
+    Scale { min: Option<f32>, max: Option<f32> },
+    Outliers { neighbors: usize, std_ratio: f32 },
+    KeepLargest(usize),
+}
+
+impl EditOperation {
+    /// Apply to `cloud`, returning a description and the number of Gaussians removed
+    pub fn apply(&self, cloud: &mut GaussianCloud) -> (String, usize) {
+        match self {
+            EditOperation::Crop { region, invert } => {
+                let side = if *invert { "inside" } else { "outside" };
+                (format!("{} {}", side, region.describe()), crop(cloud, region, *invert))
+            }
+            EditOperation::MinOpacity(min_alpha) => {
+                (format!("opacity below {}", min_alpha), filter_opacity(cloud, *min_alpha))
+            }
+            EditOperation::Scale { min, max } => {
+                let bounds = match (min, max) {
+                    (Some(min), Some(max)) => format!("outside {}..{}", min, max),
+                    (Some(min), None) => format!("below {}", min),
+                    (None, Some(max)) => format!("above {}", max),
+                    (None, None) => "unbounded".to_string(),
+                };
+                (format!("largest scale {}", bounds), filter_scale(cloud, *min, *max))
+            }
+            EditOperation::Outliers { neighbors, std_ratio } => (
+                format!("outliers ({} neighbors, {} std)", neighbors, std_ratio),
+                remove_outliers(cloud, *neighbors, *std_ratio),
+            ),
+            EditOperation::KeepLargest(count) => {
+                (format!("beyond the {} most important", count), keep_largest(cloud, *count))
+            }
+        }
+    }
+}
+
+/// Keep the Gaussians whose centers are inside `region` (outside, with `invert`)
+pub fn crop(cloud: &mut GaussianCloud, region: &Region, invert: bool) -> usize {
+    retain(cloud, |gaussian| region.contains(gaussian.position) != invert)
+}
+
+/// Remove Gaussians whose linear opacity is below `min_alpha`
+pub fn filter_opacity(cloud: &mut GaussianCloud, min_alpha: f32) -> usize {
+    retain(cloud, |gaussian| gaussian.alpha() >= min_alpha)
+}
+
+/// Remove Gaussians whose largest linear scale falls outside `[min, max]`
+pub fn filter_scale(cloud: &mut GaussianCloud, min: Option<f32>, max: Option<f32>) -> usize {
+    retain(cloud, |gaussian| {
+        let largest = gaussian.linear_scale().into_iter().fold(0.0f32, f32::max);
+        min.is_none_or(|min| largest >= min) && max.is_none_or(|max| largest <= max)
+    })
+}
+
+/// Statistical outlier removal, as in PCL: drop Gaussians whose mean distance to their
+/// `neighbors` nearest neighbors exceeds the global mean by more than `std_ratio`
+/// standard deviations. Gaussians with non-finite positions are always removed.
+pub fn remove_outliers(cloud: &mut GaussianCloud, neighbors: usize, std_ratio: f32) -> usize {
+    if cloud.len() <= neighbors || neighbors == 0 {
+        return 0;
+    }
+    let positions: Vec<[f32; 3]> = cloud.gaussians.iter().map(|gaussian| gaussian.position).collect();
+    let tree = KdTree::new(&positions);
+
+    let mean_distances: Vec<f32> = positions.iter().enumerate()
+        .map(|(index, position)| {
+            if !position.iter().all(|v| v.is_finite()) {
+                return f32::NAN;
+            }
+            let found = tree.nearest(*position, neighbors, Some(index));
+            found.iter().map(|(distance2, _)| distance2.sqrt()).sum::<f32>() / found.len().max(1) as f32
+        })
+        .collect();
+
+    let finite: Vec<f64> = mean_distances.iter().filter(|d| d.is_finite()).map(|d| *d as f64).collect();
+    let mean = finite.iter().sum::<f64>() / finite.len().max(1) as f64;
+    let variance = finite.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / finite.len().max(1) as f64;
+    let threshold = (mean + std_ratio as f64 * variance.sqrt()) as f32;
+    debug!("Outlier threshold: mean neighbor distance {:.4}, cutoff {:.4}", mean, threshold);
+
+    let mut keep = mean_distances.iter().map(|d| *d <= threshold);
+    retain(cloud, |_| keep.next().unwrap_or(false))
+}
+
+/// Keep the `count` most important Gaussians, where importance is linear opacity
+/// times ellipsoid volume: the ones that contribute most to rendered images.
+pub fn keep_largest(cloud: &mut GaussianCloud, count: usize) -> usize {
+    if cloud.len() <= count {
+        return 0;
+    }
+    let mut scores: Vec<(f32, usize)> = cloud.gaussians.iter().enumerate()
+        .map(|(index, gaussian)| (importance(gaussian), index))
+        .collect();
+    scores.select_nth_unstable_by(count, |a, b| b.0.total_cmp(&a.0));
+
+    let mut keep = vec![false; cloud.len()];
+    for (_, index) in &scores[..count] {
+        keep[*index] = true;
+    }
+    let mut keep = keep.into_iter();
+    retain(cloud, |_| keep.next().unwrap_or(false))
+}
+
+/// Linear opacity times the volume of the ellipsoid
+pub fn importance(gaussian: &Gaussian) -> f32 {
+    let volume = (gaussian.scale[0] + gaussian.scale[1] + gaussian.scale[2]).exp();
+    let score = gaussian.alpha() * volume;
+    if score.is_finite() { score } else { 0.0 }
+}
+
+fn retain<F: FnMut(&Gaussian) -> bool>(cloud: &mut GaussianCloud, keep: F) -> usize {
+    let before = cloud.len();
+    cloud.gaussians.retain(keep);
+    before - cloud.len()
+}
+
+fn expect_values<'a>(values: &'a [f32], count: usize, shape: &str) -> Result<&'a [f32]> {
+    if values.len() == count && values.iter().all(|v| v.is_finite()) {
+        Ok(values)
+    } else {
+        Err(HylaeanError::ConfigError {
+            message: format!("{} needs {} finite values, got {:?}", shape, count, values),
+        })
+    }
+}
+
+fn normalize_quaternion(q: [f32; 4]) -> Option<[f32; 4]> {
+    let norm = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+    (norm > 0.0).then(|| q.map(|v| v / norm))
+}
+
+/// Rotate `v` by the inverse of unit quaternion `q` (w, x, y, z)
+fn rotate_inverse(q: [f32; 4], v: [f32; 3]) -> [f32; 3] {
+    let rotation = math::quaternion_to_matrix(q.map(f64::from));
+    math::apply(&math::transpose(&rotation), v.map(f64::from)).map(|c| c as f32)
+}
@@ -0,0 +1,121 @@
+//! Static 3D kd-tree for neighbor queries over splat centers
+//!
+//! The tree is implicit: point indices are permuted so that every subrange's middle
+//! element splits it on the axis of its depth, so no node structs are allocated.
+
+use std::collections::BinaryHeap;
+
+pub(crate) struct KdTree<'a> {
+    points: &'a [[f32; 3]],
+    order: Vec<u32>,
+}
+
+impl<'a> KdTree<'a> {
+    /// Build over all points; non-finite points are left out
+    pub(crate) fn new(points: &'a [[f32; 3]]) -> Self {
+        let mut order: Vec<u32> = (0..points.len() as u32)
+            .filter(|i| points[*i as usize].iter().all(|v| v.is_finite()))
+            .collect();
+        build(points, &mut order, 0);
+        Self { points, order }
+    }
+
+    /// The `k` nearest indexed points to `query` as `(squared distance, index)`, nearest first.
+    /// `exclude` skips one index, typically the query point itself.
+    pub(crate) fn nearest(&self, query: [f32; 3], k: usize, exclude: Option<usize>) -> Vec<(f32, usize)> {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search(&self.order, 0, query, k, exclude, &mut heap);
+        }
+        // Squared distances are non-negative, so their bit patterns sort like the values
+        let mut found: Vec<(f32, usize)> = heap.into_iter()
+            .map(|(bits, index)| (f32::from_bits(bits), index as usize))
+            .collect();
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+        found
+    }
+
+    /// Visit every indexed point within `radius` of `query`
+    pub(crate) fn within<F: FnMut(usize)>(&self, query: [f32; 3], radius: f32, mut visit: F) {
+        self.search_radius(&self.order, 0, query, radius * radius, &mut visit);
+    }
+
+    fn search_radius<F: FnMut(usize)>(&self, range: &[u32], depth: usize, query: [f32; 3], radius2: f32, visit: &mut F) {
+        if range.is_empty() {
+            return;
+        }
+        let mid = range.len() / 2;
+        let index = range[mid];
+        let point = self.points[index as usize];
+        if distance_squared(point, query) <= radius2 {
+            visit(index as usize);
+        }
+
+        let axis = depth % 3;
+        let delta = query[axis] - point[axis];
+        if delta <= 0.0 || delta * delta <= radius2 {
+            self.search_radius(&range[..mid], depth + 1, query, radius2, visit);
+        }
+        if delta >= 0.0 || delta * delta <= radius2 {
+            self.search_radius(&range[mid + 1..], depth + 1, query, radius2, visit);
+        }
+    }
+
+    fn search(
+        &self,
+        range: &[u32],
+        depth: usize,
+        query: [f32; 3],
+        k: usize,
+        exclude: Option<usize>,
+        heap: &mut BinaryHeap<(u32, u32)>,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+        let mid = range.len() / 2;
+        let index = range[mid];
+        let point = self.points[index as usize];
+
+        if exclude != Some(index as usize) {
+            let distance = distance_squared(point, query);
+            if heap.len() < k {
+                heap.push((distance.to_bits(), index));
+            } else if heap.peek().is_some_and(|(worst, _)| distance.to_bits() < *worst) {
+                heap.pop();
+                heap.push((distance.to_bits(), index));
+            }
+        }
+
+        let axis = depth % 3;
+        let delta = query[axis] - point[axis];
+        let (near, far) = if delta < 0.0 {
+            (&range[..mid], &range[mid + 1..])
+        } else {
+            (&range[mid + 1..], &range[..mid])
+        };
+        self.search(near, depth + 1, query, k, exclude, heap);
+        let worst = heap.peek().map(|(bits, _)| f32::from_bits(*bits));
+        if heap.len() < k || worst.is_some_and(|worst| delta * delta < worst) {
+            self.search(far, depth + 1, query, k, exclude, heap);
+        }
+    }
+}
+
+fn build(points: &[[f32; 3]], order: &mut [u32], depth: usize) {
+    if order.len() <= 1 {
+        return;
+    }
+    let axis = depth % 3;
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by(mid, |a, b| {
+        points[*a as usize][axis].total_cmp(&points[*b as usize][axis])
+    });
+    let (left, right) = order.split_at_mut(mid);
+    build(points, left, depth + 1);
+    build(points, &mut right[1..], depth + 1);
+}
+
+fn distance_squared(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
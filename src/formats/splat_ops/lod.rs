@@ -0,0 +1,192 @@
+//! Level-of-detail chains for web viewers
+//!
+//! Level 0 is the full scene; every further level targets `ratio` times the Gaussians
+//! of the one before, either by voxel merging (the voxel size is searched to hit the
+//! target) or by keeping the most important Gaussians. A JSON manifest lists the files.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::gaussian::GaussianCloud;
+use crate::formats::ply::Encoding;
+use super::downsample;
+use super::save_cloud;
+use log::info;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LodMethod {
+    Voxel,
+    Importance,
+}
+
+impl LodMethod {
+    pub fn parse(method: &str) -> Result<Self> {
+        match method.to_lowercase().as_str() {
+            "voxel" => Ok(LodMethod::Voxel),
+            "importance" => Ok(LodMethod::Importance),
+            other => Err(HylaeanError::ConfigError {
+                message: format!("unknown LOD method '{}' (expected voxel or importance)", other),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LodOptions {
+    pub levels: usize,
+    /// Target fraction of the previous level's Gaussians, in (0, 1)
+    pub ratio: f64,
+    pub method: LodMethod,
+}
+
+impl LodOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.levels == 0 || !(self.ratio > 0.0 && self.ratio < 1.0) {
+            return Err(HylaeanError::ConfigError {
+                message: format!(
+                    "LOD needs at least one level and a ratio between 0 and 1, got {} and {}",
+                    self.levels, self.ratio
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Gaussian count targeted at `level` for a scene of `count`
+    pub fn target(&self, count: usize, level: usize) -> usize {
+        ((count as f64) * self.ratio.powi(level as i32)).round().max(1.0) as usize
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LodManifest {
+    pub source: String,
+    pub method: LodMethod,
+    pub ratio: f64,
+    pub sh_degree: u32,
+    pub levels: Vec<LodLevel>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LodLevel {
+    pub level: usize,
+    /// File name, relative to the manifest
+    pub file: String,
+    pub gaussians: usize,
+    pub bytes: u64,
+    /// Voxel edge length used for this level (voxel method only)
+    pub voxel_size: Option<f64>,
+    pub bounds: Option<[[f32; 3]; 2]>,
+}
+
+/// Write `levels` files named `<stem>_lod<N>.<extension>` (the extension selects the
+/// format) and `<stem>_lod.json` into `output_dir`; returns the manifest
+pub fn build_lod_chain(
+    cloud: &GaussianCloud,
+    source: &Path,
+    output_dir: &Path,
+    options: &LodOptions,
+    extension: &str,
+    encoding: Encoding,
+) -> Result<LodManifest> {
+    options.validate()?;
+    let LodOptions { levels, ratio, method } = *options;
+    std::fs::create_dir_all(output_dir)?;
+    let stem = source.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_else(|| "scene".to_string());
+
+    let mut manifest = LodManifest {
+        source: source.display().to_string(),
+        method,
+        ratio,
+        sh_degree: cloud.sh_degree,
+        levels: Vec::new(),
+    };
+    for level in 0..levels {
+        let mut reduced = cloud.clone();
+        let mut voxel_size = None;
+        if level > 0 {
+            voxel_size = reduce(&mut reduced, options.target(cloud.len(), level), method)?;
+            reduced.comments.push(format!(
+                "hylaeansplat LOD level {} of {} ({} Gaussians)",
+                level, source.display(), reduced.len()
+            ));
+        }
+
+        let file = format!("{}_lod{}.{}", stem, level, extension);
+        let path = output_dir.join(&file);
+        save_cloud(&reduced, &path, encoding)?;
+        info!("LOD {}: {} Gaussians -> {}", level, reduced.len(), path.display());
+        manifest.levels.push(LodLevel {
+            level,
+            file,
+            gaussians: reduced.len(),
+            bytes: std::fs::metadata(&path)?.len(),
+            voxel_size,
+            bounds: bounds(&reduced),
+        });
+    }
+
+    let manifest_path = output_dir.join(format!("{}_lod.json", stem));
+    serde_json::to_writer_pretty(BufWriter::new(File::create(&manifest_path)?), &manifest)?;
+    info!("LOD manifest written: {}", manifest_path.display());
+    Ok(manifest)
+}
+
+/// Reduce `cloud` to about `target` Gaussians; returns the voxel size used, if any
+pub fn reduce(cloud: &mut GaussianCloud, target: usize, method: LodMethod) -> Result<Option<f64>> {
+    match method {
+        LodMethod::Voxel => {
+            let size = voxel_size_for(cloud, target);
+            downsample::voxel_downsample(cloud, size as f32)?;
+            Ok(Some(size))
+        }
+        LodMethod::Importance => {
+            downsample::importance_downsample(cloud, target);
+            Ok(None)
+        }
+    }
+}
+
+/// Search (log-scale bisection) for the voxel size whose occupied voxel count is
+/// closest to `target`
+fn voxel_size_for(cloud: &GaussianCloud, target: usize) -> f64 {
+    let extent = bounds(cloud)
+        .map(|[min, max]| (0..3).map(|axis| (max[axis] - min[axis]) as f64).fold(0.0, f64::max))
+        .unwrap_or(1.0)
+        .max(1e-6);
+    let (mut low, mut high) = (extent * 1e-6, extent * 2.0);
+    let mut best = (usize::MAX, high);
+    for _ in 0..32 {
+        let size = (low * high).sqrt();
+        let count = downsample::occupied_voxels(cloud, size);
+        let error = count.abs_diff(target);
+        if error < best.0 {
+            best = (error, size);
+        }
+        if count > target {
+            low = size;
+        } else {
+            high = size;
+        }
+    }
+    best.1
+}
+
+/// Tight bounds of the finite Gaussian centers as `[min, max]`
+pub fn bounds(cloud: &GaussianCloud) -> Option<[[f32; 3]; 2]> {
+    let mut result: Option<[[f32; 3]; 2]> = None;
+    for position in cloud.gaussians.iter().map(|gaussian| gaussian.position) {
+        if !position.iter().all(|v| v.is_finite()) {
+            continue;
+        }
+        let [min, max] = result.get_or_insert([position, position]);
+        for axis in 0..3 {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+        }
+    }
+    result
+}
@@ -0,0 +1,117 @@
+//! Merging separately trained splat scenes into one
+//!
+//! Inputs are moved into a common frame with their own [`Similarity`], brought to a
+//! shared SH degree and set of extra properties, and concatenated. Tiles trained with
+//! overlapping margins can optionally be de-duplicated: where Gaussians from different
+//! inputs sit within a radius of each other, only the most important one is kept.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::gaussian::GaussianCloud;
+use super::edit::importance;
+use super::kdtree::KdTree;
+use super::transform::{self, Similarity};
+use log::{debug, info};
+use std::path::PathBuf;
+
+/// One scene to merge and the transform that places it in the output frame
+#[derive(Debug, Clone)]
+pub struct MergeInput {
+    pub path: PathBuf,
+    pub transform: Similarity,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// SH degree of the output; the highest input degree if not given
+    pub sh_degree: Option<u32>,
+    /// Radius within which Gaussians from different inputs count as duplicates
+    pub dedupe_radius: Option<f32>,
+}
+
+/// Merge loaded scenes, returning the merged cloud and the number of duplicates removed
+pub fn merge_clouds(mut inputs: Vec<(MergeInput, GaussianCloud)>, options: &MergeOptions) -> Result<(GaussianCloud, usize)> {
+    if inputs.is_empty() {
+        return Err(HylaeanError::ConfigError {
+            message: "merge needs at least one input scene".to_string(),
+        });
+    }
+
+    let sh_degree = options.sh_degree
+        .unwrap_or_else(|| inputs.iter().map(|(_, cloud)| cloud.sh_degree).max().unwrap_or(0));
+    let has_normals = inputs.iter().all(|(_, cloud)| cloud.has_normals);
+    // Extra properties survive only if every input has them
+    let extra_properties: Vec<_> = inputs[0].1.extra_properties.iter()
+        .filter(|(name, _)| {
+            inputs.iter().all(|(_, cloud)| cloud.extra_properties.iter().any(|(other, _)| other == name))
+        })
+        .cloned()
+        .collect();
+
+    let mut merged = GaussianCloud::new(sh_degree);
+    merged.has_normals = has_normals;
+    merged.extra_properties = extra_properties.clone();
+    let mut sources = Vec::new();
+
+    let scenes = inputs.len();
+    for (index, (input, cloud)) in inputs.iter_mut().enumerate() {
+        merged.comments.push(format!(
+            "hylaeansplat merge input {}: {} ({} Gaussians, SH degree {}, transform {:?})",
+            index, input.path.display(), cloud.len(), cloud.sh_degree, input.transform.to_matrix()
+        ));
+        if cloud.sh_degree != sh_degree {
+            debug!("{}: SH degree {} -> {}", input.path.display(), cloud.sh_degree, sh_degree);
+            cloud.set_sh_degree(sh_degree);
+        }
+        transform::transform_cloud(cloud, &input.transform);
+
+        let columns: Vec<usize> = extra_properties.iter()
+            .filter_map(|(name, _)| cloud.extra_properties.iter().position(|(other, _)| other == name))
+            .collect();
+        for mut gaussian in cloud.gaussians.drain(..) {
+            gaussian.extra = columns.iter().map(|column| gaussian.extra[*column]).collect();
+            merged.gaussians.push(gaussian);
+            sources.push(index as u32);
+        }
+    }
+
+    let removed = match options.dedupe_radius {
+        Some(radius) if radius > 0.0 && scenes > 1 => {
+            let removed = remove_duplicates(&mut merged, &sources, radius);
+            merged.comments.push(format!(
+                "hylaeansplat merge removed {} overlapping Gaussians within radius {}",
+                removed, radius
+            ));
+            removed
+        }
+        _ => 0,
+    };
+
+    info!("Merged {} scenes into {} Gaussians (SH degree {})", scenes, merged.len(), sh_degree);
+    Ok((merged, removed))
+}
+
+/// Visit Gaussians from most to least important and drop any that lie within `radius`
+/// of an already kept Gaussian from a different input. Gaussians close to others from
+/// their own input are never removed, so the interior of each scene stays intact.
+fn remove_duplicates(cloud: &mut GaussianCloud, sources: &[u32], radius: f32) -> usize {
+    let positions: Vec<[f32; 3]> = cloud.gaussians.iter().map(|gaussian| gaussian.position).collect();
+    let tree = KdTree::new(&positions);
+
+    let mut order: Vec<usize> = (0..cloud.len()).collect();
+    let scores: Vec<f32> = cloud.gaussians.iter().map(importance).collect();
+    order.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+
+    let mut kept = vec![false; cloud.len()];
+    for index in order {
+        let mut duplicate = false;
+        tree.within(positions[index], radius, |other| {
+            duplicate |= kept[other] && sources[other] != sources[index];
+        });
+        kept[index] = !duplicate;
+    }
+
+    let before = cloud.len();
+    let mut keep = kept.into_iter();
+    cloud.gaussians.retain(|_| keep.next().unwrap_or(false));
+    before - cloud.len()
+}
@@ -0,0 +1,111 @@
+//! Editing operations on trained Gaussian splat scenes and point clouds
+//!
+//! Every operation works on an in-memory [`GaussianCloud`]; [`load_cloud`] and
+//! [`save_cloud`] move scenes in and out of any Gaussian format (PLY, `.splat`,
+//! `.ksplat` and SPZ).
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::gaussian::GaussianCloud;
+use crate::formats::ply::{self, Encoding};
+use crate::formats::{self, DataFormat, PointCloudFormat, splat, spz};
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+pub mod bake;
+pub mod downsample;
+pub mod edit;
+pub mod lod;
+pub mod merge;
+pub mod transform;
+pub(crate) mod kdtree;
+
+/// Whether `path` holds a Gaussian scene rather than a plain point cloud
+pub fn is_gaussian_file(path: &Path) -> Result<bool> {
+    Ok(match formats::detect_format(path)? {
+        DataFormat::PointCloud(PointCloudFormat::PLY) => GaussianCloud::is_gaussian_header(&ply::read_header(path)?),
+        DataFormat::PointCloud(PointCloudFormat::Splat | PointCloudFormat::KSplat | PointCloudFormat::Spz) => true,
+        _ => false,
+    })
+}
+
+/// Read a Gaussian scene, returning it with the PLY encoding to reuse on output
+pub fn load_cloud(path: &Path) -> Result<(GaussianCloud, Encoding)> {
+    match formats::detect_format(path)? {
+        DataFormat::PointCloud(PointCloudFormat::PLY) => {
+            let encoding = ply::read_header(path)?.encoding;
+            Ok((GaussianCloud::read_ply(path)?, encoding))
+        }
+        DataFormat::PointCloud(PointCloudFormat::Splat) => Ok((splat::read_splat(path)?, Encoding::BinaryLittleEndian)),
+        DataFormat::PointCloud(PointCloudFormat::KSplat) => Ok((splat::read_ksplat(path)?, Encoding::BinaryLittleEndian)),
+        DataFormat::PointCloud(PointCloudFormat::Spz) => Ok((spz::read_spz(path)?, Encoding::BinaryLittleEndian)),
+        other => Err(HylaeanError::UnsupportedFormat {
+            format: format!("{:?} (Gaussian splat input)", other),
+        }),
+    }
+}
+
+/// Write a Gaussian scene in the format given by the output extension
+pub fn save_cloud(cloud: &GaussianCloud, path: &Path, encoding: Encoding) -> Result<()> {
+    match formats::detect_format(path)? {
+        DataFormat::PointCloud(PointCloudFormat::PLY) => cloud.write_ply(path, encoding),
+        DataFormat::PointCloud(PointCloudFormat::Splat) => splat::write_splat(cloud, path),
+        DataFormat::PointCloud(PointCloudFormat::KSplat) => splat::write_ksplat(cloud, path),
+        DataFormat::PointCloud(PointCloudFormat::Spz) => spz::write_spz(cloud, path),
+        other => Err(HylaeanError::UnsupportedFormat {
+            format: format!("{:?} (Gaussian splat output)", other),
+        }),
+    }
+}
+
+/// How many Gaussians each step of an edit removed
+#[derive(Debug, Clone, Serialize)]
+pub struct EditSummary {
+    pub input: usize,
+    pub steps: Vec<EditStep>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EditStep {
+    pub description: String,
+    pub removed: usize,
+}
+
+impl EditSummary {
+    pub fn new(input: usize) -> Self {
+        Self {
+            input,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, description: impl Into<String>, removed: usize) {
+        self.steps.push(EditStep {
+            description: description.into(),
+            removed,
+        });
+    }
+
+    pub fn removed(&self) -> usize {
+        self.steps.iter().map(|step| step.removed).sum()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.input - self.removed()
+    }
+}
+
+impl fmt::Display for EditSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = 100.0 * self.removed() as f64 / self.input.max(1) as f64;
+        writeln!(
+            f,
+            "Removed {} of {} Gaussians ({:.1}%), {} remain",
+            self.removed(), self.input, percent, self.remaining()
+        )?;
+        for step in &self.steps {
+            writeln!(f, "  {:>10}  {}", step.removed, step.description)?;
+        }
+        Ok(())
+    }
+}
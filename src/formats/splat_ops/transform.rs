@@ -0,0 +1,403 @@
+//! Similarity transforms of point clouds and Gaussian splats
+//!
+//! A [`Similarity`] maps `p -> s * R * p + t`. Gaussians additionally get `R` applied to
+//! their orientation quaternions and view-dependent SH coefficients, and `ln s` added to
+//! their log-scales, so the moved scene renders exactly like the original seen through
+//! correspondingly moved cameras.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::PointCloudFormat;
+use crate::formats::gaussian::GaussianCloud;
+use crate::formats::math::{self, Matrix3, IDENTITY};
+use crate::formats::ply;
+use crate::formats::point_cloud::stream::{self, SinkOptions};
+use std::path::Path;
+
+/// Uniform scale, rotation and translation: `p -> scale * rotation * p + translation`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Similarity {
+    pub rotation: Matrix3,
+    pub scale: f64,
+    pub translation: [f64; 3],
+}
+
+impl Default for Similarity {
+    fn default() -> Self {
+        Self {
+            rotation: IDENTITY,
+            scale: 1.0,
+            translation: [0.0; 3],
+        }
+    }
+}
+
+impl Similarity {
+    /// Decompose a row-major 4x4 matrix. Shear, non-uniform scale, reflections and
+    /// projective rows are rejected, since Gaussians cannot represent them.
+    pub fn from_matrix(values: &[f64]) -> Result<Self> {
+        if values.len() != 16 || values.iter().any(|v| !v.is_finite()) {
+            return Err(invalid(format!("a 4x4 matrix needs 16 finite values, got {}", values.len())));
+        }
+        let bottom = [values[12], values[13], values[14], values[15]];
+        if (bottom[0].abs() + bottom[1].abs() + bottom[2].abs() + (bottom[3] - 1.0).abs()) > 1e-6 {
+            return Err(invalid(format!("the last matrix row must be 0 0 0 1, got {:?}", bottom)));
+        }
+
+        let linear: Matrix3 = [
+            [values[0], values[1], values[2]],
+            [values[4], values[5], values[6]],
+            [values[8], values[9], values[10]],
+        ];
+        let det = math::determinant(&linear);
+        if det <= 0.0 {
+            return Err(invalid("the matrix is singular or contains a reflection".to_string()));
+        }
+        let scale = det.cbrt();
+        let rotation = linear.map(|row| row.map(|v| v / scale));
+
+        let gram = math::multiply(&math::transpose(&rotation), &rotation);
+        let error = (0..3)
+            .flat_map(|i| (0..3).map(move |j| (i, j)))
+            .map(|(i, j)| (gram[i][j] - IDENTITY[i][j]).abs())
+            .fold(0.0, f64::max);
+        if error > 1e-4 {
+            return Err(invalid(format!(
+                "the matrix has shear or non-uniform scale (orthogonality error {:.2e})",
+                error
+            )));
+        }
+
+        Ok(Self {
+            // Round-trip through a quaternion to remove numerical drift
+            rotation: math::quaternion_to_matrix(math::matrix_to_quaternion(&rotation)),
+            scale,
+            translation: [values[3], values[7], values[11]],
+        })
+    }
+
+    /// Rotation by Euler angles in degrees, about the fixed X, then Y, then Z axes
+    pub fn from_euler_degrees(angles: [f64; 3]) -> Self {
+        let [x, y, z] = angles.map(f64::to_radians);
+        let rx = [[1.0, 0.0, 0.0], [0.0, x.cos(), -x.sin()], [0.0, x.sin(), x.cos()]];
+        let ry = [[y.cos(), 0.0, y.sin()], [0.0, 1.0, 0.0], [-y.sin(), 0.0, y.cos()]];
+        let rz = [[z.cos(), -z.sin(), 0.0], [z.sin(), z.cos(), 0.0], [0.0, 0.0, 1.0]];
+        Self {
+            rotation: math::multiply(&rz, &math::multiply(&ry, &rx)),
+            ..Self::default()
+        }
+    }
+
+    /// Rotation by a quaternion (w, x, y, z); it does not need to be normalized
+    pub fn from_quaternion(q: [f64; 4]) -> Result<Self> {
+        let norm = q.iter().map(|v| v * v).sum::<f64>().sqrt();
+        if !(norm > 0.0 && norm.is_finite()) {
+            return Err(invalid("rotation quaternion must be finite and non-zero".to_string()));
+        }
+        Ok(Self {
+            rotation: math::quaternion_to_matrix(q.map(|v| v / norm)),
+            ..Self::default()
+        })
+    }
+
+    pub fn from_scale(scale: f64) -> Result<Self> {
+        if !(scale > 0.0 && scale.is_finite()) {
+            return Err(invalid(format!("scale must be positive, got {}", scale)));
+        }
+        Ok(Self {
+            scale,
+            ..Self::default()
+        })
+    }
+
+    pub fn from_translation(translation: [f64; 3]) -> Self {
+        Self {
+            translation,
+            ..Self::default()
+        }
+    }
+
+    /// The transform that applies `self` first, then `next`
+    pub fn then(&self, next: &Similarity) -> Similarity {
+        let moved = next.rotate(self.translation);
+        Similarity {
+            rotation: math::multiply(&next.rotation, &self.rotation),
+            scale: next.scale * self.scale,
+            translation: [0, 1, 2].map(|i| next.scale * moved[i] + next.translation[i]),
+        }
+    }
+
+    pub fn inverse(&self) -> Similarity {
+        let rotation = math::transpose(&self.rotation);
+        let scale = 1.0 / self.scale;
+        let moved = math::apply(&rotation, self.translation);
+        Similarity {
+            rotation,
+            scale,
+            translation: moved.map(|v| -scale * v),
+        }
+    }
+
+    pub fn transform_point(&self, point: [f64; 3]) -> [f64; 3] {
+        let rotated = self.rotate(point);
+        [0, 1, 2].map(|i| self.scale * rotated[i] + self.translation[i])
+    }
+
+    /// Rotate a direction (normals, view vectors); scale and translation do not apply
+    pub fn rotate(&self, vector: [f64; 3]) -> [f64; 3] {
+        math::apply(&self.rotation, vector)
+    }
+
+    /// The rotation as a unit quaternion (w, x, y, z)
+    pub fn quaternion(&self) -> [f64; 4] {
+        math::matrix_to_quaternion(&self.rotation)
+    }
+
+    /// Row-major 4x4 matrix
+    pub fn to_matrix(&self) -> [[f64; 4]; 4] {
+        let mut matrix = [[0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+        for (i, row) in matrix.iter_mut().take(3).enumerate() {
+            for (j, value) in row.iter_mut().take(3).enumerate() {
+                *value = self.scale * self.rotation[i][j];
+            }
+            row[3] = self.translation[i];
+        }
+        matrix
+    }
+}
+
+/// Parse a 4x4 matrix given inline as 16 numbers (row-major, separated by commas or
+/// whitespace) or as the path of a text file holding them
+pub fn parse_matrix(value: &str) -> Result<Vec<f64>> {
+    let parse = |text: &str| -> Option<Vec<f64>> {
+        text.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<f64>().ok())
+            .collect()
+    };
+    if let Some(values) = parse(value) {
+        return Ok(values);
+    }
+    let text = std::fs::read_to_string(value).map_err(|_| HylaeanError::InvalidPath {
+        path: value.to_string(),
+    })?;
+    parse(&text).ok_or_else(|| invalid(format!("{} does not contain a numeric matrix", value)))
+}
+
+/// Apply `transform` to every Gaussian: position, normal, orientation, scale and SH
+pub fn transform_cloud(cloud: &mut GaussianCloud, transform: &Similarity) {
+    let q = transform.quaternion();
+    let log_scale = transform.scale.ln() as f32;
+    let sh_rotation = ShRotation::new(&transform.rotation, cloud.sh_degree);
+
+    for gaussian in &mut cloud.gaussians {
+        gaussian.position = transform.transform_point(gaussian.position.map(f64::from)).map(|v| v as f32);
+        gaussian.normal = transform.rotate(gaussian.normal.map(f64::from)).map(|v| v as f32);
+        let rotation = math::quaternion_multiply(q, gaussian.rotation.map(f64::from));
+        gaussian.rotation = rotation.map(|v| v as f32);
+        for scale in &mut gaussian.scale {
+            *scale += log_scale;
+        }
+        sh_rotation.apply(&mut gaussian.sh_rest);
+    }
+}
+
+/// Stream a plain point cloud through `transform`: positions are transformed and
+/// `nx ny nz` normals rotated. Returns the number of points written.
+pub fn transform_points(
+    input_path: &Path,
+    from_format: &PointCloudFormat,
+    output_path: &Path,
+    to_format: &PointCloudFormat,
+    transform: &Similarity,
+) -> Result<usize> {
+    let mut source = stream::open_source(input_path, from_format)?;
+    let options = SinkOptions::matching(source.as_ref());
+    let schema = source.schema().clone();
+    let column = |name: &str| schema.iter().position(|(key, _)| key == name);
+    let columns = |names: [&str; 3]| -> Option<[usize; 3]> {
+        Some([column(names[0])?, column(names[1])?, column(names[2])?])
+    };
+    let positions = columns(["x", "y", "z"]).ok_or_else(|| HylaeanError::UnsupportedFormat {
+        format: "point cloud without x y z".to_string(),
+    })?;
+    let normals = columns(["nx", "ny", "nz"]);
+
+    let sink = stream::create_sink(output_path, to_format, source.as_ref(), options)?;
+    stream::pump_map(source.as_mut(), sink, stream::DEFAULT_CHUNK_SIZE, |chunk| {
+        for record in chunk.records_mut() {
+            let read = |record: &ply::PlyRecord, columns: [usize; 3]| {
+                columns.map(|column| ply::scalar_as_f64(&record.0[column]).unwrap_or(0.0))
+            };
+            let moved = transform.transform_point(read(record, positions));
+            for (column, value) in positions.iter().zip(moved) {
+                record.0[*column] = ply::scalar_from_f64(&schema[*column].1, value);
+            }
+            if let Some(normals) = normals {
+                let rotated = transform.rotate(read(record, normals));
+                for (column, value) in normals.iter().zip(rotated) {
+                    record.0[*column] = ply::scalar_from_f64(&schema[*column].1, value);
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Per-band rotation matrices for the real SH basis used by 3DGS (bands 1..=degree)
+pub struct ShRotation {
+    bands: Vec<Vec<Vec<f64>>>,
+}
+
+impl ShRotation {
+    pub fn new(rotation: &Matrix3, sh_degree: u32) -> Self {
+        // A band is closed under rotation, so fitting its coefficients on sample directions
+        // is exact: find M with Y(x) M c = Y(R^T x) c for every direction x.
+        let directions = fibonacci_sphere(64);
+        let inverse = math::transpose(rotation);
+        let bands = (1..=sh_degree as usize)
+            .map(|band| {
+                let basis: Vec<Vec<f64>> = directions.iter().map(|d| sh_band(band, *d)).collect();
+                let rotated: Vec<Vec<f64>> = directions.iter().map(|d| sh_band(band, math::apply(&inverse, *d))).collect();
+                least_squares(&basis, &rotated)
+            })
+            .collect();
+        Self { bands }
+    }
+
+    /// Rotate `f_rest` coefficients in 3DGS layout: each color channel's coefficients
+    /// for bands 1.. in order, channel after channel
+    pub fn apply(&self, sh_rest: &mut [f32]) {
+        let per_channel = sh_rest.len() / 3;
+        for channel in sh_rest.chunks_mut(per_channel.max(1)) {
+            let mut offset = 0;
+            for matrix in &self.bands {
+                let size = matrix.len();
+                if offset + size > channel.len() {
+                    break;
+                }
+                let coefficients: Vec<f64> = channel[offset..offset + size].iter().map(|v| *v as f64).collect();
+                for (row, out) in matrix.iter().zip(&mut channel[offset..offset + size]) {
+                    *out = row.iter().zip(&coefficients).map(|(m, c)| m * c).sum::<f64>() as f32;
+                }
+                offset += size;
+            }
+        }
+    }
+}
+
+/// Real SH basis functions of one band, with the signs and constants of the 3DGS renderer.
+/// Bands past 3, which the renderer never evaluates, follow the same convention.
+pub fn sh_band(band: usize, direction: [f64; 3]) -> Vec<f64> {
+    let [x, y, z] = direction;
+    let (xx, yy, zz) = (x * x, y * y, z * z);
+    match band {
+        1 => {
+            let c1 = 0.488_602_511_902_919_9;
+            vec![-c1 * y, c1 * z, -c1 * x]
+        }
+        2 => vec![
+            1.092_548_430_592_079_2 * x * y,
+            -1.092_548_430_592_079_2 * y * z,
+            0.315_391_565_252_520_05 * (2.0 * zz - xx - yy),
+            -1.092_548_430_592_079_2 * x * z,
+            0.546_274_215_296_039_6 * (xx - yy),
+        ],
+        3 => vec![
+            -0.590_043_589_926_643_5 * y * (3.0 * xx - yy),
+            2.890_611_442_640_554 * x * y * z,
+            -0.457_045_799_464_465_8 * y * (4.0 * zz - xx - yy),
+            0.373_176_332_590_115_4 * z * (2.0 * zz - 3.0 * xx - 3.0 * yy),
+            -0.457_045_799_464_465_8 * x * (4.0 * zz - xx - yy),
+            1.445_305_721_320_277 * z * (xx - yy),
+            -0.590_043_589_926_643_5 * x * (xx - 3.0 * yy),
+        ],
+        _ => real_sh_band(band, direction),
+    }
+}
+
+/// Real SH of any band, ordered `m = -l..=l`, with the Condon-Shortley phase the
+/// hand-written bands above carry
+fn real_sh_band(band: usize, direction: [f64; 3]) -> Vec<f64> {
+    let [x, y, z] = direction;
+    let l = band as i64;
+    (-l..=l)
+        .map(|m| {
+            let order = m.unsigned_abs() as usize;
+            // sin^m(theta) * (cos(m phi), sin(m phi)) as the real and imaginary parts of (x + iy)^m
+            let (mut re, mut im) = (1.0, 0.0);
+            for _ in 0..order {
+                (re, im) = (re * x - im * y, re * y + im * x);
+            }
+            // The associated Legendre polynomial without its sin^m(theta) factor, by upward recurrence
+            let mut previous = 0.0;
+            let mut current = (1..=order).map(|k| (2 * k - 1) as f64).product::<f64>();
+            for degree in order + 1..=band {
+                let next = ((2 * degree - 1) as f64 * z * current - (degree + order - 1) as f64 * previous)
+                    / (degree - order) as f64;
+                (previous, current) = (current, next);
+            }
+            let factorial_ratio: f64 = (band - order + 1..=band + order).map(|k| k as f64).product();
+            let normalization = ((2 * band + 1) as f64 / (4.0 * std::f64::consts::PI) / factorial_ratio).sqrt();
+            let phase = if order % 2 == 1 { -1.0 } else { 1.0 };
+            match m {
+                0 => normalization * current,
+                m if m > 0 => std::f64::consts::SQRT_2 * phase * normalization * current * re,
+                _ => std::f64::consts::SQRT_2 * phase * normalization * current * im,
+            }
+        })
+        .collect()
+}
+
+/// Evenly spread unit vectors
+fn fibonacci_sphere(count: usize) -> Vec<[f64; 3]> {
+    let golden = std::f64::consts::PI * (3.0 - 5f64.sqrt());
+    (0..count)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f64 + 0.5) / count as f64;
+            let radius = (1.0 - y * y).sqrt();
+            let theta = golden * i as f64;
+            [radius * theta.cos(), y, radius * theta.sin()]
+        })
+        .collect()
+}
+
+/// Solve `a * x = b` in the least-squares sense via the normal equations (`a` is n x m)
+fn least_squares(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let m = a[0].len();
+    let mut ata = vec![vec![0.0; m]; m];
+    let mut atb = vec![vec![0.0; m]; m];
+    for (row_a, row_b) in a.iter().zip(b) {
+        for i in 0..m {
+            for j in 0..m {
+                ata[i][j] += row_a[i] * row_a[j];
+                atb[i][j] += row_a[i] * row_b[j];
+            }
+        }
+    }
+
+    // Gauss-Jordan elimination with partial pivoting on [ata | atb]
+    for col in 0..m {
+        let pivot = (col..m).max_by(|r, s| ata[*r][col].abs().total_cmp(&ata[*s][col].abs())).unwrap_or(col);
+        ata.swap(col, pivot);
+        atb.swap(col, pivot);
+        let diagonal = ata[col][col];
+        for j in 0..m {
+            ata[col][j] /= diagonal;
+            atb[col][j] /= diagonal;
+        }
+        for row in 0..m {
+            if row != col {
+                let factor = ata[row][col];
+                for j in 0..m {
+                    ata[row][j] -= factor * ata[col][j];
+                    atb[row][j] -= factor * atb[col][j];
+                }
+            }
+        }
+    }
+    atb
+}
+
+fn invalid(message: String) -> HylaeanError {
+    HylaeanError::ConfigError { message }
+}
@@ -0,0 +1,330 @@
+//! Niantic SPZ compressed Gaussian splat format
+//!
+//! An SPZ file is a gzip stream holding a 16-byte header followed by attribute planes:
+//! positions, alphas, colors, scales, rotations and SH. Versions 1-3 are read; version 2
+//! is written since every SPZ reader understands it.
+//!
+//! SPZ stores scenes in RUB axes while 3DGS PLY files use RDF, so both directions flip
+//! the Y and Z axes (positions, rotations and SH signs) like the reference implementation.
+
+use crate::errors::{Result, HylaeanError};
+use crate::formats::gaussian::{self, Gaussian, GaussianCloud};
+use crate::formats::splat::f16_to_f32;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+pub const SPZ_MAGIC: u32 = 0x5053_474e; // "NGSP"
+pub const SPZ_HEADER_SIZE: usize = 16;
+pub const SPZ_WRITE_VERSION: u32 = 2;
+
+const FLAG_ANTIALIASED: u8 = 0x1;
+const COLOR_SCALE: f32 = 0.15;
+const MAX_FRACTIONAL_BITS: u8 = 12;
+const SH1_BUCKET: i32 = 1 << 3;
+const SH_REST_BUCKET: i32 = 1 << 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpzHeader {
+    pub version: u32,
+    pub num_points: u32,
+    pub sh_degree: u8,
+    pub fractional_bits: u8,
+    pub flags: u8,
+}
+
+impl SpzHeader {
+    pub fn antialiased(&self) -> bool {
+        self.flags & FLAG_ANTIALIASED != 0
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < SPZ_HEADER_SIZE {
+            return Err(invalid("file is shorter than the SPZ header"));
+        }
+        let word = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+        if word(0) != SPZ_MAGIC {
+            return Err(invalid("bad magic number"));
+        }
+        let header = Self {
+            version: word(4),
+            num_points: word(8),
+            sh_degree: bytes[12],
+            fractional_bits: bytes[13],
+            flags: bytes[14],
+        };
+        if !(1..=3).contains(&header.version) {
+            return Err(invalid(&format!("unsupported version {}", header.version)));
+        }
+        if header.sh_degree > 3 {
+            return Err(invalid(&format!("unsupported SH degree {}", header.sh_degree)));
+        }
+        Ok(header)
+    }
+
+    fn to_bytes(&self) -> [u8; SPZ_HEADER_SIZE] {
+        let mut bytes = [0u8; SPZ_HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&SPZ_MAGIC.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.version.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.num_points.to_le_bytes());
+        bytes[12] = self.sh_degree;
+        bytes[13] = self.fractional_bits;
+        bytes[14] = self.flags;
+        bytes
+    }
+
+    fn position_bytes(&self) -> usize {
+        if self.version == 1 { 6 } else { 9 }
+    }
+
+    fn rotation_bytes(&self) -> usize {
+        if self.version >= 3 { 4 } else { 3 }
+    }
+}
+
+/// Read only the header, inflating just its 16 bytes
+pub fn read_header(path: &Path) -> Result<SpzHeader> {
+    let mut prefix = Vec::with_capacity(SPZ_HEADER_SIZE);
+    GzDecoder::new(BufReader::new(File::open(path)?))
+        .take(SPZ_HEADER_SIZE as u64)
+        .read_to_end(&mut prefix)?;
+    SpzHeader::parse(&prefix)
+}
+
+pub fn read_spz(path: &Path) -> Result<GaussianCloud> {
+    debug!("Reading SPZ: {}", path.display());
+
+    let mut data = Vec::new();
+    GzDecoder::new(BufReader::new(File::open(path)?)).read_to_end(&mut data)?;
+    let header = SpzHeader::parse(&data)?;
+
+    let n = header.num_points as usize;
+    let sh_dim = sh_dim(header.sh_degree as u32);
+    let expected = SPZ_HEADER_SIZE
+        + n * (header.position_bytes() + 1 + 3 + 3 + header.rotation_bytes() + sh_dim * 3);
+    if data.len() < expected {
+        return Err(invalid(&format!("payload holds {} bytes, {} expected", data.len(), expected)));
+    }
+
+    let mut offset = SPZ_HEADER_SIZE;
+    let mut plane = |size: usize| {
+        let slice = &data[offset..offset + n * size];
+        offset += n * size;
+        slice
+    };
+    let positions = plane(header.position_bytes());
+    let alphas = plane(1);
+    let colors = plane(3);
+    let scales = plane(3);
+    let rotations = plane(header.rotation_bytes());
+    let sh = plane(sh_dim * 3);
+
+    let mut cloud = GaussianCloud::new(header.sh_degree as u32);
+    cloud.has_normals = false;
+    cloud.gaussians.reserve(n);
+    let scale = 1.0 / (1u32 << header.fractional_bits) as f32;
+
+    for i in 0..n {
+        let mut g = Gaussian::new(cloud.sh_degree);
+
+        for axis in 0..3 {
+            g.position[axis] = if header.version == 1 {
+                let b = &positions[i * 6 + axis * 2..];
+                f16_to_f32(u16::from_le_bytes([b[0], b[1]]))
+            } else {
+                let b = &positions[i * 9 + axis * 3..];
+                let mut fixed = b[0] as i32 | (b[1] as i32) << 8 | (b[2] as i32) << 16;
+                if fixed & 0x80_0000 != 0 {
+                    fixed |= !0xff_ffff;
+                }
+                fixed as f32 * scale
+            };
+        }
+
+        g.opacity = gaussian::inverse_sigmoid(alphas[i] as f32 / 255.0);
+        for c in 0..3 {
+            g.sh_dc[c] = (colors[i * 3 + c] as f32 / 255.0 - 0.5) / COLOR_SCALE;
+            g.scale[c] = scales[i * 3 + c] as f32 / 16.0 - 10.0;
+        }
+
+        let [x, y, z, w] = if header.version >= 3 {
+            unpack_smallest_three(&rotations[i * 4..i * 4 + 4])
+        } else {
+            let r = &rotations[i * 3..i * 3 + 3];
+            let xyz = [r[0], r[1], r[2]].map(|v| v as f32 / 127.5 - 1.0);
+            let w = (1.0 - xyz.iter().map(|v| v * v).sum::<f32>()).max(0.0).sqrt();
+            [xyz[0], xyz[1], xyz[2], w]
+        };
+        g.rotation = [w, x, y, z];
+
+        // SPZ interleaves channels per coefficient; PLY keeps each channel contiguous
+        for k in 0..sh_dim {
+            for c in 0..3 {
+                let v = sh[(i * sh_dim + k) * 3 + c];
+                g.sh_rest[c * sh_dim + k] = (v as f32 - 128.0) / 128.0;
+            }
+        }
+
+        flip_rub_rdf(&mut g, sh_dim);
+        cloud.gaussians.push(g);
+    }
+
+    info!(
+        "Read {} Gaussians from SPZ v{} (SH degree {})",
+        cloud.len(), header.version, header.sh_degree
+    );
+    Ok(cloud)
+}
+
+pub fn write_spz(cloud: &GaussianCloud, path: &Path) -> Result<()> {
+    debug!("Writing SPZ: {}", path.display());
+
+    if cloud.sh_degree > 3 {
+        return Err(invalid(&format!("SH degree {} exceeds the SPZ maximum of 3", cloud.sh_degree)));
+    }
+
+    let n = cloud.len();
+    let sh_dim = sh_dim(cloud.sh_degree);
+    let header = SpzHeader {
+        version: SPZ_WRITE_VERSION,
+        num_points: n as u32,
+        sh_degree: cloud.sh_degree as u8,
+        fractional_bits: fractional_bits_for(cloud),
+        flags: 0,
+    };
+
+    let mut positions = Vec::with_capacity(n * 9);
+    let mut alphas = Vec::with_capacity(n);
+    let mut colors = Vec::with_capacity(n * 3);
+    let mut scales = Vec::with_capacity(n * 3);
+    let mut rotations = Vec::with_capacity(n * 3);
+    let mut sh = Vec::with_capacity(n * sh_dim * 3);
+    let fixed_scale = (1u32 << header.fractional_bits) as f32;
+
+    for source in &cloud.gaussians {
+        let mut g = source.clone();
+        flip_rub_rdf(&mut g, sh_dim);
+
+        for v in g.position {
+            let fixed = (v * fixed_scale).round() as i32;
+            positions.extend_from_slice(&fixed.to_le_bytes()[..3]);
+        }
+        alphas.push(to_u8(g.alpha() * 255.0));
+        for c in 0..3 {
+            colors.push(to_u8(g.sh_dc[c] * (COLOR_SCALE * 255.0) + 0.5 * 255.0));
+            scales.push(to_u8((g.scale[c] + 10.0) * 16.0));
+        }
+
+        let [w, x, y, z] = g.rotation;
+        let norm = (w * w + x * x + y * y + z * z).sqrt();
+        let sign = if w < 0.0 { -1.0 } else { 1.0 };
+        let unit = if norm > 0.0 { sign / norm } else { 0.0 };
+        for v in [x, y, z] {
+            rotations.push(to_u8(v * unit * 127.5 + 127.5));
+        }
+
+        for k in 0..sh_dim {
+            // Degree-1 coefficients keep 5 bits, higher bands 4
+            let bucket = if k < 3 { SH1_BUCKET } else { SH_REST_BUCKET };
+            for c in 0..3 {
+                sh.push(quantize_sh(g.sh_rest[c * sh_dim + k], bucket));
+            }
+        }
+    }
+
+    let mut data = Vec::with_capacity(SPZ_HEADER_SIZE + n * (19 + sh_dim * 3));
+    data.extend_from_slice(&header.to_bytes());
+    for plane in [&positions, &alphas, &colors, &scales, &rotations, &sh] {
+        data.extend_from_slice(plane);
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&data)?;
+    let compressed = encoder.finish()?;
+    std::fs::write(path, &compressed)?;
+
+    info!(
+        "Wrote {} Gaussians to SPZ ({} bytes, {:.1}x smaller than raw float PLY)",
+        n,
+        compressed.len(),
+        (n * 4 * cloud.property_names().len()) as f64 / compressed.len().max(1) as f64
+    );
+    Ok(())
+}
+
+/// Coefficients per color channel beyond the DC term
+fn sh_dim(sh_degree: u32) -> usize {
+    gaussian::sh_rest_len(sh_degree) / 3
+}
+
+/// Use as many fractional bits as the scene extent allows within 24-bit fixed point
+fn fractional_bits_for(cloud: &GaussianCloud) -> u8 {
+    let extent = cloud.gaussians.iter()
+        .flat_map(|g| g.position)
+        .fold(0.0f32, |max, v| max.max(v.abs()));
+    let integer_bits = (extent + 1.0).log2().ceil().max(0.0) as u8;
+    23u8.saturating_sub(integer_bits).min(MAX_FRACTIONAL_BITS)
+}
+
+fn to_u8(v: f32) -> u8 {
+    v.round().clamp(0.0, 255.0) as u8
+}
+
+fn quantize_sh(v: f32, bucket: i32) -> u8 {
+    let q = (v * 128.0).round() as i32 + 128;
+    let q = (q + bucket / 2) / bucket * bucket;
+    q.clamp(0, 255) as u8
+}
+
+fn unpack_smallest_three(bytes: &[u8]) -> [f32; 4] {
+    const MASK: u32 = (1 << 9) - 1;
+    let mut comp = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let largest = (comp >> 30) as usize;
+    let mut rotation = [0.0f32; 4];
+    let mut sum_squares = 0.0;
+
+    for i in (0..4).rev() {
+        if i == largest {
+            continue;
+        }
+        let magnitude = comp & MASK;
+        let negative = (comp >> 9) & 1 == 1;
+        comp >>= 10;
+        let v = std::f32::consts::FRAC_1_SQRT_2 * magnitude as f32 / MASK as f32;
+        rotation[i] = if negative { -v } else { v };
+        sum_squares += v * v;
+    }
+    rotation[largest] = (1.0 - sum_squares).max(0.0).sqrt();
+    rotation
+}
+
+/// Convert between RDF and RUB axes by negating Y and Z (a 180 degree turn about X)
+fn flip_rub_rdf(g: &mut Gaussian, sh_dim: usize) {
+    // Sign of each real SH basis function under (x, y, z) -> (x, -y, -z), bands 1-3
+    const SH_FLIP: [f32; 15] = [
+        -1.0, -1.0, 1.0,
+        -1.0, 1.0, 1.0, -1.0, 1.0,
+        -1.0, 1.0, -1.0, -1.0, 1.0, -1.0, 1.0,
+    ];
+
+    g.position[1] = -g.position[1];
+    g.position[2] = -g.position[2];
+    g.rotation[2] = -g.rotation[2];
+    g.rotation[3] = -g.rotation[3];
+    for c in 0..3 {
+        for (k, sign) in SH_FLIP.iter().enumerate().take(sh_dim) {
+            g.sh_rest[c * sh_dim + k] *= sign;
+        }
+    }
+}
+
+fn invalid(message: &str) -> HylaeanError {
+    HylaeanError::UnsupportedFormat {
+        format: format!("SPZ: {}", message),
+    }
+}
@@ -131,6 +131,74 @@ bpy.ops.render.render(write_still=True)
         
         Ok(())
     }
+    
+    /// Create camera objects from a Blender camera JSON (see `hylaeansplat convert
+    /// --output-format blender`) and save them to a .blend file
+    pub fn import_cameras(&self, cameras_file: &str, blend_file: &str) -> Result<()> {
+        let blender = self.blender_executable.as_ref()
+            .ok_or_else(|| HylaeanError::ToolNotFound {
+                name: "blender".to_string(),
+            })?;
+        
+        info!("Importing cameras into Blender...");
+        
+        let import_script = format!(
+            r#"
+import bpy
+import json
+from mathutils import Matrix
+
+with open('{}') as f:
+    cameras = json.load(f)['cameras']
+
+scene = bpy.context.scene
+for entry in cameras:
+    data = bpy.data.cameras.new(entry['name'])
+    data.lens = entry['lens']
+    data.sensor_width = entry.get('sensor_width', 36.0)
+    data.sensor_height = entry.get('sensor_height', 24.0)
+    data.sensor_fit = entry.get('sensor_fit', 'AUTO')
+    data.shift_x = entry.get('shift_x', 0.0)
+    data.shift_y = entry.get('shift_y', 0.0)
+    camera = bpy.data.objects.new(entry['name'], data)
+    camera.matrix_world = Matrix(entry['matrix_world'])
+    scene.collection.objects.link(camera)
+
+# The render settings are per scene; they follow the first camera
+if cameras:
+    scene.camera = bpy.data.objects[cameras[0]['name']]
+    scene.render.resolution_x = cameras[0]['resolution_x']
+    scene.render.resolution_y = cameras[0]['resolution_y']
+    scene.render.resolution_percentage = 100
+    scene.render.pixel_aspect_x = cameras[0].get('pixel_aspect_x', 1.0)
+    scene.render.pixel_aspect_y = cameras[0].get('pixel_aspect_y', 1.0)
+
+bpy.ops.wm.save_as_mainfile(filepath='{}')
+"#,
+            cameras_file,
+            blend_file
+        );
+        
+        let script_path = std::env::temp_dir().join("import_cameras_skysplat.py");
+        std::fs::write(&script_path, import_script)?;
+        
+        let args = vec![
+            "--background".to_string(),
+            "--python".to_string(),
+            script_path.to_string_lossy().to_string(),
+        ];
+        
+        let output = run_command_with_output(
+            &blender.to_string_lossy(),
+            &args,
+            None,
+        )?;
+        
+        info!("Cameras imported into {}", blend_file);
+        debug!("Import output: {}", output);
+        
+        Ok(())
+    }
 }
 
 impl Integration for SkySplat {
@@ -170,6 +238,16 @@ impl Integration for SkySplat {
                     })
                 }
             }
+            "cameras" => {
+                if args.len() >= 2 {
+                    self.import_cameras(&args[0], &args[1])
+                } else {
+                    Err(HylaeanError::ToolExecutionFailed {
+                        tool: self.name().to_string(),
+                        message: "cameras command requires cameras_file and blend_file".to_string(),
+                    })
+                }
+            }
             _ => Err(HylaeanError::ToolExecutionFailed {
                 tool: self.name().to_string(),
                 message: format!("Unknown command: {}", command),
@@ -181,6 +259,7 @@ impl Integration for SkySplat {
         vec![
             "install".to_string(),
             "render".to_string(),
+            "cameras".to_string(),
         ]
     }
     
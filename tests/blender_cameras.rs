@@ -0,0 +1,124 @@
+//! Blender camera JSON: lens, sensor fit, shift and pixel aspect against COLMAP intrinsics,
+//! and matrix_world against NeRF and COLMAP poses.
+
+mod common;
+
+use hylaean_splat::formats::camera_params::colmap::{self, ColmapCamera, ColmapImage, ModelEncoding};
+use hylaean_splat::formats::camera_params::{BlenderCamera, BlenderCameras, CameraModel, NeRFCamera, Pose};
+use hylaean_splat::formats::{detect_format, CameraFormat, DataFormat};
+use common::{assert_relative, convert, scratch};
+
+fn camera(sensor_fit: &str, width: u32, height: u32) -> BlenderCamera {
+    BlenderCamera {
+        name: "Camera".to_string(),
+        image: None,
+        matrix_world: Pose::default().to_matrix(),
+        lens: 50.0,
+        sensor_width: 36.0,
+        sensor_height: 24.0,
+        sensor_fit: sensor_fit.to_string(),
+        shift_x: 0.0,
+        shift_y: 0.0,
+        resolution_x: width,
+        resolution_y: height,
+        pixel_aspect_x: 1.0,
+        pixel_aspect_y: 1.0,
+    }
+}
+
+#[test]
+fn sensor_fit_follows_blender() {
+    // AUTO fits the sensor width to the larger image dimension
+    let landscape = camera("AUTO", 1920, 1080).to_model().unwrap();
+    assert_eq!(landscape, CameraModel::Pinhole { fx: 50.0 * 1920.0 / 36.0, fy: 50.0 * 1920.0 / 36.0, cx: 960.0, cy: 540.0 });
+    let portrait = camera("AUTO", 1080, 1920).to_model().unwrap();
+    assert_relative(&portrait.focal_length(), &[50.0 * 1920.0 / 36.0; 2], 1e-9, "AUTO portrait");
+    let vertical = camera("VERTICAL", 1920, 1080).to_model().unwrap();
+    assert_relative(&vertical.focal_length(), &[50.0 * 1080.0 / 24.0; 2], 1e-9, "VERTICAL");
+
+    // Shifts are fractions of the fitted dimension; positive shift_y moves the image up
+    let mut shifted = camera("HORIZONTAL", 1000, 500);
+    (shifted.shift_x, shifted.shift_y) = (0.1, 0.05);
+    assert_relative(&shifted.to_model().unwrap().principal_point(), &[400.0, 300.0], 1e-9, "shifted principal point");
+
+    assert!(camera("DIAGONAL", 10, 10).to_model().is_err());
+}
+
+#[test]
+fn colmap_cameras_round_trip_through_blender() {
+    let dir = scratch("colmap");
+    let model_dir = dir.join("sparse");
+    std::fs::create_dir_all(&model_dir).unwrap();
+
+    let models = [
+        CameraModel::Pinhole { fx: 1450.0, fy: 1460.5, cx: 970.25, cy: 530.75 },
+        CameraModel::SimplePinhole { f: 800.0, cx: 400.0, cy: 300.0 },
+    ];
+    let cameras = vec![ColmapCamera::from_model(1, 1920, 1080, &models[0]), ColmapCamera::from_model(2, 800, 600, &models[1])];
+    let images = vec![
+        ColmapImage::new(1, 1, "shots/a.jpg".to_string(), &Pose::from_quaternion([0.9, 0.1, -0.3, 0.2], [1.0, -2.0, 3.5])),
+        ColmapImage::new(2, 2, "shots/b.jpg".to_string(), &Pose::from_quaternion([0.2, 0.7, 0.1, -0.4], [-4.0, 0.5, 2.0])),
+    ];
+    colmap::write_cameras(&model_dir, &cameras, ModelEncoding::Text).unwrap();
+    colmap::write_images(&model_dir, &images, ModelEncoding::Text).unwrap();
+
+    let cameras_json = dir.join("cameras.json");
+    convert(&model_dir, &cameras_json, CameraFormat::COLMAP, CameraFormat::Blender);
+    assert!(matches!(detect_format(&cameras_json).unwrap(), DataFormat::CameraParameters(CameraFormat::Blender)));
+    let blender = BlenderCameras::read(&cameras_json).unwrap();
+    assert_eq!(blender.cameras[0].name, "a");
+    assert_relative(&[blender.cameras[1].lens], &[36.0], 1e-9, "lens of an 800 px focal length on an 800 px wide 36 mm sensor");
+
+    // matrix_world holds the camera center and looks along -Z
+    let first = Pose::from_matrix(&blender.cameras[0].matrix_world);
+    assert_relative(&first.translation, &images[0].camera_to_world().translation, 1e-9, "camera center");
+    let forward = [0, 1, 2].map(|i| -first.rotation[i][2]);
+    let expected = [0, 1, 2].map(|i| images[0].camera_to_world().rotation[i][2]);
+    assert_relative(&forward, &expected, 1e-9, "viewing direction");
+
+    let back_dir = dir.join("back");
+    convert(&cameras_json, &back_dir, CameraFormat::Blender, CameraFormat::COLMAP);
+    let back_cameras = colmap::read_cameras(&back_dir).unwrap();
+    let back_images = colmap::read_images(&back_dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let model = back_cameras[0].camera_model().unwrap();
+    assert_relative(&[model.focal_length(), model.principal_point()].concat(), &[1450.0, 1460.5, 970.25, 530.75], 1e-9, "PINHOLE");
+    assert_relative(&back_cameras[1].camera_model().unwrap().focal_length(), &[800.0, 800.0], 1e-9, "SIMPLE_PINHOLE");
+    for (back, original) in back_images.iter().zip(&images) {
+        assert_eq!(back.name, original.name);
+        let (back, original) = (back.world_to_camera(), original.world_to_camera());
+        assert_relative(back.rotation.as_flattened(), original.rotation.as_flattened(), 1e-9, "rotation");
+        assert_relative(&back.translation, &original.translation, 1e-9, "translation");
+    }
+}
+
+#[test]
+fn nerf_synthetic_matrices_are_blender_matrices() {
+    let dir = scratch("nerf");
+    let transform = [[-0.9999, 0.0042, -0.0134, -0.0538], [-0.0140, -0.2997, 0.9539, 3.8455], [0.0, 0.9540, 0.2997, 1.2081], [0.0, 0.0, 0.0, 1.0]];
+    let transforms = serde_json::json!({
+        "camera_angle_x": 0.6911112070083618,
+        "w": 800,
+        "h": 800,
+        "frames": [{"file_path": "./train/r_0", "rotation": 0.012566370614359171, "transform_matrix": transform}]
+    });
+    let input = dir.join("transforms_train.json");
+    std::fs::write(&input, transforms.to_string()).unwrap();
+
+    let cameras_json = dir.join("cameras.json");
+    convert(&input, &cameras_json, CameraFormat::NeRF, CameraFormat::Blender);
+    let blender = BlenderCameras::read(&cameras_json).unwrap();
+    let nerf_back = dir.join("back.json");
+    convert(&cameras_json, &nerf_back, CameraFormat::Blender, CameraFormat::NeRF);
+    let back = NeRFCamera::read(&nerf_back).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // The synthetic scenes were rendered with a 50 mm lens on Blender's 36 mm sensor
+    assert_relative(&[blender.cameras[0].lens], &[18.0 / (0.6911112070083618f64 / 2.0).tan()], 1e-9, "lens");
+    assert!((blender.cameras[0].lens - 50.0).abs() < 0.01);
+    let expected = Pose::from_matrix(&transform).to_matrix();
+    assert_relative(blender.cameras[0].matrix_world.as_flattened(), expected.as_flattened(), 1e-9, "matrix_world");
+    assert_relative(back.frames[0].transform_matrix.as_flattened(), expected.as_flattened(), 1e-9, "NeRF round trip");
+    assert_relative(&[back.camera_angle_x.unwrap()], &[0.6911112070083618], 1e-9, "camera_angle_x");
+}
@@ -0,0 +1,157 @@
+//! Bundler `.out` and AliceVision `.sfm` imports: each format's camera axes, image
+//! coordinates and intrinsics, checked by reprojecting points through the COLMAP model.
+
+mod common;
+
+use hylaean_splat::formats::camera_params::colmap::{self, ColmapCamera, ColmapImage, ColmapPoint3D};
+use hylaean_splat::formats::camera_params::{BundlerCamera, CameraModel, NeRFCamera, Pose, NERFSTUDIO_PLY_FILE};
+use hylaean_splat::formats::{detect_format, CameraFormat, DataFormat};
+use common::{assert_close, convert, png_header, scratch};
+
+/// Pixel position of a world point in a COLMAP model, with RADIAL or OPENCV distortion
+fn colmap_project(camera: &ColmapCamera, image: &ColmapImage, point: [f64; 3]) -> [f64; 2] {
+    let p = image.world_to_camera().transform_point(point);
+    let (u, v) = (p[0] / p[2], p[1] / p[2]);
+    let r2 = u * u + v * v;
+    let (fx, fy, cx, cy, k1, k2) = match camera.camera_model().unwrap() {
+        CameraModel::Radial { f, cx, cy, k1, k2 } => (f, f, cx, cy, k1, k2),
+        CameraModel::OpenCv { fx, fy, cx, cy, k1, k2, .. } => (fx, fy, cx, cy, k1, k2),
+        other => panic!("unexpected camera {:?}", other),
+    };
+    let radial = 1.0 + k1 * r2 + k2 * r2 * r2;
+    [fx * radial * u + cx, fy * radial * v + cy]
+}
+
+/// Every observation of every point lands where the COLMAP model projects it
+fn assert_tracks_reproject(cameras: &[ColmapCamera], images: &[ColmapImage], points: &[ColmapPoint3D]) {
+    for point in points {
+        for element in &point.track {
+            let image = images.iter().find(|image| image.image_id == element.image_id).unwrap();
+            let camera = cameras.iter().find(|camera| camera.camera_id == image.camera_id).unwrap();
+            let observed = &image.points2d[element.point2d_idx as usize];
+            assert_eq!(observed.point3d_id, point.point3d_id as i64);
+            assert_close(&colmap_project(camera, image, point.xyz), &[observed.x, observed.y], 1e-6, "reprojection");
+        }
+    }
+}
+
+#[test]
+fn bundler_reprojects_through_colmap() {
+    let dir = scratch("out");
+    std::fs::create_dir_all(dir.join("bundle")).unwrap();
+    std::fs::create_dir_all(dir.join("images")).unwrap();
+    std::fs::write(dir.join("images/a.png"), png_header(640, 480)).unwrap();
+    std::fs::write(dir.join("images/c.png"), png_header(800, 600)).unwrap();
+    std::fs::write(dir.join("list.txt"), "images/a.png 0 500\nimages/b.png\nimages/c.png\n").unwrap();
+
+    // Bundler cameras look along -z; these look at the points near the origin from +z
+    let cameras = [
+        BundlerCamera { focal: 500.0, k1: -0.05, k2: 0.01, world_to_camera: Pose::new([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], [0.1, -0.2, -5.0]) },
+        BundlerCamera { focal: 0.0, k1: 0.0, k2: 0.0, world_to_camera: Pose::default() },
+        BundlerCamera { focal: 650.0, k1: 0.02, k2: 0.0, world_to_camera: Pose::from_quaternion([0.98, 0.1, -0.15, 0.05], [0.3, 0.2, -6.0]) },
+    ];
+    let positions = [[0.2, 0.3, -0.1], [-0.5, 0.1, 0.4]];
+    let mut bundle = format!("# Bundle file v0.3\n{} {}\n", cameras.len(), positions.len());
+    for camera in &cameras {
+        bundle += &format!("{} {} {}\n", camera.focal, camera.k1, camera.k2);
+        for row in camera.world_to_camera.rotation {
+            bundle += &format!("{} {} {}\n", row[0], row[1], row[2]);
+        }
+        let t = camera.world_to_camera.translation;
+        bundle += &format!("{} {} {}\n", t[0], t[1], t[2]);
+    }
+    for (i, position) in positions.iter().enumerate() {
+        bundle += &format!("{} {} {}\n{} 128 64\n2", position[0], position[1], position[2], 10 * i);
+        for camera in [0, 2] {
+            let [x, y] = cameras[camera].project(*position);
+            bundle += &format!(" {} {} {} {}", camera, i, x, y);
+        }
+        bundle += "\n";
+    }
+    let input = dir.join("bundle/bundle.out");
+    std::fs::write(&input, bundle).unwrap();
+    assert!(matches!(detect_format(&input).unwrap(), DataFormat::CameraParameters(CameraFormat::Bundler)));
+
+    let model_dir = dir.join("sparse");
+    convert(&input, &model_dir, CameraFormat::Bundler, CameraFormat::COLMAP);
+    let transforms = dir.join("transforms.json");
+    convert(&input, &transforms, CameraFormat::Bundler, CameraFormat::NeRF);
+    let nerf = NeRFCamera::read(&transforms).unwrap();
+    let ply_written = dir.join(NERFSTUDIO_PLY_FILE).is_file();
+    let colmap_cameras = colmap::read_cameras(&model_dir).unwrap();
+    let images = colmap::read_images(&model_dir).unwrap();
+    let points = colmap::read_points3d(&model_dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(images.iter().map(|image| image.name.as_str()).collect::<Vec<_>>(), ["images/a.png", "images/c.png"]);
+    assert_eq!(colmap_cameras[1].camera_model().unwrap(), CameraModel::Radial { f: 650.0, cx: 400.0, cy: 300.0, k1: 0.02, k2: 0.0 });
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].rgb, [10, 128, 64]);
+    assert!(points[0].error < 1e-6);
+    assert_tracks_reproject(&colmap_cameras, &images, &points);
+
+    // The first camera sits at +5 z looking down -z; NeRF keeps its OpenGL axes
+    assert_close(&images[0].camera_to_world().translation, &[-0.1, 0.2, 5.0], 1e-9, "camera center");
+    assert_close(nerf.frames[0].transform_matrix.as_flattened(), &[1.0, 0.0, 0.0, -0.1, 0.0, 1.0, 0.0, 0.2, 0.0, 0.0, 1.0, 5.0], 1e-9, "transform_matrix");
+    assert!(ply_written);
+}
+
+#[test]
+fn alicevision_sfm_reprojects_through_colmap() {
+    let dir = scratch("sfm");
+    let world_to_camera = Pose::from_quaternion([0.9, -0.2, 0.3, 0.1], [0.0; 3]);
+    let center = [0.5, -1.0, -4.0];
+    let r = world_to_camera.rotation;
+    let translation = [0, 1, 2].map(|i| -(r[i][0] * center[0] + r[i][1] * center[1] + r[i][2] * center[2]));
+    let world_to_camera = Pose::new(r, translation);
+    // Eigen's column order, as strings
+    let rotation: Vec<String> = (0..3).flat_map(|c| (0..3).map(move |row| r[row][c].to_string())).collect();
+
+    // 1.2.2: focal length in mm on a 36 mm sensor, principal point offset from the center
+    let fx = 35.0 / 36.0 * 4000.0;
+    let camera = CameraModel::OpenCv { fx, fy: fx / 1.01, cx: 2012.5, cy: 1490.0, k1: -0.1, k2: 0.02, p1: 0.0, p2: 0.0 };
+    let colmap_camera = ColmapCamera::from_model(1, 4000, 3000, &camera);
+    let image = ColmapImage::new(1, 1, "x".to_string(), &world_to_camera);
+    let landmark = [0.3, 0.2, 1.0];
+    let [u, v] = colmap_project(&colmap_camera, &image, landmark);
+
+    let sfm = serde_json::json!({
+        "version": ["1", "2", "2"],
+        "views": [
+            {"viewId": "1001", "poseId": "1001", "intrinsicId": "77", "path": dir.join("images/IMG_1.JPG").to_string_lossy(), "width": "4000", "height": "3000"},
+            {"viewId": "1002", "poseId": "1002", "intrinsicId": "77", "path": "/elsewhere/IMG_2.JPG", "width": "4000", "height": "3000"}
+        ],
+        "intrinsics": [{
+            "intrinsicId": "77", "width": "4000", "height": "3000", "sensorWidth": "36", "sensorHeight": "24",
+            "type": "radial3", "focalLength": "35", "pixelRatio": "1.01", "principalPoint": ["12.5", "-10"],
+            "distortionParams": ["-0.1", "0.02", "0"], "locked": "0"
+        }],
+        "poses": [{"poseId": "1001", "pose": {"transform": {"rotation": rotation, "center": center.map(|c| c.to_string())}, "locked": "0"}}],
+        "structure": [{
+            "landmarkId": "0", "descType": "sift", "color": ["255", "10", "0"], "X": landmark.map(|c| c.to_string()),
+            "observations": [
+                {"observationId": "1001", "featureId": "3", "x": [u.to_string(), v.to_string()]},
+                {"observationId": "1002", "featureId": "9", "x": ["1", "2"]}
+            ]
+        }]
+    });
+    let input = dir.join("cameras.sfm");
+    std::fs::write(&input, sfm.to_string()).unwrap();
+    assert!(matches!(detect_format(&input).unwrap(), DataFormat::CameraParameters(CameraFormat::AliceVision)));
+
+    let model_dir = dir.join("sparse");
+    convert(&input, &model_dir, CameraFormat::AliceVision, CameraFormat::COLMAP);
+    let cameras = colmap::read_cameras(&model_dir).unwrap();
+    let images = colmap::read_images(&model_dir).unwrap();
+    let points = colmap::read_points3d(&model_dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(images.len(), 1, "views without a pose are skipped");
+    assert_eq!(images[0].name, "images/IMG_1.JPG");
+    let model = cameras[0].camera_model().unwrap();
+    assert_close(&[model.focal_length(), model.principal_point()].concat(), &[fx, fx / 1.01, 2012.5, 1490.0], 1e-9, "intrinsics");
+    assert_close(&images[0].camera_to_world().translation, &center, 1e-9, "camera center");
+    assert_eq!(points[0].track.len(), 1);
+    assert_eq!(points[0].rgb, [255, 10, 0]);
+    assert_tracks_reproject(&cameras, &images, &points);
+}
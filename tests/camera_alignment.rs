@@ -0,0 +1,136 @@
+//! Camera set alignment and normalization: Umeyama fits between reconstructions in
+//! different gauges, RANSAC against misregistered images, and nerfstudio-style scaling.
+
+mod common;
+
+use hylaean_splat::formats::camera_params::align::{self, AlignOptions, Ransac};
+use hylaean_splat::formats::camera_params::colmap::TrackElement;
+use hylaean_splat::formats::camera_params::{CameraModel, CameraParamsConverter, ColmapCamera, ColmapImage, ColmapPoint3D, Pose};
+use hylaean_splat::formats::splat_ops::transform::Similarity;
+use hylaean_splat::formats::CameraFormat;
+use common::{assert_close, scratch};
+
+/// Cameras on a rising arc around the origin, looking roughly inwards
+fn arc_images(count: usize) -> Vec<ColmapImage> {
+    (0..count).map(|i| {
+        let angle = i as f64 * 0.4;
+        let camera_to_world = Pose::from_quaternion(
+            [(angle / 2.0).cos(), 0.1, (angle / 2.0).sin(), 0.05],
+            [4.0 * angle.sin(), 0.3 * i as f64, -4.0 * angle.cos()],
+        );
+        ColmapImage::new(i as u32 + 1, 1, format!("frame_{:03}.jpg", i), &camera_to_world.inverse())
+    }).collect()
+}
+
+fn known_transform() -> Similarity {
+    Similarity {
+        scale: 2.5,
+        translation: [10.0, -3.0, 0.5],
+        ..Similarity::from_euler_degrees([20.0, -35.0, 110.0])
+    }
+}
+
+#[test]
+fn alignment_recovers_the_similarity_between_reconstructions() {
+    let dir = scratch("recover");
+    let camera = ColmapCamera::from_model(1, 640, 480, &CameraModel::Pinhole { fx: 500.0, fy: 500.0, cx: 320.0, cy: 240.0 });
+    let source_images = arc_images(8);
+    let source_points = vec![ColmapPoint3D {
+        point3d_id: 1,
+        xyz: [0.2, -0.4, 1.0],
+        rgb: [200, 100, 50],
+        error: 0.5,
+        track: vec![TrackElement { image_id: 1, point2d_idx: 0 }],
+    }];
+
+    // The reference is the same scene in another gauge, written by nerfstudio-style tooling
+    let expected = known_transform();
+    let mut reference_images = source_images.clone();
+    align::transform_model(&mut reference_images, &mut [], &expected);
+    for image in &mut reference_images {
+        image.name = format!("images/{}", image.name);
+    }
+    let converter = CameraParamsConverter::new();
+    let source_dir = dir.join("sparse");
+    let reference_path = dir.join("reference").join("transforms.json");
+    std::fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+    converter.write_model(&source_dir, &CameraFormat::COLMAP, std::slice::from_ref(&camera), &source_images, source_points.clone()).unwrap();
+    converter.write_model(&reference_path, &CameraFormat::NeRF, &[camera], &reference_images, Vec::new()).unwrap();
+
+    let (cameras, mut images, mut points) = converter.read_model(&source_dir, &CameraFormat::COLMAP).unwrap();
+    let (_, reference, _) = converter.read_model(&reference_path, &CameraFormat::NeRF).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let (from, to) = align::matched_centers(&images, &reference);
+    assert_eq!(from.len(), 8, "names match on their file names");
+    let alignment = align::align(&from, &to, &AlignOptions::default()).unwrap();
+    assert!(alignment.rms_error < 1e-9);
+    assert_close(alignment.transform.to_matrix().as_flattened(), expected.to_matrix().as_flattened(), 1e-9, "transform");
+
+    align::transform_model(&mut images, &mut points, &alignment.transform);
+    assert_eq!(cameras.len(), 1);
+    for (image, target) in images.iter().zip(&reference) {
+        let (moved, target) = (image.world_to_camera().to_matrix(), target.world_to_camera().to_matrix());
+        assert_close(moved.as_flattened(), target.as_flattened(), 1e-9, "aligned pose");
+    }
+    assert_close(&points[0].xyz, &expected.transform_point(source_points[0].xyz), 1e-9, "aligned point");
+    assert_eq!(points[0].track, source_points[0].track);
+
+    // Without scale the fit keeps the source's size
+    let rigid = align::align(&from, &to, &AlignOptions { with_scale: false, ransac: None }).unwrap();
+    assert_eq!(rigid.transform.scale, 1.0);
+    assert_close(rigid.transform.rotation.as_flattened(), expected.rotation.as_flattened(), 1e-9, "rigid rotation");
+}
+
+#[test]
+fn ransac_ignores_misregistered_cameras() {
+    let expected = known_transform();
+    // Coplanar centers, as a capture walked at constant height gives
+    let source: Vec<[f64; 3]> = (0..12).map(|i| {
+        let angle = i as f64 * 0.5;
+        [3.0 * angle.cos(), 1.5, 3.0 * angle.sin()]
+    }).collect();
+    let mut reference: Vec<[f64; 3]> = source.iter().map(|p| expected.transform_point(*p)).collect();
+    reference[2][0] += 4.0;
+    reference[7][1] -= 6.0;
+    reference[9] = [0.0; 3];
+
+    let least_squares = align::align(&source, &reference, &AlignOptions::default()).unwrap();
+    assert!(least_squares.rms_error > 0.5);
+
+    let options = AlignOptions { with_scale: true, ransac: Some(Ransac { threshold: 0.01, iterations: 200 }) };
+    let robust = align::align(&source, &reference, &options).unwrap();
+    assert_eq!((robust.matched, robust.inliers), (12, 9));
+    assert!(robust.rms_error < 1e-9);
+    assert_close(robust.transform.to_matrix().as_flattened(), expected.to_matrix().as_flattened(), 1e-9, "RANSAC transform");
+
+    let collinear: Vec<[f64; 3]> = (0..5).map(|i| [i as f64, 2.0 * i as f64, 0.0]).collect();
+    assert!(align::align(&collinear, &collinear, &AlignOptions::default()).is_err());
+}
+
+#[test]
+fn normalize_fits_cameras_in_a_sphere() {
+    let mut images = arc_images(10);
+    // Tip the whole rig over so that up is no longer along any axis
+    align::transform_model(&mut images, &mut [], &known_transform());
+
+    let transform = align::normalize(&images, true, 1.0).unwrap();
+    align::transform_model(&mut images, &mut [], &transform);
+    let centers: Vec<[f64; 3]> = images.iter().map(|image| image.camera_to_world().translation).collect();
+    let mean = [0, 1, 2].map(|i| centers.iter().map(|c| c[i]).sum::<f64>() / centers.len() as f64);
+    let farthest = centers.iter().map(|c| c.iter().map(|v| v * v).sum::<f64>().sqrt()).fold(0.0, f64::max);
+    assert_close(&mean, &[0.0; 3], 1e-9, "mean camera center");
+    assert!((farthest - 1.0).abs() < 1e-9, "farthest camera at {}", farthest);
+
+    // Cameras look along +z with y down, so up is -y
+    let up = images.iter().fold([0.0; 3], |sum, image| {
+        let rotation = image.camera_to_world().rotation;
+        [0, 1, 2].map(|i| sum[i] - rotation[i][1])
+    });
+    let length = up.iter().map(|v| v * v).sum::<f64>().sqrt();
+    assert_close(&up.map(|v| v / length), &[0.0, 0.0, 1.0], 1e-9, "mean up direction");
+
+    let half = align::normalize(&images, false, 0.5).unwrap();
+    assert!((half.scale - 0.5).abs() < 1e-9);
+    assert!(align::normalize(&images[..1], false, 1.0).is_err());
+}
@@ -0,0 +1,55 @@
+//! Every COLMAP camera model must map its flat parameters to named intrinsics and back.
+
+use hylaean_splat::formats::camera_params::colmap::CAMERA_MODELS;
+use hylaean_splat::formats::camera_params::{CameraModel, OpenCvDistortion};
+
+/// Distinct parameters: 800 for the first focal length, then 100.01, 200.02, ...
+fn params(count: usize) -> Vec<f64> {
+    (0..count)
+        .map(|i| match i {
+            0 => 800.0,
+            _ => 100.0 * i as f64 + 0.01 * i as f64,
+        })
+        .collect()
+}
+
+#[test]
+fn params_round_trip_for_every_model() {
+    for (_, name, count) in CAMERA_MODELS {
+        let values = params(count);
+        let model = CameraModel::from_params(name, &values).unwrap();
+        assert_eq!(model.name(), name);
+        assert_eq!(model.params(), values, "{}", name);
+        assert!(CameraModel::from_params(name, &values[..count - 1]).is_err(), "{} accepted too few parameters", name);
+
+        let single_focal = name.starts_with("SIMPLE") || name == "RADIAL" || name == "RADIAL_FISHEYE";
+        let [fx, fy] = model.focal_length();
+        assert_eq!(fx, 800.0);
+        assert_eq!(fy, if single_focal { 800.0 } else { values[1] });
+        let principal = if single_focal { [values[1], values[2]] } else { [values[2], values[3]] };
+        assert_eq!(model.principal_point(), principal, "{}", name);
+        assert_eq!(model.is_fisheye(), name.contains("FISHEYE"));
+    }
+}
+
+#[test]
+fn opencv_lenses_map_back_to_equivalent_models() {
+    for (_, name, count) in CAMERA_MODELS {
+        let model = CameraModel::from_params(name, &params(count)).unwrap();
+        let Some(distortion) = model.opencv_distortion() else {
+            assert!(matches!(name, "FOV" | "THIN_PRISM_FISHEYE" | "RAD_TAN_THIN_PRISM_FISHEYE"), "{}", name);
+            continue;
+        };
+        let [fx, fy] = model.focal_length();
+        let [cx, cy] = model.principal_point();
+        let back = CameraModel::from_opencv(fx, fy, cx, cy, &distortion).unwrap();
+        assert_eq!(back.focal_length(), [fx, fy]);
+        assert_eq!(back.principal_point(), [cx, cy]);
+        assert_eq!(back.opencv_distortion(), Some(distortion), "{} -> {}", name, back.name());
+    }
+
+    let undistorted = CameraModel::from_opencv(500.0, 500.0, 320.0, 240.0, &OpenCvDistortion::Standard(vec![0.0; 5])).unwrap();
+    assert_eq!(undistorted.name(), "PINHOLE");
+    let thin_prism = OpenCvDistortion::Standard(vec![0.1, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.001]);
+    assert!(CameraModel::from_opencv(500.0, 500.0, 320.0, 240.0, &thin_prism).is_err());
+}
@@ -0,0 +1,154 @@
+//! COLMAP sparse models must survive text and binary round trips unchanged.
+
+mod common;
+
+use hylaean_splat::formats::camera_params::colmap::{
+    self, ColmapCamera, ColmapImage, ColmapPoint2D, ColmapPoint3D, ModelEncoding, Points3DSource, TrackElement,
+};
+use hylaean_splat::formats::camera_params::CameraParamsConverter;
+use hylaean_splat::formats::ply::{self, Encoding};
+use hylaean_splat::formats::point_cloud::stream::{self, PointChunk, SinkOptions};
+use hylaean_splat::formats::pcd::PcdData;
+use hylaean_splat::formats::{detect_format, CameraFormat, DataFormat, FormatConverter, PointCloudFormat};
+use common::scratch;
+
+fn model() -> (Vec<ColmapCamera>, Vec<ColmapImage>) {
+    let cameras = vec![
+        ColmapCamera { camera_id: 1, model: "SIMPLE_RADIAL".to_string(), width: 1920, height: 1080, params: vec![1500.5, 960.0, 540.0, -0.012] },
+        ColmapCamera {
+            camera_id: 2,
+            model: "OPENCV".to_string(),
+            width: 640,
+            height: 480,
+            params: vec![500.0, 501.0, 320.0, 240.0, 0.1, -0.01, 0.001, 0.002],
+        },
+    ];
+    let pose = |image_id: u32, camera_id: u32, name: &str, points2d: Vec<ColmapPoint2D>| ColmapImage {
+        image_id,
+        qw: 0.9,
+        qx: 0.1,
+        qy: 0.3,
+        qz: 0.2,
+        tx: 1.0 / 3.0,
+        ty: -2.0,
+        tz: 3.5,
+        camera_id,
+        name: name.to_string(),
+        points2d,
+    };
+    let images = vec![
+        pose(1, 1, "frame one.jpg", vec![
+            ColmapPoint2D { x: 10.5, y: 20.25, point3d_id: -1 },
+            ColmapPoint2D { x: 11.0, y: 12.0, point3d_id: 7 },
+        ]),
+        // An image without keypoints has an empty POINTS2D line
+        pose(2, 2, "b.jpg", Vec::new()),
+        pose(3, 1, "c.jpg", vec![ColmapPoint2D { x: 0.125, y: 1e-3, point3d_id: 42 }]),
+    ];
+    (cameras, images)
+}
+
+fn points() -> Vec<ColmapPoint3D> {
+    let track = |pairs: &[(u32, u32)]| pairs.iter().map(|&(image_id, point2d_idx)| TrackElement { image_id, point2d_idx }).collect();
+    vec![
+        ColmapPoint3D { point3d_id: 7, xyz: [1.5, -2.25, 3.0], rgb: [255, 128, 0], error: 0.75, track: track(&[(1, 1)]) },
+        ColmapPoint3D { point3d_id: 42, xyz: [0.1, 0.2, 1.0 / 3.0], rgb: [10, 20, 30], error: 1.25, track: track(&[(3, 0), (1, 0)]) },
+        // Points without a track are legal in a model that was edited by hand
+        ColmapPoint3D { point3d_id: 1 << 40, xyz: [-1.0, 0.0, 1e6], rgb: [0, 0, 0], error: 0.0, track: Vec::new() },
+    ]
+}
+
+fn assert_same(cameras: &[ColmapCamera], images: &[ColmapImage]) {
+    let (expected_cameras, expected_images) = model();
+    assert_eq!(cameras.len(), expected_cameras.len());
+    for (camera, expected) in cameras.iter().zip(&expected_cameras) {
+        assert_eq!((camera.camera_id, &camera.model, camera.width, camera.height), (expected.camera_id, &expected.model, expected.width, expected.height));
+        assert_eq!(camera.params, expected.params);
+    }
+    assert_eq!(images.len(), expected_images.len());
+    for (image, expected) in images.iter().zip(&expected_images) {
+        assert_eq!((image.image_id, image.camera_id, &image.name), (expected.image_id, expected.camera_id, &expected.name));
+        assert_eq!(
+            [image.qw, image.qx, image.qy, image.qz, image.tx, image.ty, image.tz],
+            [expected.qw, expected.qx, expected.qy, expected.qz, expected.tx, expected.ty, expected.tz]
+        );
+        assert_eq!(image.points2d, expected.points2d);
+    }
+}
+
+#[test]
+fn text_and_binary_models_round_trip() {
+    let (cameras, images) = model();
+    for encoding in [ModelEncoding::Text, ModelEncoding::Binary] {
+        let dir = scratch(&format!("{:?}", encoding));
+        colmap::write_cameras(&dir, &cameras, encoding).unwrap();
+        colmap::write_images(&dir, &images, encoding).unwrap();
+        colmap::write_points3d(&dir, &points(), encoding).unwrap();
+        assert_eq!(colmap::detect_encoding(&dir).unwrap(), encoding);
+        assert_same(&colmap::read_cameras(&dir).unwrap(), &colmap::read_images(&dir).unwrap());
+        assert_eq!(colmap::read_points3d(&dir).unwrap(), points());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn converter_transcodes_between_encodings() {
+    let (cameras, images) = model();
+    let root = scratch("transcode");
+    let text = root.join("text");
+    std::fs::create_dir_all(&text).unwrap();
+    colmap::write_cameras(&text, &cameras, ModelEncoding::Text).unwrap();
+    colmap::write_images(&text, &images, ModelEncoding::Text).unwrap();
+
+    let format = detect_format(&text).unwrap();
+    assert!(matches!(format, DataFormat::CameraParameters(CameraFormat::COLMAP)));
+    let binary = root.join("binary");
+    CameraParamsConverter::new()
+        .with_colmap_encoding(ModelEncoding::Binary)
+        .convert(&text, &binary, &format, &format)
+        .unwrap();
+    assert!(binary.join("cameras.bin").is_file() && binary.join("images.bin").is_file());
+
+    // A file inside the model names the whole directory
+    let back = root.join("back");
+    CameraParamsConverter::new()
+        .with_colmap_encoding(ModelEncoding::Text)
+        .convert(&binary.join("images.bin"), &back, &format, &format)
+        .unwrap();
+    assert_same(&colmap::read_cameras(&back).unwrap(), &colmap::read_images(&back).unwrap());
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn model_statistics() {
+    let (cameras, images) = model();
+    let stats = colmap::model_stats(&cameras, &images, &points());
+    assert_eq!((stats.cameras, stats.images, stats.points, stats.observations), (2, 3, 3, 3));
+    assert!((stats.mean_track_length - 1.0).abs() < 1e-12);
+    assert!((stats.mean_observations_per_image - 1.0).abs() < 1e-12);
+    assert!((stats.mean_reprojection_error - 2.0 / 3.0).abs() < 1e-12);
+}
+
+#[test]
+fn points3d_export_keeps_colors_and_errors() {
+    let dir = scratch("export");
+    let path = dir.join("points3D.ply");
+    let mut source = Points3DSource::new(points());
+    let options = SinkOptions { ply_encoding: Encoding::BinaryLittleEndian, pcd_data: PcdData::Binary };
+    let sink = stream::create_sink(&path, &PointCloudFormat::PLY, &source, options).unwrap();
+    assert_eq!(stream::pump(&mut source, sink, stream::DEFAULT_CHUNK_SIZE).unwrap(), 3);
+
+    let mut cloud = stream::open_source(&path, &PointCloudFormat::PLY).unwrap();
+    let names: Vec<&str> = cloud.schema().iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["x", "y", "z", "nx", "ny", "nz", "red", "green", "blue", "error", "track_length"]);
+    let mut chunk = PointChunk::with_capacity(16);
+    assert_eq!(cloud.read_chunk(&mut chunk).unwrap(), 3);
+    for (record, point) in chunk.records().iter().zip(points()) {
+        let value = |column: usize| ply::scalar_as_f64(&record.0[column]).unwrap();
+        assert_eq!([value(0), value(1), value(2)], point.xyz.map(|v| v as f32 as f64));
+        assert_eq!([value(6), value(7), value(8)], point.rgb.map(f64::from));
+        assert_eq!(value(9), point.error);
+        assert_eq!(value(10), point.track.len() as f64);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
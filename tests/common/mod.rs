@@ -0,0 +1,45 @@
+//! Helpers shared by the integration tests; each test file uses its own subset
+
+#![allow(dead_code)]
+
+use hylaean_splat::formats::camera_params::CameraParamsConverter;
+use hylaean_splat::formats::{CameraFormat, DataFormat, FormatConverter};
+use std::path::{Path, PathBuf};
+
+/// A fresh directory under the system temp dir, unique to this test process
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hylaean_splat_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+pub fn convert(input: &Path, output: &Path, from: CameraFormat, to: CameraFormat) {
+    convert_with(CameraParamsConverter::new(), input, output, DataFormat::CameraParameters(from), DataFormat::CameraParameters(to));
+}
+
+pub fn convert_with(converter: CameraParamsConverter, input: &Path, output: &Path, from: DataFormat, to: DataFormat) {
+    converter.convert(input, output, &from, &to).expect("conversion failed");
+}
+
+pub fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64, what: &str) {
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < tolerance, "{}: {:?} != {:?}", what, actual, expected);
+    }
+}
+
+/// Like [`assert_close`], with the tolerance relative to values above 1
+pub fn assert_relative(actual: &[f64], expected: &[f64], tolerance: f64, what: &str) {
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() <= tolerance * e.abs().max(1.0), "{}: {:?} != {:?}", what, actual, expected);
+    }
+}
+
+/// The signature and IHDR chunk of a PNG; nothing past them is read
+pub fn png_header(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', 0, 0, 0, 13];
+    bytes.extend_from_slice(b"IHDR");
+    bytes.extend_from_slice(&width.to_be_bytes());
+    bytes.extend_from_slice(&height.to_be_bytes());
+    bytes.extend_from_slice(&[8, 2, 0, 0, 0]);
+    bytes
+}
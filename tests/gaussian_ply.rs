@@ -0,0 +1,98 @@
+//! Gaussian splat PLY: every encoding reads back exactly what was written, at any SH
+//! degree and with extra per-vertex properties carried through.
+
+mod common;
+
+use hylaean_splat::formats::gaussian::{sh_rest_len, Gaussian, GaussianCloud};
+use hylaean_splat::formats::ply::{Encoding, PlyFile, ScalarType};
+use common::scratch;
+
+/// Values that lose bits under any lossy float formatting
+fn awkward(seed: usize) -> f32 {
+    let values = [0.1, -1.0 / 3.0, 1e-7, -2.5e-39, 1.7e38, 16_777_217.0, -0.0, std::f32::consts::PI];
+    values[seed % values.len()] * (1.0 + (seed / values.len()) as f32 / 7.0)
+}
+
+fn cloud(sh_degree: u32) -> GaussianCloud {
+    let mut cloud = GaussianCloud::new(sh_degree);
+    cloud.comments.push("written by gaussian_ply".to_string());
+    cloud.extra_properties.push(("confidence".to_string(), ScalarType::Float));
+    cloud.gaussians = (0..5).map(|i| {
+        let mut gaussian = Gaussian::new(sh_degree);
+        gaussian.position = [awkward(i), awkward(i + 1), awkward(i + 2)];
+        gaussian.normal = [0.0, 0.0, 1.0];
+        gaussian.sh_dc = [awkward(i + 3), awkward(i + 4), awkward(i + 5)];
+        for (j, coefficient) in gaussian.sh_rest.iter_mut().enumerate() {
+            *coefficient = awkward(i + j);
+        }
+        gaussian.opacity = awkward(i + 6);
+        gaussian.scale = [awkward(i + 7), -4.2, awkward(i)];
+        gaussian.rotation = [0.9, awkward(i + 1), -0.1, awkward(i + 2)];
+        gaussian.extra = vec![awkward(i + 3) as f64];
+        gaussian
+    }).collect();
+    cloud
+}
+
+fn bits(gaussian: &Gaussian) -> Vec<u64> {
+    let floats = gaussian.position.iter()
+        .chain(&gaussian.normal)
+        .chain(&gaussian.sh_dc)
+        .chain(&gaussian.sh_rest)
+        .chain(std::iter::once(&gaussian.opacity))
+        .chain(&gaussian.scale)
+        .chain(&gaussian.rotation);
+    floats.map(|v| v.to_bits() as u64).chain(gaussian.extra.iter().map(|v| v.to_bits())).collect()
+}
+
+#[test]
+fn every_encoding_round_trips_bit_for_bit() {
+    let dir = scratch("gaussian_ply");
+    for sh_degree in [0, 3] {
+        let expected = cloud(sh_degree);
+        for (label, encoding) in [
+            ("ascii", Encoding::Ascii),
+            ("binary_le", Encoding::BinaryLittleEndian),
+            ("binary_be", Encoding::BinaryBigEndian),
+        ] {
+            let path = dir.join(format!("sh{}_{}.ply", sh_degree, label));
+            expected.write_ply(&path, encoding).unwrap();
+            let actual = GaussianCloud::read_ply(&path).unwrap();
+
+            assert_eq!(actual.sh_degree, sh_degree, "{}", label);
+            assert!(actual.has_normals, "{}", label);
+            assert_eq!(actual.extra_properties, expected.extra_properties, "{}", label);
+            assert_eq!(actual.comments, expected.comments, "{}", label);
+            assert_eq!(actual.len(), expected.len(), "{}", label);
+            for (a, e) in actual.gaussians.iter().zip(&expected.gaussians) {
+                assert_eq!(a.sh_rest.len(), sh_rest_len(sh_degree));
+                assert_eq!(bits(a), bits(e), "{} at SH degree {}", label, sh_degree);
+            }
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn oversized_header_counts_are_errors() {
+    let dir = scratch("ply_counts");
+    let splat = dir.join("splat.ply");
+    let mut header = String::from("ply\nformat binary_little_endian 1.0\nelement vertex 18446744073709551615\n");
+    for name in ["x", "y", "z", "f_dc_0", "f_dc_1", "f_dc_2", "opacity", "scale_0", "scale_1", "scale_2", "rot_0", "rot_1", "rot_2", "rot_3"] {
+        header.push_str(&format!("property float {}\n", name));
+    }
+    header.push_str("end_header\n");
+    std::fs::write(&splat, &header).unwrap();
+
+    // A face claiming four billion indices, backed by none of them
+    let mesh = dir.join("mesh.ply");
+    let mut bytes = b"ply\nformat binary_little_endian 1.0\nelement face 1\nproperty list uint int vertex_indices\nend_header\n".to_vec();
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    std::fs::write(&mesh, &bytes).unwrap();
+
+    let splat_result = GaussianCloud::read_ply(&splat);
+    let mesh_result = PlyFile::read(&mesh);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(splat_result.is_err());
+    assert!(mesh_result.is_err());
+}
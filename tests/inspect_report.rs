@@ -0,0 +1,103 @@
+//! `inspect` reports: counts, bounds, centroid, non-finite values and histograms for
+//! Gaussian PLY, `.ksplat` and plain point clouds, and the shape of the `--json` output.
+
+mod common;
+
+use hylaean_splat::formats::gaussian::{inverse_sigmoid, Gaussian, GaussianCloud};
+use hylaean_splat::formats::inspect;
+use hylaean_splat::formats::ply::Encoding;
+use hylaean_splat::formats::{splat, PointCloudFormat};
+use common::{assert_close, scratch};
+
+/// Ten Gaussians: alpha 0.05, 0.15, ... 0.95 (one per opacity bin), largest scale 10^(i - 6)
+/// capped at one per scale decade, at x = i
+fn scene() -> GaussianCloud {
+    let mut cloud = GaussianCloud::new(1);
+    cloud.gaussians = (0..10).map(|i| {
+        let mut gaussian = Gaussian::new(1);
+        gaussian.position = [i as f32, 2.0 * i as f32 - 4.0, 1.5];
+        gaussian.opacity = inverse_sigmoid(0.05 + i as f32 * 0.1);
+        gaussian.scale = [(10f32.powi(i.min(7) - 6) * 1.5).ln(), (1e-7f32).ln(), (1e-7f32).ln()];
+        gaussian
+    }).collect();
+    cloud
+}
+
+#[test]
+fn gaussian_ply_report() {
+    let dir = scratch("inspect_ply");
+    let path = dir.join("scene.ply");
+    let mut cloud = scene();
+    cloud.gaussians[3].position[1] = f32::NAN;
+    cloud.gaussians[5].sh_rest[4] = f32::INFINITY;
+    cloud.write_ply(&path, Encoding::BinaryLittleEndian).unwrap();
+    let report = inspect::inspect(&path, &PointCloudFormat::PLY).unwrap();
+    let json = serde_json::to_value(&report).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!((report.point_count, report.nan_count, report.inf_count), (10, 1, 1));
+    assert_eq!(report.encoding.as_deref(), Some("binary_little_endian"));
+    assert_eq!((report.has_color, report.sh_degree), (true, Some(1)));
+    // The point with a NaN coordinate is counted but left out of bounds and centroid
+    let bounds = report.bounds.as_ref().unwrap();
+    assert_eq!((bounds.min, bounds.max), ([0.0, -4.0, 1.5], [9.0, 14.0, 1.5]));
+    let finite: Vec<f64> = (0..10).filter(|i| *i != 3).map(f64::from).collect();
+    let mean_x = finite.iter().sum::<f64>() / 9.0;
+    assert_close(&report.centroid.unwrap(), &[mean_x, 2.0 * mean_x - 4.0, 1.5], 1e-9, "centroid");
+
+    let opacity = report.opacity_histogram.as_ref().unwrap();
+    assert_eq!(opacity.counts, vec![1; 10]);
+    // 1.5e-6 .. 1.5e1, with the last two both in the 10..100 decade
+    let scale = report.scale_histogram.as_ref().unwrap();
+    assert_eq!(scale.counts, vec![1, 1, 1, 1, 1, 1, 1, 3]);
+
+    let vertex = &report.elements[0];
+    assert_eq!((vertex.name.as_str(), vertex.count), ("vertex", 10));
+    assert!(vertex.properties.iter().any(|p| p.name == "f_rest_8" && p.data_type == "float"));
+
+    // `--json` prints the report as is
+    for key in ["path", "format", "encoding", "elements", "details", "point_count", "bounds", "centroid",
+                "has_color", "has_normals", "sh_degree", "opacity_histogram", "scale_histogram", "nan_count", "inf_count"] {
+        assert!(json.get(key).is_some(), "missing {}", key);
+    }
+    assert_eq!(json["format"], "PLY");
+    assert_eq!(json["bounds"]["max"], serde_json::json!([9.0, 14.0, 1.5]));
+    assert_eq!(json["opacity_histogram"]["edges"].as_array().unwrap().len(), 11);
+    assert_eq!(json["scale_histogram"]["counts"].as_array().unwrap().len(), 8);
+    assert_eq!(json["elements"][0]["properties"][0], serde_json::json!({"name": "x", "data_type": "float"}));
+}
+
+#[test]
+fn ksplat_report() {
+    let dir = scratch("inspect_ksplat");
+    let path = dir.join("scene.ksplat");
+    splat::write_ksplat(&scene(), &path).unwrap();
+    let report = inspect::inspect(&path, &PointCloudFormat::KSplat).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(report.format, "KSplat");
+    assert_eq!(report.encoding.as_deref(), Some("ksplat"));
+    assert_eq!((report.point_count, report.nan_count, report.inf_count), (10, 0, 0));
+    assert_eq!(report.elements[0].count, 10);
+    assert_eq!(report.sh_degree, Some(1));
+    let bounds = report.bounds.unwrap();
+    assert_close(&[bounds.min, bounds.max].concat(), &[0.0, -4.0, 1.5, 9.0, 14.0, 1.5], 1e-2, "bounds");
+    assert_eq!(report.opacity_histogram.unwrap().counts.iter().sum::<u64>(), 10);
+}
+
+#[test]
+fn plain_point_cloud_report() {
+    let dir = scratch("inspect_xyz");
+    let path = dir.join("scan.xyz");
+    std::fs::write(&path, "# scan\n0 0 0 255 0 0\n2 4 -6 0 255 0\n1 inf 1 0 0 255\n").unwrap();
+    let report = inspect::inspect(&path, &PointCloudFormat::XYZ).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!((report.point_count, report.nan_count, report.inf_count), (3, 0, 1));
+    assert!(report.has_color && !report.has_normals);
+    assert_eq!(report.sh_degree, None);
+    assert_close(&report.centroid.unwrap(), &[1.0, 2.0, -3.0], 1e-9, "centroid");
+    assert!(report.opacity_histogram.is_none() && report.scale_histogram.is_none());
+    let names: Vec<&str> = report.elements[0].properties.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["x", "y", "z", "red", "green", "blue"]);
+}
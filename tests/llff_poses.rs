@@ -0,0 +1,151 @@
+//! LLFF `poses_bounds.npy`: the `.npy` container, the (down, right, backwards) camera
+//! axes, near/far bounds from points3D, and round trips through COLMAP and NeRF.
+
+mod common;
+
+use hylaean_splat::formats::camera_params::colmap::{self, ColmapCamera, ColmapImage, ColmapPoint3D, ModelEncoding, TrackElement};
+use hylaean_splat::formats::camera_params::llff::{self, LlffView};
+use hylaean_splat::formats::camera_params::{CameraModel, CameraParamsConverter, NeRFCamera, Pose};
+use hylaean_splat::formats::npy::{self, NpyArray};
+use hylaean_splat::formats::{detect_format, CameraFormat, DataFormat, DatasetFormat, FormatConverter};
+use common::{assert_close, convert_with, scratch};
+
+/// A hand-written version 2.0 header, as `np.save` writes for huge headers
+fn npy_file(descr: &str, fortran_order: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+    let header = format!("{{\"descr\": '{}', 'fortran_order': {}, 'shape': {}}}\n", descr, if fortran_order { "True" } else { "False" }, shape);
+    let mut bytes = b"\x93NUMPY\x02\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[test]
+fn npy_reads_what_numpy_writes() {
+    let dir = scratch("npy");
+
+    let array = NpyArray::new(vec![2, 3], vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.5]).unwrap();
+    let path = dir.join("c.npy");
+    npy::write(&path, &array).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(bytes.starts_with(b"\x93NUMPY\x01\x00"));
+    assert_eq!((bytes.len() - 6 * 8) % 64, 0, "data must start 64-byte aligned");
+    assert!(String::from_utf8_lossy(&bytes[10..bytes.len() - 48]).contains("'shape': (2, 3)"));
+    assert_eq!(npy::read(&path).unwrap(), array);
+
+    // Column-major big-endian f4 data is returned in row order
+    let data: Vec<u8> = [1.0f32, 4.0, 2.0, 5.0, 3.0, 6.5].iter().flat_map(|v| v.to_be_bytes()).collect();
+    std::fs::write(dir.join("f.npy"), npy_file(">f4", true, "(2, 3)", &data)).unwrap();
+    assert_eq!(npy::read(&dir.join("f.npy")).unwrap(), array);
+
+    std::fs::write(dir.join("i.npy"), npy_file("<i8", false, "(1,)", &[0; 8])).unwrap();
+    assert!(npy::read(&dir.join("i.npy")).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn colmap_poses_and_bounds_become_llff_rows() {
+    let dir = scratch("bounds");
+    let model_dir = dir.join("sparse/0");
+    std::fs::create_dir_all(&model_dir).unwrap();
+
+    // One camera at the origin looking along +z, observing points at depths 1..=1001
+    let cameras = vec![ColmapCamera::from_model(1, 1008, 756, &CameraModel::SimplePinhole { f: 815.0, cx: 504.0, cy: 378.0 })];
+    let images = vec![
+        ColmapImage::new(4, 1, "IMG_0002.JPG".to_string(), &Pose::default()),
+        ColmapImage::new(9, 1, "IMG_0001.JPG".to_string(), &Pose::new(Pose::default().rotation, [0.0, 0.0, -1.0])),
+    ];
+    let points: Vec<ColmapPoint3D> = (0..=1000)
+        .map(|i| ColmapPoint3D {
+            point3d_id: i + 1,
+            xyz: [0.1, -0.2, 1.0 + i as f64],
+            rgb: [0; 3],
+            error: 0.0,
+            track: vec![TrackElement { image_id: 4, point2d_idx: i as u32 }],
+        })
+        .collect();
+    colmap::write_cameras(&model_dir, &cameras, ModelEncoding::Binary).unwrap();
+    colmap::write_images(&model_dir, &images, ModelEncoding::Binary).unwrap();
+    colmap::write_points3d(&model_dir, &points, ModelEncoding::Binary).unwrap();
+
+    let colmap_format = DataFormat::CameraParameters(CameraFormat::COLMAP);
+    let llff_format = DataFormat::Dataset(DatasetFormat::LLFF);
+    convert_with(CameraParamsConverter::new(), &model_dir, &dir, colmap_format.clone(), llff_format.clone());
+    assert!(matches!(detect_format(&dir).unwrap(), DataFormat::Dataset(DatasetFormat::LLFF)));
+    let views = llff::read(&dir.join(llff::POSES_BOUNDS_FILE)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Rows are in name order; the second image sees no points and takes the scene bounds
+    assert_eq!(views.len(), 2);
+    assert_close(&views[0].camera_to_world.translation, &[0.0, 0.0, 1.0], 1e-9, "IMG_0001 center");
+    let identity = &views[1];
+    assert_eq!((identity.height, identity.width, identity.focal), (756, 1008, 815.0));
+    // LLFF's columns are the camera's down, right and backwards directions
+    assert_eq!(identity.camera_to_world.rotation, [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]]);
+    // numpy's percentile(depths, 0.1) and percentile(depths, 99.9) of 1..=1001
+    assert_close(&[identity.near, identity.far], &[2.0, 1000.0], 1e-9, "bounds");
+    assert_close(&[views[0].near, views[0].far], &[identity.near, identity.far], 1e-9, "fallback bounds");
+}
+
+#[test]
+fn llff_round_trips_through_colmap_and_nerf() {
+    let dir = scratch("round_trip");
+    std::fs::create_dir_all(dir.join("images")).unwrap();
+    let names = ["a.png", "b.png", "c.png"];
+    for name in names {
+        std::fs::write(dir.join("images").join(name), b"").unwrap();
+    }
+    let views: Vec<LlffView> = (0..3)
+        .map(|i| LlffView {
+            camera_to_world: Pose::from_quaternion([0.9, 0.1 * i as f64, 0.3, -0.2], [i as f64, 2.0, -3.0]),
+            height: 480,
+            width: 640,
+            focal: 500.0 + i as f64,
+            near: 0.5 + i as f64,
+            far: 20.0,
+        })
+        .collect();
+    llff::write(&dir.join(llff::POSES_BOUNDS_FILE), &views).unwrap();
+
+    let llff_format = DataFormat::Dataset(DatasetFormat::LLFF);
+    let colmap_format = DataFormat::CameraParameters(CameraFormat::COLMAP);
+    let nerf_format = DataFormat::CameraParameters(CameraFormat::NeRF);
+    let model_dir = dir.join("colmap");
+    convert_with(CameraParamsConverter::new(), &dir, &model_dir, llff_format.clone(), colmap_format.clone());
+    let images = colmap::read_images(&model_dir).unwrap();
+    let cameras = colmap::read_cameras(&model_dir).unwrap();
+    assert_eq!(images.iter().map(|image| image.name.as_str()).collect::<Vec<_>>(), names);
+    assert_eq!(cameras.len(), 3);
+    assert_eq!(cameras[2].camera_model().unwrap(), CameraModel::SimplePinhole { f: 502.0, cx: 320.0, cy: 240.0 });
+    assert_close(&images[1].camera_to_world().translation, &[1.0, 2.0, -3.0], 1e-9, "camera center");
+
+    let transforms = dir.join("transforms.json");
+    convert_with(CameraParamsConverter::new(), &dir.join(llff::POSES_BOUNDS_FILE), &transforms, llff_format.clone(), nerf_format);
+    assert_eq!(NeRFCamera::read(&transforms).unwrap().frames.len(), 3);
+
+    // Bounds need points, which neither a bare COLMAP model nor this NeRF file has
+    let back = dir.join("back");
+    let result = CameraParamsConverter::new().convert(&model_dir, &back, &colmap_format, &llff_format);
+    assert!(result.is_err());
+
+    // With points in front of every camera, the poses come back with usable bounds
+    let mut points: Vec<ColmapPoint3D> = Vec::new();
+    for view in &views {
+        for depth in [view.near, view.far] {
+            let backwards = [0, 1, 2].map(|i| view.camera_to_world.rotation[i][2]);
+            let xyz = [0, 1, 2].map(|i| view.camera_to_world.translation[i] - depth * backwards[i]);
+            points.push(ColmapPoint3D { point3d_id: points.len() as u64 + 1, xyz, rgb: [0; 3], error: 0.0, track: Vec::new() });
+        }
+    }
+    colmap::write_points3d(&model_dir, &points, ModelEncoding::Text).unwrap();
+    convert_with(CameraParamsConverter::new(), &model_dir, &back, colmap_format, llff_format);
+    let back_views = llff::read(&back.join(llff::POSES_BOUNDS_FILE)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    for (back, original) in back_views.iter().zip(&views) {
+        assert_close(back.camera_to_world.rotation.as_flattened(), original.camera_to_world.rotation.as_flattened(), 1e-9, "rotation");
+        assert_close(&back.camera_to_world.translation, &original.camera_to_world.translation, 1e-9, "translation");
+        assert_eq!((back.height, back.width, back.focal), (original.height, original.width, original.focal));
+        assert!(back.near > 0.0 && back.far > back.near, "bounds {} {}", back.near, back.far);
+    }
+}
@@ -0,0 +1,136 @@
+//! Mobile capture imports: Polycam raw exports, Record3D `.r3d` archives and ARKit frame
+//! JSON into COLMAP and nerfstudio, with ARKit's OpenGL camera axes.
+
+mod common;
+
+use hylaean_splat::formats::camera_params::colmap::{self, ModelEncoding};
+use hylaean_splat::formats::camera_params::{arkit, record3d, CameraModel, CameraParamsConverter, NeRFCamera, Pose};
+use hylaean_splat::formats::{detect_format, CameraFormat, DataFormat};
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+use common::{assert_close, convert, convert_with, scratch};
+
+/// A zip archive of `(name, data, deflate)` entries, as Record3D writes them
+fn zip_file(entries: &[(&str, &[u8], bool)]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data, deflate) in entries {
+        let method = if *deflate { CompressionMethod::Deflated } else { CompressionMethod::Stored };
+        writer.start_file(*name, SimpleFileOptions::default().compression_method(method)).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn polycam_export_prefers_corrected_cameras() {
+    let dir = scratch("polycam");
+    let keyframes = dir.join("keyframes");
+    for sub in ["cameras", "corrected_cameras", "corrected_images"] {
+        std::fs::create_dir_all(keyframes.join(sub)).unwrap();
+    }
+    let camera_to_world = Pose::from_quaternion([0.8, 0.2, -0.5, 0.1], [0.3, 1.4, -2.0]);
+    let m = camera_to_world.to_matrix();
+    let mut camera = serde_json::json!({"blur_score": 250.0, "fx": 1440.5, "fy": 1441.0, "cx": 958.0, "cy": 719.5, "width": 1920, "height": 1440});
+    for (r, row) in m.iter().take(3).enumerate() {
+        for (c, value) in row.iter().enumerate() {
+            camera[format!("t_{}{}", r, c)] = (*value).into();
+        }
+    }
+    std::fs::write(keyframes.join("corrected_cameras/1700000000.json"), camera.to_string()).unwrap();
+    std::fs::write(keyframes.join("corrected_images/1700000000.jpg"), b"").unwrap();
+    camera["fx"] = 1.0.into();
+    std::fs::write(keyframes.join("cameras/1700000000.json"), camera.to_string()).unwrap();
+
+    assert!(matches!(detect_format(&dir).unwrap(), DataFormat::CameraParameters(CameraFormat::Polycam)));
+    let model_dir = dir.join("sparse");
+    let converter = CameraParamsConverter::new().with_colmap_encoding(ModelEncoding::Binary);
+    convert_with(converter, &dir, &model_dir, DataFormat::CameraParameters(CameraFormat::Polycam), DataFormat::CameraParameters(CameraFormat::COLMAP));
+    assert!(model_dir.join("images.bin").is_file());
+    let cameras = colmap::read_cameras(&model_dir).unwrap();
+    let images = colmap::read_images(&model_dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(images[0].name, "corrected_images/1700000000.jpg");
+    assert_eq!(cameras[0].camera_model().unwrap(), CameraModel::Pinhole { fx: 1440.5, fy: 1441.0, cx: 958.0, cy: 719.5 });
+    assert_eq!((cameras[0].width, cameras[0].height), (1920, 1440));
+
+    // ARKit looks along -z of its camera frame, COLMAP along +z
+    let colmap_camera_to_world = images[0].camera_to_world();
+    assert_close(&colmap_camera_to_world.translation, &camera_to_world.translation, 1e-9, "camera center");
+    let forward = [0, 1, 2].map(|i| colmap_camera_to_world.rotation[i][2]);
+    let expected = [0, 1, 2].map(|i| -camera_to_world.rotation[i][2]);
+    assert_close(&forward, &expected, 1e-9, "viewing direction");
+}
+
+#[test]
+fn record3d_archive_converts_to_nerfstudio() {
+    let dir = scratch("record3d");
+    let poses = [[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0], [0.1, -0.2, 0.05, 0.97, 0.5, 0.1, -0.3]];
+    // K in column order, as Record3D writes it
+    let metadata = serde_json::json!({"w": 720, "h": 960, "K": [680.0, 0.0, 0.0, 0.0, 681.0, 0.0, 361.0, 479.0, 1.0], "fps": 60, "poses": poses});
+    let metadata = metadata.to_string();
+    let archive = dir.join("capture.r3d");
+    std::fs::write(&archive, zip_file(&[
+        ("metadata", metadata.as_bytes(), true),
+        ("rgbd/0.jpg", b"first frame", false),
+        ("rgbd/1.jpg", b"second frame", true),
+        ("rgbd/0.depth", b"depth", true),
+        ("rgbd/../escaped.jpg", b"outside", false),
+    ])).unwrap();
+
+    assert!(matches!(detect_format(&archive).unwrap(), DataFormat::CameraParameters(CameraFormat::Record3D)));
+    let frames = record3d::read(&archive).unwrap();
+    assert_eq!(frames[1].image, "rgbd/1.jpg");
+    assert_eq!(frames[0].model, CameraModel::Pinhole { fx: 680.0, fy: 681.0, cx: 361.0, cy: 479.0 });
+
+    let output = dir.join("out").join("transforms.json");
+    std::fs::create_dir_all(output.parent().unwrap()).unwrap();
+    convert(&archive, &output, CameraFormat::Record3D, CameraFormat::NeRF);
+    let nerf = NeRFCamera::read(&output).unwrap();
+    let extracted = std::fs::read(dir.join("out/rgbd/1.jpg")).unwrap();
+    let depth_extracted = dir.join("out/rgbd/0.depth").exists();
+    let escaped = dir.join("out/escaped.jpg").exists();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(extracted, b"second frame");
+    assert!(!depth_extracted);
+    assert!(!escaped, "entries may not climb out of the output directory");
+    assert_eq!(nerf.frames.len(), 2);
+    assert_eq!(nerf.frames[1].file_path, "rgbd/1.jpg");
+    // Both are camera-to-world with OpenGL camera axes, in the same world
+    let [qx, qy, qz, qw, tx, ty, tz] = poses[1];
+    let expected = Pose::from_quaternion([qw, qx, qy, qz], [tx, ty, tz]).to_matrix();
+    assert_close(nerf.frames[1].transform_matrix.as_flattened(), expected.as_flattened(), 1e-9, "transform_matrix");
+}
+
+#[test]
+fn arkit_matrices_are_read_in_either_order() {
+    let dir = scratch("arkit");
+    let camera_to_world = Pose::from_quaternion([0.6, -0.3, 0.7, 0.2], [2.0, 1.5, -0.5]);
+    let m = camera_to_world.to_matrix();
+    // simd_float4x4 and simd_float3x3 dumped column by column
+    let columns: Vec<f64> = (0..4).flat_map(|c| (0..4).map(move |r| m[r][c])).collect();
+    let frames = serde_json::json!({"frames": [
+        {"image": "a.jpg", "intrinsics": [[1500.0, 0.0, 0.0], [0.0, 1500.0, 0.0], [960.0, 720.0, 1.0]],
+         "cameraTransform": columns, "imageResolution": {"width": 1920, "height": 1440}},
+        {"image": "b.jpg", "intrinsics": [[1500.0, 0.0, 0.0], [0.0, 1500.0, 0.0], [960.0, 720.0, 1.0]],
+         "cameraTransform": Pose::default().to_matrix(), "width": 1920, "height": 1440},
+    ]});
+    let input = dir.join("frames.json");
+    std::fs::write(&input, frames.to_string()).unwrap();
+    assert!(arkit::is_arkit(&input));
+    assert!(matches!(detect_format(&input).unwrap(), DataFormat::CameraParameters(CameraFormat::ARKit)));
+
+    let output = dir.join("transforms.json");
+    convert(&input, &output, CameraFormat::ARKit, CameraFormat::NeRF);
+    let nerf = NeRFCamera::read(&output).unwrap();
+    let model_dir = dir.join("sparse");
+    convert(&input, &model_dir, CameraFormat::ARKit, CameraFormat::COLMAP);
+    let cameras = colmap::read_cameras(&model_dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_close(nerf.frames[0].transform_matrix.as_flattened(), m.as_flattened(), 1e-9, "column-major cameraTransform");
+    assert_close(&[nerf.intrinsics.fl_x.unwrap(), nerf.intrinsics.cx.unwrap(), nerf.intrinsics.cy.unwrap()], &[1500.0, 960.0, 720.0], 1e-9, "intrinsics");
+    assert_eq!(cameras.len(), 1, "frames with equal intrinsics share a camera");
+}
@@ -0,0 +1,161 @@
+//! nerfstudio `transforms.json`: per-frame intrinsics, image sizes read from the images,
+//! `applied_transform` and the sparse point cloud, in both directions with COLMAP.
+
+mod common;
+
+use hylaean_splat::formats::camera_params::colmap::{self, ColmapCamera, ColmapImage, ColmapPoint3D, ModelEncoding};
+use hylaean_splat::formats::camera_params::image_size::read_image_size;
+use hylaean_splat::formats::camera_params::{CameraModel, CameraParamsConverter, NeRFCamera, Pose, NERFSTUDIO_PLY_FILE};
+use hylaean_splat::formats::{CameraFormat, DataFormat, FormatConverter};
+use common::{assert_close, convert, png_header, scratch};
+
+/// SOI, a JFIF APP0 segment, then a baseline SOF0
+fn jpeg_header(width: u16, height: u16) -> Vec<u8> {
+    let mut bytes = vec![0xff, 0xd8, 0xff, 0xe0, 0, 16];
+    bytes.extend_from_slice(b"JFIF\0");
+    bytes.extend_from_slice(&[1, 1, 0, 0, 1, 0, 1, 0, 0]);
+    bytes.extend_from_slice(&[0xff, 0xc0, 0, 11, 8]);
+    bytes.extend_from_slice(&height.to_be_bytes());
+    bytes.extend_from_slice(&width.to_be_bytes());
+    bytes.extend_from_slice(&[1, 1, 0x11, 0]);
+    bytes
+}
+
+#[test]
+fn image_sizes_come_from_headers() {
+    let dir = scratch("sizes");
+    std::fs::write(dir.join("a.png"), png_header(1297, 840)).unwrap();
+    std::fs::write(dir.join("b.jpg"), jpeg_header(4032, 3024)).unwrap();
+    std::fs::write(dir.join("c.txt"), b"not an image").unwrap();
+
+    assert_eq!(read_image_size(&dir.join("a.png")).unwrap(), [1297, 840]);
+    assert_eq!(read_image_size(&dir.join("b.jpg")).unwrap(), [4032, 3024]);
+    assert!(read_image_size(&dir.join("c.txt")).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn nerfstudio_frames_become_colmap_cameras() {
+    let dir = scratch("import");
+    std::fs::create_dir_all(dir.join("images")).unwrap();
+    std::fs::write(dir.join("images/frame_00001.jpg"), jpeg_header(800, 600)).unwrap();
+    std::fs::write(dir.join("images/frame_00002.png"), png_header(800, 600)).unwrap();
+    std::fs::write(
+        dir.join("sparse_pc.ply"),
+        "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\n\
+         property uchar red\nproperty uchar green\nproperty uchar blue\nend_header\n1 2 3 255 0 10\n-1 0.5 0 0 128 255\n",
+    )
+    .unwrap();
+
+    // nerfstudio's applied_transform swaps y and z and negates the new z, as ns-process-data does
+    let transforms = r#"{
+        "camera_model": "OPENCV",
+        "fl_x": 700.0, "fl_y": 710.0, "k1": -0.1, "k2": 0.02, "p1": 0.001, "p2": -0.002,
+        "applied_transform": [[1, 0, 0, 0.5], [0, 0, 1, 0], [0, -1, 0, 2]],
+        "ply_file_path": "sparse_pc.ply",
+        "frames": [
+            {"file_path": "images/frame_00001.jpg", "colmap_im_id": 7,
+             "transform_matrix": [[1, 0, 0, 1], [0, 1, 0, 2], [0, 0, 1, 3], [0, 0, 0, 1]]},
+            {"file_path": "images/frame_00002.png", "colmap_im_id": 9,
+             "transform_matrix": [[0, -1, 0, 0], [1, 0, 0, 0], [0, 0, 1, -4], [0, 0, 0, 1]]},
+            {"file_path": "images/frame_00003.png", "colmap_im_id": 12,
+             "camera_model": "OPENCV_FISHEYE", "fl_x": 300.0, "fl_y": 300.0, "cx": 320.0, "cy": 240.0,
+             "w": 640, "h": 480, "k1": 0.1, "k2": 0.01, "k3": 0.001, "k4": 0.0001,
+             "transform_matrix": [[1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 1, 0], [0, 0, 0, 1]]}
+        ]
+    }"#;
+    let input = dir.join("transforms.json");
+    std::fs::write(&input, transforms).unwrap();
+
+    let model_dir = dir.join("sparse/0");
+    convert(&input, &model_dir, CameraFormat::NeRF, CameraFormat::COLMAP);
+    let cameras = colmap::read_cameras(&model_dir).unwrap();
+    let images = colmap::read_images(&model_dir).unwrap();
+    let points = colmap::read_points3d(&model_dir).unwrap();
+
+    // Two frames share the top-level camera; their sizes come from the image headers
+    assert_eq!(cameras.len(), 2);
+    assert_eq!((cameras[0].width, cameras[0].height), (800, 600));
+    assert_eq!(
+        cameras[0].camera_model().unwrap(),
+        CameraModel::OpenCv { fx: 700.0, fy: 710.0, cx: 400.0, cy: 300.0, k1: -0.1, k2: 0.02, p1: 0.001, p2: -0.002 }
+    );
+    assert_eq!((cameras[1].width, cameras[1].height), (640, 480));
+    assert_eq!(
+        cameras[1].camera_model().unwrap(),
+        CameraModel::OpenCvFisheye { fx: 300.0, fy: 300.0, cx: 320.0, cy: 240.0, k1: 0.1, k2: 0.01, k3: 0.001, k4: 0.0001 }
+    );
+    assert_eq!(images.iter().map(|image| (image.image_id, image.camera_id)).collect::<Vec<_>>(), [(7, 1), (9, 1), (12, 2)]);
+
+    // The camera centers and points are back in COLMAP's world
+    let applied = Pose::from_matrix(&[[1.0, 0.0, 0.0, 0.5], [0.0, 0.0, 1.0, 0.0], [0.0, -1.0, 0.0, 2.0], [0.0, 0.0, 0.0, 1.0]]);
+    let center = images[0].camera_to_world().translation;
+    assert_close(&applied.transform_point(center), &[1.0, 2.0, 3.0], 1e-9, "camera center");
+    assert_eq!(points.len(), 2);
+    assert_close(&applied.transform_point(points[0].xyz), &[1.0, 2.0, 3.0], 1e-9, "point");
+    assert_eq!((points[0].rgb, points[1].rgb), ([255, 0, 10], [0, 128, 255]));
+
+    // A frame without a size or an image cannot be converted
+    std::fs::remove_file(dir.join("images/frame_00002.png")).unwrap();
+    let result = CameraParamsConverter::new().convert(
+        &input,
+        &dir.join("missing"),
+        &DataFormat::CameraParameters(CameraFormat::NeRF),
+        &DataFormat::CameraParameters(CameraFormat::COLMAP),
+    );
+    assert!(result.is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn colmap_cameras_become_per_frame_intrinsics() {
+    let dir = scratch("export");
+    let model_dir = dir.join("sparse");
+    std::fs::create_dir_all(&model_dir).unwrap();
+
+    let wide = CameraModel::SimpleRadial { f: 1500.0, cx: 960.0, cy: 540.0, k: -0.02 };
+    let tele = CameraModel::Pinhole { fx: 3000.0, fy: 3001.0, cx: 2000.0, cy: 1500.0 };
+    let cameras = vec![ColmapCamera::from_model(1, 1920, 1080, &wide), ColmapCamera::from_model(2, 4000, 3000, &tele)];
+    let pose = Pose::from_quaternion([0.9, 0.1, 0.3, 0.2], [0.5, -1.0, 2.0]);
+    let images = vec![
+        ColmapImage::new(3, 1, "a.jpg".to_string(), &pose),
+        ColmapImage::new(5, 2, "b.jpg".to_string(), &pose),
+        ColmapImage::new(8, 1, "c.jpg".to_string(), &pose),
+    ];
+    let points = vec![ColmapPoint3D { point3d_id: 1, xyz: [1.0, 2.0, 3.0], rgb: [10, 20, 30], error: 0.5, track: Vec::new() }];
+    colmap::write_cameras(&model_dir, &cameras, ModelEncoding::Text).unwrap();
+    colmap::write_images(&model_dir, &images, ModelEncoding::Text).unwrap();
+    colmap::write_points3d(&model_dir, &points, ModelEncoding::Text).unwrap();
+
+    let transforms = dir.join("transforms.json");
+    convert(&model_dir, &transforms, CameraFormat::COLMAP, CameraFormat::NeRF);
+    let nerf = NeRFCamera::read(&transforms).unwrap();
+
+    assert_eq!(nerf.intrinsics.camera_model.as_deref(), Some("OPENCV"));
+    assert_eq!((nerf.intrinsics.w, nerf.intrinsics.h, nerf.intrinsics.k1), (Some(1920), Some(1080), Some(-0.02)));
+    assert!(nerf.camera_angle_x.is_some());
+    assert_eq!(nerf.ply_file_path.as_deref(), Some(NERFSTUDIO_PLY_FILE));
+    assert!(dir.join(NERFSTUDIO_PLY_FILE).is_file());
+    assert_eq!(nerf.frames.iter().map(|frame| frame.colmap_im_id).collect::<Vec<_>>(), [Some(3), Some(5), Some(8)]);
+    assert_eq!(nerf.frames[0].intrinsics, Default::default());
+    assert_eq!(nerf.frames[1].intrinsics.camera_model.as_deref(), Some("PINHOLE"));
+    assert_eq!((nerf.frames[1].intrinsics.fl_x, nerf.frames[1].intrinsics.w), (Some(3000.0), Some(4000)));
+
+    let back_dir = dir.join("back");
+    convert(&transforms, &back_dir, CameraFormat::NeRF, CameraFormat::COLMAP);
+    let back_cameras = colmap::read_cameras(&back_dir).unwrap();
+    let back_images = colmap::read_images(&back_dir).unwrap();
+    let back_points = colmap::read_points3d(&back_dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // SIMPLE_RADIAL comes back as the equivalent OPENCV camera
+    assert_eq!(
+        back_cameras[0].camera_model().unwrap(),
+        CameraModel::OpenCv { fx: 1500.0, fy: 1500.0, cx: 960.0, cy: 540.0, k1: -0.02, k2: 0.0, p1: 0.0, p2: 0.0 }
+    );
+    assert_eq!(back_cameras[1].camera_model().unwrap(), tele);
+    assert_eq!(back_images.iter().map(|image| (image.image_id, image.camera_id)).collect::<Vec<_>>(), [(3, 1), (5, 2), (8, 1)]);
+    assert_eq!(back_points.len(), 1);
+    assert_close(&back_points[0].xyz, &[1.0, 2.0, 3.0], 1e-9, "point");
+    assert_eq!(back_points[0].rgb, [10, 20, 30]);
+}
@@ -0,0 +1,200 @@
+//! OpenCV FileStorage calibrations: files as OpenCV's calibration sample writes them, rigs
+//! of several cameras, and round trips with COLMAP in YAML and XML.
+
+mod common;
+
+use hylaean_splat::formats::camera_params::colmap::{self, ColmapCamera, ColmapImage, ModelEncoding};
+use hylaean_splat::formats::camera_params::{CameraModel, OpenCvCalibration, OpenCvDistortion, Pose};
+use hylaean_splat::formats::{detect_format, CameraFormat, DataFormat};
+use common::{assert_close, convert, scratch};
+
+/// Output of OpenCV's `camera_calibration` sample, trimmed to two views
+const SAMPLE_YAML: &str = r#"%YAML:1.0
+---
+calibration_time: "Wed 08 Dec 2021 05:13:35 PM CET"
+nr_of_frames: 2
+image_width: 640
+image_height: 480
+board_width: 9
+board_height: 6
+square_size: 50.
+# flags:  +fix_principal_point +zero_tangent_dist
+flags: 12
+fisheye_model: 0
+camera_matrix: !!opencv-matrix
+   rows: 3
+   cols: 3
+   dt: d
+   data: [ 6.5746697944293521e+002, 0., 3.1950000000000000e+002, 0.,
+       6.5856697944293521e+002, 2.3950000000000000e+002, 0., 0., 1. ]
+distortion_coefficients: !!opencv-matrix
+   rows: 5
+   cols: 1
+   dt: d
+   data: [ -4.1802327176423804e-001, 5.0715244063187526e-001, 0., 0.,
+       -5.7843597214487474e-001 ]
+avg_reprojection_error: 3.9253097538359977e-001
+per_view_reprojection_errors: !!opencv-matrix
+   rows: 2
+   cols: 1
+   dt: f
+   data: [ 4.12e-001, 3.73e-001 ]
+# a set of 6-tuples (rotation vector + translation vector) for each view
+extrinsic_parameters: !!opencv-matrix
+   rows: 2
+   cols: 6
+   dt: d
+   data: [ 0., 0., 1.5707963267948966e+000, 1.0e+002, -5.0e+001, 6.0e+002,
+       1.0e-001, -2.0e-001, 3.0e-002, 0., 0., 5.0e+002 ]
+image_points: !!opencv-nd-matrix
+   sizes: [ 2, 1 ]
+   dt: "2f"
+   data: [ 1., 2., 3., 4. ]
+"#;
+
+const SAMPLE_XML: &str = r#"<?xml version="1.0"?>
+<opencv_storage>
+<!-- cv::fisheye::calibrate of the left rig camera -->
+<calibration_time>"Thu 09 Dec 2021"</calibration_time>
+<image_width>1280</image_width>
+<image_height>720</image_height>
+<camera_matrix type_id="opencv-matrix">
+  <rows>3</rows>
+  <cols>3</cols>
+  <dt>d</dt>
+  <data>
+    4.5e+02 0. 6.4e+02 0. 4.5e+02 3.6e+02 0. 0. 1.</data></camera_matrix>
+<distortion_coefficients type_id="opencv-matrix">
+  <rows>4</rows>
+  <cols>1</cols>
+  <dt>d</dt>
+  <data>
+    1.0e-02 -2.0e-03 3.0e-04 -4.0e-05</data></distortion_coefficients>
+<fisheye_model>1</fisheye_model>
+<image_name>"left/000 &amp; more.png"</image_name>
+<rvec type_id="opencv-matrix">
+  <rows>3</rows>
+  <cols>1</cols>
+  <dt>d</dt>
+  <data>
+    0. 3.1415926535897931e+00 0.</data></rvec>
+<tvec type_id="opencv-matrix">
+  <rows>3</rows>
+  <cols>1</cols>
+  <dt>d</dt>
+  <data>
+    1. 2. 3.</data></tvec>
+</opencv_storage>
+"#;
+
+#[test]
+fn rotation_vectors_follow_rodrigues() {
+    let quarter = Pose::from_rotation_vector([0.0, 0.0, std::f64::consts::FRAC_PI_2], [0.0; 3]);
+    assert_close(quarter.rotation.as_flattened(), &[0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0], 1e-9, "quarter turn about z");
+    for rvec in [[0.0; 3], [1e-9, -2e-9, 0.0], [0.3, -1.2, 0.7], [0.0, std::f64::consts::PI, 0.0], [-2.0, 0.5, 1.0]] {
+        let pose = Pose::from_rotation_vector(rvec, [0.0; 3]);
+        let back = Pose::from_rotation_vector(pose.rotation_vector(), [0.0; 3]);
+        assert_close(back.rotation.as_flattened(), pose.rotation.as_flattened(), 1e-9, "rotation vector round trip");
+    }
+    assert_close(&Pose::from_rotation_vector([0.3, -1.2, 0.7], [0.0; 3]).rotation_vector(), &[0.3, -1.2, 0.7], 1e-9, "rvec");
+}
+
+#[test]
+fn sample_calibrations_become_a_colmap_rig() {
+    let dir = scratch("rig");
+    let rig = dir.join("rig");
+    std::fs::create_dir_all(&rig).unwrap();
+    std::fs::write(rig.join("a_front.yml"), SAMPLE_YAML).unwrap();
+    std::fs::write(rig.join("b_left.xml"), SAMPLE_XML).unwrap();
+    assert!(matches!(detect_format(&rig).unwrap(), DataFormat::CameraParameters(CameraFormat::OpenCV)));
+
+    let model_dir = dir.join("sparse");
+    convert(&rig, &model_dir, CameraFormat::OpenCV, CameraFormat::COLMAP);
+    let cameras = colmap::read_cameras(&model_dir).unwrap();
+    let images = colmap::read_images(&model_dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!((cameras[0].width, cameras[0].height), (640, 480));
+    assert_eq!(
+        cameras[0].camera_model().unwrap(),
+        CameraModel::FullOpenCv {
+            fx: 657.4669794429352,
+            fy: 658.5669794429352,
+            cx: 319.5,
+            cy: 239.5,
+            k1: -0.41802327176423804,
+            k2: 0.5071524406318753,
+            p1: 0.0,
+            p2: 0.0,
+            k3: -0.5784359721448747,
+            k4: 0.0,
+            k5: 0.0,
+            k6: 0.0,
+        }
+    );
+    assert_eq!(
+        cameras[1].camera_model().unwrap(),
+        CameraModel::OpenCvFisheye { fx: 450.0, fy: 450.0, cx: 640.0, cy: 360.0, k1: 0.01, k2: -0.002, k3: 0.0003, k4: -0.00004 }
+    );
+
+    // Unnamed sample views are named after their file; rvec/tvec are world-to-camera
+    let names: Vec<&str> = images.iter().map(|image| image.name.as_str()).collect();
+    assert_eq!(names, ["a_front_0000.png", "a_front_0001.png", "left/000 & more.png"]);
+    assert_eq!(images.iter().map(|image| image.camera_id).collect::<Vec<_>>(), [1, 1, 2]);
+    let first = images[0].world_to_camera();
+    assert_close(first.rotation.as_flattened(), &[0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0], 1e-9, "first view rotation");
+    assert_close(&first.translation, &[100.0, -50.0, 600.0], 1e-9, "first view translation");
+    let flipped = images[2].world_to_camera();
+    assert_close(flipped.rotation.as_flattened(), &[-1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, -1.0], 1e-9, "half turn about y");
+}
+
+#[test]
+fn colmap_round_trips_through_yaml_and_xml() {
+    let dir = scratch("round_trip");
+    let model_dir = dir.join("sparse");
+    std::fs::create_dir_all(&model_dir).unwrap();
+
+    let models = [
+        CameraModel::OpenCv { fx: 1200.0, fy: 1190.5, cx: 960.25, cy: 539.75, k1: -0.05, k2: 0.01, p1: 1e-4, p2: -2e-4 },
+        CameraModel::OpenCvFisheye { fx: 300.0, fy: 301.0, cx: 320.0, cy: 240.0, k1: 0.1, k2: -0.01, k3: 0.001, k4: 1e-4 },
+    ];
+    let cameras = vec![ColmapCamera::from_model(1, 1920, 1080, &models[0]), ColmapCamera::from_model(2, 640, 480, &models[1])];
+    let images: Vec<ColmapImage> = (0..5u32)
+        .map(|i| {
+            let pose = Pose::from_quaternion([0.9, 0.1 * i as f64, -0.3, 0.2], [i as f64, -2.0, 3.5]);
+            ColmapImage::new(i + 1, if i < 3 { 1 } else { 2 }, format!("frame_{}.jpg", i), &pose)
+        })
+        .collect();
+    colmap::write_cameras(&model_dir, &cameras, ModelEncoding::Text).unwrap();
+    colmap::write_images(&model_dir, &images, ModelEncoding::Text).unwrap();
+
+    for extension in ["yml", "xml"] {
+        let calibration = dir.join(format!("calibration.{}", extension));
+        convert(&model_dir, &calibration, CameraFormat::COLMAP, CameraFormat::OpenCV);
+        let files = [1, 2].map(|id| dir.join(format!("calibration_{}.{}", id, extension)));
+        assert!(files.iter().all(|file| file.is_file()), "{:?}", files);
+        let fisheye = OpenCvCalibration::read(&files[1]).unwrap();
+        assert!(matches!(fisheye.distortion, OpenCvDistortion::Fisheye(_)));
+        assert_eq!(fisheye.views.len(), 2);
+
+        // A rig directory of the two files converts back to the same model
+        let rig = dir.join(format!("rig_{}", extension));
+        std::fs::create_dir_all(&rig).unwrap();
+        for file in &files {
+            std::fs::rename(file, rig.join(file.file_name().unwrap())).unwrap();
+        }
+        let back_dir = dir.join(format!("back_{}", extension));
+        convert(&rig, &back_dir, CameraFormat::OpenCV, CameraFormat::COLMAP);
+        let back_cameras = colmap::read_cameras(&back_dir).unwrap();
+        let back_images = colmap::read_images(&back_dir).unwrap();
+        assert_eq!(back_cameras.iter().map(|camera| camera.camera_model().unwrap()).collect::<Vec<_>>(), models);
+        assert_eq!(back_images.len(), images.len());
+        for (back, original) in back_images.iter().zip(&images) {
+            assert_eq!((back.name.as_str(), back.camera_id), (original.name.as_str(), original.camera_id));
+            let (back, original) = (back.world_to_camera(), original.world_to_camera());
+            assert_close(back.rotation.as_flattened(), original.rotation.as_flattened(), 1e-9, "rotation");
+            assert_close(&back.translation, &original.translation, 1e-9, "translation");
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,94 @@
+//! PCD through every DATA mode: ascii -> binary -> binary_compressed -> ascii keeps a
+//! packed `rgb` float and an extra `intensity` field exactly, and PLY output carries both.
+
+mod common;
+
+use hylaean_splat::formats::pcd::{self, PcdData, PcdReader};
+use hylaean_splat::formats::ply::{PlyFile, Property};
+use hylaean_splat::formats::point_cloud::PointCloudConverter;
+use hylaean_splat::formats::{DataFormat, FormatConverter, PointCloudFormat};
+use std::path::Path;
+use common::scratch;
+
+const POINTS: usize = 200;
+
+/// `x y z rgb intensity` as PCL writes them, colors as their integer bits
+fn ascii_pcd(path: &Path) -> Vec<[u32; 5]> {
+    let mut text = format!(
+        "# .PCD v0.7 - Point Cloud Data file format\nVERSION 0.7\nFIELDS x y z rgb intensity\n\
+         SIZE 4 4 4 4 4\nTYPE F F F F F\nCOUNT 1 1 1 1 1\nWIDTH {0}\nHEIGHT 1\n\
+         VIEWPOINT 0 0 0 1 0 0 0\nPOINTS {0}\nDATA ascii\n",
+        POINTS
+    );
+    let mut expected = Vec::new();
+    for i in 0..POINTS {
+        let t = i as f32;
+        // PCL fills alpha with 255, which makes many packed colors NaN bit patterns
+        let rgb = pcd::pack_rgba([(i * 7 % 256) as u8, (255 - i) as u8, (i * 13 % 256) as u8, if i % 2 == 0 { 255 } else { 0 }]);
+        let values = [(t * 0.3).sin() * 4.0, t * 0.01 - 1.0, 1.0 / (t + 3.0), rgb, (i % 17) as f32 * 12.5];
+        text.push_str(&format!(
+            "{} {} {} {} {}\n",
+            values[0], values[1], values[2], rgb.to_bits(), values[4]
+        ));
+        expected.push(values.map(f32::to_bits));
+    }
+    std::fs::write(path, text).unwrap();
+    expected
+}
+
+/// Every record of a PCD file as the bits of its float values
+fn read_bits(path: &Path) -> Vec<[u32; 5]> {
+    let mut reader = PcdReader::open(path).unwrap();
+    let mut records = Vec::new();
+    let mut record = Default::default();
+    while reader.read_record(&mut record).unwrap() {
+        let bits: Vec<u32> = record.0.iter().map(|value| match value {
+            Property::Float(v) => v.to_bits(),
+            other => panic!("expected float fields, found {:?}", other),
+        }).collect();
+        records.push(bits.try_into().unwrap());
+    }
+    records
+}
+
+#[test]
+fn data_modes_round_trip_colors_and_extra_fields() {
+    let dir = scratch("pcd_modes");
+    let pcd_format = DataFormat::PointCloud(PointCloudFormat::PCD);
+    let ascii = dir.join("ascii.pcd");
+    let expected = ascii_pcd(&ascii);
+
+    let mut previous = ascii.clone();
+    let mut steps = Vec::new();
+    for data in [PcdData::Binary, PcdData::BinaryCompressed, PcdData::Ascii] {
+        let path = dir.join(format!("{}.out.pcd", data));
+        PointCloudConverter::new().with_pcd_data(data).convert(&previous, &path, &pcd_format, &pcd_format).unwrap();
+        steps.push((data, pcd::read_header(&path).unwrap(), read_bits(&path)));
+        previous = path;
+    }
+
+    // PLY has no packed colors, so `rgb` becomes red/green/blue next to intensity
+    let ply_path = dir.join("points.ply");
+    let ply_format = DataFormat::PointCloud(PointCloudFormat::PLY);
+    PointCloudConverter::new().convert(&dir.join("binary_compressed.out.pcd"), &ply_path, &pcd_format, &ply_format).unwrap();
+    let ply = PlyFile::read(&ply_path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    for (data, header, records) in &steps {
+        assert_eq!(header.data, *data);
+        let names: Vec<&str> = header.fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(names, ["x", "y", "z", "rgb", "intensity"], "{}", data);
+        assert_eq!(header.points, POINTS, "{}", data);
+        assert_eq!(records, &expected, "{} records differ", data);
+    }
+
+    let vertex = &ply.header.elements["vertex"];
+    let names: Vec<&str> = vertex.properties.keys().map(|name| name.as_str()).collect();
+    assert_eq!(names, ["x", "y", "z", "red", "green", "blue", "intensity"]);
+    let (_, records) = &ply.elements[0];
+    for (record, expected) in records.iter().zip(&expected) {
+        let [r, g, b, _] = pcd::unpack_rgba(&Property::Float(f32::from_bits(expected[3])));
+        assert_eq!(record.0[3..6], [Property::UChar(r), Property::UChar(g), Property::UChar(b)]);
+        assert_eq!(record.0[6], Property::Float(f32::from_bits(expected[4])));
+    }
+}
@@ -0,0 +1,165 @@
+//! Property tests for camera pose math and the COLMAP -> NeRF -> COLMAP round trip,
+//! over seeded random rotations (including half turns, where the quaternion trace
+//! shortcut breaks down) and translations.
+
+mod common;
+
+use hylaean_splat::formats::camera_params::colmap::{self, ColmapCamera, ColmapImage, ModelEncoding};
+use hylaean_splat::formats::camera_params::pose::{self, Matrix4};
+use hylaean_splat::formats::camera_params::{CameraAxes, CameraModel, NeRFCamera, Pose};
+use hylaean_splat::formats::CameraFormat;
+use common::{assert_relative, convert};
+
+const CASES: usize = 500;
+const TOLERANCE: f64 = 1e-9;
+
+/// xorshift64*, so failures reproduce
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next()
+    }
+
+    fn quaternion(&mut self, case: usize) -> [f64; 4] {
+        // Every tenth case is a half turn (w = 0) about a random axis
+        let w = if case.is_multiple_of(10) { 0.0 } else { self.range(-1.0, 1.0) };
+        let q = [w, self.range(-1.0, 1.0), self.range(-1.0, 1.0), self.range(-1.0, 1.0)];
+        let norm = q.iter().map(|v| v * v).sum::<f64>().sqrt();
+        q.map(|v| v / norm)
+    }
+
+    fn pose(&mut self, case: usize) -> Pose {
+        let q = self.quaternion(case);
+        let t = [self.range(-50.0, 50.0), self.range(-50.0, 50.0), self.range(-50.0, 50.0)];
+        Pose::from_quaternion(q, t)
+    }
+}
+
+fn assert_same_pose(actual: &Pose, expected: &Pose, what: &str) {
+    assert_relative(actual.rotation.as_flattened(), expected.rotation.as_flattened(), TOLERANCE, what);
+    assert_relative(&actual.translation, &expected.translation, TOLERANCE, what);
+}
+
+/// Quaternions `q` and `-q` are the same rotation
+fn assert_same_quaternion(actual: [f64; 4], expected: [f64; 4], what: &str) {
+    let dot: f64 = actual.iter().zip(&expected).map(|(a, e)| a * e).sum();
+    let aligned = if dot < 0.0 { expected.map(|v| -v) } else { expected };
+    assert_relative(&actual, &aligned, TOLERANCE, what);
+}
+
+#[test]
+fn pose_algebra() {
+    let mut rng = Rng(0x5eed_0001);
+    for case in 0..CASES {
+        let q = rng.quaternion(case);
+        let pose = rng.pose(case);
+
+        assert_same_quaternion(Pose::from_quaternion(q, [0.0; 3]).quaternion(), q, "quaternion round trip");
+        assert_same_pose(&pose.then(&pose.inverse()), &Pose::default(), "pose then inverse");
+        assert_same_pose(&pose.inverse().inverse(), &pose, "double inverse");
+        assert_same_pose(&Pose::from_matrix(&pose.to_matrix()), &pose, "matrix round trip");
+
+        let point = [rng.range(-5.0, 5.0), rng.range(-5.0, 5.0), rng.range(-5.0, 5.0)];
+        let other = rng.pose(case + 1);
+        assert_relative(
+            &pose.then(&other).transform_point(point),
+            &other.transform_point(pose.transform_point(point)),
+            TOLERANCE,
+            "composition",
+        );
+    }
+}
+
+#[test]
+fn axis_changes_keep_center_and_viewing_direction() {
+    let mut rng = Rng(0x5eed_0002);
+    for case in 0..CASES {
+        let world_to_camera = rng.pose(case);
+        let opencv = world_to_camera.inverse();
+        let opengl = opencv.change_camera_axes(CameraAxes::OpenCv, CameraAxes::OpenGl);
+
+        // The camera center is -R^T t
+        let center = [0, 1, 2].map(|i| -(0..3).map(|k| world_to_camera.rotation[k][i] * world_to_camera.translation[k]).sum::<f64>());
+        assert_relative(&opengl.translation, &center, TOLERANCE, "camera center");
+
+        // OpenCV looks along +z, OpenGL along -z; both must point the same way in the world
+        let forward_cv = [0, 1, 2].map(|i| opencv.rotation[i][2]);
+        let forward_gl = [0, 1, 2].map(|i| -opengl.rotation[i][2]);
+        assert_relative(&forward_gl, &forward_cv, TOLERANCE, "viewing direction");
+        let up_cv = [0, 1, 2].map(|i| -opencv.rotation[i][1]);
+        let up_gl = [0, 1, 2].map(|i| opengl.rotation[i][1]);
+        assert_relative(&up_gl, &up_cv, TOLERANCE, "up direction");
+
+        assert_same_pose(&opengl.change_camera_axes(CameraAxes::OpenGl, CameraAxes::OpenCv), &opencv, "axes round trip");
+    }
+}
+
+#[test]
+fn nearest_rotation_removes_scale_and_noise() {
+    let mut rng = Rng(0x5eed_0003);
+    for case in 0..CASES {
+        let pose = rng.pose(case);
+        let scale = rng.range(0.1, 10.0);
+        let mut matrix: Matrix4 = pose.to_matrix();
+        for row in matrix.iter_mut().take(3) {
+            for value in row.iter_mut().take(3) {
+                *value = *value * scale + rng.range(-1e-12, 1e-12);
+            }
+        }
+        let recovered = Pose::from_matrix(&matrix);
+        for (a, e) in recovered.rotation.as_flattened().iter().zip(pose.rotation.as_flattened()) {
+            assert!((a - e).abs() < 1e-10, "case {}: {:?} != {:?}", case, recovered.rotation, pose.rotation);
+        }
+        assert_eq!(recovered.translation, pose.translation);
+    }
+    assert_eq!(pose::nearest_rotation(&[[0.0; 3]; 3]), Pose::default().rotation);
+}
+
+#[test]
+fn colmap_to_nerf_to_colmap_round_trips_poses() {
+    let dir = std::env::temp_dir().join(format!("hylaean_splat_pose_{}", std::process::id()));
+    let model_dir = dir.join("sparse");
+    std::fs::create_dir_all(&model_dir).unwrap();
+
+    let mut rng = Rng(0x5eed_0004);
+    let model = CameraModel::OpenCv { fx: 1200.0, fy: 1190.5, cx: 960.25, cy: 539.75, k1: -0.05, k2: 0.01, p1: 1e-4, p2: -2e-4 };
+    let cameras = vec![ColmapCamera::from_model(1, 1920, 1080, &model)];
+    let poses: Vec<Pose> = (0..CASES).map(|case| rng.pose(case)).collect();
+    let images: Vec<ColmapImage> = poses.iter().enumerate()
+        .map(|(i, pose)| ColmapImage::new(i as u32 + 1, 1, format!("images/{:05}.png", i), pose))
+        .collect();
+    colmap::write_cameras(&model_dir, &cameras, ModelEncoding::Binary).unwrap();
+    colmap::write_images(&model_dir, &images, ModelEncoding::Binary).unwrap();
+
+    let transforms = dir.join("transforms.json");
+    convert(&model_dir, &transforms, CameraFormat::COLMAP, CameraFormat::NeRF);
+    let nerf: NeRFCamera = serde_json::from_reader(std::fs::File::open(&transforms).unwrap()).unwrap();
+    for (frame, pose) in nerf.frames.iter().zip(&poses) {
+        // transform_matrix is camera-to-world in OpenGL axes
+        let expected = pose.inverse().change_camera_axes(CameraAxes::OpenCv, CameraAxes::OpenGl);
+        assert_same_pose(&Pose::from_matrix(&frame.transform_matrix), &expected, "NeRF frame");
+    }
+
+    let back_dir = dir.join("back");
+    convert(&transforms, &back_dir, CameraFormat::NeRF, CameraFormat::COLMAP);
+    let back_cameras = colmap::read_cameras(&back_dir).unwrap();
+    let back_images = colmap::read_images(&back_dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(back_cameras[0].camera_model().unwrap(), model);
+    assert_eq!((back_cameras[0].width, back_cameras[0].height), (1920, 1080));
+    assert_eq!(back_images.len(), images.len());
+    for (back, original) in back_images.iter().zip(&images) {
+        assert_eq!(back.name, original.name);
+        assert_same_quaternion(back.world_to_camera().quaternion(), original.world_to_camera().quaternion(), &original.name);
+        assert_relative(&[back.tx, back.ty, back.tz], &[original.tx, original.ty, original.tz], TOLERANCE, &original.name);
+    }
+}
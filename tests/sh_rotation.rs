@@ -0,0 +1,51 @@
+//! View-dependent color under rotation: after `transform_cloud`, the SH seen along a
+//! rotated direction match the original SH along the unrotated one, at every band.
+
+use hylaean_splat::formats::gaussian::{Gaussian, GaussianCloud};
+use hylaean_splat::formats::math;
+use hylaean_splat::formats::splat_ops::transform::{self, Similarity};
+
+/// One color channel of `f_rest` evaluated along `direction`
+fn evaluate(sh_rest: &[f32], sh_degree: u32, channel: usize, direction: [f64; 3]) -> f64 {
+    let per_channel = sh_rest.len() / 3;
+    let basis: Vec<f64> = (1..=sh_degree as usize).flat_map(|band| transform::sh_band(band, direction)).collect();
+    basis.iter().zip(&sh_rest[channel * per_channel..]).map(|(y, c)| y * *c as f64).sum()
+}
+
+fn directions() -> Vec<[f64; 3]> {
+    (0..40).map(|i| {
+        let t = i as f64;
+        let v = [(t * 0.7).sin(), (t * 1.3).cos(), (t * 0.37).sin() - 0.2];
+        let norm = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        v.map(|c| c / norm)
+    }).collect()
+}
+
+#[test]
+fn rotated_sh_match_along_rotated_directions() {
+    for sh_degree in [1, 3, 4, 6, 8] {
+        for euler in [[0.0, 0.0, 90.0], [90.0, 0.0, 0.0], [30.0, -45.0, 90.0]] {
+            let similarity = Similarity::from_euler_degrees(euler);
+            let mut cloud = GaussianCloud::new(sh_degree);
+            let mut gaussian = Gaussian::new(sh_degree);
+            for (k, coefficient) in gaussian.sh_rest.iter_mut().enumerate() {
+                *coefficient = ((k as f32 * 0.77).sin() + 0.1) * 0.4;
+            }
+            cloud.gaussians.push(gaussian.clone());
+            transform::transform_cloud(&mut cloud, &similarity);
+            let rotated = &cloud.gaussians[0].sh_rest;
+
+            for direction in directions() {
+                let moved = math::apply(&similarity.rotation, direction);
+                for channel in 0..3 {
+                    let expected = evaluate(&gaussian.sh_rest, sh_degree, channel, direction);
+                    let actual = evaluate(rotated, sh_degree, channel, moved);
+                    assert!(
+                        (actual - expected).abs() < 1e-4,
+                        "degree {} rotated by {:?}: {} != {} along {:?}", sh_degree, euler, actual, expected, direction
+                    );
+                }
+            }
+        }
+    }
+}
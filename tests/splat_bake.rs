@@ -0,0 +1,116 @@
+//! SH degree changes keep each color channel's own coefficients, and baking writes the
+//! DC term evaluated to 8-bit RGB (plus optional alpha) to every streaming format.
+
+mod common;
+
+use hylaean_splat::formats::gaussian::{inverse_sigmoid, sh_rest_len, Gaussian, GaussianCloud, SH_C0};
+use hylaean_splat::formats::pcd::PcdData;
+use hylaean_splat::formats::ply::{Encoding, PlyRecord, Property};
+use hylaean_splat::formats::point_cloud::stream::{self, PointChunk, SinkOptions};
+use hylaean_splat::formats::splat_ops::bake::GaussianSource;
+use hylaean_splat::formats::PointCloudFormat;
+use std::path::Path;
+use common::scratch;
+
+fn cloud(count: usize, sh_degree: u32) -> GaussianCloud {
+    let mut cloud = GaussianCloud::new(sh_degree);
+    let per_channel = sh_rest_len(sh_degree) / 3;
+    cloud.gaussians = (0..count).map(|i| {
+        let t = i as f32;
+        let mut gaussian = Gaussian::new(sh_degree);
+        gaussian.position = [t * 0.5, -t, 2.0 + t * 0.25];
+        // Spans below 0 and above 1 once evaluated, so clamping is exercised too
+        gaussian.sh_dc = [(t * 0.7).sin() * 2.2, -0.4 + t * 0.05, (t * 1.3).cos()];
+        gaussian.opacity = inverse_sigmoid(0.02 + (i % 10) as f32 * 0.1);
+        // Coefficient k of channel c is i + 100 c + k, so a wrong stride shows
+        for (index, coefficient) in gaussian.sh_rest.iter_mut().enumerate() {
+            *coefficient = t + (100 * (index / per_channel) + index % per_channel) as f32;
+        }
+        gaussian
+    }).collect();
+    cloud
+}
+
+#[test]
+fn sh_degree_changes_keep_each_channel() {
+    let original = cloud(3, 3);
+    let mut truncated = original.clone();
+    truncated.set_sh_degree(1);
+    assert_eq!(truncated.sh_degree, 1);
+    for (gaussian, source) in truncated.gaussians.iter().zip(&original.gaussians) {
+        assert_eq!(gaussian.sh_rest.len(), 9);
+        for channel in 0..3 {
+            assert_eq!(gaussian.sh_rest[channel * 3..channel * 3 + 3], source.sh_rest[channel * 15..channel * 15 + 3], "channel {}", channel);
+        }
+        assert_eq!((gaussian.position, gaussian.sh_dc), (source.position, source.sh_dc));
+    }
+
+    // Padding back up zero-fills the dropped bands, per channel
+    let mut padded = truncated.clone();
+    padded.set_sh_degree(2);
+    for (gaussian, source) in padded.gaussians.iter().zip(&original.gaussians) {
+        assert_eq!(gaussian.sh_rest.len(), 24);
+        for channel in 0..3 {
+            assert_eq!(gaussian.sh_rest[channel * 8..channel * 8 + 3], source.sh_rest[channel * 15..channel * 15 + 3]);
+            assert!(gaussian.sh_rest[channel * 8 + 3..channel * 8 + 8].iter().all(|c| *c == 0.0));
+        }
+    }
+
+    let mut flat = original.clone();
+    flat.set_sh_degree(0);
+    assert!(flat.gaussians.iter().all(|gaussian| gaussian.sh_rest.is_empty()));
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn read_points(path: &Path, format: &PointCloudFormat) -> (Vec<String>, Vec<PlyRecord>) {
+    let mut source = stream::open_source(path, format).unwrap();
+    let names = source.schema().iter().map(|(name, _)| name.clone()).collect();
+    let mut records = Vec::new();
+    let mut chunk = PointChunk::with_capacity(64);
+    while source.read_chunk(&mut chunk).unwrap() > 0 {
+        records.extend(chunk.records().iter().cloned());
+    }
+    (names, records)
+}
+
+#[test]
+fn baked_colors_are_the_evaluated_dc_term() {
+    let dir = scratch("bake");
+    let scene = cloud(40, 2);
+    let options = SinkOptions { ply_encoding: Encoding::BinaryLittleEndian, pcd_data: PcdData::Binary };
+    let cases = [
+        (PointCloudFormat::PLY, "ply", true),
+        (PointCloudFormat::PLY, "ply", false),
+        (PointCloudFormat::PCD, "pcd", false),
+        (PointCloudFormat::PCD, "pcd", true),
+        (PointCloudFormat::XYZ, "xyz", false),
+    ];
+    let mut results = Vec::new();
+    for (index, (format, extension, alpha)) in cases.iter().enumerate() {
+        let path = dir.join(format!("baked_{}.{}", index, extension));
+        let mut source = GaussianSource::new(scene.clone(), *alpha);
+        let sink = stream::create_sink(&path, format, &source, options).unwrap();
+        assert_eq!(stream::pump(&mut source, sink, 16).unwrap(), 40);
+        results.push((format, *alpha, read_points(&path, format)));
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    for (format, alpha, (names, records)) in results {
+        let mut expected_names = vec!["x", "y", "z", "red", "green", "blue"];
+        if alpha {
+            expected_names.push("alpha");
+        }
+        assert_eq!(names, expected_names, "{:?}", format);
+        assert_eq!(records.len(), 40);
+        for (record, gaussian) in records.iter().zip(&scene.gaussians) {
+            let rgb = gaussian.sh_dc.map(|dc| Property::UChar(to_u8(0.5 + SH_C0 * dc)));
+            assert_eq!(record.0[3..6], rgb, "{:?} color of {:?}", format, gaussian.position);
+            if alpha {
+                assert_eq!(record.0[6], Property::UChar(to_u8(gaussian.alpha())));
+            }
+        }
+    }
+}
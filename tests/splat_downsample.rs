@@ -0,0 +1,188 @@
+//! Downsampling: voxel merging matches the moments of the merged mixture, LOD chains hit
+//! their per-level targets and describe their files, and plain point clouds are streamed
+//! down to one averaged point per voxel.
+
+mod common;
+
+use hylaean_splat::formats::gaussian::{inverse_sigmoid, Gaussian, GaussianCloud};
+use hylaean_splat::formats::math::{self, Matrix3};
+use hylaean_splat::formats::ply::{Encoding, PlyFile, Property};
+use hylaean_splat::formats::splat_ops::downsample;
+use hylaean_splat::formats::splat_ops::lod::{self, LodMethod, LodOptions};
+use hylaean_splat::formats::splat_ops;
+use hylaean_splat::formats::PointCloudFormat;
+use std::path::Path;
+use common::{assert_close, scratch};
+
+fn gaussian(position: [f32; 3], alpha: f32, scale: [f32; 3], rotation: [f32; 4], color: [f32; 3]) -> Gaussian {
+    let mut gaussian = Gaussian::new(1);
+    gaussian.position = position;
+    gaussian.opacity = inverse_sigmoid(alpha);
+    gaussian.scale = scale.map(f32::ln);
+    gaussian.rotation = rotation;
+    gaussian.sh_dc = color;
+    for (k, coefficient) in gaussian.sh_rest.iter_mut().enumerate() {
+        *coefficient = color[0] + k as f32 * 0.1;
+    }
+    gaussian
+}
+
+fn covariance(gaussian: &Gaussian) -> Matrix3 {
+    let q = gaussian.rotation.map(f64::from);
+    let norm = q.iter().map(|v| v * v).sum::<f64>().sqrt();
+    let rotation = math::quaternion_to_matrix(q.map(|v| v / norm));
+    let variances = gaussian.scale.map(|s| (2.0 * s as f64).exp());
+    let scaled = [0, 1, 2].map(|i| [0, 1, 2].map(|j| rotation[i][j] * variances[j]));
+    math::multiply(&scaled, &math::transpose(&rotation))
+}
+
+/// Opacity-weighted mean and covariance of the mixture, and the opacity both the
+/// integrated density and compositing allow
+fn mixture(group: &[Gaussian]) -> ([f64; 3], Matrix3, f64) {
+    let weights: Vec<f64> = group.iter().map(|g| g.alpha() as f64).collect();
+    let total: f64 = weights.iter().sum();
+    let positions: Vec<[f64; 3]> = group.iter().map(|g| g.position.map(f64::from)).collect();
+    let mean = [0, 1, 2].map(|i| weights.iter().zip(&positions).map(|(w, p)| w * p[i]).sum::<f64>() / total);
+    let covariance = [0, 1, 2].map(|i| [0, 1, 2].map(|j| {
+        group.iter().zip(&weights).zip(&positions)
+            .map(|((g, w), p)| w * (covariance(g)[i][j] + (p[i] - mean[i]) * (p[j] - mean[j])))
+            .sum::<f64>() / total
+    }));
+    let mass: f64 = group.iter().map(|g| g.alpha() as f64 * g.linear_scale().iter().map(|s| *s as f64).product::<f64>()).sum();
+    let composited = 1.0 - group.iter().map(|g| 1.0 - g.alpha() as f64).product::<f64>();
+    (mean, covariance, (mass / math::determinant(&covariance).sqrt()).min(composited))
+}
+
+fn assert_merged(group: Vec<Gaussian>) {
+    let (mean, expected_covariance, alpha) = mixture(&group);
+    let weights: Vec<f32> = group.iter().map(|g| g.alpha()).collect();
+    let color = [0, 1, 2].map(|c| group.iter().zip(&weights).map(|(g, w)| w * g.sh_dc[c]).sum::<f32>() / weights.iter().sum::<f32>());
+    let merged = downsample::merge_gaussians(group);
+
+    assert_close(&merged.position.map(f64::from), &mean, 1e-6, "mixture mean");
+    assert_close(covariance(&merged).as_flattened(), expected_covariance.as_flattened(), 1e-6, "mixture covariance");
+    assert!((merged.alpha() as f64 - alpha).abs() < 1e-5, "opacity {} != {}", merged.alpha(), alpha);
+    assert_close(&merged.sh_dc.map(f64::from), &color.map(f64::from), 1e-6, "color");
+}
+
+#[test]
+fn merging_matches_the_mixture_moments() {
+    let turned = [0.9, 0.2, -0.3, 0.25];
+    // Faint and apart: the density bound applies
+    assert_merged(vec![
+        gaussian([0.1, 0.1, 0.1], 0.2, [0.02, 0.03, 0.01], [1.0, 0.0, 0.0, 0.0], [0.5, -0.2, 0.1]),
+        gaussian([0.3, 0.2, 0.15], 0.05, [0.05, 0.01, 0.02], turned, [-0.4, 0.3, 0.9]),
+    ]);
+    // Opaque and on top of each other: capped at 1 - (1 - a)(1 - b)
+    let (_, _, alpha) = mixture(&[
+        gaussian([0.1, 0.1, 0.1], 0.6, [0.05, 0.05, 0.05], turned, [0.0; 3]),
+        gaussian([0.1, 0.1, 0.1001], 0.5, [0.05, 0.05, 0.05], turned, [0.0; 3]),
+    ]);
+    assert!((alpha - 0.8).abs() < 1e-6);
+    assert_merged(vec![
+        gaussian([0.1, 0.1, 0.1], 0.6, [0.05, 0.05, 0.05], turned, [1.0, 0.0, 0.0]),
+        gaussian([0.1, 0.1, 0.1001], 0.5, [0.05, 0.05, 0.05], turned, [0.0, 1.0, 0.0]),
+    ]);
+}
+
+#[test]
+fn voxel_downsampling_merges_only_shared_voxels() {
+    let mut cloud = GaussianCloud::new(1);
+    cloud.gaussians = vec![
+        gaussian([0.1, 0.1, 0.1], 0.4, [0.02, 0.03, 0.01], [0.8, 0.1, 0.0, 0.2], [0.5, 0.5, 0.5]),
+        gaussian([0.7, 0.2, 0.3], 0.3, [0.01, 0.01, 0.04], [1.0, 0.0, 0.0, 0.0], [0.1, 0.2, 0.3]),
+        gaussian([3.2, 0.5, 0.5], 0.9, [0.1, 0.2, 0.3], [0.5, 0.5, 0.5, 0.5], [-1.0, 0.0, 1.0]),
+        gaussian([f32::NAN, 0.0, 0.0], 0.9, [0.1; 3], [1.0, 0.0, 0.0, 0.0], [0.0; 3]),
+    ];
+    let alone = cloud.gaussians[2].clone();
+    let pair = cloud.gaussians[..2].to_vec();
+
+    let removed = downsample::voxel_downsample(&mut cloud, 1.0).unwrap();
+    assert_eq!(removed, 2, "one merge and one non-finite position");
+    assert_eq!(cloud.gaussians[1], alone, "a Gaussian alone in its voxel is kept as is");
+    assert_eq!(cloud.gaussians[0], downsample::merge_gaussians(pair));
+    assert!(downsample::voxel_downsample(&mut cloud, 0.0).is_err());
+}
+
+fn scene(count: usize) -> GaussianCloud {
+    let mut cloud = GaussianCloud::new(1);
+    cloud.gaussians = (0..count).map(|i| {
+        let t = i as f32;
+        let position = [(t * 0.618).fract() * 10.0, (t * 0.414).fract() * 6.0, (t * 0.732).fract() * 3.0];
+        gaussian(position, 0.1 + (i % 9) as f32 * 0.1, [0.01 + (i % 5) as f32 * 0.005, 0.02, 0.01], [1.0, 0.0, 0.0, 0.0], [0.2; 3])
+    }).collect();
+    cloud
+}
+
+#[derive(serde::Deserialize)]
+struct Manifest {
+    method: String,
+    sh_degree: u32,
+    levels: Vec<Level>,
+}
+
+#[derive(serde::Deserialize)]
+struct Level {
+    level: usize,
+    file: String,
+    gaussians: usize,
+    bytes: u64,
+    voxel_size: Option<f64>,
+}
+
+#[test]
+fn lod_levels_hit_their_targets_and_the_manifest_matches_disk() {
+    let dir = scratch("lod_chain");
+    let original = scene(4000);
+    for method in [LodMethod::Voxel, LodMethod::Importance] {
+        let out = dir.join(format!("{:?}", method));
+        let options = LodOptions { levels: 4, ratio: 0.5, method };
+        lod::build_lod_chain(&original, Path::new("garden.ply"), &out, &options, "ply", Encoding::BinaryLittleEndian).unwrap();
+
+        let manifest: Manifest = serde_json::from_slice(&std::fs::read(out.join("garden_lod.json")).unwrap()).unwrap();
+        assert_eq!(manifest.method, format!("{:?}", method).to_lowercase());
+        assert_eq!(manifest.sh_degree, 1);
+        assert_eq!(manifest.levels.len(), 4);
+        for (index, level) in manifest.levels.iter().enumerate() {
+            let path = out.join(&level.file);
+            assert_eq!((level.level, level.file.as_str()), (index, format!("garden_lod{}.ply", index).as_str()));
+            assert_eq!(level.bytes, std::fs::metadata(&path).unwrap().len(), "{}", level.file);
+            let (cloud, _) = splat_ops::load_cloud(&path).unwrap();
+            assert_eq!(cloud.len(), level.gaussians, "{}", level.file);
+
+            let target = 4000.0 * 0.5f64.powi(index as i32);
+            let off = (level.gaussians as f64 - target).abs() / target;
+            match method {
+                LodMethod::Importance => assert_eq!(level.gaussians as f64, target, "{}", level.file),
+                LodMethod::Voxel => assert!(off < 0.1, "level {}: {} Gaussians for a target of {}", index, level.gaussians, target),
+            }
+            assert_eq!(level.voxel_size.is_some(), method == LodMethod::Voxel && index > 0);
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn point_clouds_stream_down_to_voxel_averages() {
+    let dir = scratch("voxel_points");
+    let input = dir.join("points.ply");
+    let output = dir.join("points_down.ply");
+    std::fs::write(&input, "ply\nformat ascii 1.0\nelement vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+        property uchar red\nproperty float intensity\nend_header\n\
+        0.1 0.2 0.3 10 1.5\n0.3 0.4 0.5 11 2.5\n1.5 0.5 0.5 200 7\n0.9 0.9 0.9 12 3.5\n").unwrap();
+    let written = downsample::voxel_downsample_points(&input, &PointCloudFormat::PLY, &output, &PointCloudFormat::PLY, 1.0).unwrap();
+    let ply = PlyFile::read(&output).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(written, 2);
+    let (_, records) = &ply.elements[0];
+    let float = |value: &Property| match value {
+        Property::Float(v) => *v as f64,
+        other => panic!("expected a float, found {:?}", other),
+    };
+    let first: Vec<f64> = [0, 1, 2, 4].iter().map(|&i| float(&records[0].0[i])).collect();
+    assert_close(&first, &[1.3 / 3.0, 0.5, 1.7 / 3.0, 2.5], 1e-6, "first voxel average");
+    assert_eq!(records[0].0[3], Property::UChar(11), "integers are rounded averages");
+    assert_eq!(records[1].0[3], Property::UChar(200));
+    assert!(ply.header.comments.iter().any(|comment| comment.contains("voxel downsampled 4 points")));
+}
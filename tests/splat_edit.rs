@@ -0,0 +1,124 @@
+//! Splat edits: region crops (inverted too), opacity and scale filters, statistical
+//! outlier removal and keeping the Gaussians with the largest opacity x volume.
+
+use hylaean_splat::formats::gaussian::{inverse_sigmoid, Gaussian, GaussianCloud};
+use hylaean_splat::formats::splat_ops::edit::{self, EditOperation, Region};
+
+fn cloud_at(positions: &[[f32; 3]]) -> GaussianCloud {
+    let mut cloud = GaussianCloud::new(0);
+    cloud.gaussians = positions.iter().map(|position| {
+        let mut gaussian = Gaussian::new(0);
+        gaussian.position = *position;
+        gaussian
+    }).collect();
+    cloud
+}
+
+fn positions(cloud: &GaussianCloud) -> Vec<[f32; 3]> {
+    cloud.gaussians.iter().map(|gaussian| gaussian.position).collect()
+}
+
+#[test]
+fn oriented_box_crops_in_its_own_frame() {
+    // 4 x 1 x 1 box turned 30 degrees about z; the quaternion is deliberately not unit
+    // length, and the angle tells rotating into the box frame from rotating out of it
+    let (sin, cos) = 15f32.to_radians().sin_cos();
+    let region = Region::from_oriented_box_values(&[1.0, 2.0, 3.0, 2.0, 0.5, 0.5, 2.0 * cos, 0.0, 0.0, 2.0 * sin]).unwrap();
+    let (sin, cos) = 30f32.to_radians().sin_cos();
+    let at = |a: f32, b: f32, c: f32| [1.0 + a * cos - b * sin, 2.0 + a * sin + b * cos, 3.0 + c];
+    let inside = [at(1.8, 0.0, 0.0), at(-1.5, 0.4, 0.0), at(0.0, 0.0, 0.45), at(1.9, -0.45, -0.45)];
+    // The second is inside if the box were turned the other way
+    let outside = [at(2.2, 0.0, 0.0), at(1.56, 0.9, 0.0), at(0.0, 0.0, 0.6), at(0.0, -0.6, 0.0)];
+    for point in inside {
+        assert!(region.contains(point), "{:?} should be inside", point);
+    }
+    for point in outside {
+        assert!(!region.contains(point), "{:?} should be outside", point);
+    }
+
+    let all: Vec<[f32; 3]> = inside.iter().chain(&outside).copied().collect();
+    let mut kept = cloud_at(&all);
+    assert_eq!(edit::crop(&mut kept, &region, false), 4);
+    assert_eq!(positions(&kept), inside);
+
+    let mut cut = cloud_at(&all);
+    let (description, removed) = EditOperation::Crop { region: region.clone(), invert: true }.apply(&mut cut);
+    assert_eq!(removed, 4);
+    assert_eq!(positions(&cut), outside);
+    assert!(description.starts_with("inside oriented box"), "{}", description);
+
+    assert!(Region::from_oriented_box_values(&[0.0; 10]).is_err(), "a zero quaternion is rejected");
+}
+
+#[test]
+fn box_and_sphere_regions_include_their_boundary() {
+    let aabb = Region::from_box_values(&[1.0, 1.0, 1.0, -1.0, -1.0, -1.0]).unwrap();
+    assert!(aabb.contains([1.0, -1.0, 0.0]));
+    assert!(!aabb.contains([1.01, 0.0, 0.0]));
+    let sphere = Region::from_sphere_values(&[0.0, 0.0, 1.0, -2.0]).unwrap();
+    assert!(sphere.contains([0.0, 2.0, 1.0]));
+    assert!(!sphere.contains([1.5, 1.5, 1.0]));
+}
+
+#[test]
+fn opacity_and_scale_filters() {
+    let mut cloud = cloud_at(&[[0.0; 3]; 4]);
+    for (i, gaussian) in cloud.gaussians.iter_mut().enumerate() {
+        gaussian.opacity = inverse_sigmoid(0.1 + i as f32 * 0.25);
+        gaussian.scale = [(0.01 * 10f32.powi(i as i32)).ln(), (0.001f32).ln(), (0.001f32).ln()];
+    }
+    let mut opaque = cloud.clone();
+    assert_eq!(edit::filter_opacity(&mut opaque, 0.5), 2);
+    assert!(opaque.gaussians.iter().all(|gaussian| gaussian.alpha() >= 0.5));
+
+    // Largest linear scales are 0.01, 0.1, 1 and 10
+    let mut sized = cloud.clone();
+    assert_eq!(edit::filter_scale(&mut sized, Some(0.05), Some(2.0)), 2);
+    let largest: Vec<f32> = sized.gaussians.iter().map(|gaussian| gaussian.linear_scale()[0]).collect();
+    assert!((largest[0] - 0.1).abs() < 1e-5 && (largest[1] - 1.0).abs() < 1e-5, "{:?}", largest);
+}
+
+#[test]
+fn a_planted_outlier_is_removed() {
+    let mut grid: Vec<[f32; 3]> = (0..125).map(|i| [(i % 5) as f32 * 0.1, (i / 5 % 5) as f32 * 0.1, (i / 25) as f32 * 0.1]).collect();
+    grid.insert(60, [8.0, -3.0, 5.0]);
+    grid.push([f32::NAN, 0.0, 0.0]);
+    let mut cloud = cloud_at(&grid);
+
+    let removed = edit::remove_outliers(&mut cloud, 8, 2.0);
+    let kept = positions(&cloud);
+    assert_eq!(removed, 2, "the planted point and the non-finite one");
+    assert_eq!(kept.len(), 125);
+    assert!(kept.iter().all(|p| p.iter().all(|v| (0.0..=0.4 + 1e-6).contains(v))));
+    assert_eq!(edit::remove_outliers(&mut cloud, 200, 2.0), 0, "too few points for the neighborhood");
+}
+
+#[test]
+fn keep_largest_ranks_by_opacity_times_volume() {
+    let mut cloud = cloud_at(&[[0.0; 3]; 20]);
+    for (i, gaussian) in cloud.gaussians.iter_mut().enumerate() {
+        let t = i as f32;
+        gaussian.position = [t, 0.0, 0.0];
+        gaussian.opacity = inverse_sigmoid(0.05 + ((i * 7) % 20) as f32 * 0.045);
+        gaussian.scale = [-2.0 - (t * 0.9).sin(), -2.5 + (t * 0.4).cos(), -3.0];
+    }
+    let score = |gaussian: &Gaussian| {
+        let [x, y, z] = gaussian.linear_scale();
+        gaussian.alpha() * x * y * z
+    };
+    let mut expected: Vec<(f32, f32)> = cloud.gaussians.iter().map(|gaussian| (score(gaussian), gaussian.position[0])).collect();
+    expected.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut expected: Vec<f32> = expected[..5].iter().map(|(_, x)| *x).collect();
+    expected.sort_by(f32::total_cmp);
+
+    let mut largest = cloud.clone();
+    assert_eq!(edit::keep_largest(&mut largest, 5), 15);
+    // Survivors keep their original order
+    let kept: Vec<f32> = largest.gaussians.iter().map(|gaussian| gaussian.position[0]).collect();
+    assert_eq!(kept, expected);
+    for gaussian in &largest.gaussians {
+        assert!((edit::importance(gaussian) - score(gaussian)).abs() <= 1e-6 * score(gaussian));
+    }
+    assert_eq!(edit::keep_largest(&mut largest, 5), 0);
+    assert_eq!(edit::keep_largest(&mut cloud, 25), 0);
+}
@@ -0,0 +1,119 @@
+//! Merging scenes: inputs are placed by their own transform and brought to a shared SH
+//! degree, overlaps between inputs keep only their most important Gaussian, and the
+//! written header records every input.
+
+mod common;
+
+use hylaean_splat::formats::gaussian::{inverse_sigmoid, sh_rest_len, Gaussian, GaussianCloud};
+use hylaean_splat::formats::ply::{self, Encoding};
+use hylaean_splat::formats::splat_ops::merge::{self, MergeInput, MergeOptions};
+use hylaean_splat::formats::splat_ops::transform::{self, Similarity};
+use hylaean_splat::formats::splat_ops;
+use common::scratch;
+
+fn scene(sh_degree: u32, gaussians: &[([f32; 3], f32)]) -> GaussianCloud {
+    let mut cloud = GaussianCloud::new(sh_degree);
+    cloud.gaussians = gaussians.iter().enumerate().map(|(i, (position, alpha))| {
+        let mut gaussian = Gaussian::new(sh_degree);
+        gaussian.position = *position;
+        gaussian.opacity = inverse_sigmoid(*alpha);
+        gaussian.scale = [-3.0, -3.5, -4.0];
+        gaussian.rotation = [0.9, 0.1 * i as f32, -0.3, 0.2];
+        for (k, coefficient) in gaussian.sh_rest.iter_mut().enumerate() {
+            *coefficient = ((k + i) as f32 * 0.37).sin() * 0.5;
+        }
+        gaussian
+    }).collect();
+    cloud
+}
+
+fn input(path: &str, transform: Similarity) -> MergeInput {
+    MergeInput { path: path.into(), transform }
+}
+
+fn positions(cloud: &GaussianCloud) -> Vec<[f32; 3]> {
+    cloud.gaussians.iter().map(|gaussian| gaussian.position).collect()
+}
+
+#[test]
+fn inputs_are_placed_and_share_the_highest_sh_degree() {
+    let low = scene(1, &[([0.0, 0.0, 0.0], 0.5), ([1.0, 2.0, 3.0], 0.7), ([-1.0, 0.5, 2.0], 0.2)]);
+    let high = scene(3, &[([0.5, -0.5, 1.0], 0.6), ([2.0, 1.0, -1.0], 0.9)]);
+    let placement = Similarity::from_euler_degrees([30.0, -45.0, 90.0])
+        .then(&Similarity::from_scale(2.0).unwrap())
+        .then(&Similarity::from_translation([10.0, -4.0, 1.5]));
+
+    let (merged, removed) = merge::merge_clouds(vec![
+        (input("low.ply", Similarity::default()), low.clone()),
+        (input("high.spz", placement.clone()), high.clone()),
+    ], &MergeOptions::default()).unwrap();
+
+    assert_eq!((merged.len(), merged.sh_degree, removed), (5, 3, 0));
+    // The degree 1 scene keeps its own bands per channel, zero-padded up to degree 3
+    for (gaussian, source) in merged.gaussians[..3].iter().zip(&low.gaussians) {
+        assert_eq!(gaussian.sh_rest.len(), sh_rest_len(3));
+        assert_eq!((gaussian.position, gaussian.rotation, gaussian.scale), (source.position, source.rotation, source.scale));
+        for channel in 0..3 {
+            assert_eq!(gaussian.sh_rest[channel * 15..channel * 15 + 3], source.sh_rest[channel * 3..channel * 3 + 3]);
+            assert!(gaussian.sh_rest[channel * 15 + 3..channel * 15 + 15].iter().all(|c| *c == 0.0));
+        }
+    }
+    // The degree 3 scene is moved, turned, scaled and has its SH rotated with it
+    let mut placed = high.clone();
+    transform::transform_cloud(&mut placed, &placement);
+    assert_eq!(merged.gaussians[3..], placed.gaussians[..]);
+    let expected = placement.transform_point([0.5, -0.5, 1.0]).map(|v| v as f32);
+    assert_eq!(merged.gaussians[3].position, expected);
+    assert!((merged.gaussians[3].scale[0] - (-3.0 + 2f32.ln())).abs() < 1e-6);
+
+    // An explicit degree truncates the higher input instead
+    let (truncated, _) = merge::merge_clouds(vec![
+        (input("low.ply", Similarity::default()), low),
+        (input("high.spz", placement), high),
+    ], &MergeOptions { sh_degree: Some(1), dedupe_radius: None }).unwrap();
+    assert_eq!(truncated.sh_degree, 1);
+    assert!(truncated.gaussians.iter().all(|gaussian| gaussian.sh_rest.len() == sh_rest_len(1)));
+}
+
+#[test]
+fn overlaps_keep_the_most_important_copy() {
+    // Near the origin the first input's Gaussian is the more important; near x = 20 the
+    // second input's. The first input's own close pair must both survive.
+    let first = scene(0, &[([0.0, 0.0, 0.0], 0.9), ([0.03, 0.0, 0.0], 0.1), ([5.0, 0.0, 0.0], 0.5), ([20.05, 0.0, 0.0], 0.2)]);
+    let second = scene(0, &[([0.01, 0.02, 0.0], 0.5), ([10.0, 0.0, 0.0], 0.5), ([20.0, 0.0, 0.0], 0.95)]);
+    let options = MergeOptions { sh_degree: None, dedupe_radius: Some(0.1) };
+
+    let (merged, removed) = merge::merge_clouds(vec![
+        (input("first.ply", Similarity::default()), first.clone()),
+        (input("second.ply", Similarity::default()), second.clone()),
+    ], &options).unwrap();
+
+    assert_eq!(removed, 2);
+    assert_eq!(positions(&merged), [[0.0, 0.0, 0.0], [0.03, 0.0, 0.0], [5.0, 0.0, 0.0], [10.0, 0.0, 0.0], [20.0, 0.0, 0.0]]);
+    assert!(merged.comments.last().unwrap().contains("removed 2 overlapping Gaussians within radius 0.1"));
+
+    // A single input is never de-duplicated against itself
+    let (alone, removed) = merge::merge_clouds(vec![(input("first.ply", Similarity::default()), first)], &options).unwrap();
+    assert_eq!((alone.len(), removed), (4, 0));
+}
+
+#[test]
+fn written_header_lists_every_input() {
+    let dir = scratch("splat_merge");
+    let output = dir.join("site.ply");
+    let (merged, _) = merge::merge_clouds(vec![
+        (input("tiles/tile_0.ply", Similarity::default()), scene(1, &[([0.0; 3], 0.5)])),
+        (input("tiles/tile_1.spz", Similarity::from_translation([4.0, 0.0, 0.0])), scene(2, &[([0.0; 3], 0.5), ([1.0; 3], 0.5)])),
+    ], &MergeOptions::default()).unwrap();
+    splat_ops::save_cloud(&merged, &output, Encoding::BinaryLittleEndian).unwrap();
+    let header = ply::read_header(&output).unwrap();
+    let read_back = GaussianCloud::read_ply(&output).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let inputs: Vec<&String> = header.comments.iter().filter(|comment| comment.starts_with("hylaeansplat merge input")).collect();
+    assert_eq!(inputs.len(), 2, "{:?}", header.comments);
+    assert!(inputs[0].starts_with("hylaeansplat merge input 0: tiles/tile_0.ply (1 Gaussians, SH degree 1"), "{}", inputs[0]);
+    assert!(inputs[1].starts_with("hylaeansplat merge input 1: tiles/tile_1.spz (2 Gaussians, SH degree 2"), "{}", inputs[1]);
+    assert!(inputs[1].contains("4.0"), "the transform is recorded: {}", inputs[1]);
+    assert_eq!((read_back.len(), read_back.sh_degree), (3, 2));
+}
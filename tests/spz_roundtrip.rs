@@ -0,0 +1,83 @@
+//! SPZ through the converter: PLY -> SPZ -> PLY keeps every attribute within the
+//! quantization step SPZ stores it with, at each SH degree it supports.
+
+mod common;
+
+use hylaean_splat::formats::gaussian::{inverse_sigmoid, Gaussian, GaussianCloud};
+use hylaean_splat::formats::ply::Encoding;
+use hylaean_splat::formats::point_cloud::PointCloudConverter;
+use hylaean_splat::formats::{spz, DataFormat, FormatConverter, PointCloudFormat};
+use common::scratch;
+
+fn cloud(sh_degree: u32) -> GaussianCloud {
+    let mut cloud = GaussianCloud::new(sh_degree);
+    cloud.has_normals = false;
+    cloud.gaussians = (0..64).map(|i| {
+        let t = i as f32;
+        let mut gaussian = Gaussian::new(sh_degree);
+        gaussian.position = [(t * 0.41).sin() * 30.0, t * 0.25 - 8.0, (t * 0.13).cos() * -12.0];
+        gaussian.sh_dc = [(t * 0.3).sin() * 2.0, -1.5 + t * 0.04, (t * 0.9).cos()];
+        gaussian.opacity = inverse_sigmoid(0.05 + (i % 10) as f32 * 0.1);
+        gaussian.scale = [-6.0 + t * 0.05, -3.3, (t * 0.2).sin() - 2.0];
+        gaussian.rotation = [(t * 0.17).cos() * 2.0, (t * 0.29).sin(), -0.4, 0.3 - t * 0.01];
+        for (k, coefficient) in gaussian.sh_rest.iter_mut().enumerate() {
+            *coefficient = ((k as f32 * 1.7 + t) * 0.31).sin() * 0.9;
+        }
+        gaussian
+    }).collect();
+    cloud
+}
+
+fn unit_quaternion(rotation: [f32; 4]) -> [f32; 4] {
+    let norm = rotation.iter().map(|v| v * v).sum::<f32>().sqrt();
+    rotation.map(|v| v / norm)
+}
+
+fn assert_within(actual: &[f32], expected: &[f32], step: f32, what: &str) {
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() <= step * 1.001, "{}: {:?} != {:?} (step {})", what, actual, expected, step);
+    }
+}
+
+#[test]
+fn ply_spz_ply_stays_within_quantization() {
+    let dir = scratch("spz_roundtrip");
+    let converter = PointCloudConverter::new();
+    let ply = DataFormat::PointCloud(PointCloudFormat::PLY);
+    let spz_format = DataFormat::PointCloud(PointCloudFormat::Spz);
+
+    for sh_degree in 0..=3 {
+        let expected = cloud(sh_degree);
+        let input = dir.join(format!("input_{}.ply", sh_degree));
+        let compressed = dir.join(format!("scene_{}.spz", sh_degree));
+        let output = dir.join(format!("output_{}.ply", sh_degree));
+        expected.write_ply(&input, Encoding::BinaryLittleEndian).unwrap();
+        converter.convert(&input, &compressed, &ply, &spz_format).unwrap();
+        converter.convert(&compressed, &output, &spz_format, &ply).unwrap();
+        let header = spz::read_header(&compressed).unwrap();
+        let actual = GaussianCloud::read_ply(&output).unwrap();
+
+        assert_eq!((header.num_points, header.sh_degree), (64, sh_degree as u8));
+        assert_eq!((actual.len(), actual.sh_degree), (64, sh_degree));
+        let position_step = 0.5 / (1u32 << header.fractional_bits) as f32;
+        for (a, e) in actual.gaussians.iter().zip(&expected.gaussians) {
+            assert_within(&a.position, &e.position, position_step, "position");
+            assert_within(&[a.alpha()], &[e.alpha()], 0.5 / 255.0, "alpha");
+            assert_within(&a.sh_dc, &e.sh_dc, 0.5 / (0.15 * 255.0), "color");
+            assert_within(&a.scale, &e.scale, 0.5 / 16.0, "log scale");
+
+            // Both signs describe the same rotation; SPZ keeps w non-negative
+            let (qa, qe) = (unit_quaternion(a.rotation), unit_quaternion(e.rotation));
+            let sign = if qe[0] < 0.0 { -1.0 } else { 1.0 };
+            assert_within(&qa, &qe.map(|v| v * sign), 0.02, "rotation");
+
+            // Degree-1 coefficients are kept in steps of 8/128, higher bands in steps of 16/128
+            let per_channel = a.sh_rest.len() / 3;
+            for (k, (a, e)) in a.sh_rest.iter().zip(&e.sh_rest).enumerate() {
+                let step = if k % per_channel < 3 { 4.5 / 128.0 } else { 8.5 / 128.0 };
+                assert_within(&[*a], &[*e], step, "SH");
+            }
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
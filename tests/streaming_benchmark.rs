@@ -0,0 +1,80 @@
+//! Throughput and peak-memory check for the streaming point cloud pipeline.
+//!
+//! Ignored by default since it writes several gigabytes; run with
+//! `cargo test --release --test streaming_benchmark -- --ignored --nocapture`.
+//! `HYLAEAN_BENCH_POINTS` overrides the default of 50 million points.
+
+use hylaean_splat::formats::ply::{Encoding, PlyPointWriter};
+use hylaean_splat::formats::point_cloud::PointCloudConverter;
+use hylaean_splat::formats::{DataFormat, FormatConverter, PointCloudFormat};
+use std::path::Path;
+use std::time::Instant;
+
+/// Allowed growth of peak resident memory over the whole run
+const MEMORY_BUDGET_BYTES: u64 = 96 * 1024 * 1024;
+
+/// Read a `/proc/self/status` field such as `VmHWM`, in bytes
+fn proc_status_bytes(field: &str) -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with(field))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn convert(input: &Path, output: &Path, from: PointCloudFormat, to: PointCloudFormat) -> f64 {
+    let started = Instant::now();
+    PointCloudConverter::new()
+        .convert(input, output, &DataFormat::PointCloud(from), &DataFormat::PointCloud(to))
+        .expect("conversion failed");
+    started.elapsed().as_secs_f64()
+}
+
+#[test]
+#[ignore]
+fn streaming_conversion_keeps_memory_flat() {
+    let points = std::env::var("HYLAEAN_BENCH_POINTS")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(50_000_000);
+
+    let dir = std::env::temp_dir().join(format!("hylaean_splat_bench_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let ply = dir.join("points.ply");
+    let pcd = dir.join("points.pcd");
+    let back = dir.join("back.ply");
+
+    let mut writer = PlyPointWriter::create(&ply, Encoding::BinaryLittleEndian, points).unwrap();
+    for i in 0..points {
+        let t = i as f32;
+        writer.write_point([t * 1.0e-3, (t * 0.618).sin(), (t * 0.382).cos()]).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let baseline = proc_status_bytes("VmRSS:").unwrap_or(0);
+    let to_pcd = convert(&ply, &pcd, PointCloudFormat::PLY, PointCloudFormat::PCD);
+    let to_ply = convert(&pcd, &back, PointCloudFormat::PCD, PointCloudFormat::PLY);
+    let peak = proc_status_bytes("VmHWM:");
+
+    let input_size = std::fs::metadata(&ply).unwrap().len();
+    let output_size = std::fs::metadata(&back).unwrap().len();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    println!(
+        "{} points: PLY -> PCD {:.1}s ({:.2} M points/s), PCD -> PLY {:.1}s ({:.2} M points/s)",
+        points,
+        to_pcd, points as f64 / to_pcd / 1.0e6,
+        to_ply, points as f64 / to_ply / 1.0e6,
+    );
+    assert_eq!(input_size, output_size, "round trip changed the PLY size");
+
+    if let Some(peak) = peak {
+        let growth = peak.saturating_sub(baseline);
+        println!("peak RSS {} MB, {} MB over baseline", peak >> 20, growth >> 20);
+        assert!(
+            growth < MEMORY_BUDGET_BYTES,
+            "peak memory grew by {} MB converting {} points",
+            growth >> 20,
+            points
+        );
+    }
+}
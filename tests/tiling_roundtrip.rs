@@ -0,0 +1,83 @@
+//! Tiling a splat scene and merging the tiles back must reproduce every Gaussian.
+
+use hylaean_splat::formats::gaussian::{Gaussian, GaussianCloud};
+use hylaean_splat::formats::ply::Encoding;
+use hylaean_splat::formats::point_cloud::tiles::{self, TilingScheme};
+use hylaean_splat::formats::splat_ops::{self, lod::{LodMethod, LodOptions}};
+use hylaean_splat::formats::splat_ops::merge::{self, MergeInput, MergeOptions};
+use hylaean_splat::formats::splat_ops::transform::Similarity;
+use std::path::{Path, PathBuf};
+
+fn scene(count: usize) -> GaussianCloud {
+    let mut cloud = GaussianCloud::new(1);
+    cloud.gaussians = (0..count)
+        .map(|i| {
+            let t = i as f32;
+            let mut gaussian = Gaussian::new(1);
+            // A spiral plus a dense cluster, so octree cells split unevenly
+            gaussian.position = if i % 4 == 0 {
+                [0.3 + (t * 0.37).sin() * 0.05, 0.2 + (t * 0.11).cos() * 0.05, 0.1]
+            } else {
+                [(t * 0.013).cos() * t * 0.004, (t * 0.013).sin() * t * 0.004, (t * 0.29).sin() * 2.0]
+            };
+            gaussian.sh_dc = [(t * 0.1).sin(), (t * 0.2).cos(), 0.5];
+            gaussian.sh_rest = (0..9).map(|k| (t + k as f32) * 1.0e-3).collect();
+            gaussian.opacity = (t * 0.7).sin() * 3.0;
+            gaussian.scale = [-4.0, -4.5, -5.0];
+            gaussian
+        })
+        .collect();
+    cloud
+}
+
+fn round_trip(scheme: TilingScheme, name: &str) {
+    let dir = std::env::temp_dir().join(format!("hylaean_splat_tiles_{}_{}", name, std::process::id()));
+    let original = scene(5_000);
+    let lod = LodOptions { levels: 2, ratio: 0.5, method: LodMethod::Voxel };
+    let tileset = tiles::tile_cloud(&original, Path::new("scene.ply"), &dir, scheme, &lod, "ply", Encoding::BinaryLittleEndian)
+        .expect("tiling failed");
+
+    let index = tiles::read_tileset(&dir.join(tiles::TILESET_FILE)).expect("tileset index unreadable");
+    assert_eq!(index.gaussians, original.len());
+    assert_eq!(index.tiles.len(), tileset.tiles.len());
+    assert!(index.tiles.len() > 1, "{} produced a single tile", name);
+
+    let inputs: Vec<(MergeInput, GaussianCloud)> = index.tiles.iter()
+        .map(|tile| {
+            assert_eq!(tile.levels.len(), 2);
+            assert!(tile.levels[1].gaussians <= tile.gaussians);
+            let path: PathBuf = dir.join(&tile.levels[0].file);
+            let (cloud, _) = splat_ops::load_cloud(&path).expect("tile unreadable");
+            assert_eq!(cloud.len(), tile.gaussians);
+            for gaussian in &cloud.gaussians {
+                for axis in 0..3 {
+                    let position = gaussian.position[axis] as f64;
+                    assert!(position >= tile.region.min[axis] && position <= tile.region.max[axis]);
+                }
+            }
+            (MergeInput { path, transform: Similarity::default() }, cloud)
+        })
+        .collect();
+    let (merged, removed) = merge::merge_clouds(inputs, &MergeOptions::default()).expect("merge failed");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(removed, 0);
+    assert_eq!(merged.len(), original.len());
+    assert_eq!(merged.sh_degree, original.sh_degree);
+    let key = |gaussian: &Gaussian| gaussian.position.map(f32::to_bits);
+    let mut expected: Vec<_> = original.gaussians.iter().map(key).collect();
+    let mut actual: Vec<_> = merged.gaussians.iter().map(key).collect();
+    expected.sort_unstable();
+    actual.sort_unstable();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn grid_tiles_merge_back_to_the_original() {
+    round_trip(TilingScheme::Grid { tile_size: 0.5 }, "grid");
+}
+
+#[test]
+fn octree_tiles_merge_back_to_the_original() {
+    round_trip(TilingScheme::Octree { max_gaussians: 400, max_depth: 8 }, "octree");
+}
@@ -0,0 +1,203 @@
+//! Web viewer formats: antimatter15 `.splat` records sorted by importance,
+//! GaussianSplats3D `.ksplat` headers, buckets and quantized splat data, and the
+//! converter moving scenes between every pair of Gaussian formats.
+
+mod common;
+
+use hylaean_splat::formats::gaussian::{inverse_sigmoid, Gaussian, GaussianCloud, SH_C0};
+use hylaean_splat::formats::ply::Encoding;
+use hylaean_splat::formats::point_cloud::PointCloudConverter;
+use hylaean_splat::formats::{splat, splat_ops, DataFormat, FormatConverter, PointCloudFormat};
+use common::scratch;
+
+fn gaussian(i: usize, sh_degree: u32) -> Gaussian {
+    let t = i as f32;
+    let mut gaussian = Gaussian::new(sh_degree);
+    gaussian.position = [(t * 0.37).sin() * 2.0, t * 0.01, (t * 0.11).cos() * 2.0];
+    gaussian.sh_dc = [0.3 - t * 0.002, -0.5, (t * 0.7).sin()];
+    gaussian.opacity = inverse_sigmoid(0.2 + (i % 7) as f32 * 0.1);
+    gaussian.scale = [-3.0 - (i % 5) as f32 * 0.2, -2.5, -4.0];
+    gaussian.rotation = [0.8, 0.1 * (t * 0.3).sin(), -0.3, 0.2];
+    for (k, coefficient) in gaussian.sh_rest.iter_mut().enumerate() {
+        *coefficient = ((k as f32 + t) * 0.13).sin() * 0.5;
+    }
+    gaussian
+}
+
+fn cloud(count: usize, sh_degree: u32) -> GaussianCloud {
+    let mut cloud = GaussianCloud::new(sh_degree);
+    cloud.gaussians = (0..count).map(|i| gaussian(i, sh_degree)).collect();
+    cloud
+}
+
+fn importance(gaussian: &Gaussian) -> f32 {
+    (gaussian.scale[0] + gaussian.scale[1] + gaussian.scale[2]).exp() * gaussian.alpha()
+}
+
+fn unit_rotation(gaussian: &Gaussian) -> [f32; 4] {
+    let norm = gaussian.rotation.iter().map(|v| v * v).sum::<f32>().sqrt();
+    gaussian.rotation.map(|v| v / norm)
+}
+
+/// The written splat for `actual`, found by its position
+fn source<'a>(expected: &'a GaussianCloud, actual: &Gaussian, tolerance: f32) -> &'a Gaussian {
+    expected.gaussians.iter()
+        .find(|e| e.position.iter().zip(actual.position).all(|(e, a)| (e - a).abs() <= tolerance))
+        .unwrap_or_else(|| panic!("no source splat near {:?}", actual.position))
+}
+
+fn assert_attributes(actual: &Gaussian, expected: &Gaussian, scale_tolerance: f32, rotation_tolerance: f32) {
+    for (a, e) in actual.base_color().iter().zip(expected.base_color()) {
+        assert!((a - e).abs() <= 0.5 / 255.0 + 1e-6, "color {:?} != {:?}", actual.base_color(), expected.base_color());
+    }
+    assert!((actual.alpha() - expected.alpha()).abs() <= 0.5 / 255.0 + 1e-6, "alpha");
+    for (a, e) in actual.linear_scale().iter().zip(expected.linear_scale()) {
+        assert!((a - e).abs() <= scale_tolerance * e, "scale {:?} != {:?}", actual.linear_scale(), expected.linear_scale());
+    }
+    for (a, e) in actual.rotation.iter().zip(unit_rotation(expected)) {
+        assert!((a - e).abs() <= rotation_tolerance, "rotation {:?} != {:?}", actual.rotation, expected.rotation);
+    }
+}
+
+#[test]
+fn splat_records_are_sorted_and_read_back() {
+    let dir = scratch("web_splat");
+    let path = dir.join("scene.splat");
+    let expected = cloud(40, 3);
+    splat::write_splat(&expected, &path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let actual = splat::read_splat(&path).unwrap();
+
+    // Writing what was read keeps every position and color byte
+    let again = dir.join("again.splat");
+    splat::write_splat(&actual, &again).unwrap();
+    let rewritten = std::fs::read(&again).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(bytes.len(), 40 * splat::SPLAT_RECORD_SIZE);
+    assert_eq!((actual.len(), actual.sh_degree), (40, 0));
+    let importances: Vec<f32> = actual.gaussians.iter().map(|g| importance(source(&expected, g, 0.0))).collect();
+    assert!(importances.windows(2).all(|pair| pair[0] >= pair[1]), "not sorted by importance");
+    for gaussian in &actual.gaussians {
+        assert_attributes(gaussian, source(&expected, gaussian, 0.0), 1e-6, 1.0 / 128.0);
+    }
+    let positions_and_colors = |bytes: &[u8]| {
+        let mut records: Vec<Vec<u8>> = bytes.chunks(splat::SPLAT_RECORD_SIZE)
+            .map(|record| [&record[..12], &record[24..28]].concat())
+            .collect();
+        records.sort();
+        records
+    };
+    assert_eq!(positions_and_colors(&rewritten), positions_and_colors(&bytes));
+}
+
+#[test]
+fn splat_colors_round_to_the_nearest_level() {
+    let dir = scratch("web_splat_levels");
+    let path = dir.join("levels.splat");
+    let mut levels = GaussianCloud::new(0);
+    levels.gaussians = (0..=255u8).map(|level| {
+        let mut gaussian = gaussian(level as usize, 0);
+        gaussian.sh_dc = [(level as f32 / 255.0 - 0.5) / SH_C0; 3];
+        gaussian.opacity = inverse_sigmoid(level.max(1) as f32 / 255.0);
+        gaussian
+    }).collect();
+    splat::write_splat(&levels, &path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut colors: Vec<u8> = bytes.chunks(splat::SPLAT_RECORD_SIZE).map(|record| record[24]).collect();
+    assert!(bytes.chunks(splat::SPLAT_RECORD_SIZE).all(|record| record[24] == record[25] && record[25] == record[26]));
+    colors.sort_unstable();
+    assert_eq!(colors, (0..=255u8).collect::<Vec<_>>());
+}
+
+#[test]
+fn ksplat_buckets_and_sections_read_back() {
+    let dir = scratch("web_ksplat");
+    let path = dir.join("scene.ksplat");
+    // Most splats share one 5-unit block; the rest spill into a few neighbours
+    let mut expected = cloud(300, 3);
+    for (i, gaussian) in expected.gaussians.iter_mut().enumerate().skip(270) {
+        gaussian.position[0] += 6.0 + i as f32 * 0.01;
+    }
+    splat::write_ksplat(&expected, &path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let actual = splat::read_ksplat(&path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    let u16_at = |offset: usize| u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap());
+    assert_eq!((bytes[0], bytes[1]), (0, 1), "version");
+    assert_eq!((u32_at(8), u32_at(16)), (1, 300), "section and splat counts");
+    assert_eq!(u16_at(20), 1, "compression level");
+    let section = 4096;
+    assert_eq!((u32_at(section), u32_at(section + 8)), (300, 256), "section splats and bucket size");
+    let (buckets, full, partial) = (u32_at(section + 12) as usize, u32_at(section + 32) as usize, u32_at(section + 36) as usize);
+    assert_eq!(full + partial, buckets);
+    assert_eq!(u16_at(section + 40), 2, "SH degree is capped at 2");
+
+    // Partial bucket lengths, then 12-byte centers, then 24 bytes plus 8 half-float SH triplets per splat
+    let data = section + 1024 + partial * 4 + buckets * 12;
+    assert_eq!(bytes.len(), data + 300 * (24 + 8 * 3 * 2));
+    let partial_lengths: usize = (0..partial).map(|i| u32_at(section + 1024 + i * 4) as usize).sum();
+    assert_eq!(full * 256 + partial_lengths, 300);
+
+    assert_eq!((actual.len(), actual.sh_degree), (300, 2));
+    let position_step = 2.5 / 32767.0;
+    for gaussian in &actual.gaussians {
+        let source = source(&expected, gaussian, position_step);
+        assert_attributes(gaussian, source, 1e-3, 1e-3);
+        // The first eight coefficients of each channel are kept, at half precision
+        for channel in 0..3 {
+            for k in 0..8 {
+                let (a, e) = (gaussian.sh_rest[channel * 8 + k], source.sh_rest[channel * 15 + k]);
+                assert!((a - e).abs() <= 1e-3, "SH {} of channel {}: {} != {}", k, channel, a, e);
+            }
+        }
+    }
+}
+
+#[test]
+fn converter_moves_scenes_between_gaussian_formats() {
+    let dir = scratch("web_convert");
+    let expected = cloud(50, 2);
+    let input = dir.join("scene.ply");
+    expected.write_ply(&input, Encoding::BinaryLittleEndian).unwrap();
+    // Every format is both read and written, and no step goes through PLY twice in a row
+    let chain = [
+        ("ply", PointCloudFormat::PLY),
+        ("splat", PointCloudFormat::Splat),
+        ("ksplat", PointCloudFormat::KSplat),
+        ("spz", PointCloudFormat::Spz),
+        ("splat", PointCloudFormat::Splat),
+        ("ksplat", PointCloudFormat::KSplat),
+        ("ply", PointCloudFormat::PLY),
+    ];
+    let converter = PointCloudConverter::new();
+    let mut previous = input.clone();
+    let mut steps = Vec::new();
+    for (index, pair) in chain.windows(2).enumerate() {
+        let (_, from) = &pair[0];
+        let (extension, to) = &pair[1];
+        let output = dir.join(format!("step_{}.{}", index, extension));
+        converter.convert(&previous, &output, &DataFormat::PointCloud(from.clone()), &DataFormat::PointCloud(to.clone()))
+            .unwrap_or_else(|error| panic!("{:?} -> {:?}: {}", from, to, error));
+        steps.push((format!("{:?} -> {:?}", from, to), splat_ops::load_cloud(&output).unwrap().0));
+        previous = output;
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    for (step, actual) in &steps {
+        assert_eq!(actual.len(), 50, "{}", step);
+        for gaussian in &actual.gaussians {
+            let source = source(&expected, gaussian, 1e-3);
+            assert!((gaussian.alpha() - source.alpha()).abs() <= 2.0 / 255.0, "{}: alpha", step);
+            for (a, e) in gaussian.base_color().iter().zip(source.base_color()) {
+                assert!((a - e).abs() <= 3.0 / 255.0, "{}: color {:?} != {:?}", step, gaussian.base_color(), source.base_color());
+            }
+        }
+    }
+    // `.splat` keeps no SH bands; the ksplat written after it has none to keep either
+    assert_eq!(steps.last().unwrap().1.sh_degree, 0);
+}
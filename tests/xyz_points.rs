@@ -0,0 +1,62 @@
+//! XYZ text through the streaming converter: trailing `r g b` columns become PLY colors
+//! and survive XYZ -> PCD -> XYZ, while plain `x y z` files stay colorless.
+
+mod common;
+
+use hylaean_splat::formats::ply::{PlyFile, Property};
+use hylaean_splat::formats::point_cloud::PointCloudConverter;
+use hylaean_splat::formats::{DataFormat, FormatConverter, PointCloudFormat};
+use std::path::Path;
+use common::scratch;
+
+fn convert(input: &Path, output: &Path, from: PointCloudFormat, to: PointCloudFormat) {
+    PointCloudConverter::new()
+        .convert(input, output, &DataFormat::PointCloud(from), &DataFormat::PointCloud(to))
+        .expect("conversion failed");
+}
+
+fn property_names(ply: &PlyFile) -> Vec<String> {
+    ply.header.elements["vertex"].properties.keys().cloned().collect()
+}
+
+#[test]
+fn colored_xyz_keeps_its_colors() {
+    let dir = scratch("xyz_colors");
+    let input = dir.join("colored.xyz");
+    let lines: Vec<String> = (0..50)
+        .map(|i| format!("{} {} {} {} {} {}", i as f32 * 0.5, -(i as f32) * 0.25, 1.5, i * 5, 255 - i, i % 7))
+        .collect();
+    std::fs::write(&input, format!("# exported scan\n\n{}\n", lines.join("\n"))).unwrap();
+
+    let ply_path = dir.join("colored.ply");
+    convert(&input, &ply_path, PointCloudFormat::XYZ, PointCloudFormat::PLY);
+    let pcd_path = dir.join("colored.pcd");
+    convert(&input, &pcd_path, PointCloudFormat::XYZ, PointCloudFormat::PCD);
+    let xyz_path = dir.join("again.xyz");
+    convert(&pcd_path, &xyz_path, PointCloudFormat::PCD, PointCloudFormat::XYZ);
+
+    let ply = PlyFile::read(&ply_path).unwrap();
+    let round_trip = std::fs::read_to_string(&xyz_path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(property_names(&ply), ["x", "y", "z", "red", "green", "blue"]);
+    let (_, records) = &ply.elements[0];
+    assert_eq!(records.len(), 50);
+    assert_eq!(records[3].0[3..], [Property::UChar(15), Property::UChar(252), Property::UChar(3)]);
+    assert_eq!(round_trip.lines().collect::<Vec<_>>(), lines);
+}
+
+#[test]
+fn plain_xyz_has_no_colors() {
+    let dir = scratch("xyz_plain");
+    let input = dir.join("plain.xyz");
+    // Columns past x y z that are not 8-bit colors are still ignored
+    std::fs::write(&input, "1 2 3 0.5 0.25 0.125\n4 5 6 0.5 0.25 0.125\n").unwrap();
+    let ply_path = dir.join("plain.ply");
+    convert(&input, &ply_path, PointCloudFormat::XYZ, PointCloudFormat::PLY);
+    let ply = PlyFile::read(&ply_path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(property_names(&ply), ["x", "y", "z"]);
+    assert_eq!(ply.elements[0].1.len(), 2);
+}